@@ -0,0 +1,134 @@
+//! Allocations and time per frame of the conversion path at 1440p, with the capture thread's
+//! `BufferPool` and with a fresh buffer for every step.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use liteview_lib::buffer_pool::BufferPool;
+use liteview_lib::resize::{resize_frame, resize_into, ResizeAlgorithm};
+use liteview_lib::yuv::{nv12_to_buffer, YuvFormat};
+use scap::frame::YUVFrame;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+const SRC: (u32, u32) = (2560, 1440);
+const DST: (u32, u32) = (1920, 1080);
+const FRAMES: usize = 100;
+
+/// The system allocator, counting allocations and their bytes.
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        BYTES.fetch_add(new_size, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+/// Allocations and allocated bytes so far.
+fn counts() -> (usize, usize) {
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    (allocations, BYTES.load(Ordering::Relaxed))
+}
+
+/// A mid-gray NV12 frame with a luma gradient.
+fn nv12((w, h): (u32, u32)) -> YUVFrame {
+    let (w, h) = (w as usize, h as usize);
+    YUVFrame {
+        display_time: 0,
+        width: w as i32,
+        height: h as i32,
+        luminance_bytes: (0..w * h).map(|i| (16 + i % w * 219 / w) as u8).collect(),
+        luminance_stride: w as i32,
+        chrominance_bytes: vec![128; w * h / 2],
+        chrominance_stride: w as i32,
+    }
+}
+
+/// What the capture loop does with a buffer per frame: convert, resize into a pooled buffer
+/// and publish it, while the preview keeps the latest frame.
+struct Pooled {
+    buffer: Vec<u32>,
+    pool: BufferPool,
+    latest: Option<Arc<Vec<u32>>>,
+}
+
+impl Pooled {
+    fn frame(&mut self, frame: &YUVFrame) {
+        nv12_to_buffer(frame, YuvFormat::SCAP, 1, &mut self.buffer).unwrap();
+        let mut out = self.pool.take();
+        resize_into(ResizeAlgorithm::Bilinear, SRC, &self.buffer, DST, &mut out);
+        self.latest = Some(self.pool.publish(out));
+    }
+}
+
+/// The same steps with new buffers every frame.
+struct Unpooled {
+    latest: Option<Arc<Vec<u32>>>,
+}
+
+impl Unpooled {
+    fn frame(&mut self, frame: &YUVFrame) {
+        let mut buffer = Vec::new();
+        nv12_to_buffer(frame, YuvFormat::SCAP, 1, &mut buffer).unwrap();
+        let (src, (w, h)) = (SRC, DST);
+        let out = resize_frame(ResizeAlgorithm::Bilinear, src.0, src.1, &buffer, w, h);
+        self.latest = Some(Arc::new(out));
+    }
+}
+
+/// Allocations and allocated KiB per frame over `FRAMES` frames, after a few frames to warm up.
+fn allocations_per_frame(mut frame: impl FnMut()) -> (f64, f64) {
+    for _ in 0..4 {
+        frame();
+    }
+    let before = counts();
+    for _ in 0..FRAMES {
+        frame();
+    }
+    let after = counts();
+    let frames = FRAMES as f64;
+    let allocations = (after.0 - before.0) as f64 / frames;
+    (allocations, (after.1 - before.1) as f64 / 1024.0 / frames)
+}
+
+fn buffer_pool(c: &mut Criterion) {
+    let frame = nv12(SRC);
+    let mut pooled = Pooled {
+        buffer: Vec::new(),
+        pool: BufferPool::default(),
+        latest: None,
+    };
+    let mut unpooled = Unpooled { latest: None };
+    for (name, (allocations, kib)) in [
+        ("pooled", allocations_per_frame(|| pooled.frame(&frame))),
+        ("unpooled", allocations_per_frame(|| unpooled.frame(&frame))),
+    ] {
+        println!("{name}: {allocations:.1} allocations, {kib:.0} KiB per frame");
+    }
+
+    let mut group = c.benchmark_group("frame_1440p_to_1080p");
+    group.sample_size(20);
+    group.bench_function("pooled", |b| b.iter(|| pooled.frame(&frame)));
+    group.bench_function("unpooled", |b| b.iter(|| unpooled.frame(&frame)));
+    group.finish();
+}
+
+criterion_group!(benches, buffer_pool);
+criterion_main!(benches);
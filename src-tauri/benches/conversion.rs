@@ -0,0 +1,48 @@
+//! NV12 conversion per frame at 1080p, 1440p and 4K, on the capture thread alone and split
+//! across the conversion pool.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use liteview_lib::parallel::conversion_threads;
+use liteview_lib::yuv::{nv12_to_buffer, YuvFormat};
+use scap::frame::YUVFrame;
+
+const SIZES: [(&str, (u32, u32)); 3] = [
+    ("1080p", (1920, 1080)),
+    ("1440p", (2560, 1440)),
+    ("4k", (3840, 2160)),
+];
+
+/// A frame with a luma gradient across and chroma varying down, so no row is like the next.
+fn nv12((w, h): (u32, u32)) -> YUVFrame {
+    let (w, h) = (w as usize, h as usize);
+    YUVFrame {
+        display_time: 0,
+        width: w as i32,
+        height: h as i32,
+        luminance_bytes: (0..w * h).map(|i| (16 + i % w * 219 / w) as u8).collect(),
+        luminance_stride: w as i32,
+        chrominance_bytes: (0..w * h / 2).map(|i| (i / w * 255 / h) as u8).collect(),
+        chrominance_stride: w as i32,
+    }
+}
+
+fn conversion(c: &mut Criterion) {
+    let parallel = conversion_threads(true);
+    let mut buffer = Vec::new();
+    for (name, size) in SIZES {
+        let frame = nv12(size);
+        let mut group = c.benchmark_group(format!("nv12_{name}"));
+        group.throughput(Throughput::Elements((size.0 * size.1) as u64));
+        group.sample_size(20);
+        // Both counts, unless this machine has a single core.
+        for threads in std::iter::once(1).chain((parallel > 1).then_some(parallel)) {
+            group.bench_function(BenchmarkId::new("threads", threads), |b| {
+                b.iter(|| nv12_to_buffer(&frame, YuvFormat::SCAP, threads, &mut buffer))
+            });
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, conversion);
+criterion_main!(benches);
@@ -0,0 +1,43 @@
+//! CPU resize cost of each algorithm on a 4K capture shown at 1080p and at 720p.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use liteview_lib::resize::{resize_into, ResizeAlgorithm};
+
+const SRC: (u32, u32) = (3840, 2160);
+const DSTS: [(&str, (u32, u32)); 2] = [("1080p", (1920, 1080)), ("720p", (1280, 720))];
+
+/// A frame with gradients and hard edges, so no filter gets a trivially flat input.
+fn frame((w, h): (u32, u32)) -> Vec<u32> {
+    (0..h)
+        .flat_map(|y| {
+            (0..w).map(move |x| {
+                let edge = if (x / 8 + y / 8) % 2 == 0 { 0xFF } else { 0 };
+                ((x * 255 / w) << 16) | ((y * 255 / h) << 8) | edge
+            })
+        })
+        .collect()
+}
+
+fn resize(c: &mut Criterion) {
+    let src = frame(SRC);
+    let mut out = Vec::new();
+    for (name, dst) in DSTS {
+        let mut group = c.benchmark_group(format!("resize_4k_to_{name}"));
+        group.throughput(Throughput::Elements((dst.0 * dst.1) as u64));
+        group.sample_size(20);
+        for algorithm in [
+            ResizeAlgorithm::Nearest,
+            ResizeAlgorithm::Bilinear,
+            ResizeAlgorithm::Area,
+            ResizeAlgorithm::Lanczos,
+        ] {
+            group.bench_function(BenchmarkId::from_parameter(algorithm.label()), |b| {
+                b.iter(|| resize_into(algorithm, SRC, &src, dst, &mut out))
+            });
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, resize);
+criterion_main!(benches);
@@ -1,13 +1,87 @@
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Bakes the git commit and build time into the binary for the About window.
+fn emit_build_info() {
+    let commit = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    println!("cargo:rustc-env=LITEVIEW_GIT_COMMIT={commit}");
+    println!("cargo:rustc-env=LITEVIEW_BUILD_TIMESTAMP={timestamp}");
+    println!("cargo:rerun-if-changed=../.git/HEAD");
+    println!("cargo:rerun-if-changed=../.git/refs/heads");
+}
+
 fn main() {
+    emit_build_info();
     tauri_build::try_build(
         tauri_build::Attributes::new().app_manifest(
             tauri_build::AppManifest::new()
                 .commands(&[
             "get_capture_targets",
+            "request_capture_permission",
+            "get_capture_applications",
+            "get_capture_capabilities",
             "get_capture_settings",
             "set_capture_settings",
+            "set_picture_adjustments",
+            "copy_adjustments_to_global",
+            "get_capture_status",
+            "get_capture_stats",
             "start_capture",
             "stop_capture",
+            "pause_capture",
+            "resume_capture",
+            "set_preview_frozen",
+            "show_preview",
+            "hide_preview",
+            "open_preview_window",
+            "close_preview_window",
+            "save_layout",
+            "load_layout",
+            "list_layouts",
+            "delete_layout",
+            "arrange_previews",
+            "set_preview_scale",
+            "get_monitors",
+            "reset_keybindings",
+            "set_crop_for_target",
+            "set_crop_area",
+            "clear_crop_for_target",
+            "lock_detected_crop",
+            "pick_anchor_window",
+            "enter_mirror_mode",
+            "exit_mirror_mode",
+            "set_overlay_mode",
+            "set_overlay_locked",
+            "set_preview_bounds",
+            "set_pip_mode",
+            "get_diagnostics",
+            "open_data_folder",
+            "check_for_update",
+            "install_update",
+            "restart_app",
+            "get_resumable_session",
+            "dismiss_resumable_session",
+            "resume_session",
+            "get_recent_errors",
+            "clear_errors",
+            "run_self_test",
+            "get_current_frame",
+            "start_recording",
+            "stop_recording",
+            "save_replay",
+            "capture_screenshot",
+            "copy_frame_to_clipboard",
+            "start_capture_by_title",
         ]),
         ),
     )
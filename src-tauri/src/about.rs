@@ -0,0 +1,78 @@
+use crate::CaptureState;
+use serde::Serialize;
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder};
+use tauri_plugin_opener::OpenerExt;
+
+const ABOUT_LABEL: &str = "about";
+
+/// What support needs to know about this build and machine.
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostics {
+    version: &'static str,
+    /// Short git commit the binary was built from ("unknown" outside a git checkout).
+    commit: &'static str,
+    /// Unix seconds.
+    build_timestamp: u64,
+    os: &'static str,
+    arch: &'static str,
+    capture_backend: &'static str,
+    /// None until a preview window has been opened in this run.
+    gpu_adapter: Option<String>,
+}
+
+fn capture_backend() -> &'static str {
+    if cfg!(target_os = "windows") {
+        "Windows.Graphics.Capture"
+    } else if cfg!(target_os = "macos") {
+        "ScreenCaptureKit"
+    } else {
+        "PipeWire (xdg-desktop-portal)"
+    }
+}
+
+/// Opens the About window, or focuses it if it is already open. It is a separate page so it
+/// still works when the settings page fails to load.
+pub fn open_about_window(app: &AppHandle) -> tauri::Result<()> {
+    if let Some(window) = app.get_webview_window(ABOUT_LABEL) {
+        window.unminimize()?;
+        window.show()?;
+        return window.set_focus();
+    }
+    WebviewWindowBuilder::new(app, ABOUT_LABEL, WebviewUrl::App("about".into()))
+        .title("About LiteView")
+        .inner_size(400.0, 640.0)
+        .resizable(false)
+        .decorations(false)
+        .always_on_top(true)
+        .build()?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_diagnostics(app: AppHandle) -> Diagnostics {
+    Diagnostics {
+        version: env!("CARGO_PKG_VERSION"),
+        commit: env!("LITEVIEW_GIT_COMMIT"),
+        build_timestamp: env!("LITEVIEW_BUILD_TIMESTAMP").parse().unwrap_or(0),
+        os: std::env::consts::OS,
+        arch: std::env::consts::ARCH,
+        capture_backend: capture_backend(),
+        gpu_adapter: app
+            .state::<CaptureState>()
+            .render_status
+            .lock()
+            .unwrap()
+            .adapter
+            .clone(),
+    }
+}
+
+/// Opens the folder with the settings and layouts in the file manager.
+#[tauri::command]
+pub fn open_data_folder(app: AppHandle) -> Result<(), String> {
+    let dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    app.opener()
+        .open_path(dir.to_string_lossy(), None::<&str>)
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,129 @@
+use crate::preview::WindowGeometry;
+use crate::{target_id, target_title, update_settings, window_region, CaptureState};
+use scap::{get_all_targets, Target};
+use serde::{Deserialize, Serialize};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(100);
+/// How often a closed anchor is looked up again by title (e.g. after the app restarted).
+const RELOCATE_EVERY: u32 = 20;
+
+/// Window the preview follows, keeping a fixed offset from its top-left corner.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Anchor {
+    /// Target ID of the anchor window; replaced by a window with the same title once it closes.
+    pub window_id: u32,
+    pub title: String,
+    /// Preview outer position minus the anchor's top-left corner, in physical pixels.
+    pub offset_x: i32,
+    pub offset_y: i32,
+}
+
+/// Moves the preview with its anchor window and hides it while the anchor is minimized.
+/// The preview is only moved when the anchor moved, so dragging it elsewhere never fights
+/// back and moving it cannot retrigger itself.
+pub fn spawn_anchor_follower(app: AppHandle) {
+    thread::spawn(move || {
+        let mut last_position = None;
+        let mut missing_ticks = 0u32;
+        loop {
+            thread::sleep(FOLLOW_INTERVAL);
+            let state = app.state::<CaptureState>();
+            let anchor = state.settings.lock().unwrap().anchor.clone();
+            let Some(preview) = state.primary_session() else {
+                last_position = None;
+                continue;
+            };
+            let Some(anchor) = anchor else {
+                preview.set_hidden(false);
+                last_position = None;
+                continue;
+            };
+            match window_region::window_rect(anchor.window_id) {
+                Some((x, y, _, _)) => {
+                    missing_ticks = 0;
+                    preview.set_hidden(false);
+                    let position = (x + anchor.offset_x, y + anchor.offset_y);
+                    if last_position != Some(position) {
+                        if let Some(g) = preview.geometry() {
+                            preview.request_geometry(WindowGeometry {
+                                x: position.0,
+                                y: position.1,
+                                ..g
+                            });
+                            last_position = Some(position);
+                        }
+                    }
+                }
+                // Minimized or hidden
+                None if window_region::window_exists(anchor.window_id) => preview.set_hidden(true),
+                None => {
+                    preview.set_hidden(false);
+                    missing_ticks += 1;
+                    if missing_ticks % RELOCATE_EVERY == 1 {
+                        relocate(&app, &anchor);
+                    }
+                }
+            }
+        }
+    });
+}
+
+/// Points a closed anchor at an open window with the same title, if there is one.
+fn relocate(app: &AppHandle, anchor: &Anchor) {
+    let found = get_all_targets().into_iter().find_map(|t| match t {
+        Target::Window(w) if w.title == anchor.title => Some(w.id),
+        _ => None,
+    });
+    if let Some(id) = found {
+        let _ = update_settings(app, |s| {
+            if let Some(a) = s.anchor.as_mut() {
+                a.window_id = id;
+            }
+        });
+    }
+}
+
+/// Attaches the preview to window `window_id` at its current offset from it (or to its right
+/// edge while no preview is open); None detaches it.
+#[tauri::command]
+pub fn pick_anchor_window(app: AppHandle, window_id: Option<u32>) -> Result<(), String> {
+    let Some(window_id) = window_id else {
+        return update_settings(&app, |s| s.anchor = None);
+    };
+    let target = get_all_targets()
+        .into_iter()
+        .find(|t| matches!(t, Target::Window(_)) && target_id(t) == window_id)
+        .ok_or("That window is no longer open.")?;
+    let title = target_title(&target).to_string();
+    if title.starts_with("LiteView") {
+        return Err("The preview cannot follow a LiteView window.".to_string());
+    }
+    let state = app.state::<CaptureState>();
+    {
+        let settings = state.settings.lock().unwrap();
+        // Captured through its monitor, the preview would sit inside its own capture.
+        if settings.include_child_windows && settings.target_id == Some(window_id) {
+            return Err(
+                "The captured window cannot be the anchor while popups are included.".to_string(),
+            );
+        }
+    }
+    let (x, y, width, _) = window_region::window_rect(window_id).ok_or(
+        "Cannot read that window's position (following windows needs Windows, and the window must not be minimized).",
+    )?;
+    let (offset_x, offset_y) = match state.primary_session().and_then(|p| p.geometry()) {
+        Some(g) => (g.x - x, g.y - y),
+        None => (width as i32, 0),
+    };
+    update_settings(&app, |s| {
+        s.anchor = Some(Anchor {
+            window_id,
+            title,
+            offset_x,
+            offset_y,
+        });
+    })
+}
@@ -0,0 +1,111 @@
+use scap::{get_all_targets, has_permission, Target};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// An application (process) owning at least one capturable window.
+#[derive(Debug, Clone, Serialize)]
+pub struct ApplicationDto {
+    pid: u32,
+    name: String,
+    window_count: usize,
+}
+
+/// IDs of the window targets owned by process `pid`.
+pub fn window_ids(targets: &[Target], pid: u32) -> Vec<u32> {
+    targets
+        .iter()
+        .filter_map(|t| match t {
+            Target::Window(w) if window_pid(w.id) == Some(pid) => Some(w.id),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Display name of process `pid`: its executable name, else the title of one of its windows.
+pub fn application_name(targets: &[Target], pid: u32) -> Option<String> {
+    process_name(pid).or_else(|| {
+        targets.iter().find_map(|t| match t {
+            Target::Window(w) if window_pid(w.id) == Some(pid) => Some(w.title.clone()),
+            _ => None,
+        })
+    })
+}
+
+/// Process owning window `id` (Windows only).
+#[cfg(target_os = "windows")]
+pub fn window_pid(id: u32) -> Option<u32> {
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::UI::WindowsAndMessaging::GetWindowThreadProcessId;
+
+    let mut pid = 0u32;
+    unsafe { GetWindowThreadProcessId(HWND(id as isize as _), Some(&mut pid)) };
+    (pid != 0).then_some(pid)
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn window_pid(_id: u32) -> Option<u32> {
+    None
+}
+
+/// Executable name of `pid` without its extension, e.g. "Code".
+#[cfg(target_os = "windows")]
+pub fn process_name(pid: u32) -> Option<String> {
+    use windows::core::PWSTR;
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::System::Threading::{
+        OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32,
+        PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+
+    let mut buf = [0u16; 1024];
+    let mut len = buf.len() as u32;
+    unsafe {
+        let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid).ok()?;
+        let result = QueryFullProcessImageNameW(
+            handle,
+            PROCESS_NAME_WIN32,
+            PWSTR(buf.as_mut_ptr()),
+            &mut len,
+        );
+        let _ = CloseHandle(handle);
+        result.ok()?;
+    }
+    let path = String::from_utf16_lossy(&buf[..len as usize]);
+    std::path::Path::new(&path)
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn process_name(_pid: u32) -> Option<String> {
+    None
+}
+
+/// Applications with visible windows, by name. Empty where window owners cannot be determined
+/// (currently everywhere but Windows).
+#[tauri::command]
+pub fn get_capture_applications() -> Vec<ApplicationDto> {
+    // Listing windows must not trigger the permission prompt; see get_capture_targets.
+    if !has_permission() {
+        return Vec::new();
+    }
+    let mut apps: BTreeMap<u32, ApplicationDto> = BTreeMap::new();
+    for target in get_all_targets() {
+        let Target::Window(w) = target else {
+            continue;
+        };
+        let Some(pid) = window_pid(w.id) else {
+            continue;
+        };
+        apps.entry(pid)
+            .or_insert_with(|| ApplicationDto {
+                pid,
+                name: process_name(pid).unwrap_or_else(|| w.title.clone()),
+                window_count: 0,
+            })
+            .window_count += 1;
+    }
+    let mut apps: Vec<_> = apps.into_values().collect();
+    apps.sort_by_key(|a| a.name.to_lowercase());
+    apps
+}
@@ -0,0 +1,503 @@
+use crate::layout;
+use crate::preview::{PreviewState, SizeScale, WindowGeometry};
+use crate::CaptureState;
+use serde::{Deserialize, Serialize};
+use std::ops::RangeInclusive;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use tauri::menu::CheckMenuItem;
+use tauri::{AppHandle, Manager, Wry};
+
+/// How often the tray's size presets are re-checked against the preview window.
+const SIZE_MENU_REFRESH: Duration = Duration::from_millis(500);
+
+const FALLBACK_ASPECT: f32 = 16.0 / 9.0;
+
+/// Preview sizes set_preview_scale accepts, in percent of the frame size.
+const SCALE_PERCENT: RangeInclusive<u32> = 10..=400;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArrangeMode {
+    Grid,
+    Horizontal,
+    Vertical,
+}
+
+impl ArrangeMode {
+    fn parse(s: &str) -> Result<Self, String> {
+        match s.to_lowercase().as_str() {
+            "grid" => Ok(Self::Grid),
+            "horizontal" => Ok(Self::Horizontal),
+            "vertical" => Ok(Self::Vertical),
+            _ => Err(format!("Invalid arrangement: {s}")),
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            Self::Grid => Self::Horizontal,
+            Self::Horizontal => Self::Vertical,
+            Self::Vertical => Self::Grid,
+        }
+    }
+}
+
+/// Largest (width, height) with the given aspect ratio that fits in max_w x max_h.
+fn fit_aspect(max_w: u32, max_h: u32, aspect: f32) -> (u32, u32) {
+    let aspect = if aspect.is_finite() && aspect > 0.0 {
+        aspect
+    } else {
+        FALLBACK_ASPECT
+    };
+    let w_for_full_h = (max_h as f32 * aspect) as u32;
+    if w_for_full_h <= max_w {
+        (w_for_full_h.max(1), max_h.max(1))
+    } else {
+        (max_w.max(1), ((max_w as f32 / aspect) as u32).max(1))
+    }
+}
+
+/// Non-overlapping window geometries (outer position, inner size) inside `area`, one per
+/// window given as its source's aspect ratio (width/height) and its decoration size (outer
+/// minus inner size). Each window, decorations included, is letterboxed and centered within
+/// its cell.
+fn compute_arrangement(
+    mode: ArrangeMode,
+    area: WindowGeometry,
+    windows: &[(f32, (u32, u32))],
+) -> Vec<WindowGeometry> {
+    let n = windows.len() as u32;
+    if n == 0 {
+        return Vec::new();
+    }
+    let (cols, rows) = match mode {
+        ArrangeMode::Grid => {
+            let cols = (n as f32).sqrt().ceil() as u32;
+            (cols, n.div_ceil(cols))
+        }
+        ArrangeMode::Horizontal => (n, 1),
+        ArrangeMode::Vertical => (1, n),
+    };
+    let cell_w = (area.width / cols).max(1);
+    let cell_h = (area.height / rows).max(1);
+    windows
+        .iter()
+        .enumerate()
+        .map(|(i, &(aspect, (dw, dh)))| {
+            let col = i as u32 % cols;
+            let row = i as u32 / cols;
+            let (w, h) = fit_aspect(cell_w.saturating_sub(dw), cell_h.saturating_sub(dh), aspect);
+            let (outer_w, outer_h) = (w + dw, h + dh);
+            WindowGeometry {
+                x: area.x + (col * cell_w + cell_w.saturating_sub(outer_w) / 2) as i32,
+                y: area.y + (row * cell_h + cell_h.saturating_sub(outer_h) / 2) as i32,
+                width: w,
+                height: h,
+            }
+        })
+        .collect()
+}
+
+fn contains_point(area: &WindowGeometry, x: i32, y: i32) -> bool {
+    x >= area.x && y >= area.y && x < area.x + area.width as i32 && y < area.y + area.height as i32
+}
+
+/// Work area of the chosen monitor: by index, else the one holding `near`, else the primary.
+fn monitor_work_area(
+    app: &AppHandle,
+    index: Option<usize>,
+    near: Option<WindowGeometry>,
+) -> Result<WindowGeometry, String> {
+    let monitors = app.available_monitors().map_err(|e| e.to_string())?;
+    let to_area = |m: &tauri::Monitor| {
+        let wa = m.work_area();
+        WindowGeometry {
+            x: wa.position.x,
+            y: wa.position.y,
+            width: wa.size.width,
+            height: wa.size.height,
+        }
+    };
+    if let Some(i) = index {
+        return monitors
+            .get(i)
+            .map(to_area)
+            .ok_or_else(|| format!("No monitor with index {i}."));
+    }
+    if let Some(g) = near {
+        let (cx, cy) = (g.x + g.width as i32 / 2, g.y + g.height as i32 / 2);
+        if let Some(area) = monitors
+            .iter()
+            .map(to_area)
+            .find(|a| contains_point(a, cx, cy))
+        {
+            return Ok(area);
+        }
+    }
+    if let Ok(Some(primary)) = app.primary_monitor() {
+        return Ok(to_area(&primary));
+    }
+    monitors
+        .first()
+        .map(to_area)
+        .ok_or_else(|| "No monitor available.".to_string())
+}
+
+/// A window showing `frame` at `percent` of its size in physical pixels, at `current`'s
+/// position; shrunk with the frame's aspect ratio to fit `area` (a monitor's work area) and
+/// moved into it, so a 100% preview of a 4K source stays on screen.
+pub fn scaled_geometry(
+    frame: (u32, u32),
+    percent: u32,
+    current: WindowGeometry,
+    area: WindowGeometry,
+) -> WindowGeometry {
+    let (fw, fh) = frame;
+    let mut width = (fw as u64 * percent as u64 / 100).max(1) as u32;
+    let mut height = (fh as u64 * percent as u64 / 100).max(1) as u32;
+    if width > area.width || height > area.height {
+        (width, height) = fit_aspect(area.width, area.height, fw as f32 / fh.max(1) as f32);
+    }
+    let max_x = area.x + area.width.saturating_sub(width) as i32;
+    let max_y = area.y + area.height.saturating_sub(height) as i32;
+    WindowGeometry {
+        x: current.x.clamp(area.x, max_x),
+        y: current.y.clamp(area.y, max_y),
+        width,
+        height,
+    }
+}
+
+/// Moves/resizes all preview windows into `mode` on the given monitor and remembers the mode
+/// (also in the active layout, if one was loaded).
+pub fn arrange(app: &AppHandle, mode: ArrangeMode, monitor: Option<usize>) -> Result<(), String> {
+    let state = app.state::<CaptureState>();
+    let previews = state.preview_sessions();
+    if previews.is_empty() {
+        return Err("No preview window to arrange.".to_string());
+    }
+    let near = previews.iter().find_map(|p| p.geometry());
+    let area = monitor_work_area(app, monitor, near)?;
+    let windows: Vec<(f32, (u32, u32))> = previews
+        .iter()
+        .map(|p| {
+            let aspect = p
+                .display_size()
+                .or_else(|| p.geometry().map(|g| (g.width, g.height)))
+                .map(|(w, h)| w as f32 / h.max(1) as f32)
+                .unwrap_or(FALLBACK_ASPECT);
+            (aspect, p.decoration_size())
+        })
+        .collect();
+    for (preview, rect) in previews
+        .iter()
+        .zip(compute_arrangement(mode, area, &windows))
+    {
+        preview.request_geometry(rect);
+        preview.set_size_scale(None);
+    }
+    *state.last_arrangement.lock().unwrap() = Some(mode);
+    let active_layout = state.active_layout.lock().unwrap().clone();
+    if let Some(name) = active_layout {
+        layout::remember_arrangement(app, &name, mode)?;
+    }
+    Ok(())
+}
+
+/// Applies the next arrangement after the last one used (grid first).
+pub fn cycle(app: &AppHandle) -> Result<(), String> {
+    let last = *app.state::<CaptureState>().last_arrangement.lock().unwrap();
+    let mode = last.map(ArrangeMode::next).unwrap_or(ArrangeMode::Grid);
+    arrange(app, mode, None)
+}
+
+#[tauri::command]
+pub fn arrange_previews(
+    app: AppHandle,
+    mode: String,
+    monitor: Option<usize>,
+) -> Result<(), String> {
+    arrange(&app, ArrangeMode::parse(&mode)?, monitor)
+}
+
+/// Preview window sizes relative to the frame, in physical pixels (100% = one window pixel per
+/// frame pixel, regardless of DPI scaling).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SizePreset {
+    Quarter,
+    Half,
+    Full,
+    /// Largest size with the frame's aspect ratio that fits the monitor's work area.
+    FitScreen,
+}
+
+impl SizePreset {
+    pub const ALL: [SizePreset; 4] = [Self::Quarter, Self::Half, Self::Full, Self::FitScreen];
+
+    pub fn id(self) -> &'static str {
+        match self {
+            Self::Quarter => "size_25",
+            Self::Half => "size_50",
+            Self::Full => "size_100",
+            Self::FitScreen => "size_fit",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Quarter => "25%",
+            Self::Half => "50%",
+            Self::Full => "100%",
+            Self::FitScreen => "Fit to screen",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|p| p.id() == id)
+    }
+
+    /// Size relative to the frame; None for Fit to screen.
+    fn percent(self) -> Option<u32> {
+        match self {
+            Self::Quarter => Some(25),
+            Self::Half => Some(50),
+            Self::Full => Some(100),
+            Self::FitScreen => None,
+        }
+    }
+
+    /// Geometry this preset gives `preview`; None while its window or frame size is unknown.
+    fn geometry_for(self, app: &AppHandle, preview: &PreviewState) -> Option<WindowGeometry> {
+        let current = preview.geometry()?;
+        let (fw, fh) = preview.display_size()?;
+        let area = monitor_work_area(app, None, Some(current)).ok()?;
+        match self.percent() {
+            Some(percent) => Some(scaled_geometry((fw, fh), percent, current, area)),
+            None => {
+                let (w, h) = fit_aspect(area.width, area.height, fw as f32 / fh.max(1) as f32);
+                Some(WindowGeometry {
+                    x: area.x + ((area.width - w) / 2) as i32,
+                    y: area.y + ((area.height - h) / 2) as i32,
+                    width: w,
+                    height: h,
+                })
+            }
+        }
+    }
+}
+
+/// Resizes every preview window to `preset`.
+pub fn apply_size(app: &AppHandle, preset: SizePreset) -> Result<(), String> {
+    if let Some(percent) = preset.percent() {
+        return apply_scale(app, percent);
+    }
+    let previews = app.state::<CaptureState>().preview_sessions();
+    let mut resized = false;
+    for preview in &previews {
+        if let Some(g) = preset.geometry_for(app, preview) {
+            preview.request_geometry(g);
+            preview.set_size_scale(None);
+            resized = true;
+        }
+    }
+    if resized {
+        Ok(())
+    } else {
+        Err("No preview window to resize.".to_string())
+    }
+}
+
+/// Resizes every preview window to `percent` of its frame size and keeps it there when the
+/// frame size changes.
+pub fn apply_scale(app: &AppHandle, percent: u32) -> Result<(), String> {
+    if !SCALE_PERCENT.contains(&percent) {
+        return Err(format!(
+            "Preview scale must be {}-{}%.",
+            SCALE_PERCENT.start(),
+            SCALE_PERCENT.end()
+        ));
+    }
+    let previews = app.state::<CaptureState>().preview_sessions();
+    let mut resized = false;
+    for preview in &previews {
+        let (Some(current), Some(frame)) = (preview.geometry(), preview.display_size()) else {
+            continue;
+        };
+        let area = monitor_work_area(app, None, Some(current))?;
+        preview.request_geometry(scaled_geometry(frame, percent, current, area));
+        preview.set_size_scale(Some(SizeScale { percent, area }));
+        resized = true;
+    }
+    if resized {
+        Ok(())
+    } else {
+        Err("No preview window to resize.".to_string())
+    }
+}
+
+/// Sets every preview window to `percent` of the frame size (100 = pixel-perfect).
+#[tauri::command]
+pub fn set_preview_scale(app: AppHandle, percent: u32) -> Result<(), String> {
+    apply_scale(&app, percent)
+}
+
+/// Preset the first preview window currently matches exactly (size only, except for Fit to
+/// screen, which also includes the centered position).
+fn current_size_preset(app: &AppHandle, preview: &PreviewState) -> Option<SizePreset> {
+    let current = preview.geometry()?;
+    SizePreset::ALL.into_iter().find(|p| {
+        p.geometry_for(app, preview).is_some_and(|g| match p {
+            SizePreset::FitScreen => g == current,
+            _ => (g.width, g.height) == (current.width, current.height),
+        })
+    })
+}
+
+/// The tray's "Preview size" items: disabled without a preview window, checked when the window
+/// matches a preset. Managed as app state; refreshed by `spawn_size_menu_watcher`.
+pub struct SizeMenu {
+    items: Vec<(SizePreset, CheckMenuItem<Wry>)>,
+    /// (enabled, checked preset) last shown; None forces the next refresh to rewrite the items.
+    shown: Mutex<Option<(bool, Option<SizePreset>)>>,
+}
+
+impl SizeMenu {
+    pub fn new(items: Vec<(SizePreset, CheckMenuItem<Wry>)>) -> Self {
+        Self {
+            items,
+            shown: Mutex::new(None),
+        }
+    }
+
+    /// Forces the next refresh, e.g. after a click toggled an item's checkmark on its own.
+    pub fn invalidate(&self) {
+        *self.shown.lock().unwrap() = None;
+    }
+
+    fn refresh(&self, app: &AppHandle) {
+        let preview = app.state::<CaptureState>().primary_session();
+        let enabled = preview.as_ref().is_some_and(|p| p.geometry().is_some());
+        let current = preview.and_then(|p| current_size_preset(app, &p));
+        let mut shown = self.shown.lock().unwrap();
+        if *shown == Some((enabled, current)) {
+            return;
+        }
+        for (preset, item) in &self.items {
+            let _ = item.set_enabled(enabled);
+            let _ = item.set_checked(current == Some(*preset));
+        }
+        *shown = Some((enabled, current));
+    }
+}
+
+/// Periodically refreshes the managed `SizeMenu`.
+pub fn spawn_size_menu_watcher(app: AppHandle) {
+    thread::spawn(move || loop {
+        app.state::<SizeMenu>().refresh(&app);
+        thread::sleep(SIZE_MENU_REFRESH);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AREA: WindowGeometry = WindowGeometry {
+        x: 100,
+        y: 50,
+        width: 1920,
+        height: 1080,
+    };
+
+    /// `window` with its decorations, as the rectangle it covers on screen.
+    fn outer(window: WindowGeometry, (dw, dh): (u32, u32)) -> (i32, i32, i32, i32) {
+        let (x, y) = (window.x, window.y);
+        (
+            x,
+            y,
+            x + (window.width + dw) as i32,
+            y + (window.height + dh) as i32,
+        )
+    }
+
+    fn overlaps(a: (i32, i32, i32, i32), b: (i32, i32, i32, i32)) -> bool {
+        a.0 < b.2 && b.0 < a.2 && a.1 < b.3 && b.1 < a.3
+    }
+
+    #[test]
+    fn fit_aspect_fills_the_limiting_side() {
+        assert_eq!(fit_aspect(1920, 1080, 16.0 / 9.0), (1920, 1080));
+        // Wider than the box: full width, letterboxed.
+        assert_eq!(fit_aspect(1000, 1000, 2.0), (1000, 500));
+        // Taller than the box: full height, pillarboxed.
+        assert_eq!(fit_aspect(1000, 1000, 0.5), (500, 1000));
+    }
+
+    #[test]
+    fn fit_aspect_falls_back_on_bad_ratios_and_never_returns_zero() {
+        for aspect in [0.0, -1.0, f32::NAN, f32::INFINITY] {
+            assert_eq!(fit_aspect(1600, 1600, aspect), (1600, 900));
+        }
+        assert_eq!(fit_aspect(0, 0, 1.0), (1, 1));
+        assert_eq!(fit_aspect(10, 1000, 1000.0), (10, 1));
+    }
+
+    #[test]
+    fn grid_cells_center_each_window() {
+        let windows = [(16.0 / 9.0, (0, 0)); 4];
+        let cells = compute_arrangement(ArrangeMode::Grid, AREA, &windows);
+        let size = (960, 540);
+        let at = |x, y| WindowGeometry {
+            x,
+            y,
+            width: size.0,
+            height: size.1,
+        };
+        assert_eq!(
+            cells,
+            [at(100, 50), at(1060, 50), at(100, 590), at(1060, 590)]
+        );
+        // Three windows still take a 2x2 grid, leaving the last cell empty.
+        let cells = compute_arrangement(ArrangeMode::Grid, AREA, &windows[..3]);
+        assert_eq!(cells[2], at(100, 590));
+    }
+
+    #[test]
+    fn rows_and_columns_letterbox_within_their_cells() {
+        let windows = [(16.0 / 9.0, (0, 0)); 2];
+        let rows = compute_arrangement(ArrangeMode::Horizontal, AREA, &windows);
+        assert_eq!((rows[1].x, rows[1].y), (1060, 320));
+        assert_eq!((rows[1].width, rows[1].height), (960, 540));
+        let columns = compute_arrangement(ArrangeMode::Vertical, AREA, &windows);
+        assert_eq!((columns[1].x, columns[1].y), (580, 590));
+        assert_eq!((columns[1].width, columns[1].height), (960, 540));
+        assert!(compute_arrangement(ArrangeMode::Grid, AREA, &[]).is_empty());
+    }
+
+    #[test]
+    fn decorated_windows_fit_their_cells_without_overlapping() {
+        let decoration = (16, 39);
+        for mode in [
+            ArrangeMode::Grid,
+            ArrangeMode::Horizontal,
+            ArrangeMode::Vertical,
+        ] {
+            let windows = [(16.0 / 9.0, decoration); 4];
+            let cells = compute_arrangement(mode, AREA, &windows);
+            let rects: Vec<_> = cells.iter().map(|&g| outer(g, decoration)).collect();
+            for (i, a) in rects.iter().enumerate() {
+                assert!(a.0 >= AREA.x && a.1 >= AREA.y, "{mode:?}: {a:?}");
+                assert!(a.2 <= AREA.x + AREA.width as i32, "{mode:?}: {a:?}");
+                assert!(a.3 <= AREA.y + AREA.height as i32, "{mode:?}: {a:?}");
+                for b in &rects[i + 1..] {
+                    assert!(!overlaps(*a, *b), "{mode:?}: {a:?} and {b:?}");
+                }
+            }
+        }
+        // The client area keeps the source's aspect ratio.
+        let cells = compute_arrangement(ArrangeMode::Grid, AREA, &[(16.0 / 9.0, decoration)]);
+        assert_eq!((cells[0].width, cells[0].height), (1850, 1041));
+    }
+}
@@ -0,0 +1,38 @@
+use std::sync::Arc;
+
+/// Published buffers tracked for reuse. The preview slot, the delay line, a recording and the
+/// latest-frame copy each hold a few at most; buffers beyond this (e.g. frames kept by the
+/// replay buffer) are let go and freed by their last holder.
+const MAX_SHARED: usize = 8;
+
+/// Output buffers of one capture thread, recycled so steady-state capture allocates none per
+/// frame. A published buffer comes back once every consumer (preview windows, recording, the
+/// replay buffer) has dropped it.
+#[derive(Default)]
+pub struct BufferPool {
+    /// Buffers handed out with `publish`, oldest first.
+    shared: Vec<Arc<Vec<u32>>>,
+}
+
+impl BufferPool {
+    /// An empty buffer, with the capacity of an earlier frame where one is free again.
+    pub fn take(&mut self) -> Vec<u32> {
+        // Only the pool holds it, so nothing can clone it in the meantime.
+        let Some(i) = self.shared.iter().position(|b| Arc::strong_count(b) == 1) else {
+            return Vec::new();
+        };
+        let mut buffer = Arc::try_unwrap(self.shared.remove(i)).unwrap_or_default();
+        buffer.clear();
+        buffer
+    }
+
+    /// Shares `buffer` with the frame's consumers and keeps track of it to reuse later.
+    pub fn publish(&mut self, buffer: Vec<u32>) -> Arc<Vec<u32>> {
+        let buffer = Arc::new(buffer);
+        if self.shared.len() == MAX_SHARED {
+            self.shared.remove(0);
+        }
+        self.shared.push(buffer.clone());
+        buffer
+    }
+}
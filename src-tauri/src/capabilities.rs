@@ -0,0 +1,51 @@
+use serde::Serialize;
+
+/// Settings that only take effect on Windows, where LiteView can read window geometry,
+/// owners and layered-window attributes.
+const WINDOWS_ONLY_SETTINGS: &[&str] = &[
+    "target_pid",
+    "follow_focus",
+    "include_child_windows",
+    "trim_window_chrome",
+    "mask_window_corners",
+    "overlay_opacity",
+    "window_opacity",
+    "anchor",
+];
+
+/// Options of the screencast portal request (Wayland) that the capture backend (scap 0.0.8)
+/// does not let LiteView set. It asks for one source without persistence, and maps
+/// `show_cursor` to the embedded (on) or hidden (off) cursor mode.
+const UNAVAILABLE_PORTAL_OPTIONS: &[&str] =
+    &["cursor_mode_metadata", "multiple_sources", "persist_mode"];
+
+/// What the current platform's capture backend supports, so the UI can flag settings that
+/// would be ignored.
+#[derive(Debug, Clone, Serialize)]
+pub struct CaptureCapabilities {
+    /// "windows", "macos" or "linux".
+    platform: &'static str,
+    /// CaptureSettings keys that have no effect on this platform.
+    unsupported_settings: Vec<&'static str>,
+    /// Portal options that cannot be requested; empty off Linux.
+    unavailable_portal_options: Vec<&'static str>,
+}
+
+#[tauri::command]
+pub fn get_capture_capabilities() -> CaptureCapabilities {
+    let unsupported_settings = if cfg!(target_os = "windows") {
+        Vec::new()
+    } else {
+        WINDOWS_ONLY_SETTINGS.to_vec()
+    };
+    let unavailable_portal_options = if cfg!(target_os = "linux") {
+        UNAVAILABLE_PORTAL_OPTIONS.to_vec()
+    } else {
+        Vec::new()
+    };
+    CaptureCapabilities {
+        platform: std::env::consts::OS,
+        unsupported_settings,
+        unavailable_portal_options,
+    }
+}
@@ -0,0 +1,28 @@
+use crate::{current_frame, CaptureState};
+use tauri::image::Image;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+/// Puts the latest frame of the primary session on the clipboard as an image. The clipboard
+/// plugin converts it to what the platform expects (DIB on Windows, image/png elsewhere).
+pub fn copy_frame(app: &AppHandle) -> Result<(), String> {
+    let preview_state = app
+        .state::<CaptureState>()
+        .primary_session()
+        .ok_or("No capture is running.")?;
+    let (width, height, buffer) = preview_state
+        .latest_frame()
+        .ok_or("The capture has not delivered a frame yet.")?;
+    let rgba = current_frame::to_rgba(&buffer);
+    app.clipboard()
+        .write_image(&Image::new(&rgba, width, height))
+        .map_err(|e| format!("Copying the frame failed: {e}"))
+}
+
+/// Copies the current frame to the system clipboard. Fails when no capture is running.
+#[tauri::command]
+pub async fn copy_frame_to_clipboard(app: AppHandle) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || copy_frame(&app))
+        .await
+        .map_err(|e| e.to_string())?
+}
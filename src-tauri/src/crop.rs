@@ -0,0 +1,203 @@
+use crate::{update_settings, CaptureState};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+/// How a remembered crop adapts when the source size differs from when it was saved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CropMode {
+    /// Same pixel rectangle (clamped to the frame).
+    #[default]
+    Absolute,
+    /// Same fractions of the frame, so the crop scales with the source.
+    Relative,
+}
+
+/// A crop in source pixels together with the source size it was made for; the pair gives both
+/// the absolute and the relative form.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CropRegion {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub source_width: u32,
+    pub source_height: u32,
+}
+
+impl CropRegion {
+    pub fn is_valid(&self) -> bool {
+        self.width > 0 && self.height > 0 && self.source_width > 0 && self.source_height > 0
+    }
+}
+
+/// A rectangle in source pixels, as sent by the frontend.
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct CropArea {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Crop handed to the capture thread.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AppliedCrop {
+    pub region: CropRegion,
+    pub mode: CropMode,
+}
+
+impl AppliedCrop {
+    /// Rectangle (x, y, width, height) for a frame of the given size, clamped to it.
+    /// None when nothing of the crop is left or it covers the whole frame.
+    pub fn resolve(&self, frame_w: u32, frame_h: u32) -> Option<(u32, u32, u32, u32)> {
+        let r = &self.region;
+        let (x, y, w, h) = match self.mode {
+            CropMode::Absolute => (r.x, r.y, r.width, r.height),
+            CropMode::Relative => {
+                let sx = frame_w as f64 / r.source_width.max(1) as f64;
+                let sy = frame_h as f64 / r.source_height.max(1) as f64;
+                (
+                    (r.x as f64 * sx).round() as u32,
+                    (r.y as f64 * sy).round() as u32,
+                    (r.width as f64 * sx).round() as u32,
+                    (r.height as f64 * sy).round() as u32,
+                )
+            }
+        };
+        if x >= frame_w || y >= frame_h {
+            return None;
+        }
+        let w = w.min(frame_w - x);
+        let h = h.min(frame_h - y);
+        if w == 0 || h == 0 || (w, h) == (frame_w, frame_h) {
+            return None;
+        }
+        Some((x, y, w, h))
+    }
+}
+
+/// Copies the rectangle out of a packed frame of width `src_w`.
+pub fn crop_buffer(src: &[u32], src_w: u32, rect: (u32, u32, u32, u32)) -> Vec<u32> {
+    let mut out = Vec::new();
+    crop_into(src, src_w, rect, &mut out);
+    out
+}
+
+/// `crop_buffer` into `out`, replacing its contents and reusing its allocation.
+pub fn crop_into(src: &[u32], src_w: u32, rect: (u32, u32, u32, u32), out: &mut Vec<u32>) {
+    let (x, y, w, h) = (
+        rect.0 as usize,
+        rect.1 as usize,
+        rect.2 as usize,
+        rect.3 as usize,
+    );
+    let src_w = src_w as usize;
+    out.clear();
+    out.reserve(w * h);
+    for row in src.chunks_exact(src_w).skip(y).take(h) {
+        out.extend_from_slice(&row[x..x + w]);
+    }
+}
+
+/// Matches `area` to the size of the running capture, clamping it to the source.
+fn region_for_running_source(app: &AppHandle, area: CropArea) -> Result<CropRegion, String> {
+    let CropArea {
+        x,
+        y,
+        width,
+        height,
+    } = area;
+    let (source_width, source_height) = app
+        .state::<CaptureState>()
+        .primary_session()
+        .and_then(|p| p.source_size())
+        .ok_or("Start capturing the target first so the crop can be matched to its size.")?;
+    if width == 0 || height == 0 || x >= source_width || y >= source_height {
+        return Err(format!(
+            "Crop lies outside the {source_width}x{source_height} source."
+        ));
+    }
+    Ok(CropRegion {
+        x,
+        y,
+        width: width.min(source_width - x),
+        height: height.min(source_height - y),
+        source_width,
+        source_height,
+    })
+}
+
+/// Remembers a crop (in current source pixels) for the selected target and applies it.
+/// Needs a running capture so the source size is known.
+#[tauri::command]
+pub fn set_crop_for_target(
+    app: AppHandle,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+) -> Result<(), String> {
+    let title = app
+        .state::<CaptureState>()
+        .settings
+        .lock()
+        .unwrap()
+        .target_title
+        .clone()
+        .ok_or("Select a capture target first.")?;
+    let region = region_for_running_source(
+        &app,
+        CropArea {
+            x,
+            y,
+            width,
+            height,
+        },
+    )?;
+    update_settings(&app, |s| {
+        s.target_crops.insert(title, region);
+    })
+}
+
+/// Sets (or with None clears) the crop used for targets without their own, such as the
+/// default target. It applies to the running capture right away; like a target crop, it
+/// is matched to the running capture's size.
+#[tauri::command]
+pub fn set_crop_area(app: AppHandle, area: Option<CropArea>) -> Result<(), String> {
+    let region = area
+        .map(|area| region_for_running_source(&app, area))
+        .transpose()?;
+    update_settings(&app, |s| s.crop = region)
+}
+
+/// Forgets the crop of `target` (a target title; default: the selected target).
+#[tauri::command]
+pub fn clear_crop_for_target(app: AppHandle, target: Option<String>) -> Result<(), String> {
+    let title = match target {
+        Some(t) => t,
+        None => app
+            .state::<CaptureState>()
+            .settings
+            .lock()
+            .unwrap()
+            .target_title
+            .clone()
+            .ok_or("Select a capture target first.")?,
+    };
+    update_settings(&app, |s| {
+        s.target_crops.remove(&title);
+    })
+}
+
+/// Remembers the black-bar crop auto-crop found for the selected target, which then stays
+/// fixed regardless of the content.
+#[tauri::command]
+pub fn lock_detected_crop(app: AppHandle) -> Result<(), String> {
+    let (x, y, width, height) = app
+        .state::<CaptureState>()
+        .primary_session()
+        .and_then(|p| p.detected_crop())
+        .ok_or("No black bars detected in the running capture.")?;
+    set_crop_for_target(app, x, y, width, height)
+}
@@ -0,0 +1,145 @@
+use crate::resize;
+use crate::CaptureState;
+use base64::Engine;
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::PngEncoder;
+use image::{ExtendedColorType, ImageEncoder};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+/// Width used when the caller does not pass one.
+const DEFAULT_MAX_WIDTH: u32 = 1280;
+/// Largest data URL returned over IPC; bigger results fail with `TooLarge`.
+const MAX_DATA_URL_BYTES: usize = 8 * 1024 * 1024;
+const JPEG_QUALITY: u8 = 85;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FrameFormat {
+    #[default]
+    Png,
+    Jpeg,
+    /// Unencoded RGBA8, rows top to bottom; the size is in the media type parameters.
+    Raw,
+}
+
+/// Why no frame was returned; serialized as `{ "kind": ..., "message": ... }`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", content = "message", rename_all = "snake_case")]
+pub enum CurrentFrameError {
+    /// No capture is running.
+    NotCapturing,
+    /// A capture is running but has not delivered a frame yet.
+    NoFrame,
+    /// The result would exceed `MAX_DATA_URL_BYTES`; ask for a smaller width or JPEG.
+    TooLarge(String),
+    Encode(String),
+}
+
+/// Size of a frame `width` × `height` scaled down to at most `max_width` wide.
+fn fit_width(width: u32, height: u32, max_width: u32) -> (u32, u32) {
+    if width <= max_width {
+        return (width, height);
+    }
+    let scaled_height = (height as u64 * max_width as u64 / width as u64).max(1);
+    (max_width, scaled_height as u32)
+}
+
+pub fn to_rgb(buffer: &[u32]) -> Vec<u8> {
+    buffer
+        .iter()
+        .flat_map(|&p| [(p >> 16) as u8, (p >> 8) as u8, p as u8])
+        .collect()
+}
+
+pub fn to_rgba(buffer: &[u32]) -> Vec<u8> {
+    buffer
+        .iter()
+        .flat_map(|&p| [(p >> 16) as u8, (p >> 8) as u8, p as u8, 255])
+        .collect()
+}
+
+/// Length of the base64 encoding of `len` bytes.
+fn base64_len(len: usize) -> usize {
+    len.div_ceil(3) * 4
+}
+
+fn encode(
+    format: FrameFormat,
+    width: u32,
+    height: u32,
+    buffer: &[u32],
+) -> Result<(String, Vec<u8>), CurrentFrameError> {
+    let encode_error = |e: image::ImageError| CurrentFrameError::Encode(e.to_string());
+    let mut out = Vec::new();
+    let mime = match format {
+        FrameFormat::Png => {
+            PngEncoder::new(&mut out)
+                .write_image(&to_rgb(buffer), width, height, ExtendedColorType::Rgb8)
+                .map_err(encode_error)?;
+            "image/png".to_string()
+        }
+        FrameFormat::Jpeg => {
+            JpegEncoder::new_with_quality(&mut out, JPEG_QUALITY)
+                .write_image(&to_rgb(buffer), width, height, ExtendedColorType::Rgb8)
+                .map_err(encode_error)?;
+            "image/jpeg".to_string()
+        }
+        FrameFormat::Raw => {
+            out = to_rgba(buffer);
+            format!("application/x-liteview-rgba;width={width};height={height}")
+        }
+    };
+    Ok((mime, out))
+}
+
+/// Snapshots the frame most recently handed to the preview, scales it to at most `max_width`
+/// (default 1280) and returns it as a data URL. The frame is shared, not copied, under the
+/// lock, and the work runs off the main thread, so a few calls per second are fine.
+#[tauri::command]
+pub async fn get_current_frame(
+    app: AppHandle,
+    max_width: Option<u32>,
+    format: Option<FrameFormat>,
+) -> Result<String, CurrentFrameError> {
+    let state = app.state::<CaptureState>();
+    let preview_state = state
+        .primary_session()
+        .ok_or(CurrentFrameError::NotCapturing)?;
+    let choice = state.settings.lock().unwrap().resize_algorithm;
+    let (width, height, buffer) = preview_state
+        .latest_frame()
+        .ok_or(CurrentFrameError::NoFrame)?;
+    let format = format.unwrap_or_default();
+    let max_width = max_width.unwrap_or(DEFAULT_MAX_WIDTH).max(1);
+    let (out_width, out_height) = fit_width(width, height, max_width);
+    // Raw output has a known size; refuse it before converting anything.
+    let raw_len = base64_len(out_width as usize * out_height as usize * 4);
+    if format == FrameFormat::Raw && raw_len > MAX_DATA_URL_BYTES {
+        return Err(CurrentFrameError::TooLarge(format!(
+            "{out_width}×{out_height} raw is {raw_len} bytes, limit {MAX_DATA_URL_BYTES}."
+        )));
+    }
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let scaled;
+        let pixels: &[u32] = if (out_width, out_height) == (width, height) {
+            &buffer
+        } else {
+            let algorithm = choice.resolve(width, height, out_width, out_height);
+            scaled = resize::resize_frame(algorithm, width, height, &buffer, out_width, out_height);
+            &scaled
+        };
+        let (mime, bytes) = encode(format, out_width, out_height, pixels)?;
+        let len = base64_len(bytes.len());
+        if len > MAX_DATA_URL_BYTES {
+            return Err(CurrentFrameError::TooLarge(format!(
+                "{out_width}×{out_height} encodes to {len} bytes, limit {MAX_DATA_URL_BYTES}."
+            )));
+        }
+        let data = base64::engine::general_purpose::STANDARD.encode(bytes);
+        Ok(format!("data:{mime};base64,{data}"))
+    })
+    .await
+    .map_err(|e| CurrentFrameError::Encode(e.to_string()))?
+}
@@ -0,0 +1,203 @@
+use crate::preview::{FrameData, PreviewState};
+use std::collections::VecDeque;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub const MAX_DELAY_SECS: u32 = 60;
+/// Bytes of frames the queue may hold; longer delays of large frames keep fewer frames per
+/// second.
+const MAX_QUEUE_BYTES: usize = 1024 * 1024 * 1024;
+/// Longest wait between two checks of the delay setting while nothing is queued.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Holds frames for the preview until they are `PreviewState::delay` old. Only the preview is
+/// delayed; screenshots, recordings and the replay buffer stay live.
+pub struct DelayLine {
+    frames: Sender<FrameData>,
+}
+
+impl DelayLine {
+    /// Starts the thread that releases frames to `state`; it ends when the line is dropped.
+    pub fn spawn(state: Arc<PreviewState>) -> Self {
+        let (frames, receiver) = mpsc::channel();
+        thread::spawn(move || run(state, receiver));
+        Self { frames }
+    }
+
+    pub fn push(&self, frame: FrameData) {
+        let _ = self.frames.send(frame);
+    }
+}
+
+fn frame_bytes(frame: &FrameData) -> usize {
+    frame.bytes.as_bytes().len()
+}
+
+/// Shortest gap between two queued frames of `size` bytes, so that `delay` of them fits into
+/// `MAX_QUEUE_BYTES`.
+fn min_interval(delay: Duration, size: usize) -> Duration {
+    let capacity = (MAX_QUEUE_BYTES / size.max(1)).max(1) as u32;
+    delay / capacity
+}
+
+fn run(state: Arc<PreviewState>, receiver: Receiver<FrameData>) {
+    run_with(
+        receiver,
+        || state.delay(),
+        |frame| state.publish_frame(frame),
+    );
+}
+
+/// `run` with the delay and the preview supplied, so tests can use short delays and see when
+/// frames are released.
+fn run_with(
+    receiver: Receiver<FrameData>,
+    delay: impl Fn() -> Duration,
+    mut publish: impl FnMut(FrameData),
+) {
+    let mut queue: VecDeque<FrameData> = VecDeque::new();
+    let mut bytes = 0;
+    loop {
+        let delay = delay();
+        let now = Instant::now();
+        if delay.is_zero() {
+            // The capture thread publishes directly again.
+            queue.clear();
+            bytes = 0;
+        }
+        // Only the newest due frame is worth showing; a shortened delay skips ahead.
+        let mut due = None;
+        while queue.front().is_some_and(|f| f.captured_at + delay <= now) {
+            let frame = queue.pop_front().unwrap();
+            bytes -= frame_bytes(&frame);
+            due = Some(frame);
+        }
+        if let Some(frame) = due {
+            publish(frame);
+        }
+
+        let wait = queue
+            .front()
+            .map_or(POLL_INTERVAL, |f| {
+                (f.captured_at + delay).saturating_duration_since(now)
+            })
+            .min(POLL_INTERVAL);
+        let frame = match receiver.recv_timeout(wait) {
+            Ok(frame) => frame,
+            Err(RecvTimeoutError::Timeout) => continue,
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+        // Spread what fits into memory evenly over the delay by dropping frames that follow
+        // the previous one too closely.
+        let size = frame_bytes(&frame).max(1);
+        let since_last = queue
+            .back()
+            .map(|last| frame.captured_at.saturating_duration_since(last.captured_at));
+        if since_last.is_some_and(|gap| gap < min_interval(delay, size)) {
+            continue;
+        }
+        bytes += size;
+        queue.push_back(frame);
+        // After a resize the older frames may still be larger.
+        while bytes > MAX_QUEUE_BYTES && queue.len() > 1 {
+            let dropped = queue.pop_front().unwrap();
+            bytes -= frame_bytes(&dropped);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::preview::{FrameBytes, PixelFormat};
+
+    /// A frame of `size` bytes captured at `captured_at`; zeroed, so a large one costs no memory
+    /// until it is read.
+    fn frame(size: usize, captured_at: Instant) -> FrameData {
+        FrameData {
+            width: 1,
+            height: 1,
+            display_size: (1, 1),
+            stride: 4,
+            format: PixelFormat::Bgrx,
+            bytes: FrameBytes::Raw(Arc::new(vec![0; size])),
+            captured_at,
+            received_at: captured_at,
+            corner_radius: 0.0,
+        }
+    }
+
+    /// Runs a delay line of `delay`; its frames come out of the receiver with the time each
+    /// was published.
+    fn spawn(delay: Duration) -> (Sender<FrameData>, Receiver<(FrameData, Instant)>) {
+        let (frames, receiver) = mpsc::channel();
+        let (published, released) = mpsc::channel();
+        thread::spawn(move || {
+            run_with(
+                receiver,
+                move || delay,
+                |frame| {
+                    let _ = published.send((frame, Instant::now()));
+                },
+            )
+        });
+        (frames, released)
+    }
+
+    #[test]
+    fn min_interval_spreads_the_queue_over_the_delay() {
+        let delay = Duration::from_secs(60);
+        // Small frames are all kept.
+        assert!(min_interval(delay, 1920 * 1080 * 4) < Duration::from_millis(500));
+        assert_eq!(
+            min_interval(delay, MAX_QUEUE_BYTES / 10),
+            Duration::from_secs(6)
+        );
+        // A frame larger than the whole queue still gets one slot per delay.
+        assert_eq!(min_interval(delay, MAX_QUEUE_BYTES * 2), delay);
+        assert_eq!(
+            min_interval(Duration::ZERO, MAX_QUEUE_BYTES),
+            Duration::ZERO
+        );
+        assert_eq!(min_interval(delay, 0), delay / MAX_QUEUE_BYTES as u32);
+    }
+
+    #[test]
+    fn frames_are_released_once_they_are_delay_old() {
+        let delay = Duration::from_millis(200);
+        let (frames, released) = spawn(delay);
+        let first = Instant::now();
+        frames.send(frame(16, first)).unwrap();
+        thread::sleep(Duration::from_millis(100));
+        let second = Instant::now();
+        frames.send(frame(16, second)).unwrap();
+        for captured_at in [first, second] {
+            let (frame, at) = released.recv_timeout(Duration::from_secs(5)).unwrap();
+            assert_eq!(frame.captured_at, captured_at);
+            assert!(at >= captured_at + delay, "released early");
+            assert!(at < captured_at + delay + POLL_INTERVAL, "released late");
+        }
+    }
+
+    #[test]
+    fn frames_following_too_closely_are_dropped() {
+        // Ten of these fill the queue, so a one-second delay keeps one per 100 ms.
+        let size = MAX_QUEUE_BYTES / 10;
+        let delay = Duration::from_secs(1);
+        let (frames, released) = spawn(delay);
+        let start = Instant::now();
+        let gap = Duration::from_millis(60);
+        for i in 0..8 {
+            frames.send(frame(size, start + gap * i)).unwrap();
+        }
+        // Every other frame is 120 ms after the last one kept.
+        for i in [0, 2, 4, 6] {
+            let (frame, _) = released.recv_timeout(Duration::from_secs(5)).unwrap();
+            assert_eq!(frame.captured_at, start + gap * i);
+        }
+        assert!(released.recv_timeout(Duration::from_millis(500)).is_err());
+        drop(frames);
+    }
+}
@@ -0,0 +1,555 @@
+use crate::overlay::{Crosshair, Grid};
+use crate::pip::Pip;
+use crate::zoom::ZoomView;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+
+/// Color-vision-deficiency simulation (Machado et al. 2009 matrices, linear RGB).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorBlindness {
+    #[default]
+    None,
+    Protanopia,
+    Deuteranopia,
+    Tritanopia,
+}
+
+impl ColorBlindness {
+    pub fn next(self) -> Self {
+        match self {
+            Self::None => Self::Protanopia,
+            Self::Protanopia => Self::Deuteranopia,
+            Self::Deuteranopia => Self::Tritanopia,
+            Self::Tritanopia => Self::None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::None => "None",
+            Self::Protanopia => "Protanopia",
+            Self::Deuteranopia => "Deuteranopia",
+            Self::Tritanopia => "Tritanopia",
+        }
+    }
+
+    /// Full-severity simulation matrix (row-major).
+    fn matrix(self) -> [[f32; 3]; 3] {
+        match self {
+            Self::None => IDENTITY,
+            Self::Protanopia => [
+                [0.152286, 1.052583, -0.204868],
+                [0.114503, 0.786281, 0.099216],
+                [-0.003882, -0.048116, 1.051998],
+            ],
+            Self::Deuteranopia => [
+                [0.367322, 0.860646, -0.227968],
+                [0.280085, 0.672501, 0.047413],
+                [-0.011820, 0.042940, 0.968881],
+            ],
+            Self::Tritanopia => [
+                [1.255528, -0.076749, -0.178779],
+                [-0.078411, 0.930809, 0.147602],
+                [0.004733, 0.691367, 0.303900],
+            ],
+        }
+    }
+}
+
+/// Look of the preview only; screenshots and recordings keep the original colors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorFilter {
+    #[default]
+    None,
+    Grayscale,
+    /// Inverted in sRGB, so midtones stay midtones.
+    Invert,
+}
+
+/// Per-image picture controls, applied in linear RGB before the color-blindness simulation.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PictureAdjustments {
+    /// -1.0..=1.0, added after contrast.
+    pub brightness: f32,
+    /// 0.0..=2.0, around mid-gray.
+    pub contrast: f32,
+    /// 0.1..=5.0; values above 1 brighten midtones.
+    pub gamma: f32,
+    /// Hue rotation in degrees, -180..=180.
+    pub hue: f32,
+    /// 0.0 (grayscale) ..= 2.0.
+    pub saturation: f32,
+    /// Clockwise rotation of the image: 0, 90, 180 or 270 degrees.
+    pub rotation: u32,
+}
+
+impl Default for PictureAdjustments {
+    fn default() -> Self {
+        Self {
+            brightness: 0.0,
+            contrast: 1.0,
+            gamma: 1.0,
+            hue: 0.0,
+            saturation: 1.0,
+            rotation: 0,
+        }
+    }
+}
+
+/// Rec. 709 luma weights (linear RGB).
+const LUMA: [f32; 3] = [0.2126, 0.7152, 0.0722];
+
+impl PictureAdjustments {
+    /// Clamps every field into its range (non-finite values fall back to the default) and
+    /// snaps rotation to a multiple of 90.
+    pub fn clamped(self) -> Self {
+        let d = Self::default();
+        let fix = |v: f32, default: f32, lo: f32, hi: f32| {
+            if v.is_finite() {
+                v.clamp(lo, hi)
+            } else {
+                default
+            }
+        };
+        Self {
+            brightness: fix(self.brightness, d.brightness, -1.0, 1.0),
+            contrast: fix(self.contrast, d.contrast, 0.0, 2.0),
+            gamma: fix(self.gamma, d.gamma, 0.1, 5.0),
+            hue: fix(self.hue, d.hue, -180.0, 180.0),
+            saturation: fix(self.saturation, d.saturation, 0.0, 2.0),
+            rotation: (self.rotation % 360 + 45) / 90 % 4 * 90,
+        }
+    }
+
+    /// Affine color transform: rows of contrast * hue * saturation, offset in .w.
+    fn affine_rows(&self) -> [[f32; 4]; 3] {
+        let s = self.saturation;
+        let mut sat = [[0.0; 3]; 3];
+        for (r, row) in sat.iter_mut().enumerate() {
+            for (c, v) in row.iter_mut().enumerate() {
+                *v = (1.0 - s) * LUMA[c] + s * IDENTITY[r][c];
+            }
+        }
+        // Rotation about the gray axis.
+        let (sin, cos) = self.hue.to_radians().sin_cos();
+        let k = (1.0 - cos) / 3.0;
+        let q = (1.0f32 / 3.0).sqrt() * sin;
+        let hue = [
+            [cos + k, k - q, k + q],
+            [k + q, cos + k, k - q],
+            [k - q, k + q, cos + k],
+        ];
+        let offset = 0.5 * (1.0 - self.contrast) + self.brightness;
+        let mut rows = [[0.0; 4]; 3];
+        for (r, row) in rows.iter_mut().enumerate() {
+            for (c, v) in row.iter_mut().take(3).enumerate() {
+                *v = self.contrast * (0..3).map(|i| hue[r][i] * sat[i][c]).sum::<f32>();
+            }
+            row[3] = offset;
+        }
+        rows
+    }
+}
+
+/// How the frame is fitted into the preview window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScaleMode {
+    /// Fill the window, ignoring the aspect ratio.
+    #[default]
+    Stretch,
+    /// Largest size that keeps the aspect ratio; the rest is letterboxed.
+    Fit,
+    /// Smallest size that keeps the aspect ratio and covers the window; the rest is cut off.
+    Fill,
+    /// Largest whole multiple of the frame size that fits, sampled without filtering; falls
+    /// back to Fit in windows smaller than the frame.
+    Integer,
+}
+
+impl ScaleMode {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Stretch => Self::Fit,
+            Self::Fit => Self::Fill,
+            Self::Fill => Self::Integer,
+            Self::Integer => Self::Stretch,
+        }
+    }
+}
+
+/// How the preview samples the frame texture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Filtering {
+    /// Smooth; best for downscaled frames.
+    #[default]
+    Linear,
+    /// Sharp texel edges; for pixel art and frames shown larger than captured.
+    Nearest,
+}
+
+/// Preview window behavior, applied by the preview thread whenever it changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WindowOptions {
+    pub always_on_top: bool,
+    pub borderless: bool,
+    /// Mouse input passes through to the windows below.
+    pub click_through: bool,
+    /// Borderless fullscreen on this monitor (winit enumeration order); None = windowed.
+    pub fullscreen_monitor: Option<usize>,
+    /// Window opacity, 255 = opaque (Windows only).
+    pub alpha: u8,
+    /// Overlay calibration: mouse drags move (left button) and resize (right button) the window.
+    pub drag_to_place: bool,
+    /// Keep the window off the taskbar and the Alt-Tab list.
+    pub skip_taskbar: bool,
+}
+
+impl Default for WindowOptions {
+    fn default() -> Self {
+        Self {
+            always_on_top: true,
+            borderless: true,
+            click_through: false,
+            fullscreen_monitor: None,
+            alpha: 255,
+            drag_to_place: false,
+            skip_taskbar: false,
+        }
+    }
+}
+
+/// Requested preview output mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HdrOutput {
+    /// HDR only for HDR sources (captures are SDR today, so this currently means SDR).
+    #[default]
+    Auto,
+    /// scRGB output whenever the surface offers an extended-range format.
+    On,
+    Off,
+}
+
+/// Requested presentation of the preview surface; the preview falls back to what the surface
+/// supports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PresentPreference {
+    /// Fifo: waits for the display's refresh, never tears, adds up to a frame of latency.
+    #[default]
+    Vsync,
+    /// Mailbox (the newest frame at the next refresh), else Immediate, which may tear.
+    LowLatency,
+}
+
+/// How the fragment shader's linear output reaches the surface.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputEncoding {
+    /// sRGB surface; hardware encodes on write.
+    Srgb,
+    /// Non-sRGB 8-bit surface; the shader encodes to sRGB.
+    SrgbInShader,
+    /// Rgba16Float surface in scRGB (linear, 1.0 = 80 nits); SDR white is scaled up.
+    ScRgb,
+}
+
+impl OutputEncoding {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Srgb | Self::SrgbInShader => "sdr",
+            Self::ScRgb => "hdr-scrgb",
+        }
+    }
+}
+
+/// SDR reference white in scRGB units (200 nits / 80 nits).
+const SCRGB_SDR_WHITE: f32 = 2.5;
+
+/// Parses "#rrggbb" or "#rgb" (the # is optional).
+pub fn parse_color(s: &str) -> Result<[u8; 3], String> {
+    let trimmed = s.trim();
+    let hex = trimmed.strip_prefix('#').unwrap_or(trimmed);
+    let digits: Option<Vec<u8>> = hex
+        .chars()
+        .map(|c| c.to_digit(16).map(|d| d as u8))
+        .collect();
+    match digits.as_deref() {
+        Some(&[r, g, b]) => Ok([r * 17, g * 17, b * 17]),
+        Some(&[r1, r0, g1, g0, b1, b0]) => Ok([r1 << 4 | r0, g1 << 4 | g0, b1 << 4 | b0]),
+        _ => Err(format!("Invalid color \"{s}\"; expected #rrggbb")),
+    }
+}
+
+/// `rgb` (sRGB) as the value to write to a surface with `encoding`.
+pub fn background_color(rgb: [u8; 3], encoding: OutputEncoding) -> [f32; 4] {
+    let [r, g, b] = rgb.map(|c| {
+        let c = c as f32 / 255.0;
+        match encoding {
+            OutputEncoding::SrgbInShader => c,
+            OutputEncoding::Srgb | OutputEncoding::ScRgb => {
+                let linear = if c <= 0.04045 {
+                    c / 12.92
+                } else {
+                    ((c + 0.055) / 1.055).powf(2.4)
+                };
+                if encoding == OutputEncoding::ScRgb {
+                    linear * SCRGB_SDR_WHITE
+                } else {
+                    linear
+                }
+            }
+        }
+    });
+    [r, g, b, 1.0]
+}
+
+const IDENTITY: [[f32; 3]; 3] = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+
+/// Options the preview thread picks up on the next redraw.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DisplayOptions {
+    pub color_blindness: ColorBlindness,
+    /// 0.0 (no effect) ..= 1.0 (full dichromacy).
+    pub color_blindness_severity: f32,
+    /// Applied after the color-blindness simulation.
+    pub color_filter: ColorFilter,
+    pub hdr_output: HdrOutput,
+    pub present_mode: PresentPreference,
+    /// Debug overlay with recent capture/present intervals.
+    pub show_frame_graph: bool,
+    /// FPS, resolution and source name in the top-left corner.
+    pub show_info_overlay: bool,
+    /// None when the crosshair is off.
+    pub crosshair: Option<Crosshair>,
+    /// None when the grid is off.
+    pub grid: Option<Grid>,
+    pub adjustments: PictureAdjustments,
+    /// A remembered crop is applied to the selected target (shown in the info overlay).
+    pub crop_active: bool,
+    /// Mirror the image left-to-right.
+    pub flip_horizontal: bool,
+    /// Mirror the image top-to-bottom.
+    pub flip_vertical: bool,
+    pub scale_mode: ScaleMode,
+    /// Always Nearest for ScaleMode::Integer.
+    pub filtering: Filtering,
+    /// sRGB color of the letterbox bars (ScaleMode::Fit and ScaleMode::Integer).
+    pub background: [u8; 3],
+    /// Crossfade between consecutive frames (cosmetic; preview only).
+    pub frame_smoothing: bool,
+    /// Closing a preview window hides it instead of stopping its session.
+    pub close_hides_preview: bool,
+    /// Picture-in-picture placement; None = the window's normal placement.
+    pub pip: Option<Pip>,
+    pub window: WindowOptions,
+}
+
+impl Default for DisplayOptions {
+    fn default() -> Self {
+        Self {
+            color_blindness: ColorBlindness::None,
+            color_blindness_severity: 1.0,
+            color_filter: ColorFilter::None,
+            hdr_output: HdrOutput::Auto,
+            present_mode: PresentPreference::Vsync,
+            show_frame_graph: false,
+            show_info_overlay: false,
+            crosshair: None,
+            grid: None,
+            adjustments: PictureAdjustments::default(),
+            crop_active: false,
+            flip_horizontal: false,
+            flip_vertical: false,
+            scale_mode: ScaleMode::Stretch,
+            filtering: Filtering::Linear,
+            background: [0, 0, 0],
+            frame_smoothing: false,
+            close_hides_preview: false,
+            pip: None,
+            window: WindowOptions::default(),
+        }
+    }
+}
+
+impl DisplayOptions {
+    /// Short description of active effects for the preview's status text; None when neutral.
+    pub fn status_label(&self) -> Option<String> {
+        let mut parts = Vec::new();
+        if self.crop_active {
+            parts.push("Cropped".to_string());
+        }
+        (!parts.is_empty()).then(|| parts.join(" — "))
+    }
+
+    /// The active color-blindness simulation and its severity; None when off.
+    pub fn simulation_label(&self) -> Option<String> {
+        (self.color_blindness != ColorBlindness::None).then(|| {
+            format!(
+                "{} {:.0}%",
+                self.color_blindness.label(),
+                self.color_blindness_severity * 100.0
+            )
+        })
+    }
+}
+
+pub type DisplayOptionsSlot = Arc<Mutex<DisplayOptions>>;
+
+/// Uniform block shared with shader.wgsl (`Uniforms`). Keep field order and padding in sync.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Uniforms {
+    /// Rows of the color matrix applied in linear RGB; .w unused.
+    color_rows: [[f32; 4]; 3],
+    /// Picture adjustments as an affine transform (rows, offset in .w).
+    adjust_rows: [[f32; 4]; 3],
+    /// 1 when the surface is not sRGB and the shader must encode its linear output itself.
+    encode_srgb: u32,
+    /// Multiplier on the final linear color (SDR white level for scRGB output).
+    output_scale: f32,
+    gamma: f32,
+    /// Clockwise quarter turns (0..=3).
+    rotation: u32,
+    /// Bit 0 mirrors left-to-right, bit 1 top-to-bottom.
+    flip: u32,
+    /// Radius of the rounded-corner mask in texture pixels (0 = off).
+    corner_radius: f32,
+    /// Size of the image quad relative to the window (letterboxing for ScaleMode::Fit, cropping
+    /// for ScaleMode::Fill).
+    scale: [f32; 2],
+    /// Weight of the current frame against the previous one (1 = no smoothing).
+    blend: f32,
+    /// Magnification of the image quad about the window center.
+    zoom: f32,
+    /// Offset of the zoomed quad in clip space.
+    pan: [f32; 2],
+    /// Letterbox color as written to the surface.
+    background: [f32; 4],
+    /// 1 to sample the nearest texel (Filtering::Nearest, ScaleMode::Integer).
+    nearest: u32,
+    /// 0 = none, 1 = grayscale, 2 = invert (ColorFilter).
+    color_filter: u32,
+    _pad: [u32; 2],
+}
+
+impl Uniforms {
+    /// `frame` and `surface` are the display size of the frame and the window size in pixels
+    /// (for ScaleMode::Fit).
+    pub fn new(
+        options: &DisplayOptions,
+        encoding: OutputEncoding,
+        frame: (u32, u32),
+        surface: (u32, u32),
+        corner_radius: f32,
+        blend: f32,
+        zoom: &ZoomView,
+    ) -> Self {
+        let s = options.color_blindness_severity.clamp(0.0, 1.0);
+        let sim = options.color_blindness.matrix();
+        let mut color_rows = [[0.0; 4]; 3];
+        for (r, row) in color_rows.iter_mut().enumerate() {
+            for (c, v) in row.iter_mut().take(3).enumerate() {
+                *v = IDENTITY[r][c] * (1.0 - s) + sim[r][c] * s;
+            }
+        }
+        let adjustments = options.adjustments.clamped();
+        let scale = options.quad_scale(frame, surface);
+        let integer = options.scale_mode == ScaleMode::Integer;
+        let offset = if integer {
+            pixel_offset(scale, surface)
+        } else {
+            [0.0, 0.0]
+        };
+        Self {
+            color_rows,
+            adjust_rows: adjustments.affine_rows(),
+            encode_srgb: (encoding == OutputEncoding::SrgbInShader) as u32,
+            output_scale: if encoding == OutputEncoding::ScRgb {
+                SCRGB_SDR_WHITE
+            } else {
+                1.0
+            },
+            gamma: adjustments.gamma,
+            rotation: adjustments.rotation / 90,
+            flip: options.flip_horizontal as u32 | (options.flip_vertical as u32) << 1,
+            corner_radius,
+            scale,
+            blend,
+            zoom: zoom.factor,
+            pan: [zoom.pan[0] + offset[0], zoom.pan[1] + offset[1]],
+            background: background_color(options.background, encoding),
+            nearest: (integer || options.filtering == Filtering::Nearest) as u32,
+            color_filter: options.color_filter as u32,
+            _pad: [0; 2],
+        }
+    }
+
+    /// The same picture drawn unzoomed into `rect` (x0, y0, x1, y1 in clip space), for the
+    /// full-frame inset shown while zoomed in. Filtered even with nearest sampling, since the
+    /// inset is always a thumbnail.
+    pub fn inset(self, rect: [f32; 4]) -> Self {
+        let [x0, y0, x1, y1] = rect;
+        Self {
+            scale: [(x1 - x0) / 2.0, (y1 - y0) / 2.0],
+            zoom: 1.0,
+            pan: [(x0 + x1) / 2.0, (y0 + y1) / 2.0],
+            nearest: 0,
+            ..self
+        }
+    }
+}
+
+impl DisplayOptions {
+    /// Size of the unzoomed image quad relative to the window.
+    pub fn quad_scale(&self, frame: (u32, u32), surface: (u32, u32)) -> [f32; 2] {
+        let rotation = self.adjustments.clamped().rotation;
+        quad_scale(self.scale_mode, rotation, frame, surface)
+    }
+}
+
+fn quad_scale(mode: ScaleMode, rotation: u32, frame: (u32, u32), surface: (u32, u32)) -> [f32; 2] {
+    if mode == ScaleMode::Stretch || frame.0 == 0 || frame.1 == 0 || surface.0 == 0 || surface.1 == 0
+    {
+        return [1.0, 1.0];
+    }
+    let (fw, fh) = if rotation % 180 == 90 {
+        (frame.1, frame.0)
+    } else {
+        frame
+    };
+    if mode == ScaleMode::Integer {
+        let factor = (surface.0 / fw).min(surface.1 / fh);
+        if factor >= 1 {
+            return [
+                (fw * factor) as f32 / surface.0 as f32,
+                (fh * factor) as f32 / surface.1 as f32,
+            ];
+        }
+    }
+    let image_aspect = fw as f32 / fh as f32;
+    let window_aspect = surface.0 as f32 / surface.1 as f32;
+    // Fit matches the window on the image's wider side, Fill on the narrower one.
+    if (image_aspect > window_aspect) == (mode != ScaleMode::Fill) {
+        [1.0, window_aspect / image_aspect]
+    } else {
+        [image_aspect / window_aspect, 1.0]
+    }
+}
+
+/// Half-pixel shift (clip space) that puts the edges of a quad of `scale` on pixel
+/// boundaries, so each texel covers whole pixels.
+fn pixel_offset(scale: [f32; 2], surface: (u32, u32)) -> [f32; 2] {
+    [(scale[0], surface.0), (scale[1], surface.1)].map(|(scale, size)| {
+        let border = size.saturating_sub((scale * size as f32).round() as u32);
+        if border % 2 == 1 {
+            1.0 / size as f32
+        } else {
+            0.0
+        }
+    })
+}
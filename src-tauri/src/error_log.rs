@@ -0,0 +1,67 @@
+use crate::CaptureState;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager, State};
+
+/// Entries kept; older ones are dropped.
+const CAPACITY: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LoggedError {
+    /// Unix milliseconds.
+    at: u64,
+    level: Level,
+    message: String,
+}
+
+/// The most recent errors and warnings, so they can be looked up after the fact even if no
+/// window was listening when they were emitted.
+#[derive(Debug, Default)]
+pub struct ErrorLog {
+    entries: VecDeque<LoggedError>,
+}
+
+impl ErrorLog {
+    fn push(&mut self, level: Level, message: String) {
+        if self.entries.len() == CAPACITY {
+            self.entries.pop_front();
+        }
+        let at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        self.entries.push_back(LoggedError { at, level, message });
+    }
+
+    /// Oldest first.
+    pub fn entries(&self) -> Vec<LoggedError> {
+        self.entries.iter().cloned().collect()
+    }
+}
+
+/// Appends to the log in `CaptureState`; call wherever an error or warning event is emitted.
+pub fn record(app: &AppHandle, level: Level, message: impl Into<String>) {
+    app.state::<CaptureState>()
+        .error_log
+        .lock()
+        .unwrap()
+        .push(level, message.into());
+}
+
+#[tauri::command]
+pub fn get_recent_errors(state: State<CaptureState>) -> Vec<LoggedError> {
+    state.error_log.lock().unwrap().entries()
+}
+
+#[tauri::command]
+pub fn clear_errors(state: State<CaptureState>) {
+    state.error_log.lock().unwrap().entries.clear();
+}
@@ -0,0 +1,85 @@
+use crate::apps;
+use scap::{get_all_targets, Target};
+use std::time::{Duration, Instant};
+
+/// How long focus must rest on a window before the capture switches to it, so alt-tabbing
+/// past windows does not rebuild the capturer for each.
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// The window with keyboard focus (Windows only).
+#[cfg(target_os = "windows")]
+fn foreground_window() -> Option<u32> {
+    use windows::Win32::UI::WindowsAndMessaging::GetForegroundWindow;
+
+    let hwnd = unsafe { GetForegroundWindow() };
+    (!hwnd.is_invalid()).then_some(hwnd.0 as isize as u32)
+}
+
+#[cfg(not(target_os = "windows"))]
+fn foreground_window() -> Option<u32> {
+    None
+}
+
+/// Picks the capture target of a `follow_focus` session: the focused window, once focus has
+/// stayed on it for `DEBOUNCE`. LiteView's own windows and windows that are not capture
+/// targets (the desktop, the taskbar) are passed over, so the last valid target stays.
+#[derive(Debug)]
+pub struct FocusTracker {
+    /// Window being captured.
+    current: Option<u32>,
+    /// Focused window other than `current`, and since when it has focus.
+    candidate: Option<(u32, Instant)>,
+    /// Last window found uncapturable; not looked up again while it keeps focus.
+    rejected: Option<u32>,
+}
+
+impl FocusTracker {
+    pub fn new(current: Option<u32>) -> Self {
+        Self {
+            current,
+            candidate: None,
+            rejected: None,
+        }
+    }
+
+    /// The window to switch to, when focus has moved to a capturable one and settled. Called
+    /// by the capture thread for every frame.
+    pub fn poll(&mut self, now: Instant) -> Option<Target> {
+        let focused = foreground_window()?;
+        let own = apps::window_pid(focused) == Some(std::process::id());
+        if own || Some(focused) == self.current || Some(focused) == self.rejected {
+            self.candidate = None;
+            return None;
+        }
+        match self.candidate {
+            Some((id, since)) if id == focused => {
+                if now.saturating_duration_since(since) < DEBOUNCE {
+                    return None;
+                }
+            }
+            _ => {
+                self.candidate = Some((focused, now));
+                return None;
+            }
+        }
+        self.candidate = None;
+        let target = get_all_targets()
+            .into_iter()
+            .find(|t| matches!(t, Target::Window(w) if w.id == focused));
+        if target.is_none() {
+            self.rejected = Some(focused);
+        }
+        target
+    }
+
+    /// The capture now records window `id`.
+    pub fn switched(&mut self, id: u32) {
+        self.current = Some(id);
+        self.rejected = None;
+    }
+
+    /// Capturing window `id` failed; stay on the current target.
+    pub fn reject(&mut self, id: u32) {
+        self.rejected = Some(id);
+    }
+}
@@ -0,0 +1,106 @@
+use crate::error_log::{self, Level};
+use scap::frame::Frame;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+/// Minimum time between two "frame-rejected" events.
+const WARNING_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Largest frames accepted from the capture backend. Anything beyond is dropped before it is
+/// converted, so a backend reporting a bogus size cannot make LiteView allocate gigabytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FrameLimits {
+    /// Per side, in pixels.
+    pub max_dimension: u32,
+    /// Width × height, in millions of pixels.
+    pub max_megapixels: u32,
+}
+
+impl Default for FrameLimits {
+    /// 16K per side and twice the pixels of 16K × 4K: room for multi-monitor desktops.
+    fn default() -> Self {
+        Self {
+            max_dimension: 16_384,
+            max_megapixels: 134,
+        }
+    }
+}
+
+impl FrameLimits {
+    /// Keeps the limits high enough for ordinary displays (at least 4K per side).
+    pub fn clamped(self) -> Self {
+        Self {
+            max_dimension: self.max_dimension.max(4096),
+            max_megapixels: self.max_megapixels.max(9),
+        }
+    }
+
+    pub fn allows(&self, width: u32, height: u32) -> bool {
+        width <= self.max_dimension
+            && height <= self.max_dimension
+            && width as u64 * height as u64 <= self.max_megapixels as u64 * 1_000_000
+    }
+}
+
+/// Size the frame claims to have; None for negative sizes. (Field types differ between frame
+/// types, hence the casts.)
+pub fn frame_size(frame: &Frame) -> Option<(u32, u32)> {
+    let (width, height) = match frame {
+        Frame::BGRA(f) => (f.width as i64, f.height as i64),
+        Frame::BGR0(f) => (f.width as i64, f.height as i64),
+        Frame::RGB(f) => (f.width as i64, f.height as i64),
+        Frame::RGBx(f) => (f.width as i64, f.height as i64),
+        Frame::XBGR(f) => (f.width as i64, f.height as i64),
+        Frame::BGRx(f) => (f.width as i64, f.height as i64),
+        Frame::YUVFrame(f) => (f.width as i64, f.height as i64),
+    };
+    Some((u32::try_from(width).ok()?, u32::try_from(height).ok()?))
+}
+
+/// Payload of the "frame-rejected" event.
+#[derive(Debug, Clone, Serialize)]
+struct FrameRejected {
+    width: u32,
+    height: u32,
+    /// Frames rejected in this session so far.
+    count: u64,
+}
+
+/// Counts rejected frames of a session and reports them, at most once per `WARNING_INTERVAL`.
+#[derive(Debug, Default)]
+pub struct RejectedFrames {
+    count: u64,
+    last_warning: Option<Instant>,
+}
+
+impl RejectedFrames {
+    pub fn record(&mut self, app: &AppHandle, width: u32, height: u32) {
+        self.count += 1;
+        let now = Instant::now();
+        if self
+            .last_warning
+            .is_some_and(|t| now.duration_since(t) < WARNING_INTERVAL)
+        {
+            return;
+        }
+        self.last_warning = Some(now);
+        error_log::record(
+            app,
+            Level::Warning,
+            format!(
+                "Dropped oversized frame {width}×{height} ({} so far this session).",
+                self.count
+            ),
+        );
+        let _ = app.emit(
+            "frame-rejected",
+            FrameRejected {
+                width,
+                height,
+                count: self.count,
+            },
+        );
+    }
+}
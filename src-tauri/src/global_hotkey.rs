@@ -0,0 +1,84 @@
+use tauri::AppHandle;
+
+/// Registers the `toggle_click_through` shortcut with the system and toggles click-through when
+/// it is pressed, even while the preview window ignores the mouse and another application has
+/// focus. Re-registers when the shortcut changes (Windows only).
+#[cfg(target_os = "windows")]
+pub fn spawn_hotkey_listener(app: AppHandle) {
+    use crate::error_log::{self, Level};
+    use crate::keybindings::{self, CLICK_THROUGH};
+    use crate::toggle_menu::{self, Toggle};
+    use crate::{emit_capture_error, CaptureState};
+    use std::thread;
+    use std::time::Duration;
+    use tauri::Manager;
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::UI::Input::KeyboardAndMouse::{
+        RegisterHotKey, UnregisterHotKey, MOD_ALT, MOD_CONTROL, MOD_NOREPEAT, MOD_SHIFT, MOD_WIN,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::{PeekMessageW, MSG, PM_REMOVE, WM_HOTKEY};
+
+    const POLL_INTERVAL: Duration = Duration::from_millis(50);
+    const HOTKEY_ID: i32 = 1;
+
+    // The hotkey belongs to the thread that registers it, so one thread does it all.
+    thread::spawn(move || {
+        let mut registered: Option<String> = None;
+        loop {
+            let wanted = app
+                .state::<CaptureState>()
+                .settings
+                .lock()
+                .unwrap()
+                .keybindings
+                .get(CLICK_THROUGH)
+                .cloned()
+                .unwrap_or_default();
+            if registered.as_ref() != Some(&wanted) {
+                if registered.take().is_some() {
+                    let _ = unsafe { UnregisterHotKey(HWND::default(), HOTKEY_ID) };
+                }
+                if let Some(hotkey) = keybindings::global_hotkey(&wanted) {
+                    let mut modifiers = MOD_NOREPEAT;
+                    for (on, modifier) in [
+                        (hotkey.ctrl, MOD_CONTROL),
+                        (hotkey.shift, MOD_SHIFT),
+                        (hotkey.alt, MOD_ALT),
+                        (hotkey.win, MOD_WIN),
+                    ] {
+                        if on {
+                            modifiers |= modifier;
+                        }
+                    }
+                    let result = unsafe {
+                        RegisterHotKey(HWND::default(), HOTKEY_ID, modifiers, hotkey.key)
+                    };
+                    if result.is_err() {
+                        error_log::record(
+                            &app,
+                            Level::Warning,
+                            format!("The hotkey {wanted} is taken by another application."),
+                        );
+                    }
+                }
+                // Not retried until the setting changes, also when registering failed.
+                registered = Some(wanted);
+            }
+
+            let mut msg = MSG::default();
+            while unsafe {
+                PeekMessageW(&mut msg, HWND::default(), WM_HOTKEY, WM_HOTKEY, PM_REMOVE)
+            }
+            .as_bool()
+            {
+                if let Err(e) = toggle_menu::flip(&app, Toggle::ClickThrough) {
+                    emit_capture_error(&app, e);
+                }
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    });
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn spawn_hotkey_listener(_app: AppHandle) {}
@@ -0,0 +1,270 @@
+use std::cell::UnsafeCell;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+/// Bits of `TripleBuffer::back` holding the slot index.
+const INDEX: u8 = 0b011;
+/// Set in `TripleBuffer::back` while the back slot holds a value the reader has not taken.
+const FRESH: u8 = 0b100;
+
+/// Three slots shared by one writer and one reader. Each side owns one slot; the third, the
+/// back slot, is traded through an atomic swap: the writer fills its slot and swaps it in as
+/// the fresh back, and the reader swaps its slot for a fresh back. Neither side waits for the
+/// other, and a slot is only touched by its owner, so the reader never sees a value the writer
+/// has not finished.
+struct TripleBuffer<T> {
+    slots: [UnsafeCell<Option<T>>; 3],
+    /// Index of the back slot, with `FRESH` when it holds an unread value.
+    back: AtomicU8,
+}
+
+// SAFETY: the writer and the reader only touch the slot they own; ownership of the back slot
+// passes with the `back` swap, whose AcqRel ordering publishes what the previous owner wrote.
+unsafe impl<T: Send> Sync for TripleBuffer<T> {}
+
+/// Write side of a `triple_buffer`.
+pub struct Writer<T> {
+    shared: Arc<TripleBuffer<T>>,
+    index: u8,
+}
+
+/// Read side of a `triple_buffer`.
+pub struct Reader<T> {
+    shared: Arc<TripleBuffer<T>>,
+    index: u8,
+}
+
+/// A latest-value channel: the reader gets the newest value written, values it misses are
+/// dropped, and neither side ever blocks.
+pub fn triple_buffer<T>() -> (Writer<T>, Reader<T>) {
+    let shared = Arc::new(TripleBuffer {
+        slots: [const { UnsafeCell::new(None) }; 3],
+        back: AtomicU8::new(2),
+    });
+    let writer = Writer {
+        shared: shared.clone(),
+        index: 0,
+    };
+    (writer, Reader { shared, index: 1 })
+}
+
+impl<T> Writer<T> {
+    /// Publishes `value`; returns whether it replaced one the reader never took.
+    pub fn write(&mut self, value: T) -> bool {
+        // SAFETY: slot `index` is the writer's until it is swapped in below.
+        unsafe { *self.shared.slots[self.index as usize].get() = Some(value) };
+        let back = self.shared.back.swap(self.index | FRESH, Ordering::AcqRel);
+        self.index = back & INDEX;
+        // SAFETY: the former back slot is the writer's now. Its value, an unread one or the
+        // one the reader let go of, is dropped here rather than kept alive until the slot is
+        // written again.
+        drop(unsafe { (*self.shared.slots[self.index as usize].get()).take() });
+        back & FRESH != 0
+    }
+}
+
+impl<T> Reader<T> {
+    /// Takes over the back slot if the writer published a value since; returns whether it did.
+    pub fn update(&mut self) -> bool {
+        if self.shared.back.load(Ordering::Relaxed) & FRESH == 0 {
+            return false;
+        }
+        let back = self.shared.back.swap(self.index, Ordering::AcqRel);
+        self.index = back & INDEX;
+        true
+    }
+
+    /// The newest value taken over with `update`, unless it was taken out.
+    pub fn get(&self) -> Option<&T> {
+        // SAFETY: slot `index` is the reader's until the next `update`, which needs `&mut self`.
+        unsafe { (*self.shared.slots[self.index as usize].get()).as_ref() }
+    }
+
+    pub fn take(&mut self) -> Option<T> {
+        // SAFETY: as in `get`.
+        unsafe { (*self.shared.slots[self.index as usize].get()).take() }
+    }
+}
+
+/// Sending side of the channel from a session's capture thread to its converter thread (see
+/// `spawn`).
+pub struct Sender<T> {
+    writer: Writer<T>,
+    closed: Arc<AtomicBool>,
+    receiver: JoinHandle<()>,
+}
+
+/// Receiving side, handed to the thread `spawn` starts.
+pub struct Receiver<T> {
+    reader: Reader<T>,
+    closed: Arc<AtomicBool>,
+}
+
+/// Runs `receive` on a new thread with the receiving end of a single-item channel. An item
+/// not received yet is dropped for the next one, so a slow receiver never holds up the sender,
+/// and sending never blocks.
+pub fn spawn<T, F>(receive: F) -> Sender<T>
+where
+    T: Send + 'static,
+    F: FnOnce(Receiver<T>) + Send + 'static,
+{
+    let (writer, reader) = triple_buffer();
+    let closed = Arc::new(AtomicBool::new(false));
+    let receiver = Receiver {
+        reader,
+        closed: closed.clone(),
+    };
+    Sender {
+        writer,
+        closed,
+        receiver: thread::spawn(move || receive(receiver)),
+    }
+}
+
+impl<T> Sender<T> {
+    /// Queues `item`; returns whether it replaced one that was still waiting.
+    pub fn send(&mut self, item: T) -> bool {
+        let replaced = self.writer.write(item);
+        self.receiver.thread().unpark();
+        replaced
+    }
+
+    /// Ends the channel and waits for the receiving thread, which gets what is still
+    /// waiting, then None.
+    pub fn close(self) {
+        self.closed.store(true, Ordering::Release);
+        self.receiver.thread().unpark();
+        let _ = self.receiver.join();
+    }
+}
+
+impl<T> Receiver<T> {
+    /// Blocks until an item is queued; None once the sender closed and nothing is waiting.
+    pub fn recv(&mut self) -> Option<T> {
+        loop {
+            if self.reader.update() {
+                return self.reader.take();
+            }
+            if self.closed.load(Ordering::Acquire) {
+                // Items sent before closing are visible now.
+                return self.reader.update().then(|| self.reader.take()).flatten();
+            }
+            // Woken by `send` and `close`; an unpark that came first makes this return at once.
+            thread::park();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    #[test]
+    fn reader_gets_the_newest_value() {
+        let (mut writer, mut reader) = triple_buffer();
+        assert!(!reader.update());
+        assert_eq!(reader.get(), None);
+        assert!(!writer.write(1));
+        assert!(writer.write(2));
+        assert!(reader.update());
+        assert_eq!(reader.get(), Some(&2));
+        // Nothing new: the reader keeps its value.
+        assert!(!reader.update());
+        assert_eq!(reader.get(), Some(&2));
+        assert!(!writer.write(3));
+        assert_eq!(reader.get(), Some(&2));
+        assert!(reader.update());
+        assert_eq!(reader.take(), Some(3));
+        assert_eq!(reader.get(), None);
+    }
+
+    struct Counted(Arc<AtomicUsize>);
+
+    impl Drop for Counted {
+        fn drop(&mut self) {
+            self.0.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn overwritten_values_are_dropped_right_away() {
+        let alive = Arc::new(AtomicUsize::new(0));
+        let value = || {
+            alive.fetch_add(1, Ordering::Relaxed);
+            Counted(alive.clone())
+        };
+        let (mut writer, mut reader) = triple_buffer();
+        for _ in 0..10 {
+            writer.write(value());
+        }
+        // Only the unread back value is left.
+        assert_eq!(alive.load(Ordering::Relaxed), 1);
+        reader.update();
+        writer.write(value());
+        writer.write(value());
+        // The reader's value and the newest.
+        assert_eq!(alive.load(Ordering::Relaxed), 2);
+        drop((writer, reader));
+        assert_eq!(alive.load(Ordering::Relaxed), 0);
+    }
+
+    /// Values of `LEN` copies of their sequence number, to catch torn reads.
+    const LEN: usize = 4096;
+
+    #[test]
+    fn stress_reader_never_sees_a_torn_or_older_value() {
+        const WRITES: u64 = 20_000;
+        let (mut writer, mut reader) = triple_buffer::<Vec<u64>>();
+        let producer = thread::spawn(move || {
+            for n in 1..=WRITES {
+                writer.write(vec![n; LEN]);
+            }
+        });
+        let mut last = 0;
+        let mut seen = 0;
+        while last < WRITES {
+            if !reader.update() {
+                std::hint::spin_loop();
+                continue;
+            }
+            let value = reader.get().unwrap();
+            let n = value[0];
+            assert!(value.iter().all(|&v| v == n), "torn value {n}");
+            assert!(n > last, "{n} after {last}");
+            (last, seen) = (n, seen + 1);
+        }
+        producer.join().unwrap();
+        assert!(seen > 0);
+    }
+
+    #[test]
+    fn stress_receiver_gets_every_last_item() {
+        for _ in 0..50 {
+            let received = Arc::new(AtomicUsize::new(0));
+            let last = Arc::new(AtomicUsize::new(0));
+            let mut sender = {
+                let (received, last) = (received.clone(), last.clone());
+                spawn(move |mut items: Receiver<Vec<usize>>| {
+                    let mut previous = 0;
+                    while let Some(item) = items.recv() {
+                        let n = item[0];
+                        assert!(item.iter().all(|&v| v == n), "torn item {n}");
+                        assert!(n > previous, "{n} after {previous}");
+                        previous = n;
+                        received.fetch_add(1, Ordering::Relaxed);
+                    }
+                    last.store(previous, Ordering::Relaxed);
+                })
+            };
+            for n in 1..=1000 {
+                sender.send(vec![n; LEN / 8]);
+            }
+            sender.close();
+            // The last item is never lost, and the receiver saw at least that one.
+            assert_eq!(last.load(Ordering::Relaxed), 1000);
+            assert!(received.load(Ordering::Relaxed) >= 1);
+        }
+    }
+}
@@ -0,0 +1,515 @@
+use crate::arrange::SizePreset;
+use crate::preview::PreviewAction;
+use crate::toggle_menu::Toggle;
+use crate::{update_settings, CaptureState};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Manager};
+use winit::keyboard::{Key, ModifiersState, NamedKey};
+
+/// The action that is also a system-wide hotkey (Windows), so click-through can be turned off
+/// while the preview ignores the mouse and another application has focus.
+pub const CLICK_THROUGH: &str = "toggle_click_through";
+
+/// Bindable actions: (settings name, action, default chord).
+const ACTIONS: &[(&str, PreviewAction, &str)] = &[
+    ("cycle_arrangement", PreviewAction::CycleArrangement, "A"),
+    (
+        "cycle_color_blindness",
+        PreviewAction::CycleColorBlindness,
+        "C",
+    ),
+    ("cycle_scale_mode", PreviewAction::CycleScaleMode, "Shift+S"),
+    ("toggle_frame_graph", PreviewAction::ToggleFrameGraph, "F3"),
+    ("toggle_info_overlay", PreviewAction::ToggleInfoOverlay, "I"),
+    ("toggle_crosshair", PreviewAction::ToggleCrosshair, "X"),
+    ("cycle_grid", PreviewAction::CycleGrid, "G"),
+    ("toggle_overlay", PreviewAction::ToggleOverlay, "O"),
+    ("toggle_pip", PreviewAction::TogglePip, "Shift+P"),
+    (
+        "toggle_always_on_top",
+        PreviewAction::Toggle(Toggle::AlwaysOnTop),
+        "T",
+    ),
+    (
+        CLICK_THROUGH,
+        PreviewAction::Toggle(Toggle::ClickThrough),
+        "Ctrl+Alt+P",
+    ),
+    ("rotate", PreviewAction::Rotate, "R"),
+    (
+        "reset_adjustments",
+        PreviewAction::ResetAdjustments,
+        "Backspace",
+    ),
+    (
+        "toggle_flip_horizontal",
+        PreviewAction::Toggle(Toggle::FlipHorizontal),
+        "M",
+    ),
+    ("size_25", PreviewAction::Resize(SizePreset::Quarter), "1"),
+    ("size_50", PreviewAction::Resize(SizePreset::Half), "2"),
+    ("size_100", PreviewAction::Resize(SizePreset::Full), "3"),
+    (
+        "size_fit",
+        PreviewAction::Resize(SizePreset::FitScreen),
+        "4",
+    ),
+    ("copy_frame", PreviewAction::CopyFrame, "Ctrl+C"),
+    ("reset_zoom", PreviewAction::ResetZoom, "0"),
+    ("zoom_in", PreviewAction::ZoomIn, "Plus"),
+    ("zoom_out", PreviewAction::ZoomOut, "-"),
+    ("toggle_zoom_inset", PreviewAction::ToggleZoomInset, "Z"),
+    ("toggle_pause", PreviewAction::TogglePause, "P"),
+    ("toggle_freeze", PreviewAction::ToggleFreeze, "Space"),
+    ("screenshot", PreviewAction::Screenshot, "S"),
+    ("toggle_fullscreen", PreviewAction::ToggleFullscreen, "F"),
+    ("close_preview", PreviewAction::ClosePreview, "Escape"),
+    ("show_shortcuts", PreviewAction::ShowShortcuts, "?"),
+];
+
+/// Chord names for non-character keys.
+const NAMED_KEYS: &[(&str, NamedKey)] = &[
+    ("F1", NamedKey::F1),
+    ("F2", NamedKey::F2),
+    ("F3", NamedKey::F3),
+    ("F4", NamedKey::F4),
+    ("F5", NamedKey::F5),
+    ("F6", NamedKey::F6),
+    ("F7", NamedKey::F7),
+    ("F8", NamedKey::F8),
+    ("F9", NamedKey::F9),
+    ("F10", NamedKey::F10),
+    ("F11", NamedKey::F11),
+    ("F12", NamedKey::F12),
+    ("Space", NamedKey::Space),
+    ("Tab", NamedKey::Tab),
+    ("Enter", NamedKey::Enter),
+    ("Escape", NamedKey::Escape),
+    ("Backspace", NamedKey::Backspace),
+    ("Delete", NamedKey::Delete),
+    ("Insert", NamedKey::Insert),
+    ("Home", NamedKey::Home),
+    ("End", NamedKey::End),
+    ("PageUp", NamedKey::PageUp),
+    ("PageDown", NamedKey::PageDown),
+    ("Up", NamedKey::ArrowUp),
+    ("Down", NamedKey::ArrowDown),
+    ("Left", NamedKey::ArrowLeft),
+    ("Right", NamedKey::ArrowRight),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ChordKey {
+    /// Uppercased character as produced by the keyboard layout.
+    Char(String),
+    Named(NamedKey),
+}
+
+/// A key with modifiers, written like "Ctrl+Shift+S" or "F3".
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct KeyChord {
+    ctrl: bool,
+    shift: bool,
+    alt: bool,
+    meta: bool,
+    key: ChordKey,
+}
+
+impl KeyChord {
+    fn parse(s: &str) -> Result<Self, String> {
+        let parts: Vec<&str> = s.split('+').map(str::trim).collect();
+        let (key, modifiers) = parts.split_last().unwrap();
+        let mut chord = KeyChord {
+            ctrl: false,
+            shift: false,
+            alt: false,
+            meta: false,
+            key: parse_key(key).ok_or_else(|| format!("Invalid key in shortcut \"{s}\""))?,
+        };
+        for m in modifiers {
+            let flag = match m.to_lowercase().as_str() {
+                "ctrl" | "control" => &mut chord.ctrl,
+                "shift" => &mut chord.shift,
+                "alt" | "option" => &mut chord.alt,
+                "super" | "meta" | "cmd" | "win" => &mut chord.meta,
+                _ => return Err(format!("Invalid modifier \"{m}\" in shortcut \"{s}\"")),
+            };
+            *flag = true;
+        }
+        Ok(chord)
+    }
+
+    /// Whether a key press matches. Shift is ignored for non-letter characters, since it is
+    /// already reflected in the character the layout produced (e.g. "?" on US keyboards).
+    fn matches(&self, key: &Key, mods: ModifiersState) -> bool {
+        let key_matches = match (&self.key, key) {
+            (ChordKey::Char(c), Key::Character(k)) => k.to_uppercase() == *c,
+            (ChordKey::Named(n), Key::Named(k)) => n == k,
+            _ => false,
+        };
+        let ignore_shift =
+            matches!(&self.key, ChordKey::Char(c) if !c.chars().all(char::is_alphabetic));
+        key_matches
+            && self.ctrl == mods.control_key()
+            && self.alt == mods.alt_key()
+            && self.meta == mods.super_key()
+            && (ignore_shift || self.shift == mods.shift_key())
+    }
+
+    /// Windows virtual-key code of the key: letters, digits and F1-F12 only.
+    fn virtual_key(&self) -> Option<u32> {
+        match &self.key {
+            ChordKey::Char(c) => {
+                let mut chars = c.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) if c.is_ascii_alphanumeric() => Some(c as u32),
+                    _ => None,
+                }
+            }
+            // NAMED_KEYS starts with F1-F12.
+            ChordKey::Named(n) => NAMED_KEYS[..12]
+                .iter()
+                .position(|(_, k)| k == n)
+                .map(|i| 0x70 + i as u32),
+        }
+    }
+
+    /// Checks a chord for `CLICK_THROUGH`, which is registered with the system: a bare key
+    /// would be swallowed in every application, so it needs a modifier unless it is an F-key.
+    fn check_system_wide(&self) -> Result<(), String> {
+        let key = self.virtual_key().ok_or_else(|| {
+            format!("\"{self}\" cannot be a system-wide hotkey; use a letter, digit or F-key")
+        })?;
+        let function_key = (0x70..0x70 + 12).contains(&key);
+        if function_key || self.ctrl || self.alt || self.meta {
+            Ok(())
+        } else {
+            Err(format!(
+                "System-wide hotkey \"{self}\" needs Ctrl, Alt or Win"
+            ))
+        }
+    }
+}
+
+/// A chord registered as a system-wide hotkey: modifiers and a Windows virtual-key code.
+#[cfg(target_os = "windows")]
+pub struct GlobalHotkey {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+    pub win: bool,
+    pub key: u32,
+}
+
+/// `chord` as a system-wide hotkey; None when empty or not valid as one.
+#[cfg(target_os = "windows")]
+pub fn global_hotkey(chord: &str) -> Option<GlobalHotkey> {
+    let chord = KeyChord::parse(chord).ok()?;
+    chord.check_system_wide().ok()?;
+    Some(GlobalHotkey {
+        ctrl: chord.ctrl,
+        shift: chord.shift,
+        alt: chord.alt,
+        win: chord.meta,
+        key: chord.virtual_key()?,
+    })
+}
+
+fn parse_key(s: &str) -> Option<ChordKey> {
+    if s.eq_ignore_ascii_case("plus") {
+        return Some(ChordKey::Char("+".to_string()));
+    }
+    if let Some((_, named)) = NAMED_KEYS.iter().find(|(n, _)| n.eq_ignore_ascii_case(s)) {
+        return Some(ChordKey::Named(*named));
+    }
+    let mut chars = s.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if !c.is_whitespace() => Some(ChordKey::Char(c.to_uppercase().collect())),
+        _ => None,
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (on, name) in [
+            (self.ctrl, "Ctrl+"),
+            (self.shift, "Shift+"),
+            (self.alt, "Alt+"),
+            (self.meta, "Super+"),
+        ] {
+            if on {
+                f.write_str(name)?;
+            }
+        }
+        match &self.key {
+            ChordKey::Char(c) if c == "+" => f.write_str("Plus"),
+            ChordKey::Char(c) => f.write_str(c),
+            ChordKey::Named(n) => {
+                let name = NAMED_KEYS.iter().find(|(_, k)| k == n).map(|(s, _)| *s);
+                f.write_str(name.unwrap_or("?"))
+            }
+        }
+    }
+}
+
+/// Parsed bindings used by the preview window's input handler, with the settings name of each
+/// action.
+#[derive(Debug, Clone, Default)]
+pub struct Keybindings(Vec<(KeyChord, PreviewAction, &'static str)>);
+
+impl Keybindings {
+    /// Builds bindings from a map already checked by `validate`.
+    pub fn from_map(map: &BTreeMap<String, String>) -> Self {
+        Self(
+            ACTIONS
+                .iter()
+                .filter_map(|(name, action, _)| {
+                    let chord = KeyChord::parse(map.get(*name)?).ok()?;
+                    Some((chord, *action, *name))
+                })
+                .collect(),
+        )
+    }
+
+    pub fn action_for(&self, key: &Key, mods: ModifiersState) -> Option<PreviewAction> {
+        self.0
+            .iter()
+            .find(|(chord, ..)| chord.matches(key, mods))
+            .map(|(_, action, _)| *action)
+    }
+
+    /// "<chord>  <action>" lines for the shortcut list in the preview window.
+    pub fn describe(&self) -> Vec<String> {
+        self.0
+            .iter()
+            .map(|(chord, _, name)| format!("{chord}  {}", name.replace('_', " ")))
+            .collect()
+    }
+}
+
+pub type KeybindingsSlot = Arc<Mutex<Keybindings>>;
+
+pub fn defaults() -> BTreeMap<String, String> {
+    ACTIONS
+        .iter()
+        .map(|(name, _, chord)| (name.to_string(), chord.to_string()))
+        .collect()
+}
+
+/// Normalizes chords ("ctrl+s" -> "Ctrl+S") and rejects unknown actions, invalid chords and
+/// chords bound to more than one action. An empty chord leaves the action unbound; actions
+/// missing from the map get their default unless that chord is already taken.
+pub fn validate(map: BTreeMap<String, String>) -> Result<BTreeMap<String, String>, String> {
+    let mut out = BTreeMap::new();
+    let mut used: BTreeMap<String, String> = BTreeMap::new();
+    for (name, chord) in map {
+        if !ACTIONS.iter().any(|(n, _, _)| *n == name) {
+            return Err(format!("Unknown shortcut action: {name}"));
+        }
+        let chord = chord.trim();
+        if chord.is_empty() {
+            out.insert(name, String::new());
+            continue;
+        }
+        let chord = KeyChord::parse(chord)?;
+        if name == CLICK_THROUGH {
+            chord.check_system_wide()?;
+        }
+        let chord = chord.to_string();
+        if let Some(other) = used.insert(chord.clone(), name.clone()) {
+            return Err(format!("\"{chord}\" is bound to both {other} and {name}"));
+        }
+        out.insert(name, chord);
+    }
+    for (name, _, chord) in ACTIONS {
+        if !out.contains_key(*name) {
+            let chord = if used.contains_key(*chord) { "" } else { chord };
+            out.insert(name.to_string(), chord.to_string());
+        }
+    }
+    Ok(out)
+}
+
+/// Restores the default shortcuts and returns them.
+#[tauri::command]
+pub fn reset_keybindings(app: AppHandle) -> Result<BTreeMap<String, String>, String> {
+    update_settings(&app, |s| s.keybindings = defaults())?;
+    Ok(app
+        .state::<CaptureState>()
+        .settings
+        .lock()
+        .unwrap()
+        .keybindings
+        .clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with(name: &str, chord: &str) -> BTreeMap<String, String> {
+        BTreeMap::from([(name.to_string(), chord.to_string())])
+    }
+
+    fn chord(s: &str) -> KeyChord {
+        KeyChord::parse(s).unwrap()
+    }
+
+    fn char_key(c: &str) -> Key {
+        Key::Character(c.into())
+    }
+
+    #[test]
+    fn parses_modifiers_and_keys_in_any_case() {
+        let c = chord("ctrl + shift+s");
+        assert!(c.ctrl && c.shift && !c.alt && !c.meta);
+        assert_eq!(c.key, ChordKey::Char("S".to_string()));
+        let c = chord("Option+Cmd+f3");
+        assert!(c.alt && c.meta && !c.ctrl);
+        assert_eq!(c.key, ChordKey::Named(NamedKey::F3));
+        assert_eq!(chord("plus").key, ChordKey::Char("+".to_string()));
+        assert_eq!(chord("?").key, ChordKey::Char("?".to_string()));
+        assert!(KeyChord::parse("").is_err());
+        assert!(KeyChord::parse("Ctrl+").is_err());
+        // "+" separates the parts; the key itself is written "Plus".
+        assert!(KeyChord::parse("+").is_err());
+        assert!(KeyChord::parse("Hyper+S").is_err());
+        assert!(KeyChord::parse("Ctrl+SS").is_err());
+    }
+
+    #[test]
+    fn display_round_trips_to_canonical_names() {
+        for (input, shown) in [
+            ("ctrl+s", "Ctrl+S"),
+            ("alt+shift+ctrl+win+x", "Ctrl+Shift+Alt+Super+X"),
+            ("pageup", "PageUp"),
+            ("plus", "Plus"),
+            ("shift+plus", "Shift+Plus"),
+            ("escape", "Escape"),
+        ] {
+            let c = chord(input);
+            assert_eq!(c.to_string(), shown);
+            assert_eq!(chord(shown), c);
+        }
+    }
+
+    #[test]
+    fn matches_the_key_and_exact_modifiers() {
+        let none = ModifiersState::empty();
+        let c = chord("Ctrl+S");
+        assert!(c.matches(&char_key("s"), ModifiersState::CONTROL));
+        assert!(c.matches(&char_key("S"), ModifiersState::CONTROL));
+        assert!(!c.matches(&char_key("s"), none));
+        assert!(!c.matches(
+            &char_key("s"),
+            ModifiersState::CONTROL | ModifiersState::ALT
+        ));
+        // Shift counts for letters.
+        assert!(!c.matches(
+            &char_key("S"),
+            ModifiersState::CONTROL | ModifiersState::SHIFT
+        ));
+        assert!(chord("Shift+S").matches(&char_key("S"), ModifiersState::SHIFT));
+        assert!(!chord("Shift+S").matches(&char_key("s"), none));
+        let f3 = chord("F3");
+        assert!(f3.matches(&Key::Named(NamedKey::F3), none));
+        assert!(!f3.matches(&Key::Named(NamedKey::F4), none));
+        assert!(!f3.matches(&char_key("3"), none));
+    }
+
+    #[test]
+    fn shift_is_ignored_for_non_letters() {
+        // "?" and "+" take Shift on US keyboards, but the character already says so.
+        let question = chord("?");
+        assert!(question.matches(&char_key("?"), ModifiersState::SHIFT));
+        assert!(question.matches(&char_key("?"), ModifiersState::empty()));
+        let plus = chord("Plus");
+        assert!(plus.matches(&char_key("+"), ModifiersState::SHIFT));
+        assert!(plus.matches(&char_key("+"), ModifiersState::empty()));
+        assert!(!plus.matches(&char_key("+"), ModifiersState::CONTROL));
+    }
+
+    #[test]
+    fn validate_normalizes_and_fills_in_defaults() {
+        let map = validate(with("screenshot", "ctrl+shift+s")).unwrap();
+        assert_eq!(map["screenshot"], "Ctrl+Shift+S");
+        assert_eq!(map.len(), ACTIONS.len());
+        for (name, _, default) in ACTIONS {
+            if *name != "screenshot" {
+                assert_eq!(map[*name], *default, "{name}");
+            }
+        }
+        // An empty chord unbinds the action.
+        assert_eq!(validate(with("screenshot", " ")).unwrap()["screenshot"], "");
+    }
+
+    #[test]
+    fn validate_rejects_unknown_actions_bad_chords_and_duplicates() {
+        assert!(validate(with("no_such_action", "K")).is_err());
+        assert!(validate(with("screenshot", "Ctrl+SS")).is_err());
+        let mut map = with("screenshot", "k");
+        map.insert("rotate".to_string(), "K".to_string());
+        let err = validate(map).unwrap_err();
+        assert!(
+            err.contains("rotate") && err.contains("screenshot"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn defaults_are_not_filled_in_over_taken_chords() {
+        // "R" is rotate's default; the screenshot took it, so rotate stays unbound.
+        let map = validate(with("screenshot", "r")).unwrap();
+        assert_eq!(map["screenshot"], "R");
+        assert_eq!(map["rotate"], "");
+        // Rebinding rotate frees "R" without touching other defaults.
+        let mut map = with("screenshot", "R");
+        map.insert("rotate".to_string(), "Shift+R".to_string());
+        let map = validate(map).unwrap();
+        assert_eq!(map["rotate"], "Shift+R");
+        assert_eq!(map["toggle_fullscreen"], "F");
+        assert_eq!(validate(BTreeMap::new()).unwrap(), defaults());
+    }
+
+    #[test]
+    fn click_through_needs_a_modifier_unless_it_is_a_function_key() {
+        let map = validate(with(CLICK_THROUGH, "ctrl+shift+k")).unwrap();
+        assert_eq!(map[CLICK_THROUGH], "Ctrl+Shift+K");
+        let map = validate(with(CLICK_THROUGH, "f9")).unwrap();
+        assert_eq!(map[CLICK_THROUGH], "F9");
+        assert!(validate(with(CLICK_THROUGH, "K")).is_err());
+        assert!(validate(with(CLICK_THROUGH, "Shift+K")).is_err());
+        // Only letters, digits and F-keys can be registered with the system.
+        assert!(validate(with(CLICK_THROUGH, "Ctrl+Space")).is_err());
+        assert!(validate(with(CLICK_THROUGH, "Ctrl+?")).is_err());
+        // Window shortcuts keep allowing bare keys.
+        assert!(validate(with("toggle_pause", "K")).is_ok());
+    }
+
+    #[test]
+    fn click_through_may_not_reuse_another_shortcut() {
+        let mut map = with(CLICK_THROUGH, "Ctrl+C");
+        map.insert("copy_frame".to_string(), "Ctrl+C".to_string());
+        assert!(validate(map).is_err());
+        // Its default is left out when another action took it.
+        let map = validate(with("toggle_pause", "ctrl+alt+p")).unwrap();
+        assert_eq!(map[CLICK_THROUGH], "");
+        assert_eq!(
+            validate(BTreeMap::new()).unwrap()[CLICK_THROUGH],
+            "Ctrl+Alt+P"
+        );
+    }
+
+    #[test]
+    fn virtual_keys_of_letters_digits_and_function_keys() {
+        let key = |s: &str| KeyChord::parse(s).unwrap().virtual_key();
+        assert_eq!(key("Ctrl+p"), Some(u32::from(b'P')));
+        assert_eq!(key("Alt+7"), Some(u32::from(b'7')));
+        assert_eq!(key("F1"), Some(0x70));
+        assert_eq!(key("F12"), Some(0x7B));
+        assert_eq!(key("Escape"), None);
+        assert_eq!(key("-"), None);
+    }
+}
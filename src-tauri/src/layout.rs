@@ -0,0 +1,229 @@
+use crate::arrange::{self, ArrangeMode};
+use crate::error_log::{self, Level};
+use crate::persist;
+use crate::preview::WindowGeometry;
+use crate::{
+    start_capture_session, stop_capture_session, store_settings, target_id, target_title,
+    test_pattern, validate_settings, CaptureSettings, CaptureState, SessionOptions,
+};
+use scap::{get_all_targets, Target};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+const LAYOUTS_FILENAME: &str = "layouts.json";
+
+/// One capture session of a layout: what was captured, how, and where its preview sat.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct LayoutSession {
+    target_id: Option<u32>,
+    /// Title at save time; used to find the target again when its id changed (e.g. app restarted).
+    #[serde(default)]
+    target_title: Option<String>,
+    settings: CaptureSettings,
+    #[serde(default)]
+    geometry: Option<WindowGeometry>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Layout {
+    sessions: Vec<LayoutSession>,
+    /// Last arrangement applied while this layout was active; re-applied on load.
+    #[serde(default)]
+    arrangement: Option<ArrangeMode>,
+}
+
+fn layouts_path(app: &AppHandle) -> Option<PathBuf> {
+    app.path().app_data_dir().ok().map(|p| p.join(LAYOUTS_FILENAME))
+}
+
+fn load_layouts(app: &AppHandle) -> BTreeMap<String, Layout> {
+    layouts_path(app)
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+fn save_layouts(app: &AppHandle, layouts: &BTreeMap<String, Layout>) -> Result<(), String> {
+    let path = layouts_path(app).ok_or("App data dir not available")?;
+    let contents = serde_json::to_string_pretty(layouts).map_err(|e| e.to_string())?;
+    persist::write_atomic(&path, contents.as_bytes()).map_err(|e| e.to_string())
+}
+
+/// Finds the session's target in the current target list: by id first, then by title.
+/// Ok(None) means "default target" (nothing pinned).
+fn resolve_target(session: &LayoutSession, targets: &[Target]) -> Result<Option<u32>, String> {
+    let Some(id) = session.target_id else {
+        return Ok(None);
+    };
+    if id == test_pattern::TARGET_ID || targets.iter().any(|t| target_id(t) == id) {
+        return Ok(Some(id));
+    }
+    let title = session.target_title.as_deref().unwrap_or_default();
+    if !title.is_empty() {
+        if let Some(t) = targets.iter().find(|t| target_title(t) == title) {
+            return Ok(Some(target_id(t)));
+        }
+    }
+    let name = if title.is_empty() {
+        format!("#{id}")
+    } else {
+        format!("\"{title}\"")
+    };
+    Err(format!("Target {name} is not available; session skipped."))
+}
+
+/// Replaces the running sessions with those of layout `name`. Sessions whose target is gone are
+/// skipped; their warnings are returned and emitted as `layout-warning` events. Each session's
+/// settings are applied before it starts, so the last one's remain selected.
+pub fn apply_layout(app: &AppHandle, name: &str) -> Result<Vec<String>, String> {
+    let layouts = load_layouts(app);
+    let layout = layouts
+        .get(name)
+        .ok_or_else(|| format!("No layout named \"{name}\"."))?;
+    let targets = get_all_targets();
+    let state = app.state::<CaptureState>();
+    let mut warnings = Vec::new();
+    let mut started = false;
+    stop_capture_session(&state);
+
+    for session in &layout.sessions {
+        let target = match resolve_target(session, &targets) {
+            Ok(t) => t,
+            Err(w) => {
+                warnings.push(w);
+                continue;
+            }
+        };
+        let mut settings = session.settings.clone();
+        settings.target_id = target;
+        // Like a missing target, a session that fails to start does not stop the others.
+        let options = SessionOptions {
+            geometry: session.geometry,
+            ..Default::default()
+        };
+        let result = validate_settings(settings)
+            .and_then(|settings| store_settings(app, settings))
+            .and_then(|()| start_capture_session(app, options));
+        if let Err(e) = result {
+            warnings.push(format!("{e} Session skipped."));
+            continue;
+        }
+        started = true;
+    }
+
+    *state.active_layout.lock().unwrap() = Some(name.to_string());
+    if let (true, Some(mode)) = (started, layout.arrangement) {
+        if let Err(e) = arrange::arrange(app, mode, None) {
+            warnings.push(e);
+        }
+    }
+
+    for w in &warnings {
+        error_log::record(app, Level::Warning, w.clone());
+        let _ = app.emit("layout-warning", w);
+    }
+    Ok(warnings)
+}
+
+/// Stores `mode` as the arrangement of layout `name` (no-op if the layout was deleted).
+pub fn remember_arrangement(app: &AppHandle, name: &str, mode: ArrangeMode) -> Result<(), String> {
+    let mut layouts = load_layouts(app);
+    match layouts.get_mut(name) {
+        Some(layout) if layout.arrangement != Some(mode) => {
+            layout.arrangement = Some(mode);
+            save_layouts(app, &layouts)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Value of `--layout <name>` / `--layout=<name>` on the command line.
+pub fn cli_layout_name() -> Option<String> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--layout" {
+            return args.next();
+        }
+        if let Some(name) = arg.strip_prefix("--layout=") {
+            return Some(name.to_string());
+        }
+    }
+    None
+}
+
+/// Saves the running sessions (or just the current settings when idle) under `name`.
+#[tauri::command]
+pub fn save_layout(app: AppHandle, name: String, state: State<CaptureState>) -> Result<(), String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Layout name must not be empty.".to_string());
+    }
+    let settings = state.settings.lock().unwrap().clone();
+    let mut sessions: Vec<LayoutSession> = state
+        .preview_sessions()
+        .iter()
+        .map(|p| {
+            let mut settings = settings.clone();
+            // Sessions started for another target than the selected one.
+            if p.target.is_some() {
+                settings.target_id = p.target;
+                settings.target_title = p.target_title.clone();
+                settings.target_pid = None;
+            }
+            LayoutSession {
+                target_id: settings.target_id,
+                target_title: settings.target_title.clone(),
+                settings,
+                geometry: p.geometry(),
+            }
+        })
+        .collect();
+    if sessions.is_empty() {
+        let target_title = settings.target_id.and_then(|id| {
+            get_all_targets()
+                .iter()
+                .find(|t| target_id(t) == id)
+                .map(|t| target_title(t).to_string())
+        });
+        sessions.push(LayoutSession {
+            target_id: settings.target_id,
+            target_title,
+            settings,
+            geometry: None,
+        });
+    }
+    let arrangement = *state.last_arrangement.lock().unwrap();
+    let mut layouts = load_layouts(&app);
+    layouts.insert(
+        name.clone(),
+        Layout {
+            sessions,
+            arrangement,
+        },
+    );
+    save_layouts(&app, &layouts)?;
+    *state.active_layout.lock().unwrap() = Some(name);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn load_layout(app: AppHandle, name: String) -> Result<Vec<String>, String> {
+    apply_layout(&app, &name)
+}
+
+#[tauri::command]
+pub fn list_layouts(app: AppHandle) -> Vec<String> {
+    load_layouts(&app).into_keys().collect()
+}
+
+#[tauri::command]
+pub fn delete_layout(app: AppHandle, name: String) -> Result<(), String> {
+    let mut layouts = load_layouts(&app);
+    if layouts.remove(&name).is_none() {
+        return Err(format!("No layout named \"{name}\"."));
+    }
+    save_layouts(&app, &layouts)
+}
@@ -0,0 +1,259 @@
+use std::time::{Duration, Instant};
+
+/// How often frames are analyzed.
+const CHECK_INTERVAL: Duration = Duration::from_millis(250);
+/// How long a detection must hold before the crop changes.
+const STABLE_FOR: Duration = Duration::from_millis(1500);
+/// Rows/columns with a mean luma (0..=255) up to this count as black.
+const BLACK_LEVEL: u64 = 16;
+/// Detections differing by at most this many pixels per edge count as the same.
+const TOLERANCE: u32 = 4;
+
+/// Finds black bars (letterbox/pillarbox) in the captured frames and reports the crop removing
+/// them once it has been stable for a while, so flickering content does not keep re-cropping.
+#[derive(Debug, Default)]
+pub struct BlackBarDetector {
+    frame_size: (u32, u32),
+    last_check: Option<Instant>,
+    /// Latest detection and since when it has held.
+    candidate: Option<((u32, u32, u32, u32), Instant)>,
+    /// Crop currently reported; None while no bars are cropped.
+    applied: Option<(u32, u32, u32, u32)>,
+}
+
+impl BlackBarDetector {
+    /// Feeds a packed 0x00RRGGBB frame and returns the crop (x, y, width, height) to apply.
+    pub fn update(
+        &mut self,
+        buffer: &[u32],
+        width: u32,
+        height: u32,
+        now: Instant,
+    ) -> Option<(u32, u32, u32, u32)> {
+        if self.frame_size != (width, height) {
+            *self = Self {
+                frame_size: (width, height),
+                ..Self::default()
+            };
+        }
+        if self
+            .last_check
+            .is_some_and(|t| now.duration_since(t) < CHECK_INTERVAL)
+        {
+            return self.applied;
+        }
+        self.last_check = Some(now);
+        // Fully black frames (fades, loading screens) say nothing about the bars.
+        let Some(detected) = detect(buffer, width, height) else {
+            return self.applied;
+        };
+        match self.candidate {
+            Some((rect, since)) if close(rect, detected) => {
+                if now.duration_since(since) >= STABLE_FOR {
+                    let full = (0, 0, width, height);
+                    let current = self.applied.unwrap_or(full);
+                    if !close(current, rect) {
+                        self.applied = (rect != full).then_some(rect);
+                    }
+                }
+            }
+            _ => self.candidate = Some((detected, now)),
+        }
+        self.applied
+    }
+}
+
+fn close(a: (u32, u32, u32, u32), b: (u32, u32, u32, u32)) -> bool {
+    a.0.abs_diff(b.0) <= TOLERANCE
+        && a.1.abs_diff(b.1) <= TOLERANCE
+        && (a.0 + a.2).abs_diff(b.0 + b.2) <= TOLERANCE
+        && (a.1 + a.3).abs_diff(b.1 + b.3) <= TOLERANCE
+}
+
+/// Bounds of the non-black rows and columns; None when the whole frame is black.
+fn detect(buffer: &[u32], width: u32, height: u32) -> Option<(u32, u32, u32, u32)> {
+    let (w, h) = (width as usize, height as usize);
+    if w == 0 || h == 0 || buffer.len() < w * h {
+        return None;
+    }
+    let mut rows = vec![0u64; h];
+    let mut cols = vec![0u64; w];
+    for (row, sum) in buffer.chunks_exact(w).take(h).zip(rows.iter_mut()) {
+        for (&p, col) in row.iter().zip(cols.iter_mut()) {
+            let luma = (((p >> 16) & 0xFF) * 77 + ((p >> 8) & 0xFF) * 150 + (p & 0xFF) * 29) >> 8;
+            *sum += luma as u64;
+            *col += luma as u64;
+        }
+    }
+    let lit = |sums: &[u64], len: usize| {
+        let first = sums.iter().position(|&s| s > BLACK_LEVEL * len as u64)?;
+        let last = sums.iter().rposition(|&s| s > BLACK_LEVEL * len as u64)?;
+        Some((first as u32, (last - first + 1) as u32))
+    };
+    let (y, crop_h) = lit(&rows, w)?;
+    let (x, crop_w) = lit(&cols, h)?;
+    Some((x, y, crop_w, crop_h))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const W: u32 = 64;
+    const H: u32 = 48;
+    const FULL: (u32, u32, u32, u32) = (0, 0, W, H);
+    /// Bars left and right.
+    const PILLARBOX: (u32, u32, u32, u32) = (8, 0, 48, H);
+
+    /// A `W`×`H` frame, white inside `rect` and black around it.
+    fn frame(rect: (u32, u32, u32, u32)) -> Vec<u32> {
+        let (x, y, w, h) = rect;
+        (0..H)
+            .flat_map(|row| {
+                (0..W).map(move |col| {
+                    let inside = (x..x + w).contains(&col) && (y..y + h).contains(&row);
+                    if inside {
+                        0x00FF_FFFF
+                    } else {
+                        0
+                    }
+                })
+            })
+            .collect()
+    }
+
+    /// Feeds `buffer` once per check interval from `*now` until `duration` has passed; returns
+    /// the last crop.
+    fn feed(
+        detector: &mut BlackBarDetector,
+        buffer: &[u32],
+        now: &mut Instant,
+        duration: Duration,
+    ) -> Option<(u32, u32, u32, u32)> {
+        let end = *now + duration;
+        let mut crop = None;
+        while *now <= end {
+            crop = detector.update(buffer, W, H, *now);
+            *now += CHECK_INTERVAL;
+        }
+        crop
+    }
+
+    #[test]
+    fn detects_pillarbox_and_letterbox_bars() {
+        assert_eq!(detect(&frame(PILLARBOX), W, H), Some(PILLARBOX));
+        let letterbox = (0, 6, W, 36);
+        assert_eq!(detect(&frame(letterbox), W, H), Some(letterbox));
+        assert_eq!(detect(&frame(FULL), W, H), Some(FULL));
+        // Dark gray still counts as black.
+        let dim = vec![0x0010_1010; (W * H) as usize];
+        assert_eq!(detect(&dim, W, H), None);
+    }
+
+    #[test]
+    fn all_black_or_short_frames_detect_nothing() {
+        let black = frame((0, 0, 0, 0));
+        assert_eq!(detect(&black, W, H), None);
+        assert_eq!(detect(&frame(FULL)[1..], W, H), None);
+        assert_eq!(detect(&[], 0, 0), None);
+    }
+
+    #[test]
+    fn crops_once_the_bars_held_long_enough() {
+        let mut detector = BlackBarDetector::default();
+        let mut now = Instant::now();
+        let bars = frame(PILLARBOX);
+        let almost = STABLE_FOR - CHECK_INTERVAL;
+        assert_eq!(feed(&mut detector, &bars, &mut now, almost), None);
+        assert_eq!(
+            feed(&mut detector, &bars, &mut now, Duration::ZERO),
+            Some(PILLARBOX)
+        );
+    }
+
+    #[test]
+    fn jitter_within_tolerance_keeps_the_crop() {
+        let mut detector = BlackBarDetector::default();
+        let mut now = Instant::now();
+        let (a, b) = (frame(PILLARBOX), frame((10, 0, 46, H)));
+        // Flickering edges a few pixels apart still settle.
+        let end = now + STABLE_FOR;
+        let mut crop = None;
+        for buffer in [&a, &b].into_iter().cycle() {
+            crop = detector.update(buffer, W, H, now);
+            now += CHECK_INTERVAL;
+            if now > end {
+                break;
+            }
+        }
+        assert_eq!(crop, Some(PILLARBOX));
+        assert_eq!(
+            feed(&mut detector, &b, &mut now, STABLE_FOR * 2),
+            Some(PILLARBOX)
+        );
+        // Wider bars than that are a new detection, and have to hold too.
+        let wider = (8 + TOLERANCE + 1, 0, 48 - 2 * (TOLERANCE + 1), H);
+        let buffer = frame(wider);
+        let almost = STABLE_FOR - CHECK_INTERVAL;
+        assert_eq!(
+            feed(&mut detector, &buffer, &mut now, almost),
+            Some(PILLARBOX)
+        );
+        assert_eq!(
+            feed(&mut detector, &buffer, &mut now, Duration::ZERO),
+            Some(wider)
+        );
+    }
+
+    #[test]
+    fn black_frames_keep_the_crop() {
+        let mut detector = BlackBarDetector::default();
+        let mut now = Instant::now();
+        let black = frame((0, 0, 0, 0));
+        assert_eq!(feed(&mut detector, &black, &mut now, STABLE_FOR), None);
+        feed(&mut detector, &frame(PILLARBOX), &mut now, STABLE_FOR);
+        assert_eq!(
+            feed(&mut detector, &black, &mut now, STABLE_FOR * 2),
+            Some(PILLARBOX)
+        );
+    }
+
+    #[test]
+    fn drops_the_crop_once_content_fills_the_frame() {
+        let mut detector = BlackBarDetector::default();
+        let mut now = Instant::now();
+        feed(&mut detector, &frame(PILLARBOX), &mut now, STABLE_FOR);
+        let full = frame(FULL);
+        let almost = STABLE_FOR - CHECK_INTERVAL;
+        assert_eq!(
+            feed(&mut detector, &full, &mut now, almost),
+            Some(PILLARBOX)
+        );
+        assert_eq!(feed(&mut detector, &full, &mut now, Duration::ZERO), None);
+    }
+
+    #[test]
+    fn frames_between_checks_are_not_analyzed() {
+        let mut detector = BlackBarDetector::default();
+        let start = Instant::now();
+        let mut now = start;
+        feed(&mut detector, &frame(PILLARBOX), &mut now, STABLE_FOR);
+        // Full frames arriving faster than the check interval count once per interval.
+        let full = frame(FULL);
+        let at = now;
+        for ms in 0..CHECK_INTERVAL.as_millis() as u64 {
+            detector.update(&full, W, H, at + Duration::from_millis(ms));
+        }
+        assert_eq!(detector.candidate.map(|(_, since)| since), Some(at));
+        assert_eq!(detector.applied, Some(PILLARBOX));
+    }
+
+    #[test]
+    fn a_new_frame_size_starts_over() {
+        let mut detector = BlackBarDetector::default();
+        let mut now = Instant::now();
+        feed(&mut detector, &frame(PILLARBOX), &mut now, STABLE_FOR);
+        let bigger = vec![0x00FF_FFFF; (2 * W * H) as usize];
+        assert_eq!(detector.update(&bigger, 2 * W, H, now), None);
+    }
+}
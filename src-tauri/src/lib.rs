@@ -1,60 +1,449 @@
+mod about;
+mod anchor;
+mod apps;
+mod arrange;
+pub mod buffer_pool;
+mod capabilities;
+mod clipboard;
+mod crop;
+mod current_frame;
+mod delay;
+mod display;
+mod error_log;
+mod follow_focus;
+mod frame_limits;
+mod global_hotkey;
+mod handoff;
+mod keybindings;
+mod layout;
+mod letterbox;
+mod mirror;
+mod monitors;
+mod overlay;
+mod overlay_mode;
+mod pacing;
+pub mod parallel;
+mod persist;
+mod pip;
 mod preview;
+mod recording;
+mod recovery;
+mod replay;
+pub mod resize;
+mod screenshot;
+mod self_test;
+mod sound;
+mod stats;
+mod target_cache;
+mod target_match;
+mod target_menu;
+mod target_watch;
+mod test_pattern;
+mod toggle_menu;
+mod updater;
+mod window_region;
+pub mod yuv;
+mod zoom;
 
-use preview::{FrameData, PreviewState};
+use anchor::Anchor;
+use arrange::{ArrangeMode, SizeMenu, SizePreset};
+use buffer_pool::BufferPool;
+use crop::{AppliedCrop, CropMode, CropRegion};
+use delay::DelayLine;
+use display::{
+    ColorBlindness, ColorFilter, DisplayOptions, DisplayOptionsSlot, Filtering, HdrOutput,
+    PictureAdjustments, PresentPreference, ScaleMode, WindowOptions,
+};
+use keybindings::{Keybindings, KeybindingsSlot};
+use error_log::{ErrorLog, Level};
+use follow_focus::FocusTracker;
+use frame_limits::{FrameLimits, RejectedFrames};
+use handoff::Receiver;
+use letterbox::BlackBarDetector;
+use mirror::MirrorSnapshot;
+use overlay::{Crosshair, Grid, GridMode};
+use pacing::{DynamicRate, FrameClock, FrameThrottle};
+use persist::DebouncedWriter;
+use pip::{Corner, Pip};
+use preview::{
+    FrameBytes, FrameData, PixelFormat, PreviewAction, PreviewState, PreviewStateSlot,
+    RenderStatusSlot, WindowGeometry,
+};
+use replay::{ReplayBuffer, ReplayFormat};
+use resize::{ResizeAlgorithm, ResizeOn};
+use sound::{Cue, SoundCues};
+use target_cache::{TargetCache, Targets};
+use test_pattern::TestPattern;
+use target_menu::TargetMenu;
+use toggle_menu::{Toggle, ToggleMenu};
+use updater::UpdateChannel;
+use window_region::WindowRegion;
+use yuv::YuvFormat;
 use scap::capturer::{Capturer, Options, Resolution as ScapResolution};
 use scap::frame::{Frame, FrameType};
 use scap::{get_all_targets, has_permission, is_supported, request_permission, Target};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::PathBuf;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::{Duration, Instant};
 use tauri::{
-    menu::{Menu, MenuItem, PredefinedMenuItem},
+    menu::{CheckMenuItem, IsMenuItem, Menu, MenuItem, PredefinedMenuItem, Submenu},
     tray::TrayIconBuilder,
 };
-use tauri::{AppHandle, Emitter, Manager, State};
+use tauri::{AppHandle, Emitter, Manager, State, WebviewWindowBuilder};
+use tauri_plugin_notification::NotificationExt;
 
 const DEFAULT_CAPTURE_FPS: u32 = 60;
 const DEFAULT_RESOLUTION: &str = "captured";
 const SETTINGS_FILENAME: &str = "settings.json";
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+const MAX_IDLE_STOP_MINUTES: u32 = 24 * 60;
+const MAX_CAPTURE_DURATION_SECS: u32 = 7 * 24 * 3600;
+/// How often a lost target is looked for while reconnecting.
+const RECONNECT_POLL_INTERVAL: Duration = Duration::from_secs(1);
 
 fn settings_path(app: &AppHandle) -> Option<PathBuf> {
     app.path().app_data_dir().ok().map(|p| p.join(SETTINGS_FILENAME))
 }
 
-fn load_settings_from_disk(app: &AppHandle) -> Option<CaptureSettings> {
+/// Loads the settings file; the flag is set when an old file was migrated and should be saved.
+fn load_settings_from_disk(app: &AppHandle) -> Option<(CaptureSettings, bool)> {
     let path = settings_path(app)?;
     let contents = fs::read_to_string(&path).ok()?;
-    serde_json::from_str(&contents).ok()
+    let mut value: serde_json::Value = serde_json::from_str(&contents).ok()?;
+    let legacy_index = value.as_object_mut()?.remove("target_index");
+    let legacy_hotkey = value.as_object_mut()?.remove("click_through_hotkey");
+    let migrated = legacy_index.is_some() || legacy_hotkey.is_some();
+    let mut settings: CaptureSettings = serde_json::from_value(value).ok()?;
+    // Older files stored the target's position in get_all_targets(). Resolving it once against
+    // today's list is as good as that index ever was; from then on the ID is kept.
+    if let Some(index) = legacy_index.and_then(|v| v.as_u64()) {
+        if settings.target_id.is_none() {
+            if let Some(t) = get_all_targets().get(index as usize) {
+                settings.target_id = Some(target_id(t));
+                settings.target_title = Some(target_title(t).to_string());
+            }
+        }
+    }
+    // The click-through hotkey used to be a setting of its own; it is now a shortcut action.
+    // Kept only if it still validates, e.g. not bound to another action meanwhile.
+    if let Some(serde_json::Value::String(hotkey)) = legacy_hotkey {
+        let mut map = settings.keybindings.clone();
+        map.insert(keybindings::CLICK_THROUGH.to_string(), hotkey);
+        if let Ok(map) = keybindings::validate(map) {
+            settings.keybindings = map;
+        }
+    }
+    Some((settings, migrated))
 }
 
+/// Schedules a debounced, atomic write of the settings file.
 fn save_settings_to_disk(app: &AppHandle, settings: &CaptureSettings) -> Result<(), String> {
     let path = settings_path(app).ok_or("App data dir not available")?;
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
-    }
     let contents = serde_json::to_string_pretty(settings).map_err(|e| e.to_string())?;
-    fs::write(&path, contents).map_err(|e| e.to_string())
+    app.state::<CaptureState>().settings_writer.schedule(app, path, contents);
+    Ok(())
 }
 
 fn default_show_cursor() -> bool {
     true
 }
 
+fn default_true() -> bool {
+    true
+}
+
+fn default_reconnect_timeout_secs() -> u32 {
+    60
+}
+
+fn default_severity() -> f32 {
+    1.0
+}
+
+fn default_overlay_opacity() -> f32 {
+    0.5
+}
+
+fn default_pip_width() -> u32 {
+    pip::DEFAULT_PIP_WIDTH
+}
+
+fn default_letterbox_color() -> String {
+    "#000000".to_string()
+}
+
+fn default_crosshair_color() -> String {
+    "#ff0000".to_string()
+}
+
+fn default_crosshair_thickness() -> u32 {
+    2
+}
+
+fn default_grid_cells() -> u32 {
+    4
+}
+
+fn default_grid_color() -> String {
+    "#ffffff".to_string()
+}
+
+fn default_grid_opacity() -> f32 {
+    0.5
+}
+
+fn default_window_opacity() -> f32 {
+    1.0
+}
+
+fn default_replay_memory_mb() -> u32 {
+    512
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct CaptureSettings {
     fps: u32,
     resolution: String,
+    /// Scaler used when `resolution` differs from the captured size and frames are resized on
+    /// the CPU, and for get_current_frame.
     #[serde(default)]
-    target_index: Option<usize>,
-    /// Target ID (stable across get_all_targets calls). Used on Windows where enumeration order is non-deterministic.
+    resize_algorithm: ResizeAlgorithm,
+    #[serde(default)]
+    resize_on: ResizeOn,
+    /// Drop to a few frames per second while the content is static; full rate on change.
+    #[serde(default)]
+    dynamic_fps: bool,
+    /// Don't scale, upload or record frames identical to the previous one.
+    #[serde(default = "default_true")]
+    skip_duplicate_frames: bool,
+    /// Convert frames to pixels on several threads; off keeps it on the capture thread.
+    #[serde(default = "default_true")]
+    parallel_conversion: bool,
+    /// Target ID, stable across get_all_targets calls (unlike the position in the list).
     #[serde(default)]
     target_id: Option<u32>,
+    /// Title of `target_id` when it was selected; key for per-target settings and the fallback
+    /// when the ID is gone (e.g. the application restarted). Set by the backend.
+    #[serde(default)]
+    target_title: Option<String>,
+    /// Targets of the sessions that ran last, oldest first; restarted together from the tray
+    /// in headless mode.
+    #[serde(default)]
+    session_targets: Vec<u32>,
+    /// Capture all windows of this process instead of `target_id` (Windows only).
+    #[serde(default)]
+    target_pid: Option<u32>,
+    /// Switch the capture to whichever window has focus, starting from `target_id` (Windows
+    /// only; not combined with `target_pid` or `include_child_windows`).
+    #[serde(default)]
+    follow_focus: bool,
+    /// On Wayland this selects the portal's embedded (on) or hidden (off) cursor mode.
     #[serde(default = "default_show_cursor")]
     show_cursor: bool,
+    /// For window targets, also show popups and tooltips drawn over the window (Windows only;
+    /// see `WindowRegion` for the limits).
+    #[serde(default)]
+    include_child_windows: bool,
+    /// For window targets, crop away the title bar and borders (Windows only; not combined with
+    /// `include_child_windows` or application capture).
+    #[serde(default)]
+    trim_window_chrome: bool,
+    /// Mask the rounded corners of window captures; defaults to on where the OS rounds them.
+    #[serde(default = "window_region::has_rounded_corners")]
+    mask_window_corners: bool,
+    /// Stop capture after this many minutes without a visible change (0 = disabled).
+    #[serde(default)]
+    idle_stop_minutes: u32,
+    /// Seconds to wait before restarting a capture that was stopped for being idle (0 = no restart).
+    #[serde(default)]
+    idle_restart_secs: u32,
+    /// Stop each capture this many seconds after it started, unless start_capture is given a
+    /// duration of its own (0 = unlimited).
+    #[serde(default)]
+    capture_duration_secs: u32,
+    /// When capture fails (e.g. the captured application restarted), wait for the target to
+    /// reappear and continue instead of ending the session.
+    #[serde(default)]
+    auto_reconnect: bool,
+    /// Seconds to wait for the target before giving up with "capture-error".
+    #[serde(default = "default_reconnect_timeout_secs")]
+    reconnect_timeout_secs: u32,
+    /// Start capturing the saved target when LiteView launches.
+    #[serde(default)]
+    autostart_capture: bool,
+    /// Seconds of the primary session kept for save_replay (0 = no replay buffer).
+    #[serde(default)]
+    replay_secs: u32,
+    /// Memory the replay buffer may use; larger frames are downscaled to fit.
+    #[serde(default = "default_replay_memory_mb")]
+    replay_memory_mb: u32,
+    #[serde(default)]
+    replay_format: ReplayFormat,
+    #[serde(default)]
+    color_blindness: ColorBlindness,
+    /// 0.0..=1.0; how strongly the color-blindness simulation is applied.
+    #[serde(default = "default_severity")]
+    color_blindness_severity: f32,
+    /// "none" | "grayscale" | "invert"; preview only.
+    #[serde(default)]
+    color_filter: ColorFilter,
+    /// "auto" | "on" | "off": scRGB output on HDR-capable surfaces.
+    #[serde(default)]
+    hdr_output: HdrOutput,
+    /// "vsync" | "low-latency": how the preview presents frames (see get_capture_status for
+    /// the mode in use).
+    #[serde(default)]
+    present_mode: PresentPreference,
+    /// Frame-time graph overlay in the preview (default shortcut F3).
+    #[serde(default)]
+    show_frame_graph: bool,
+    /// FPS, resolution and source name drawn in the preview (default shortcut I). Only in the
+    /// preview window; screenshots and recordings use the captured frames.
+    #[serde(default)]
+    show_info_overlay: bool,
+    /// Crosshair at the center of the displayed image (default shortcut X); preview only, like
+    /// `show_info_overlay`.
+    #[serde(default)]
+    show_crosshair: bool,
+    /// "#rrggbb".
+    #[serde(default = "default_crosshair_color")]
+    crosshair_color: String,
+    /// Line width in physical pixels (1-16).
+    #[serde(default = "default_crosshair_thickness")]
+    crosshair_thickness: u32,
+    /// Space left free around the center, in physical pixels (0-200).
+    #[serde(default)]
+    crosshair_gap: u32,
+    /// Lines across the whole image instead of a small cross.
+    #[serde(default)]
+    crosshair_full: bool,
+    /// Composition grid over the image (default shortcut G cycles it); preview only.
+    #[serde(default)]
+    grid_mode: GridMode,
+    /// Cells of GridMode::Custom (2-32 each).
+    #[serde(default = "default_grid_cells")]
+    grid_columns: u32,
+    #[serde(default = "default_grid_cells")]
+    grid_rows: u32,
+    /// "#rrggbb".
+    #[serde(default = "default_grid_color")]
+    grid_color: String,
+    /// 0.1 ..= 1.0.
+    #[serde(default = "default_grid_opacity")]
+    grid_opacity: f32,
+    /// Picture adjustments for targets without their own entry in `target_adjustments`.
+    #[serde(default)]
+    adjustments: PictureAdjustments,
+    /// Per-target picture adjustments, keyed by target title.
+    #[serde(default)]
+    target_adjustments: BTreeMap<String, PictureAdjustments>,
+    /// Whether remembered crops keep their pixel size or scale with the source.
+    #[serde(default)]
+    crop_mode: CropMode,
+    /// Remembered crops, keyed by target title.
+    #[serde(default)]
+    target_crops: BTreeMap<String, CropRegion>,
+    /// Crop for targets without a remembered one of their own (including the default target).
+    #[serde(default)]
+    crop: Option<CropRegion>,
+    /// Crop black bars found in the frames; a remembered crop takes precedence.
+    #[serde(default)]
+    auto_crop_black_bars: bool,
+    /// Mirror the preview left-to-right.
+    #[serde(default)]
+    flip_horizontal: bool,
+    /// Mirror the preview top-to-bottom.
+    #[serde(default)]
+    flip_vertical: bool,
+    #[serde(default)]
+    scale_mode: ScaleMode,
+    #[serde(default)]
+    filtering: Filtering,
+    /// "#rrggbb"; the preview background, seen as the letterbox bars in ScaleMode::Fit and
+    /// ScaleMode::Integer.
+    #[serde(default = "default_letterbox_color")]
+    letterbox_color: String,
+    /// Show the preview this many seconds behind the capture (0 = live).
+    #[serde(default)]
+    delay_secs: u32,
+    /// Crossfade between frames of low-fps sources. Cosmetic: only the preview window shows it,
+    /// captured frames (and anything derived from them) are unchanged.
+    #[serde(default)]
+    frame_smoothing: bool,
+    /// Closing a preview window (or Escape) only hides it; capture keeps running and
+    /// show_preview or the tray brings it back.
+    #[serde(default)]
+    close_hides_preview: bool,
+    #[serde(default = "default_true")]
+    always_on_top: bool,
+    #[serde(default = "default_true")]
+    borderless: bool,
+    /// Let mouse input pass through the preview window.
+    #[serde(default)]
+    click_through: bool,
+    /// Keep the preview window off the taskbar and the Alt-Tab list; the tray's "Show window"
+    /// brings it to the front.
+    #[serde(default)]
+    skip_taskbar: bool,
+    /// Borderless fullscreen on this monitor index; None = windowed.
+    #[serde(default)]
+    fullscreen_monitor: Option<usize>,
+    /// 0.3..=1.0; opacity of the normal preview window (Windows only).
+    #[serde(default = "default_window_opacity")]
+    window_opacity: f32,
+    /// Open the first preview window where the last one was closed.
+    #[serde(default = "default_true")]
+    remember_window_geometry: bool,
+    /// Placement of the last windowed preview when its session ended.
+    #[serde(default)]
+    last_window_geometry: Option<WindowGeometry>,
+    /// Name of the monitor new preview windows open on (centered) when no placement is
+    /// restored; None = where the OS puts them.
+    #[serde(default)]
+    preview_monitor: Option<String>,
+    /// Preview as a translucent window over `overlay_geometry`; overrides the window options.
+    #[serde(default)]
+    overlay_mode: bool,
+    /// 0.1..=1.0; window opacity in overlay mode (Windows only).
+    #[serde(default = "default_overlay_opacity")]
+    overlay_opacity: f32,
+    /// Calibrated overlay rectangle.
+    #[serde(default)]
+    overlay_geometry: Option<WindowGeometry>,
+    /// Overlay placement is done: the window ignores mouse input.
+    #[serde(default)]
+    overlay_locked: bool,
+    /// Picture-in-picture: a small, always-on-top window off the taskbar docked to
+    /// `pip_corner`; overrides the window options (overlay mode wins).
+    #[serde(default)]
+    pip_mode: bool,
+    /// Logical pixels.
+    #[serde(default = "default_pip_width")]
+    pip_width: u32,
+    #[serde(default)]
+    pip_corner: Corner,
+    /// Window the preview follows (Windows only).
+    #[serde(default)]
+    anchor: Option<Anchor>,
+    /// Settings from before mirror mode was entered; Some while it is active.
+    #[serde(default)]
+    mirror_snapshot: Option<MirrorSnapshot>,
+    /// Frames larger than this are dropped (with a "frame-rejected" warning).
+    #[serde(default)]
+    frame_limits: FrameLimits,
+    #[serde(default)]
+    sound_cues: SoundCues,
+    /// Releases offered by the in-app updater.
+    #[serde(default)]
+    update_channel: UpdateChannel,
+    /// Action name -> key chord ("Ctrl+Shift+S", "F3"; empty = unbound).
+    #[serde(default = "keybindings::defaults")]
+    keybindings: BTreeMap<String, String>,
 }
 
 impl Default for CaptureSettings {
@@ -62,9 +451,233 @@ impl Default for CaptureSettings {
         Self {
             fps: DEFAULT_CAPTURE_FPS,
             resolution: DEFAULT_RESOLUTION.to_string(),
-            target_index: None,
+            resize_algorithm: ResizeAlgorithm::Auto,
+            resize_on: ResizeOn::Gpu,
+            dynamic_fps: false,
+            skip_duplicate_frames: true,
+            parallel_conversion: true,
             target_id: None,
+            target_title: None,
+            session_targets: Vec::new(),
+            target_pid: None,
+            follow_focus: false,
             show_cursor: true,
+            include_child_windows: false,
+            trim_window_chrome: false,
+            mask_window_corners: window_region::has_rounded_corners(),
+            idle_stop_minutes: 0,
+            idle_restart_secs: 0,
+            capture_duration_secs: 0,
+            auto_reconnect: false,
+            reconnect_timeout_secs: default_reconnect_timeout_secs(),
+            autostart_capture: false,
+            replay_secs: 0,
+            replay_memory_mb: default_replay_memory_mb(),
+            replay_format: ReplayFormat::Mp4,
+            color_blindness: ColorBlindness::None,
+            color_blindness_severity: 1.0,
+            color_filter: ColorFilter::None,
+            hdr_output: HdrOutput::Auto,
+            present_mode: PresentPreference::Vsync,
+            show_frame_graph: false,
+            show_info_overlay: false,
+            show_crosshair: false,
+            crosshair_color: default_crosshair_color(),
+            crosshair_thickness: default_crosshair_thickness(),
+            crosshair_gap: 0,
+            crosshair_full: false,
+            grid_mode: GridMode::Off,
+            grid_columns: default_grid_cells(),
+            grid_rows: default_grid_cells(),
+            grid_color: default_grid_color(),
+            grid_opacity: default_grid_opacity(),
+            adjustments: PictureAdjustments::default(),
+            target_adjustments: BTreeMap::new(),
+            crop_mode: CropMode::Absolute,
+            target_crops: BTreeMap::new(),
+            crop: None,
+            auto_crop_black_bars: false,
+            flip_horizontal: false,
+            flip_vertical: false,
+            scale_mode: ScaleMode::Stretch,
+            filtering: Filtering::Linear,
+            letterbox_color: default_letterbox_color(),
+            delay_secs: 0,
+            frame_smoothing: false,
+            close_hides_preview: false,
+            always_on_top: true,
+            borderless: true,
+            click_through: false,
+            skip_taskbar: false,
+            fullscreen_monitor: None,
+            window_opacity: default_window_opacity(),
+            remember_window_geometry: true,
+            last_window_geometry: None,
+            preview_monitor: None,
+            overlay_mode: false,
+            overlay_opacity: default_overlay_opacity(),
+            overlay_geometry: None,
+            pip_mode: false,
+            pip_width: default_pip_width(),
+            pip_corner: Corner::BottomRight,
+            overlay_locked: false,
+            anchor: None,
+            mirror_snapshot: None,
+            frame_limits: FrameLimits::default(),
+            sound_cues: SoundCues::default(),
+            update_channel: UpdateChannel::Stable,
+            keybindings: keybindings::defaults(),
+        }
+    }
+}
+
+impl CaptureSettings {
+    /// Whether the selected target is the built-in test pattern rather than a scap target.
+    fn uses_test_pattern(&self) -> bool {
+        self.target_pid.is_none() && self.target_id == Some(test_pattern::TARGET_ID)
+    }
+
+    /// Adjustments for the selected target, or the global ones if it has none of its own.
+    fn effective_adjustments(&self) -> PictureAdjustments {
+        self.target_title
+            .as_ref()
+            .and_then(|title| self.target_adjustments.get(title))
+            .copied()
+            .unwrap_or(self.adjustments)
+    }
+
+    /// Stores `adjustments` for the selected target (or globally when no target is selected).
+    fn set_effective_adjustments(&mut self, adjustments: PictureAdjustments) {
+        match self.target_title.clone() {
+            Some(title) => {
+                self.target_adjustments.insert(title, adjustments);
+            }
+            None => self.adjustments = adjustments,
+        }
+    }
+
+    /// Takes over from `new` the settings a running capture session follows; the rest (the
+    /// target, window trimming and corners, black-bar detection, reconnecting, duration and
+    /// idle limits) stay as the session started. Frame rate, scaling, dynamic FPS, duplicate
+    /// skipping, parallel conversion, frame limits and the reconnect timeout apply from the
+    /// next frame. Returns None when none of them changed, else whether the cursor or the
+    /// output resolution did, which the capturer has to be rebuilt for.
+    fn update_session(&mut self, new: &CaptureSettings) -> Option<bool> {
+        let rebuild = new.show_cursor != self.show_cursor || new.resolution != self.resolution;
+        let live = |s: &CaptureSettings| {
+            let frames = (s.fps, s.dynamic_fps, s.skip_duplicate_frames);
+            let output = (s.resize_algorithm, s.resize_on, s.parallel_conversion);
+            (frames, output, s.frame_limits, s.reconnect_timeout_secs)
+        };
+        if !rebuild && live(self) == live(new) {
+            return None;
+        }
+        self.fps = new.fps;
+        self.resolution = new.resolution.clone();
+        self.show_cursor = new.show_cursor;
+        self.resize_algorithm = new.resize_algorithm;
+        self.resize_on = new.resize_on;
+        self.dynamic_fps = new.dynamic_fps;
+        self.skip_duplicate_frames = new.skip_duplicate_frames;
+        self.parallel_conversion = new.parallel_conversion;
+        self.frame_limits = new.frame_limits;
+        self.reconnect_timeout_secs = new.reconnect_timeout_secs;
+        Some(rebuild)
+    }
+
+    /// Remembered crop of the selected target, else the global crop.
+    fn applied_crop(&self) -> Option<AppliedCrop> {
+        self.crop_for(self.target_title.as_deref())
+    }
+
+    /// Remembered crop of the target titled `title`, else the global crop.
+    fn crop_for(&self, title: Option<&str>) -> Option<AppliedCrop> {
+        let region = title
+            .and_then(|title| self.target_crops.get(title))
+            .or(self.crop.as_ref())?;
+        Some(AppliedCrop {
+            region: *region,
+            mode: self.crop_mode,
+        })
+    }
+
+    fn display_options(&self) -> DisplayOptions {
+        DisplayOptions {
+            color_blindness: self.color_blindness,
+            color_blindness_severity: self.color_blindness_severity,
+            color_filter: self.color_filter,
+            hdr_output: self.hdr_output,
+            present_mode: self.present_mode,
+            show_frame_graph: self.show_frame_graph,
+            show_info_overlay: self.show_info_overlay,
+            crosshair: self.show_crosshair.then(|| Crosshair {
+                color: display::parse_color(&self.crosshair_color).unwrap_or_default(),
+                thickness: self.crosshair_thickness,
+                gap: self.crosshair_gap,
+                full: self.crosshair_full,
+            }),
+            grid: {
+                let (columns, rows) = match self.grid_mode {
+                    GridMode::Off => (0, 0),
+                    GridMode::Thirds => (3, 3),
+                    GridMode::Custom => (self.grid_columns, self.grid_rows),
+                };
+                (columns > 0).then(|| Grid {
+                    columns,
+                    rows,
+                    color: display::parse_color(&self.grid_color).unwrap_or_default(),
+                    opacity: self.grid_opacity,
+                })
+            },
+            adjustments: self.effective_adjustments(),
+            crop_active: self.applied_crop().is_some(),
+            flip_horizontal: self.flip_horizontal,
+            flip_vertical: self.flip_vertical,
+            // Overlays must cover their rectangle exactly.
+            scale_mode: if self.overlay_mode {
+                ScaleMode::Stretch
+            } else {
+                self.scale_mode
+            },
+            filtering: self.filtering,
+            background: display::parse_color(&self.letterbox_color).unwrap_or_default(),
+            frame_smoothing: self.frame_smoothing,
+            close_hides_preview: self.close_hides_preview,
+            pip: (self.pip_mode && !self.overlay_mode).then_some(Pip {
+                width: self.pip_width,
+                corner: self.pip_corner,
+            }),
+            window: if self.overlay_mode {
+                WindowOptions {
+                    always_on_top: true,
+                    borderless: true,
+                    click_through: self.overlay_locked,
+                    fullscreen_monitor: None,
+                    alpha: (self.overlay_opacity * 255.0).round() as u8,
+                    drag_to_place: !self.overlay_locked,
+                    skip_taskbar: self.skip_taskbar,
+                }
+            } else if self.pip_mode {
+                WindowOptions {
+                    always_on_top: true,
+                    borderless: true,
+                    click_through: false,
+                    fullscreen_monitor: None,
+                    alpha: (self.window_opacity * 255.0).round() as u8,
+                    drag_to_place: false,
+                    skip_taskbar: true,
+                }
+            } else {
+                WindowOptions {
+                    always_on_top: self.always_on_top,
+                    borderless: self.borderless,
+                    click_through: self.click_through,
+                    fullscreen_monitor: self.fullscreen_monitor,
+                    alpha: (self.window_opacity * 255.0).round() as u8,
+                    skip_taskbar: self.skip_taskbar,
+                    ..Default::default()
+                }
+            },
         }
     }
 }
@@ -96,76 +709,90 @@ fn resolution_target_size(resolution: &str, aspect_ratio: f32) -> Option<(u32, u
     Some((base_w, base_h.max(1)))
 }
 
-/// Nearest-neighbor resize of RGBA32 buffer (packed u32: 0xAABBGGRR or similar).
-fn resize_frame(
-    src_w: u32,
-    src_h: u32,
-    src: &[u32],
-    dst_w: u32,
-    dst_h: u32,
-) -> Vec<u32> {
-    let mut dst = vec![0u32; (dst_w as usize).saturating_mul(dst_h as usize)];
-    if src_w == 0 || src_h == 0 || dst_w == 0 || dst_h == 0 {
-        return dst;
-    }
-    let src_w = src_w as usize;
-    let src_h = src_h as usize;
-    let dst_w = dst_w as usize;
-    let dst_h = dst_h as usize;
-    for y in 0..dst_h {
-        let sy = (y as u64 * (src_h as u64 - 1) / dst_h.max(1) as u64) as usize;
-        let src_row = sy.saturating_mul(src_w);
-        for x in 0..dst_w {
-            let sx = (x as u64 * (src_w as u64 - 1) / dst_w.max(1) as u64) as usize;
-            dst[y * dst_w + x] = src.get(src_row + sx).copied().unwrap_or(0);
-        }
-    }
-    dst
-}
-
 #[derive(Debug, Clone, Serialize)]
 struct TargetDto {
     index: usize,
     id: u32,
     title: String,
     kind: String,
+    /// Owning process of window targets, where known.
+    pid: Option<u32>,
 }
 
 struct CaptureState {
-    stop_requested: Arc<AtomicBool>,
-    preview_state: Arc<Mutex<Option<Arc<PreviewState>>>>,
+    /// Running capture sessions by session ID.
+    sessions: PreviewStateSlot,
+    next_session_id: AtomicU32,
+    /// Source of the IDs of cloned preview windows (open_preview_window).
+    next_window_id: AtomicU32,
     settings: Arc<Mutex<CaptureSettings>>,
+    /// Display options read live by the preview thread; derived from `settings`.
+    display: DisplayOptionsSlot,
+    /// Preview window shortcuts, derived from `settings`.
+    keybindings: KeybindingsSlot,
+    render_status: RenderStatusSlot,
+    /// Bumped on every start/stop so delayed actions can tell whether the user intervened.
+    session_generation: AtomicU64,
+    /// Layout most recently loaded or saved; arrangements are remembered there.
+    active_layout: Mutex<Option<String>>,
+    last_arrangement: Mutex<Option<ArrangeMode>>,
+    settings_writer: Arc<DebouncedWriter>,
+    /// Session marker of the running capture (see `recovery`).
+    session_marker: Mutex<Option<PathBuf>>,
+    /// Session that was running when LiteView last died, until resumed or dismissed.
+    resumable_session: Mutex<Option<recovery::SessionMarker>>,
+    /// Recent errors and warnings (get_recent_errors).
+    error_log: Mutex<ErrorLog>,
+    /// Recording of a running session, if one was started.
+    recording: Mutex<Option<recording::Recording>>,
+    replay: Mutex<ReplayBuffer>,
+    targets: TargetCache,
+}
+
+impl CaptureState {
+    /// Preview states of all running sessions, oldest first.
+    fn preview_sessions(&self) -> Vec<Arc<PreviewState>> {
+        self.sessions.lock().unwrap().values().cloned().collect()
+    }
+
+    /// The oldest running session; single-session features (recording, screenshots, crops
+    /// matched to the source) work on it.
+    fn primary_session(&self) -> Option<Arc<PreviewState>> {
+        self.sessions.lock().unwrap().values().next().cloned()
+    }
+
+    fn is_capturing(&self) -> bool {
+        !self.sessions.lock().unwrap().is_empty()
+    }
 }
 
 impl Default for CaptureState {
     fn default() -> Self {
         Self {
-            stop_requested: Arc::new(AtomicBool::new(false)),
-            preview_state: Arc::new(Mutex::new(None)),
+            sessions: Arc::new(Mutex::new(BTreeMap::new())),
+            next_session_id: AtomicU32::new(1),
+            next_window_id: AtomicU32::new(1),
             settings: Arc::new(Mutex::new(CaptureSettings::default())),
+            display: Arc::new(Mutex::new(DisplayOptions::default())),
+            keybindings: Arc::new(Mutex::new(Keybindings::from_map(&keybindings::defaults()))),
+            render_status: Arc::new(Mutex::new(Default::default())),
+            session_generation: AtomicU64::new(0),
+            active_layout: Mutex::new(None),
+            last_arrangement: Mutex::new(None),
+            settings_writer: DebouncedWriter::spawn(),
+            session_marker: Mutex::new(None),
+            resumable_session: Mutex::new(None),
+            error_log: Mutex::new(ErrorLog::default()),
+            recording: Mutex::new(None),
+            replay: Mutex::new(ReplayBuffer::default()),
+            targets: TargetCache::default(),
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize)]
-struct CaptureSettingsDto {
-    fps: u32,
-    resolution: String,
-    target_index: Option<usize>,
-    target_id: Option<u32>,
-    show_cursor: bool,
-}
-
 #[tauri::command]
-fn get_capture_settings(state: State<CaptureState>) -> CaptureSettingsDto {
-    let s = state.settings.lock().unwrap();
-    CaptureSettingsDto {
-        fps: s.fps,
-        resolution: s.resolution.clone(),
-        target_index: s.target_index,
-        target_id: s.target_id,
-        show_cursor: s.show_cursor,
-    }
+fn get_capture_settings(state: State<CaptureState>) -> CaptureSettings {
+    state.settings.lock().unwrap().clone()
 }
 
 fn target_id(t: &Target) -> u32 {
@@ -175,270 +802,1559 @@ fn target_id(t: &Target) -> u32 {
     }
 }
 
+fn target_title(t: &Target) -> &str {
+    match t {
+        Target::Display(d) => &d.title,
+        Target::Window(w) => &w.title,
+    }
+}
+
+/// The target with `id`, or else the first one titled `title`.
+fn find_target<'a>(targets: &'a [Target], id: u32, title: Option<&str>) -> Option<&'a Target> {
+    target_position(targets, id, title).map(|i| &targets[i])
+}
+
+fn target_position(targets: &[Target], id: u32, title: Option<&str>) -> Option<usize> {
+    targets
+        .iter()
+        .position(|t| target_id(t) == id)
+        .or_else(|| targets.iter().position(|t| Some(target_title(t)) == title))
+}
+
+/// Clamps numeric fields and rejects values the capture thread cannot handle.
+fn validate_settings(mut settings: CaptureSettings) -> Result<CaptureSettings, String> {
+    settings.fps = settings.fps.clamp(1, 120);
+    settings.resolution = settings.resolution.to_lowercase();
+    let valid = ["captured", "480p", "720p", "1080p", "1440p", "2160p", "4320p"];
+    if !valid.contains(&settings.resolution.as_str()) {
+        return Err(format!("Invalid resolution: {}", settings.resolution));
+    }
+    settings.idle_stop_minutes = settings.idle_stop_minutes.min(MAX_IDLE_STOP_MINUTES);
+    settings.capture_duration_secs = settings.capture_duration_secs.min(MAX_CAPTURE_DURATION_SECS);
+    settings.replay_secs = settings.replay_secs.min(replay::MAX_REPLAY_SECS);
+    settings.delay_secs = settings.delay_secs.min(delay::MAX_DELAY_SECS);
+    settings.replay_memory_mb = settings
+        .replay_memory_mb
+        .clamp(replay::MIN_REPLAY_MEMORY_MB, replay::MAX_REPLAY_MEMORY_MB);
+    settings.reconnect_timeout_secs = settings.reconnect_timeout_secs.clamp(1, 3600);
+    if !settings.color_blindness_severity.is_finite() {
+        settings.color_blindness_severity = default_severity();
+    }
+    settings.color_blindness_severity = settings.color_blindness_severity.clamp(0.0, 1.0);
+    if !settings.overlay_opacity.is_finite() {
+        settings.overlay_opacity = default_overlay_opacity();
+    }
+    settings.overlay_opacity = settings.overlay_opacity.clamp(0.1, 1.0);
+    settings.pip_width = settings
+        .pip_width
+        .clamp(pip::MIN_PIP_WIDTH, pip::MAX_PIP_WIDTH);
+    if !settings.window_opacity.is_finite() {
+        settings.window_opacity = default_window_opacity();
+    }
+    settings.window_opacity = settings.window_opacity.clamp(0.3, 1.0);
+    settings.adjustments = settings.adjustments.clamped();
+    settings.sound_cues = settings.sound_cues.clamped();
+    settings.frame_limits = settings.frame_limits.clamped();
+    for adjustments in settings.target_adjustments.values_mut() {
+        *adjustments = adjustments.clamped();
+    }
+    settings.target_crops.retain(|_, c| c.is_valid());
+    settings.crop = settings.crop.filter(CropRegion::is_valid);
+    settings.keybindings = keybindings::validate(settings.keybindings)?;
+    for color in [
+        &mut settings.letterbox_color,
+        &mut settings.crosshair_color,
+        &mut settings.grid_color,
+    ] {
+        let [r, g, b] = display::parse_color(color)?;
+        *color = format!("#{r:02x}{g:02x}{b:02x}");
+    }
+    settings.crosshair_thickness = settings.crosshair_thickness.clamp(1, 16);
+    settings.crosshair_gap = settings.crosshair_gap.min(200);
+    settings.grid_columns = settings.grid_columns.clamp(2, 32);
+    settings.grid_rows = settings.grid_rows.clamp(2, 32);
+    if !settings.grid_opacity.is_finite() {
+        settings.grid_opacity = default_grid_opacity();
+    }
+    settings.grid_opacity = settings.grid_opacity.clamp(0.1, 1.0);
+    Ok(settings)
+}
+
+/// Replaces the in-memory settings, pushes display options and shortcuts to the preview and
+/// persists.
+fn store_settings(app: &AppHandle, mut settings: CaptureSettings) -> Result<(), String> {
+    let state = app.state::<CaptureState>();
+    let previous_id = state.settings.lock().unwrap().target_id;
+    if settings.target_id != previous_id {
+        // The new target was picked from the cached list; only one that opened since needs a
+        // fresh list.
+        let title = |targets: &[Target], id| {
+            targets
+                .iter()
+                .find(|t| target_id(t) == id)
+                .map(|t| target_title(t).to_string())
+        };
+        settings.target_title = settings.target_id.and_then(|id| {
+            title(&state.targets.current(), id).or_else(|| title(&state.targets.refresh(), id))
+        });
+    }
+    *state.display.lock().unwrap() = settings.display_options();
+    *state.keybindings.lock().unwrap() = Keybindings::from_map(&settings.keybindings);
+    preview::wake();
+    state.replay.lock().unwrap().configure(
+        settings.replay_secs,
+        settings.replay_memory_mb,
+        settings.fps,
+    );
+    for preview in state.preview_sessions() {
+        preview.set_crop(settings.crop_for(preview.target_title.as_deref()));
+        preview.set_resolution(&settings.resolution);
+        preview.set_delay(settings.delay_secs);
+        preview.set_rotation(settings.effective_adjustments().rotation);
+        preview.push_settings(settings.clone());
+    }
+    if let Some(menu) = app.try_state::<ToggleMenu>() {
+        menu.sync(&settings);
+    }
+    if let Some(menu) = app.try_state::<TargetMenu>() {
+        menu.sync(&settings);
+    }
+    *state.settings.lock().unwrap() = settings.clone();
+    save_settings_to_disk(app, &settings)
+}
+
+/// Applies `f` to the current settings (validated and persisted) and tells the frontend.
+/// Used for changes made outside the settings page, e.g. preview window shortcuts.
+fn update_settings(app: &AppHandle, f: impl FnOnce(&mut CaptureSettings)) -> Result<(), String> {
+    let mut settings = app.state::<CaptureState>().settings.lock().unwrap().clone();
+    f(&mut settings);
+    let settings = validate_settings(settings)?;
+    store_settings(app, settings.clone())?;
+    let _ = app.emit("settings-changed", settings);
+    Ok(())
+}
+
+/// Merges `settings` (a partial settings object) into the current settings. Fields not present keep their value.
 #[tauri::command]
 fn set_capture_settings(
     app: AppHandle,
-    fps: u32,
-    resolution: String,
-    target_index: Option<usize>,
-    target_id: Option<u32>,
-    show_cursor: bool,
+    settings: serde_json::Value,
     state: State<CaptureState>,
 ) -> Result<(), String> {
-    let fps = fps.clamp(1, 120);
-    let resolution = resolution.to_lowercase();
-    let valid = ["captured", "480p", "720p", "1080p", "1440p", "2160p", "4320p"];
-    if !valid.contains(&resolution.as_str()) {
-        return Err(format!("Invalid resolution: {}", resolution));
-    }
-    let settings = CaptureSettings {
-        fps,
-        resolution: resolution.clone(),
-        target_index,
-        target_id,
-        show_cursor,
+    let patch = match settings {
+        serde_json::Value::Object(map) => map,
+        _ => return Err("Settings must be an object".to_string()),
     };
-    *state.settings.lock().unwrap() = settings.clone();
-    save_settings_to_disk(&app, &settings)?;
-    Ok(())
+    let current = state.settings.lock().unwrap().clone();
+    let mut merged = serde_json::to_value(&current).map_err(|e| e.to_string())?;
+    if let Some(obj) = merged.as_object_mut() {
+        obj.extend(patch);
+    }
+    let settings: CaptureSettings = serde_json::from_value(merged).map_err(|e| e.to_string())?;
+    store_settings(&app, validate_settings(settings)?)
 }
 
+/// Stores `adjustments` for the selected target (or globally when no target is selected).
 #[tauri::command]
-fn get_app_version() -> String {
-    env!("CARGO_PKG_VERSION").to_string()
+fn set_picture_adjustments(app: AppHandle, adjustments: PictureAdjustments) -> Result<(), String> {
+    update_settings(&app, |s| s.set_effective_adjustments(adjustments))
 }
 
+/// Makes the selected target's adjustments the defaults for targets without their own.
 #[tauri::command]
-fn get_capture_targets() -> Result<Vec<TargetDto>, String> {
-    if !is_supported() {
-        return Err("Screen capture is not supported on this system.".to_string());
-    }
-    if !has_permission() && !request_permission() {
-        return Err("Screen capture permission was denied.".to_string());
+fn copy_adjustments_to_global(app: AppHandle) -> Result<(), String> {
+    update_settings(&app, |s| s.adjustments = s.effective_adjustments())
+}
+
+/// A running capture session as listed in the status.
+#[derive(Debug, Clone, Serialize)]
+struct SessionDto {
+    id: u32,
+    /// What is being captured, once the capture thread has found it.
+    title: Option<String>,
+    target_id: Option<u32>,
+    /// Seconds until the session's duration limit stops it.
+    remaining_secs: Option<u64>,
+    /// Preview window hidden with hide_preview (or by closing it) while capture runs.
+    hidden: bool,
+    /// IDs of the extra windows opened with open_preview_window.
+    clones: Vec<u32>,
+}
+
+/// Capture status; the per-frame figures are those of the primary (oldest) session.
+#[derive(Debug, Clone, Serialize)]
+struct CaptureStatus {
+    capturing: bool,
+    sessions: Vec<SessionDto>,
+    /// "sdr" | "hdr-scrgb" while a preview window exists.
+    output_mode: Option<&'static str>,
+    /// "fifo" | "mailbox" | "immediate" while a preview window exists.
+    present_mode: Option<&'static str>,
+    /// Scaler in use ("nearest" | "bilinear" | "area" | "lanczos"); None when frames are not
+    /// resized on the CPU.
+    resize_algorithm: Option<&'static str>,
+    /// Black-bar crop (x, y, width, height) applied by auto-crop.
+    detected_crop: Option<(u32, u32, u32, u32)>,
+    /// New frames shown per second, by capture timestamp.
+    capture_fps: Option<f32>,
+    /// Capture rate currently aimed for; below the setting while dynamic FPS backs off.
+    effective_fps: Option<u32>,
+    /// Frames skipped this session for being identical to the previous one.
+    duplicate_frames: Option<u64>,
+    /// Seconds until the duration limit stops the session; None when it has none.
+    remaining_secs: Option<u64>,
+    /// Seconds of frames in the replay buffer, and the memory they take.
+    replay_buffered_secs: f32,
+    replay_memory_bytes: u64,
+    /// Frames the preview presents per second, and their average render + present time.
+    render_fps: Option<f32>,
+    present_ms: Option<f32>,
+    paused: bool,
+    frozen: bool,
+}
+
+#[tauri::command]
+fn get_capture_status(state: State<CaptureState>) -> CaptureStatus {
+    let render_status = state.render_status.lock().unwrap().clone();
+    let (replay_buffered_secs, replay_memory_bytes) = {
+        let replay = state.replay.lock().unwrap();
+        (replay.buffered_secs(), replay.memory_bytes())
+    };
+    let sessions = state.preview_sessions();
+    let primary = sessions.first();
+    CaptureStatus {
+        capturing: !sessions.is_empty(),
+        sessions: sessions
+            .iter()
+            .map(|p| SessionDto {
+                id: p.id,
+                title: p.source_title(),
+                target_id: p.target,
+                remaining_secs: p.remaining().map(|d| d.as_secs()),
+                hidden: p.is_dismissed(),
+                clones: p.clones(),
+            })
+            .collect(),
+        output_mode: render_status.output.map(|o| o.label()),
+        present_mode: render_status.present_mode.map(preview::present_mode_label),
+        resize_algorithm: primary
+            .and_then(|p| p.resize_algorithm())
+            .map(ResizeAlgorithm::label),
+        detected_crop: primary.and_then(|p| p.detected_crop()),
+        capture_fps: render_status.capture_fps,
+        effective_fps: primary.map(|p| p.effective_fps()),
+        duplicate_frames: primary.map(|p| p.duplicate_frames()),
+        remaining_secs: primary.and_then(|p| p.remaining()).map(|d| d.as_secs()),
+        replay_buffered_secs,
+        replay_memory_bytes,
+        render_fps: render_status.render_fps,
+        present_ms: render_status.present_ms,
+        paused: primary.is_some_and(|p| p.is_paused()),
+        frozen: primary.is_some_and(|p| p.is_frozen()),
     }
-    let targets = get_all_targets();
-    Ok(targets
+}
+
+#[tauri::command]
+fn get_app_version() -> String {
+    env!("CARGO_PKG_VERSION").to_string()
+}
+
+/// Result of get_capture_targets. Listing targets never prompts for permission; without it
+/// the frontend explains why access is needed and calls request_capture_permission. Virtual
+/// targets are listed either way.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", content = "targets", rename_all = "snake_case")]
+enum CaptureTargets {
+    Ready(Vec<TargetDto>),
+    NeedsPermission(Vec<TargetDto>),
+}
+
+/// Targets generated by LiteView itself, listed after the scap targets starting at `index`.
+fn virtual_targets(index: usize) -> Vec<TargetDto> {
+    vec![TargetDto {
+        index,
+        id: test_pattern::TARGET_ID,
+        title: test_pattern::TITLE.to_string(),
+        kind: "virtual".to_string(),
+        pid: None,
+    }]
+}
+
+/// DTOs of `targets` followed by the virtual targets.
+fn target_dtos(targets: Vec<Target>) -> Vec<TargetDto> {
+    let mut targets: Vec<TargetDto> = targets
         .into_iter()
         .enumerate()
         .map(|(index, t)| {
-            let (id, title, kind) = match &t {
-                Target::Display(d) => (d.id, d.title.clone(), "display"),
-                Target::Window(w) => (w.id, w.title.clone(), "window"),
+            let (id, title, kind, pid) = match &t {
+                Target::Display(d) => (d.id, d.title.clone(), "display", None),
+                Target::Window(w) => (w.id, w.title.clone(), "window", apps::window_pid(w.id)),
             };
             TargetDto {
                 index,
                 id,
                 title,
                 kind: kind.to_string(),
+                pid,
             }
         })
-        .collect())
-}
-
-fn frame_to_buffer(frame: &Frame) -> Option<(u32, u32, Vec<u32>)> {
-    let (width, height, buffer) = match frame {
-        Frame::BGRA(f) => {
-            let buf = f
-                .data
-                .chunks_exact(4)
-                .map(|c| {
-                    let r = c[2] as u32;
-                    let g = c[1] as u32;
-                    let b = c[0] as u32;
-                    (r << 16) | (g << 8) | b
-                })
-                .collect::<Vec<u32>>();
-            (f.width as u32, f.height as u32, buf)
-        }
-        Frame::BGR0(f) => {
-            let buf = f
-                .data
-                .chunks_exact(4)
-                .map(|c| {
-                    let r = c[2] as u32;
-                    let g = c[1] as u32;
-                    let b = c[0] as u32;
-                    (r << 16) | (g << 8) | b
-                })
-                .collect::<Vec<u32>>();
-            (f.width as u32, f.height as u32, buf)
-        }
-        Frame::RGB(f) => {
-            let buf = f
-                .data
-                .chunks_exact(3)
-                .map(|c| {
-                    let r = c[0] as u32;
-                    let g = c[1] as u32;
-                    let b = c[2] as u32;
-                    (r << 16) | (g << 8) | b
-                })
-                .collect::<Vec<u32>>();
-            (f.width as u32, f.height as u32, buf)
-        }
-        Frame::RGBx(f) => {
-            let buf = f
-                .data
-                .chunks_exact(4)
-                .map(|c| {
-                    let r = c[0] as u32;
-                    let g = c[1] as u32;
-                    let b = c[2] as u32;
-                    (r << 16) | (g << 8) | b
-                })
-                .collect::<Vec<u32>>();
-            (f.width as u32, f.height as u32, buf)
-        }
-        Frame::XBGR(f) => {
-            let buf = f
-                .data
-                .chunks_exact(4)
-                .map(|c| {
-                    let r = c[3] as u32;
-                    let g = c[2] as u32;
-                    let b = c[1] as u32;
-                    (r << 16) | (g << 8) | b
-                })
-                .collect::<Vec<u32>>();
-            (f.width as u32, f.height as u32, buf)
-        }
-        Frame::BGRx(f) => {
-            let buf = f
-                .data
-                .chunks_exact(4)
-                .map(|c| {
-                    let r = c[2] as u32;
-                    let g = c[1] as u32;
-                    let b = c[0] as u32;
-                    (r << 16) | (g << 8) | b
-                })
-                .collect::<Vec<u32>>();
-            (f.width as u32, f.height as u32, buf)
-        }
-        _ => return None,
-    };
-    let expected_len = (height as usize).saturating_mul(width as usize);
-    if width == 0 || buffer.len() != expected_len {
-        return None;
-    }
-    Some((width, height, buffer))
+        .collect();
+    targets.extend(virtual_targets(targets.len()));
+    targets
 }
 
 #[tauri::command]
-fn start_capture(
-    target_index: Option<usize>,
-    app_handle: tauri::AppHandle,
-    state: State<CaptureState>,
-) -> Result<(), String> {
+fn get_capture_targets(state: State<CaptureState>) -> Result<CaptureTargets, String> {
     if !is_supported() {
-        return Err("Screen capture is not supported.".to_string());
+        return Err("Screen capture is not supported on this system.".to_string());
     }
-    if !has_permission() && !request_permission() {
-        return Err("Permission denied.".to_string());
+    if !has_permission() {
+        return Ok(CaptureTargets::NeedsPermission(virtual_targets(0)));
     }
-    state.stop_requested.store(false, Ordering::Relaxed);
+    Ok(CaptureTargets::Ready(target_dtos(state.targets.refresh().into_vec())))
+}
 
-    let settings = state.settings.lock().unwrap().clone();
-    let resolution_for_scale = settings.resolution.clone();
-    let target_fps = settings.fps.max(1);
-    let target_index_for_thread = target_index.or(settings.target_index);
+/// Shows the OS permission prompt (macOS screen recording, Wayland portal) if needed; returns
+/// whether capturing is allowed now.
+#[tauri::command]
+fn request_capture_permission() -> bool {
+    has_permission() || request_permission()
+}
 
-    if let Some(old_state) = state.preview_state.lock().unwrap().take() {
-        old_state.running.store(false, Ordering::Relaxed);
-        old_state.frame_available.notify_one();
+/// Converts a frame to packed 0x00RRGGBB pixels in `buffer`, reusing its allocation, and
+/// returns its size, on up to `threads` threads. None for malformed frames and for frames
+/// beyond `limits`, which are refused before anything is allocated.
+fn frame_to_buffer(
+    frame: &Frame,
+    limits: &FrameLimits,
+    threads: usize,
+    buffer: &mut Vec<u32>,
+) -> Option<(u32, u32)> {
+    let (width, height) = frame_limits::frame_size(frame)?;
+    if !limits.allows(width, height) {
+        return None;
     }
+    // Bytes per pixel and the byte offsets of red, green and blue.
+    let (data, bpp, channels) = match frame {
+        Frame::YUVFrame(f) => {
+            yuv::nv12_to_buffer(f, YuvFormat::SCAP, threads, buffer)?;
+            return Some((width, height));
+        }
+        Frame::BGRA(f) => (&f.data, 4, [2, 1, 0]),
+        Frame::BGR0(f) => (&f.data, 4, [2, 1, 0]),
+        Frame::RGB(f) => (&f.data, 3, [0, 1, 2]),
+        Frame::RGBx(f) => (&f.data, 4, [0, 1, 2]),
+        Frame::XBGR(f) => (&f.data, 4, [3, 2, 1]),
+        Frame::BGRx(f) => (&f.data, 4, [2, 1, 0]),
+    };
+    let size = (width as usize, height as usize);
+    pack_rows(data, size, bpp, channels, threads, buffer)?;
+    Some((width, height))
+}
 
-    let preview_state = Arc::new(PreviewState::default());
-    state
-        .preview_state
-        .lock()
-        .unwrap()
-        .replace(preview_state.clone());
+/// A frame's bytes as the source delivered them, for the preview to upload as they are.
+struct SourcePixels {
+    width: u32,
+    height: u32,
+    format: PixelFormat,
+    /// Bytes from one row to the next.
+    stride: u32,
+    bytes: Vec<u8>,
+}
 
-    let stop_requested_clone = state.stop_requested.clone();
+/// Takes the bytes out of frames whose pixels a texture takes as they are (four bytes each,
+/// blue or red first). None for the others and for frames beyond `limits`, which are left for
+/// `frame_to_buffer`.
+fn take_source_pixels(frame: &mut Frame, limits: &FrameLimits) -> Option<SourcePixels> {
+    let (width, height) = frame_limits::frame_size(frame)?;
+    if !limits.allows(width, height) {
+        return None;
+    }
+    let (data, format) = match frame {
+        Frame::BGRA(f) => (&mut f.data, PixelFormat::Bgrx),
+        Frame::BGR0(f) => (&mut f.data, PixelFormat::Bgrx),
+        Frame::BGRx(f) => (&mut f.data, PixelFormat::Bgrx),
+        Frame::RGBx(f) => (&mut f.data, PixelFormat::Rgbx),
+        Frame::YUVFrame(_) | Frame::RGB(_) | Frame::XBGR(_) => return None,
+    };
+    let stride = row_stride(data.len(), width as usize * 4, height as usize)?;
+    Some(SourcePixels {
+        width,
+        height,
+        format,
+        stride: u32::try_from(stride).ok()?,
+        bytes: std::mem::take(data),
+    })
+}
 
-    thread::spawn(move || {
-        let targets = get_all_targets();
-        let target = settings.target_id
-            .and_then(|id| targets.iter().find(|t| target_id(t) == id).cloned())
-            .or_else(|| {
-                target_index_for_thread.and_then(|idx| targets.into_iter().nth(idx))
-            });
-        let options = Options {
-            fps: settings.fps,
-            show_cursor: settings.show_cursor,
-            show_highlight: false,
-            target,
-            crop_area: None,
-            output_type: FrameType::BGRAFrame,
-            output_resolution: resolution_from_str(&settings.resolution),
-            excluded_targets: None,
-            ..Default::default()
-        };
-        let mut capturer = match Capturer::build(options) {
-            Ok(c) => c,
-            Err(e) => {
-                let _ = app_handle.emit("capture-error", e.to_string());
-                preview_state.running.store(false, Ordering::Relaxed);
-                return;
-            }
-        };
-        capturer.start_capture();
+/// Row stride of `height` rows of `row_len` bytes in `len` bytes. scap's frames don't carry
+/// it, so it is inferred: rows may be padded (e.g. to 64 bytes on Windows), and the last row's
+/// padding may be missing.
+fn row_stride(len: usize, row_len: usize, height: usize) -> Option<usize> {
+    let stride = if len.is_multiple_of(height) || height == 1 {
+        len / height
+    } else {
+        len.checked_sub(row_len)? / (height - 1)
+    };
+    let needed = stride.checked_mul(height - 1)?.checked_add(row_len)?;
+    (stride >= row_len && needed <= len).then_some(stride)
+}
 
-        let frame_interval = Duration::from_secs_f64(1.0 / target_fps as f64);
-        let mut last_push = Instant::now();
+/// Packs `bpp`-byte pixels row by row into `buffer`, skipping row padding, on up to `threads`
+/// threads.
+fn pack_rows(
+    data: &[u8],
+    (width, height): (usize, usize),
+    bpp: usize,
+    [r, g, b]: [usize; 3],
+    threads: usize,
+    buffer: &mut Vec<u32>,
+) -> Option<()> {
+    if width == 0 || height == 0 {
+        return None;
+    }
+    let row_len = width.checked_mul(bpp)?;
+    let stride = row_stride(data.len(), row_len, height)?;
+    let pixels = |y: usize| data[y * stride..][..row_len].chunks_exact(bpp);
+    if bpp == 4 && [r, g, b] == [2, 1, 0] {
+        // BGRA-style rows are packed pixels already, bar the fourth byte.
+        let pixel = |c: &[u8]| u32::from_le_bytes([c[0], c[1], c[2], 0]);
+        return parallel::fill_rows(buffer, width, height, threads, |y| pixels(y).map(pixel));
+    }
+    let pixel = |c: &[u8]| ((c[r] as u32) << 16) | ((c[g] as u32) << 8) | c[b] as u32;
+    parallel::fill_rows(buffer, width, height, threads, |y| pixels(y).map(pixel))
+}
 
-        while !stop_requested_clone.load(Ordering::Relaxed)
-            && preview_state.running.load(Ordering::Relaxed)
-        {
-            let frame = match capturer.get_next_frame() {
-                Ok(f) => f,
-                Err(_) => break,
-            };
+/// Checksum over the converted frame, used to detect visible changes between frames.
+fn frame_checksum(buffer: &[u32]) -> u64 {
+    buffer.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &px| {
+        (hash ^ px as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
 
-            if let Some((width, height, buffer)) = frame_to_buffer(&frame) {
-                let now = Instant::now();
-                if now.duration_since(last_push) < frame_interval {
-                    continue;
-                }
-                last_push = now;
+/// Where a session's frames come from.
+enum FrameSource {
+    Capturer(Capturer),
+    TestPattern(TestPattern),
+}
 
-                let (out_width, out_height, out_buffer) =
-                    if let Some((tw, th)) =
-                        resolution_target_size(&resolution_for_scale, width as f32 / height as f32)
-                    {
-                        let scaled = resize_frame(width, height, &buffer, tw, th);
-                        (tw, th, scaled)
-                    } else {
-                        (width, height, buffer)
-                    };
-                *preview_state.frame.lock().unwrap() = Some(FrameData {
-                    width: out_width,
-                    height: out_height,
-                    buffer: out_buffer,
-                });
-                preview_state.frame_available.notify_one();
-            }
+impl FrameSource {
+    /// Blocks until the next frame; None once the source has ended.
+    fn next_frame(&mut self) -> Option<Frame> {
+        match self {
+            Self::Capturer(capturer) => capturer.get_next_frame().ok(),
+            Self::TestPattern(pattern) => Some(pattern.next_frame()),
         }
+    }
+
+    fn stop(&mut self) {
+        if let Self::Capturer(capturer) = self {
+            capturer.stop_capture();
+        }
+    }
+}
+
+/// The test pattern at the output size for test pattern sessions, else a capturer of `target`.
+fn open_source(settings: &CaptureSettings, target: Option<Target>) -> Result<FrameSource, String> {
+    if settings.uses_test_pattern() {
+        // Generated at the output size, so the resize step passes it through.
+        let size = resolution_target_size(&settings.resolution, 16.0 / 9.0)
+            .unwrap_or(test_pattern::DEFAULT_SIZE);
+        let pattern = TestPattern::new(settings.fps, size);
+        return Ok(FrameSource::TestPattern(pattern));
+    }
+    build_capturer(settings, target).map(FrameSource::Capturer)
+}
+
+fn build_capturer(settings: &CaptureSettings, target: Option<Target>) -> Result<Capturer, String> {
+    let options = Options {
+        fps: settings.fps,
+        show_cursor: settings.show_cursor,
+        show_highlight: false,
+        target,
+        crop_area: None,
+        output_type: FrameType::BGRAFrame,
+        output_resolution: resolution_from_str(&settings.resolution),
+        excluded_targets: None,
+    };
+    let mut capturer = Capturer::build(options).map_err(|e| e.to_string())?;
+    capturer.start_capture();
+    Ok(capturer)
+}
+
+/// Looks for `lost` (by ID, then title) until it is back or `settings.reconnect_timeout_secs`
+/// have passed, and starts capturing it again. Returns None on timeout or when the session
+/// ends in the meantime.
+fn reconnect(
+    settings: &CaptureSettings,
+    lost: &Target,
+    session_active: impl Fn() -> bool,
+) -> Option<(Capturer, Target)> {
+    let deadline = Instant::now() + Duration::from_secs(settings.reconnect_timeout_secs as u64);
+    while session_active() && Instant::now() < deadline {
+        thread::sleep(RECONNECT_POLL_INTERVAL);
+        let targets = get_all_targets();
+        let Some(target) = find_target(&targets, target_id(lost), Some(target_title(lost))) else {
+            continue;
+        };
+        // A window that is still opening may refuse capture for a moment; try again.
+        if let Ok(capturer) = build_capturer(settings, Some(target.clone())) {
+            return Some((capturer, target.clone()));
+        }
+    }
+    None
+}
+
+/// Per-start overrides for a capture session.
+#[derive(Debug, Default)]
+struct SessionOptions {
+    /// Target to capture instead of the selected target or application.
+    target_id: Option<u32>,
+    /// Title of `target_id`, to find it by if its ID is gone.
+    target_title: Option<String>,
+    /// Application to capture instead of the selected one, with `target_id` and `target_title`
+    /// as it was selected.
+    target_pid: Option<u32>,
+    /// Preview window placement to restore instead of letting the OS choose.
+    geometry: Option<WindowGeometry>,
+    /// Seconds after which the session stops, instead of `capture_duration_secs` (0 = unlimited).
+    duration_secs: Option<u32>,
+}
+
+/// Starts a capture session with the current settings next to any running ones and returns
+/// its ID. Shared by the start_capture command and internal restarts (idle auto-restart, layouts).
+fn start_capture_session(app: &AppHandle, options: SessionOptions) -> Result<u32, String> {
+    let state = app.state::<CaptureState>();
+    let mut settings = state.settings.lock().unwrap().clone();
+    if let Some(pid) = options.target_pid {
+        settings.target_pid = Some(pid);
+        settings.target_id = options.target_id;
+        settings.target_title = options.target_title.clone();
+    } else if let Some(id) = options.target_id {
+        if settings.target_pid.is_some() || settings.target_id != Some(id) {
+            settings.target_id = Some(id);
+            settings.target_title = None;
+            settings.target_pid = None;
+        }
+        if options.target_title.is_some() {
+            settings.target_title = options.target_title.clone();
+        }
+    }
+    // The test pattern needs neither capture support nor permission.
+    let test_pattern = settings.uses_test_pattern();
+    if !test_pattern {
+        if !is_supported() {
+            return Err("Screen capture is not supported.".to_string());
+        }
+        if !has_permission() && !request_permission() {
+            return Err("Permission denied.".to_string());
+        }
+    }
+    let mut targets = if test_pattern {
+        Targets::default()
+    } else {
+        state.targets.current()
+    };
+    if settings.target_title.is_none() {
+        settings.target_title = settings.target_id.and_then(|id| {
+            if test_pattern {
+                return Some(test_pattern::TITLE.to_string());
+            }
+            targets
+                .iter()
+                .find(|t| target_id(t) == id)
+                .map(|t| target_title(t).to_string())
+        });
+    }
+    // Resolved from the list the target was picked from; only a target that opened since
+    // needs a fresh one. Never fall back to scap's default; that would silently capture the
+    // wrong thing.
+    let chosen = match settings.target_id {
+        Some(id) if !test_pattern && settings.target_pid.is_none() => {
+            let title = settings.target_title.clone();
+            let position = |targets: &[Target]| target_position(targets, id, title.as_deref());
+            if position(&targets).is_none() {
+                targets = state.targets.refresh();
+            }
+            let Some(index) = position(&targets) else {
+                return Err(format!(
+                    "The selected target \"{}\" is not available; choose it again.",
+                    title.as_deref().unwrap_or("unknown")
+                ));
+            };
+            Some(index)
+        }
+        _ => None,
+    };
+    state.session_generation.fetch_add(1, Ordering::SeqCst);
+
+    let target_fps = settings.fps.max(1);
+    let duration_secs = options
+        .duration_secs
+        .unwrap_or(settings.capture_duration_secs)
+        .min(MAX_CAPTURE_DURATION_SECS);
+    let idle_stop_minutes = settings.idle_stop_minutes;
+    let idle_restart_secs = settings.idle_restart_secs;
+    let track_idle = idle_stop_minutes > 0;
+    // An idle restart captures what this session did, even if the selection changed since.
+    let restart = SessionOptions {
+        target_id: settings.target_id,
+        target_title: settings.target_title.clone(),
+        target_pid: settings.target_pid,
+        duration_secs: Some(duration_secs),
+        ..Default::default()
+    };
+    let sound_cues = settings.sound_cues;
+
+    let session_id = state.next_session_id.fetch_add(1, Ordering::Relaxed);
+    // Sessions next to running ones would cover them at the remembered place.
+    let remembered = settings
+        .last_window_geometry
+        .filter(|_| settings.remember_window_geometry && !state.is_capturing());
+    let geometry = options
+        .geometry
+        .or(settings.overlay_geometry.filter(|_| settings.overlay_mode))
+        .or(remembered);
+    let monitor = settings
+        .preview_monitor
+        .clone()
+        .filter(|_| geometry.is_none())
+        .filter(|name| {
+            let connected = monitors::is_connected(app, name);
+            if !connected {
+                // The OS places the window, on the primary monitor.
+                let _ = app.emit("preview-monitor-missing", name);
+            }
+            connected
+        });
+    let preview_state = Arc::new(PreviewState::new(
+        session_id,
+        settings.target_id.filter(|_| settings.target_pid.is_none()),
+        settings.target_title.clone(),
+        geometry,
+        monitor,
+    ));
+    preview_state.set_crop(settings.applied_crop());
+    preview_state.set_resolution(&settings.resolution);
+    preview_state.set_delay(settings.delay_secs);
+    preview_state.set_rotation(settings.effective_adjustments().rotation);
+    state
+        .sessions
+        .lock()
+        .unwrap()
+        .insert(session_id, preview_state.clone());
+    preview::wake();
+
+    recovery::write_marker(app, &settings);
+
+    let app_handle = app.clone();
+    let thread_preview_state = preview_state.clone();
+
+    let capture = move || {
+        let mut settings = settings;
+        let preview_state = thread_preview_state;
+        let targets = targets.into_vec();
+        let target = chosen.map(|i| targets[i].clone());
+        preview_state.set_source_title(match settings.target_pid {
+            Some(pid) => apps::application_name(&targets, pid),
+            None if test_pattern => Some(test_pattern::TITLE.to_string()),
+            None => target.as_ref().map(|t| target_title(t).to_string()),
+        });
+        // Applications and popups are captured by recording the monitor and cropping to the
+        // windows. An application's windows are the ones open at capture start.
+        let (target, window_region) = match (settings.target_pid, target) {
+            (Some(pid), _) => {
+                let ids = apps::window_ids(&targets, pid);
+                match WindowRegion::for_windows(&ids, &targets) {
+                    Some((display, region)) => (Some(display), Some(region)),
+                    None => {
+                        emit_capture_error(
+                            &app_handle,
+                            "The selected application has no capturable windows.".to_string(),
+                        );
+                        preview_state.running.store(false, Ordering::Relaxed);
+                        return;
+                    }
+                }
+            }
+            (None, Some(Target::Window(w)))
+                if settings.include_child_windows && !settings.follow_focus =>
+            {
+                match WindowRegion::for_windows(&[w.id], &targets) {
+                    Some((display, region)) => (Some(display), Some(region)),
+                    None => (Some(Target::Window(w)), None),
+                }
+            }
+            (None, target) => (target, None),
+        };
+        let plain_window = |target: &Option<Target>| match target {
+            Some(Target::Window(w)) if window_region.is_none() => Some(w.id),
+            _ => None,
+        };
+        let mut chrome_window = plain_window(&target).filter(|_| settings.trim_window_chrome);
+        let mut corner_window = plain_window(&target).filter(|_| settings.mask_window_corners);
+        let mut focus_tracker = (settings.follow_focus && !test_pattern && window_region.is_none())
+            .then(|| FocusTracker::new(plain_window(&target)));
+        preview_state.set_capture_target(target.as_ref().map(target_id));
+        // What a reconnect looks for: the captured target (a display for region captures).
+        let mut reconnect_target = target.clone().filter(|_| settings.auto_reconnect);
+        // What the source captures and the rate it was opened with, for rebuilding it.
+        let mut source_target = target.clone();
+        let mut source_fps = settings.fps;
+        let mut source = match open_source(&settings, target) {
+            Ok(source) => source,
+            Err(e) => {
+                emit_capture_error(&app_handle, e);
+                preview_state.running.store(false, Ordering::Relaxed);
+                return;
+            }
+        };
+
+        let mut clock = FrameClock::default();
+        let mut throttle = FrameThrottle::new(target_fps);
+        preview_state.set_effective_fps(target_fps);
+        let mut rejected_frames = RejectedFrames::default();
+        // Handed out with every frame until the settings change.
+        let mut frame_settings = Arc::new(settings.clone());
+        let mut target_switches = 0;
+        let mut handoff = {
+            let (app, state) = (app_handle.clone(), preview_state.clone());
+            let region = window_region.clone();
+            handoff::spawn(move |frames| convert_frames(&app, &state, frames, region, track_idle))
+        };
+
+        while preview_state.running.load(Ordering::Relaxed) {
+            let Some(frame) = source.next_frame() else {
+                let session_active = || preview_state.running.load(Ordering::Relaxed);
+                let Some(lost) = reconnect_target.take() else {
+                    // The backend gave up on its own, e.g. because the target went away.
+                    if session_active() && !test_pattern {
+                        emit_capture_error(
+                            &app_handle,
+                            "Capture ended: the target is no longer available.".to_string(),
+                        );
+                    }
+                    break;
+                };
+                source.stop();
+                let title = target_title(&lost).to_string();
+                let _ = app_handle.emit("capture-reconnecting", &title);
+                let Some((capturer, target)) = reconnect(&settings, &lost, session_active) else {
+                    if session_active() {
+                        emit_capture_error(
+                            &app_handle,
+                            format!(
+                                "Lost \"{title}\" and it did not come back within {} s.",
+                                settings.reconnect_timeout_secs
+                            ),
+                        );
+                    }
+                    break;
+                };
+                // A reopened window usually has a new ID.
+                let target = Some(target);
+                chrome_window = plain_window(&target).filter(|_| settings.trim_window_chrome);
+                corner_window = plain_window(&target).filter(|_| settings.mask_window_corners);
+                source = FrameSource::Capturer(capturer);
+                (source_target, source_fps) = (target.clone(), settings.fps);
+                preview_state.set_capture_target(target.as_ref().map(target_id));
+                reconnect_target = target;
+                let _ = app_handle.emit("capture-reconnected", &title);
+                continue;
+            };
+            let arrival = Instant::now();
+            if let Some(rebuild) = preview_state
+                .take_settings_update()
+                .and_then(|update| settings.update_session(&update))
+            {
+                // scap delivers at most the rate the capturer was built with.
+                let rebuild = rebuild || settings.fps > source_fps;
+                preview_state.set_effective_fps(settings.fps.max(1));
+                frame_settings = Arc::new(settings.clone());
+                // The old source keeps running until the new one works.
+                let applied = match rebuild.then(|| open_source(&settings, source_target.clone())) {
+                    Some(Ok(rebuilt)) => {
+                        source.stop();
+                        source = rebuilt;
+                        source_fps = settings.fps;
+                        Some(true)
+                    }
+                    Some(Err(e)) => {
+                        let message = format!("Could not apply the capture settings: {e}");
+                        emit_capture_error(&app_handle, message);
+                        None
+                    }
+                    None => Some(false),
+                };
+                if let Some(rebuilt) = applied {
+                    let payload = SettingsApplied {
+                        session_id,
+                        rebuilt,
+                    };
+                    let _ = app_handle.emit("settings-applied", payload);
+                }
+            }
+            preview_state.stats.count_captured();
+            preview_state.stats.sample(arrival);
+            if let Some(behind) = preview_state.stats.behind(session_id, arrival) {
+                let _ = app_handle.emit("preview-behind", behind);
+            }
+            // Keep draining the source so resuming shows a current frame.
+            if preview_state.is_paused() {
+                continue;
+            }
+            if preview_state.is_unseen() {
+                let state = app_handle.state::<CaptureState>();
+                if !recording::is_recording(&state, session_id)
+                    && !replay::is_buffering(&state, session_id)
+                {
+                    preview_state.stats.count_unseen();
+                    // Changes go unnoticed meanwhile, so they can't count as idle time.
+                    preview_state.touch();
+                    continue;
+                }
+            }
+            if let Some(tracker) = &mut focus_tracker {
+                if let Some(focused) = tracker.poll(arrival) {
+                    let id = target_id(&focused);
+                    // The old capturer keeps running until the new one works.
+                    match build_capturer(&settings, Some(focused.clone())) {
+                        Ok(capturer) => {
+                            source.stop();
+                            source = FrameSource::Capturer(capturer);
+                            (source_target, source_fps) = (Some(focused.clone()), settings.fps);
+                            tracker.switched(id);
+                            chrome_window = Some(id).filter(|_| settings.trim_window_chrome);
+                            corner_window = Some(id).filter(|_| settings.mask_window_corners);
+                            target_switches += 1;
+                            preview_state.set_capture_target(Some(id));
+                            preview_state
+                                .set_source_title(Some(target_title(&focused).to_string()));
+                            reconnect_target = Some(focused).filter(|_| settings.auto_reconnect);
+                            continue;
+                        }
+                        Err(_) => tracker.reject(id),
+                    }
+                }
+            }
+
+            let captured_at = clock.captured_at(pacing::display_time(&frame), arrival);
+            // Dynamic FPS lowers the rate from the converter.
+            throttle.set_fps(preview_state.effective_fps());
+            if !throttle.admit(captured_at) {
+                preview_state.stats.count_throttled();
+                continue;
+            }
+            preview_state.stats.record_admitted(captured_at);
+            if let Some((w, h)) = frame_limits::frame_size(&frame) {
+                if !settings.frame_limits.allows(w, h) {
+                    rejected_frames.record(&app_handle, w, h);
+                    continue;
+                }
+            }
+            let raw = RawFrame {
+                frame,
+                received_at: arrival,
+                captured_at,
+                settings: frame_settings.clone(),
+                chrome_window,
+                corner_window,
+                target_switches,
+            };
+            if handoff.send(raw) {
+                preview_state.stats.count_discarded();
+            }
+        }
+
+        source.stop();
+        handoff.close();
+    };
+    let app_handle = app.clone();
+    let ended_state = preview_state.clone();
+    thread::spawn(move || {
+        capture();
+        // A session that ended on its own (error, closed window) leaves the list too.
+        end_session(&app_handle.state::<CaptureState>(), session_id);
+        remember_window_geometry(&app_handle, &ended_state);
+    });
+
+    spawn_duration_limit(app.clone(), preview_state.clone(), duration_secs);
+    spawn_idle_watchdog(
+        app.clone(),
+        preview_state,
+        idle_stop_minutes,
+        idle_restart_secs,
+        restart,
+    );
+    sound::play(sound_cues, Cue::Started);
+    remember_session_targets(app);
+
+    Ok(session_id)
+}
+
+/// A frame the capture thread hands to the session's converter, with how the source and the
+/// settings stood when it was captured.
+struct RawFrame {
+    frame: Frame,
+    received_at: Instant,
+    captured_at: Instant,
+    settings: Arc<CaptureSettings>,
+    /// Window whose title bar and borders are trimmed, and whose rounded corners are masked.
+    chrome_window: Option<u32>,
+    corner_window: Option<u32>,
+    /// Follow-focus switches so far; black-bar detection starts over on each.
+    target_switches: u32,
+}
+
+/// Converts, crops and scales the frames handed over by a session's capture thread, and passes
+/// them to the preview, the recording and the replay buffer until the handoff is closed.
+/// Running apart from the capture thread keeps a slow conversion from backing up the
+/// capturer; frames it cannot keep up with are dropped in the handoff.
+fn convert_frames(
+    app_handle: &AppHandle,
+    preview_state: &Arc<PreviewState>,
+    mut frames: Receiver<RawFrame>,
+    window_region: Option<WindowRegion>,
+    track_idle: bool,
+) {
+    let session_id = preview_state.id;
+    // Settings and follow-focus switches of the previous frame; the state below starts over
+    // when they change.
+    let mut previous_settings: Option<Arc<CaptureSettings>> = None;
+    let mut previous_switches = None;
+    let mut threads = 1;
+    let mut dynamic_rate = None;
+    let mut bar_detector = None;
+    let mut last_checksum: Option<u64> = None;
+    // Checksum, size and output resolution of the last frame handed to the preview.
+    let mut last_pushed: Option<(Option<u64>, u32, u32, String)> = None;
+    let delay_line = DelayLine::spawn(preview_state.clone());
+    // The frame being converted, and the target of each crop, which then takes its place.
+    let mut buffer = Vec::new();
+    let mut cropped = Vec::new();
+    let mut pool = BufferPool::default();
+
+    while let Some(raw) = frames.recv() {
+        let RawFrame {
+            mut frame,
+            received_at,
+            captured_at,
+            settings,
+            chrome_window,
+            corner_window,
+            target_switches,
+        } = raw;
+        let same_settings = previous_settings
+            .as_ref()
+            .is_some_and(|previous| Arc::ptr_eq(previous, &settings));
+        if !same_settings {
+            threads = parallel::conversion_threads(settings.parallel_conversion);
+            let fps = settings.fps.max(1);
+            dynamic_rate = settings.dynamic_fps.then(|| DynamicRate::new(fps));
+            previous_settings = Some(settings.clone());
+        }
+        if previous_switches != Some(target_switches) {
+            previous_switches = Some(target_switches);
+            bar_detector = settings
+                .auto_crop_black_bars
+                .then(BlackBarDetector::default);
+        }
+        let state = app_handle.state::<CaptureState>();
+        let resolution = preview_state.resolution();
+        let user_crop = preview_state.crop();
+        let scaled_on_cpu = settings.resize_on == ResizeOn::Cpu
+            && frame_limits::frame_size(&frame)
+                .and_then(|(w, h)| resolution_target_size(&resolution, w as f32 / h as f32))
+                .is_some();
+        // Everything but the upload works on packed pixels; frames nothing here looks at go
+        // to the preview as delivered.
+        let inspected = scaled_on_cpu
+            || dynamic_rate.is_some()
+            || bar_detector.is_some()
+            || window_region.is_some()
+            || chrome_window.is_some()
+            || user_crop.is_some()
+            || track_idle
+            || settings.skip_duplicate_frames
+            || recording::is_recording(&state, session_id)
+            || replay::is_buffering(&state, session_id);
+        let source = if inspected {
+            None
+        } else {
+            take_source_pixels(&mut frame, &settings.frame_limits)
+        };
+        let size = match &source {
+            Some(source) => Some((source.width, source.height)),
+            None => frame_to_buffer(&frame, &settings.frame_limits, threads, &mut buffer),
+        };
+        let Some((mut width, mut height)) = size else {
+            continue;
+        };
+        if let Some(rate) = &mut dynamic_rate {
+            let changed = rate.update(frame_checksum(&buffer), captured_at);
+            let fps = rate.fps(captured_at);
+            preview_state.set_effective_fps(fps);
+            // The preview already shows this frame.
+            if !changed && rate.is_static(captured_at) {
+                continue;
+            }
+        }
+        if let Some(region) = &window_region {
+            let Some(rect) = region.rect(width, height) else {
+                continue;
+            };
+            crop::crop_into(&buffer, width, rect, &mut cropped);
+            std::mem::swap(&mut buffer, &mut cropped);
+            (width, height) = (rect.2, rect.3);
+        }
+        if let Some(rect) =
+            chrome_window.and_then(|id| window_region::client_area(id, width, height))
+        {
+            crop::crop_into(&buffer, width, rect, &mut cropped);
+            std::mem::swap(&mut buffer, &mut cropped);
+            (width, height) = (rect.2, rect.3);
+        }
+        preview_state.set_source_size(width, height);
+        let user_crop = user_crop.and_then(|c| c.resolve(width, height));
+        if let Some(rect) = user_crop {
+            crop::crop_into(&buffer, width, rect, &mut cropped);
+            std::mem::swap(&mut buffer, &mut cropped);
+            (width, height) = (rect.2, rect.3);
+        }
+        let bar_crop = match &mut bar_detector {
+            Some(detector) if user_crop.is_none() => {
+                detector.update(&buffer, width, height, captured_at)
+            }
+            _ => None,
+        };
+        preview_state.set_detected_crop(bar_crop);
+        if let Some(rect) = bar_crop {
+            crop::crop_into(&buffer, width, rect, &mut cropped);
+            std::mem::swap(&mut buffer, &mut cropped);
+            (width, height) = (rect.2, rect.3);
+        }
+        // A crop usually cuts the corners off already.
+        let corner_radius = corner_window
+            .filter(|_| user_crop.is_none() && bar_crop.is_none())
+            .and_then(window_region::corner_radius)
+            .unwrap_or(0.0);
+
+        let checksum =
+            (track_idle || settings.skip_duplicate_frames).then(|| frame_checksum(&buffer));
+        if track_idle && last_checksum != checksum {
+            last_checksum = checksum;
+            preview_state.touch();
+        }
+
+        // A drawn cursor is part of the pixels, so cursor moves still count as changes.
+        if settings.skip_duplicate_frames {
+            let pushed = (checksum, width, height, resolution.clone());
+            if last_pushed.as_ref() == Some(&pushed) {
+                preview_state.count_duplicate_frame();
+                continue;
+            }
+            last_pushed = Some(pushed);
+        }
+        let target = resolution_target_size(&resolution, width as f32 / height as f32);
+        let (bytes, format, stride, out_width, out_height) = if let Some(source) = source {
+            preview_state.set_resize_algorithm(None);
+            let bytes = FrameBytes::Raw(Arc::new(source.bytes));
+            (bytes, source.format, source.stride, width, height)
+        } else {
+            let cpu_resize =
+                settings.resize_on == ResizeOn::Cpu || recording::is_recording(&state, session_id);
+            let mut out_buffer = pool.take();
+            let (out_width, out_height) = match target {
+                Some((tw, th)) if cpu_resize => {
+                    let algorithm = settings.resize_algorithm.resolve(width, height, tw, th);
+                    preview_state.set_resize_algorithm(Some(algorithm));
+                    let (src, dst) = ((width, height), (tw, th));
+                    resize::resize_into(algorithm, src, &buffer, dst, &mut out_buffer);
+                    (tw, th)
+                }
+                _ => {
+                    preview_state.set_resize_algorithm(None);
+                    // Published as converted; the pooled buffer takes its place.
+                    std::mem::swap(&mut buffer, &mut out_buffer);
+                    (width, height)
+                }
+            };
+            let out_buffer = pool.publish(out_buffer);
+            recording::push_frame(
+                &state,
+                session_id,
+                out_width,
+                out_height,
+                &out_buffer,
+                captured_at,
+            );
+            replay::push_frame(
+                &state,
+                session_id,
+                out_width,
+                out_height,
+                &out_buffer,
+                captured_at,
+            );
+            let (bytes, stride) = (FrameBytes::Packed(out_buffer), out_width * 4);
+            (bytes, PixelFormat::Bgrx, stride, out_width, out_height)
+        };
+        // Scaled on the GPU, frames are shown at the target size all the same.
+        let display_size = target.unwrap_or((out_width, out_height));
+        preview_state.set_frame_size(display_size.0, display_size.1);
+        let frame = FrameData {
+            width: out_width,
+            height: out_height,
+            display_size,
+            stride,
+            format,
+            bytes,
+            captured_at,
+            received_at,
+            corner_radius: corner_radius * out_width as f32 / width as f32,
+        };
+        preview_state.set_latest_frame(frame.clone());
+        if preview_state.delay().is_zero() {
+            preview_state.publish_frame(frame);
+        } else {
+            delay_line.push(frame);
+        }
+    }
+}
+
+/// Remembers the targets of the running sessions as the set to restart (see
+/// `restart_capture`), unless none of them has one.
+fn remember_session_targets(app: &AppHandle) {
+    let state = app.state::<CaptureState>();
+    let targets: Vec<u32> = state.preview_sessions().iter().filter_map(|p| p.target).collect();
+    let unchanged = state.settings.lock().unwrap().session_targets == targets;
+    if !targets.is_empty() && !unchanged {
+        let _ = update_settings(app, |s| s.session_targets = targets);
+    }
+}
+
+/// Freezes (or unfreezes) every running preview on its last frame. Capture keeps running, so
+/// resuming needs neither the target nor permission again.
+fn set_capture_paused(app: &AppHandle, paused: bool) -> Result<(), String> {
+    let sessions = app.state::<CaptureState>().preview_sessions();
+    if sessions.is_empty() {
+        return Err("No capture is running.".to_string());
+    }
+    for preview in sessions {
+        preview.set_paused(paused);
+        if !paused {
+            // The idle countdown starts over after a pause.
+            preview.touch();
+        }
+    }
+    let _ = app.emit("capture-paused", paused);
+    Ok(())
+}
+
+#[tauri::command]
+fn pause_capture(app: AppHandle) -> Result<(), String> {
+    set_capture_paused(&app, true)
+}
+
+#[tauri::command]
+fn resume_capture(app: AppHandle) -> Result<(), String> {
+    set_capture_paused(&app, false)
+}
+
+/// Freezes (or unfreezes) every running preview on its current frame. Unlike pausing, capture
+/// carries on: recordings and the replay buffer keep getting frames, and unfreezing shows the
+/// live picture right away. Screenshots and copies while frozen use the frozen frame.
+fn set_frozen(app: &AppHandle, frozen: bool) -> Result<(), String> {
+    let sessions = app.state::<CaptureState>().preview_sessions();
+    if sessions.is_empty() {
+        return Err("No capture is running.".to_string());
+    }
+    for preview in sessions {
+        preview.set_frozen(frozen);
+    }
+    let _ = app.emit("preview-frozen", frozen);
+    Ok(())
+}
+
+#[tauri::command]
+fn set_preview_frozen(app: AppHandle, frozen: bool) -> Result<(), String> {
+    set_frozen(&app, frozen)
+}
+
+/// Pauses the capture, or resumes it when paused (tray item and preview shortcut).
+fn toggle_capture_paused(app: &AppHandle) -> Result<(), String> {
+    let paused = app
+        .state::<CaptureState>()
+        .primary_session()
+        .is_some_and(|p| p.is_paused());
+    set_capture_paused(app, !paused)
+}
+
+/// Saves a screenshot to the default location and tells where in a notification (tray item
+/// and preview shortcut). Blocks until the capture has a frame.
+fn save_screenshot_notified(app: &AppHandle) {
+    match screenshot::save_screenshot(app, None) {
+        Ok(path) => {
+            let _ = app
+                .notification()
+                .builder()
+                .title("LiteView")
+                .body(format!("Screenshot saved to {}", path.display()))
+                .show();
+        }
+        Err(e) => emit_capture_error(app, e),
+    }
+}
+
+/// Ends session `id`: its capture thread stops and its preview window closes. A recording of it
+/// is finished, and the session marker goes with the last session. Returns whether it was
+/// running.
+fn end_session(state: &CaptureState, id: u32) -> bool {
+    let Some(preview_state) = state.sessions.lock().unwrap().remove(&id) else {
+        return false;
+    };
+    preview_state.running.store(false, Ordering::Relaxed);
+    preview::wake();
+    let mut recording = state.recording.lock().unwrap();
+    if recording.as_ref().is_some_and(|r| r.session() == id) {
+        // Dropping the recording finishes its file.
+        recording.take();
+    }
+    drop(recording);
+    if !state.is_capturing() {
+        recovery::clear_marker(state);
+    }
+    true
+}
+
+/// Stores where the window of an ended session was, for the next first window. Overlay and
+/// fullscreen placements have their own settings and are left out.
+fn remember_window_geometry(app: &AppHandle, preview_state: &PreviewState) {
+    let Some(geometry) = preview_state.geometry() else {
+        return;
+    };
+    let settings = app.state::<CaptureState>().settings.lock().unwrap().clone();
+    let windowed = !settings.overlay_mode && settings.fullscreen_monitor.is_none();
+    if settings.remember_window_geometry
+        && windowed
+        && settings.last_window_geometry != Some(geometry)
+    {
+        let _ = update_settings(app, |s| s.last_window_geometry = Some(geometry));
+    }
+}
+
+/// Stops session `id`; returns whether it was running.
+fn stop_session(state: &CaptureState, id: u32) -> bool {
+    state.session_generation.fetch_add(1, Ordering::SeqCst);
+    let stopped = end_session(state, id);
+    if stopped {
+        sound::play(state.settings.lock().unwrap().sound_cues, Cue::Stopped);
+    }
+    stopped
+}
+
+/// Stops every session.
+fn stop_capture_session(state: &CaptureState) {
+    state.session_generation.fetch_add(1, Ordering::SeqCst);
+    let ids: Vec<u32> = state.sessions.lock().unwrap().keys().copied().collect();
+    let mut stopped = false;
+    for id in ids {
+        stopped |= end_session(state, id);
+    }
+    if stopped {
+        sound::play(state.settings.lock().unwrap().sound_cues, Cue::Stopped);
+    }
+    recovery::clear_marker(state);
+    state.recording.lock().unwrap().take();
+}
+
+/// Reports an error to the frontend and the error log (and with the error sound cue, if
+/// enabled).
+fn emit_capture_error(app: &AppHandle, message: String) {
+    sound::play(app.state::<CaptureState>().settings.lock().unwrap().sound_cues, Cue::Error);
+    error_log::record(app, Level::Error, message.clone());
+    let _ = app.emit("capture-error", message);
+}
+
+/// Payload of "settings-applied": a running session follows changed settings.
+#[derive(Debug, Clone, Serialize)]
+struct SettingsApplied {
+    session_id: u32,
+    /// Whether its capturer was rebuilt for them (cursor, output resolution, higher rate).
+    rebuilt: bool,
+}
 
-        capturer.stop_capture();
-        preview_state.running.store(false, Ordering::Relaxed);
-        preview_state.frame_available.notify_one();
+/// Payload of "capture-stopped".
+#[derive(Debug, Clone, Serialize)]
+struct CaptureStopped {
+    session_id: u32,
+    reason: &'static str,
+}
+
+/// Stops the session `secs` seconds after it started (0 = never) and emits "capture-stopped".
+/// Timed on its own thread like the idle watchdog, so a blocked get_next_frame cannot delay it.
+fn spawn_duration_limit(app: AppHandle, preview_state: Arc<PreviewState>, secs: u32) {
+    if secs == 0 {
+        return;
+    }
+    let ends_at = Instant::now() + Duration::from_secs(secs as u64);
+    preview_state.set_ends_at(Some(ends_at));
+    thread::spawn(move || {
+        while preview_state.running.load(Ordering::Relaxed) {
+            let left = ends_at.saturating_duration_since(Instant::now());
+            if !left.is_zero() {
+                thread::sleep(left.min(IDLE_CHECK_INTERVAL));
+                continue;
+            }
+            if stop_session(&app.state::<CaptureState>(), preview_state.id) {
+                let _ = app.emit(
+                    "capture-stopped",
+                    CaptureStopped {
+                        session_id: preview_state.id,
+                        reason: "duration elapsed",
+                    },
+                );
+            }
+            return;
+        }
+    });
+}
+
+/// Stops the session once the source has shown no change (and nobody touched the preview) for
+/// `minutes`, and starts it again with `restart` after `restart_secs` if set. Runs on its own
+/// thread because backends that only deliver frames on change stay blocked in get_next_frame
+/// while the source is frozen.
+fn spawn_idle_watchdog(
+    app: AppHandle,
+    preview_state: Arc<PreviewState>,
+    minutes: u32,
+    restart_secs: u32,
+    mut restart: SessionOptions,
+) {
+    if minutes == 0 {
+        return;
+    }
+    let limit = Duration::from_secs(minutes as u64 * 60);
+    thread::spawn(move || {
+        while preview_state.running.load(Ordering::Relaxed) {
+            thread::sleep(IDLE_CHECK_INTERVAL);
+            // A paused preview is frozen on purpose, so it never counts as idle.
+            if preview_state.is_paused() || preview_state.idle_for() < limit {
+                continue;
+            }
+            let state = app.state::<CaptureState>();
+            if !stop_session(&state, preview_state.id) {
+                return;
+            }
+            let generation = state.session_generation.load(Ordering::SeqCst);
+            let _ = app.emit("capture-idle-stopped", minutes);
+            let _ = app
+                .notification()
+                .builder()
+                .title("LiteView")
+                .body(format!("Capture stopped after {minutes} min without changes."))
+                .show();
+
+            if restart_secs > 0 {
+                thread::sleep(Duration::from_secs(restart_secs as u64));
+                // Any start/stop in the meantime means the user took over.
+                if state.session_generation.load(Ordering::SeqCst) == generation {
+                    restart.geometry = preview_state.geometry();
+                    // Started without a target, it captured whatever scap picked by default.
+                    if restart.target_id.is_none() && restart.target_pid.is_none() {
+                        restart.target_id = preview_state.capture_target();
+                    }
+                    match start_capture_session(&app, restart) {
+                        Ok(_) => {
+                            let _ = app.emit("capture-idle-restarted", ());
+                        }
+                        Err(e) => {
+                            emit_capture_error(&app, e);
+                        }
+                    }
+                }
+            }
+            return;
+        }
     });
+}
+
+/// Starts a session for `target_id` (or the selected target) next to any running ones and
+/// returns its ID. It stops after `duration_secs` (default `capture_duration_secs`, 0 =
+/// unlimited).
+#[tauri::command]
+fn start_capture(
+    target_id: Option<u32>,
+    duration_secs: Option<u32>,
+    app_handle: tauri::AppHandle,
+) -> Result<u32, String> {
+    start_capture_session(
+        &app_handle,
+        SessionOptions {
+            target_id,
+            duration_secs,
+            ..Default::default()
+        },
+    )
+    .inspect_err(|e| {
+        let cues = app_handle.state::<CaptureState>().settings.lock().unwrap().sound_cues;
+        sound::play(cues, Cue::Error);
+        error_log::record(&app_handle, Level::Error, e.clone());
+    })
+}
 
+/// Hides or shows the preview window of session `session_id`, or of every session without one.
+/// Capture carries on while a window is hidden.
+fn set_preview_dismissed(
+    app: &AppHandle,
+    session_id: Option<u32>,
+    dismissed: bool,
+) -> Result<(), String> {
+    let state = app.state::<CaptureState>();
+    let sessions = match session_id {
+        Some(id) => {
+            let session = state.sessions.lock().unwrap().get(&id).cloned();
+            vec![session.ok_or_else(|| format!("Capture session {id} is not running."))?]
+        }
+        None => state.preview_sessions(),
+    };
+    if sessions.is_empty() {
+        return Err("No capture is running.".to_string());
+    }
+    for preview in sessions {
+        preview.set_dismissed(dismissed);
+    }
     Ok(())
 }
 
 #[tauri::command]
-fn stop_capture(state: State<CaptureState>) -> Result<(), String> {
-    state.stop_requested.store(true, Ordering::Relaxed);
-    if let Some(preview_state) = state.preview_state.lock().unwrap().take() {
-        preview_state.running.store(false, Ordering::Relaxed);
-        preview_state.frame_available.notify_one();
+fn show_preview(app: AppHandle, session_id: Option<u32>) -> Result<(), String> {
+    set_preview_dismissed(&app, session_id, false)
+}
+
+#[tauri::command]
+fn hide_preview(app: AppHandle, session_id: Option<u32>) -> Result<(), String> {
+    set_preview_dismissed(&app, session_id, true)
+}
+
+/// Opens another preview window of session `session_id` (the primary one without it) and
+/// returns its ID. The clone shows the same frames in a window of its own size, position,
+/// scale mode and fullscreen state.
+#[tauri::command]
+fn open_preview_window(app: AppHandle, session_id: Option<u32>) -> Result<u32, String> {
+    let state = app.state::<CaptureState>();
+    let session = match session_id {
+        Some(id) => state.sessions.lock().unwrap().get(&id).cloned(),
+        None => state.primary_session(),
+    };
+    let session = session.ok_or_else(|| match session_id {
+        Some(id) => format!("Capture session {id} is not running."),
+        None => "No capture is running.".to_string(),
+    })?;
+    let id = state.next_window_id.fetch_add(1, Ordering::Relaxed);
+    session.add_clone(id);
+    Ok(id)
+}
+
+/// Closes preview window `id` opened with open_preview_window; capture carries on.
+#[tauri::command]
+fn close_preview_window(app: AppHandle, id: u32) -> Result<(), String> {
+    let state = app.state::<CaptureState>();
+    if state.preview_sessions().iter().any(|p| p.remove_clone(id)) {
+        Ok(())
+    } else {
+        Err(format!("Preview window {id} is not open."))
     }
+}
+
+/// Stops session `session_id`, or every session without one.
+#[tauri::command]
+fn stop_capture(app: AppHandle, session_id: Option<u32>) -> Result<(), String> {
+    let state = app.state::<CaptureState>();
+    let Some(id) = session_id else {
+        stop_capture_session(&state);
+        return Ok(());
+    };
+    if !stop_session(&state, id) {
+        return Err(format!("Capture session {id} is not running."));
+    }
+    remember_session_targets(&app);
     Ok(())
 }
 
+/// Whether `flag` (e.g. "--headless") was passed on the command line.
+fn cli_flag(flag: &str) -> bool {
+    std::env::args().skip(1).any(|a| a == flag)
+}
+
+/// Restarts capture of the remembered session targets, or of the selected target if there are
+/// none; used by the tray when there is no webview to drive it.
+fn restart_capture(app: &AppHandle) {
+    let state = app.state::<CaptureState>();
+    stop_capture_session(&state);
+    let targets = state.settings.lock().unwrap().session_targets.clone();
+    let options: Vec<SessionOptions> = if targets.is_empty() {
+        vec![SessionOptions::default()]
+    } else {
+        targets
+            .into_iter()
+            .map(|id| SessionOptions {
+                target_id: Some(id),
+                ..Default::default()
+            })
+            .collect()
+    };
+    for options in options {
+        if let Err(e) = start_capture_session(app, options) {
+            eprintln!("Capture: {e}");
+            error_log::record(app, Level::Error, e);
+        }
+    }
+}
+
+/// Starts capturing the saved target at launch (`autostart_capture`). A saved target that is
+/// gone is reported with a notification and "capture-error" rather than capturing the default
+/// target instead.
+fn autostart_capture(app: &AppHandle) {
+    let settings = app.state::<CaptureState>().settings.lock().unwrap().clone();
+    // Without permission the capture thread checks the target once starting has asked for it.
+    if !settings.uses_test_pattern() && has_permission() {
+        let targets = get_all_targets();
+        let missing = match (settings.target_pid, settings.target_id) {
+            (Some(pid), _) => apps::window_ids(&targets, pid)
+                .is_empty()
+                .then(|| "application".to_string()),
+            (None, Some(id)) => find_target(&targets, id, settings.target_title.as_deref())
+                .is_none()
+                .then(|| {
+                    let title = settings.target_title.as_deref().unwrap_or("unknown");
+                    format!("target \"{title}\"")
+                }),
+            (None, None) => None,
+        };
+        if let Some(what) = missing {
+            let message = format!("Autostart: the saved {what} is not available.");
+            let _ = app
+                .notification()
+                .builder()
+                .title("LiteView")
+                .body(&message)
+                .show();
+            emit_capture_error(app, message);
+            return;
+        }
+    }
+    if let Err(e) = start_capture_session(app, SessionOptions::default()) {
+        let _ = app
+            .notification()
+            .builder()
+            .title("LiteView")
+            .body(format!("Autostart: {e}"))
+            .show();
+        emit_capture_error(app, e);
+    }
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     #[cfg(target_os = "windows")]
@@ -449,23 +2365,248 @@ pub fn run() {
         let _ = unsafe { SetProcessDpiAwarenessContext(DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2) };
     }
 
-    tauri::Builder::default()
-        .plugin(tauri_plugin_opener::init())
+    let headless = cli_flag("--headless");
+
+    let context = tauri::generate_context!();
+    let mut builder = tauri::Builder::default().plugin(tauri_plugin_opener::init());
+    if updater::has_pubkey(context.config()) {
+        builder = builder.plugin(tauri_plugin_updater::Builder::new().build());
+    }
+    builder
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_clipboard_manager::init())
         .manage(CaptureState::default())
         .invoke_handler(tauri::generate_handler![
             get_app_version,
             get_capture_targets,
+            request_capture_permission,
+            about::get_diagnostics,
+            about::open_data_folder,
+            apps::get_capture_applications,
+            error_log::get_recent_errors,
+            error_log::clear_errors,
+            self_test::run_self_test,
+            current_frame::get_current_frame,
+            recording::start_recording,
+            recording::stop_recording,
+            replay::save_replay,
+            screenshot::capture_screenshot,
+            clipboard::copy_frame_to_clipboard,
+            target_match::start_capture_by_title,
+            recovery::get_resumable_session,
+            recovery::dismiss_resumable_session,
+            recovery::resume_session,
+            updater::check_for_update,
+            updater::install_update,
+            updater::restart_app,
+            capabilities::get_capture_capabilities,
             get_capture_settings,
             set_capture_settings,
+            set_picture_adjustments,
+            copy_adjustments_to_global,
+            get_capture_status,
+            stats::get_capture_stats,
             start_capture,
             stop_capture,
+            pause_capture,
+            resume_capture,
+            set_preview_frozen,
+            show_preview,
+            hide_preview,
+            open_preview_window,
+            close_preview_window,
+            layout::save_layout,
+            layout::load_layout,
+            layout::list_layouts,
+            layout::delete_layout,
+            arrange::arrange_previews,
+            arrange::set_preview_scale,
+            monitors::get_monitors,
+            keybindings::reset_keybindings,
+            crop::set_crop_for_target,
+            crop::set_crop_area,
+            crop::clear_crop_for_target,
+            crop::lock_detected_crop,
+            anchor::pick_anchor_window,
+            mirror::enter_mirror_mode,
+            mirror::exit_mirror_mode,
+            overlay_mode::set_overlay_mode,
+            overlay_mode::set_overlay_locked,
+            overlay_mode::set_preview_bounds,
+            pip::set_pip_mode,
         ])
-        .setup(|app| {
-            if let Some(loaded) = load_settings_from_disk(&app.handle()) {
-                *app.state::<CaptureState>().settings.lock().unwrap() = loaded;
+        .setup(move |app| {
+            // --headless: no settings window (and no webview); the tray and CLI drive capture.
+            if !headless {
+                let config = app
+                    .config()
+                    .app
+                    .windows
+                    .iter()
+                    .find(|w| w.label == "main")
+                    .ok_or("Window \"main\" missing from tauri.conf.json")?;
+                WebviewWindowBuilder::from_config(app.handle(), config)?.build()?;
+            }
+            if let Some((loaded, migrated)) = load_settings_from_disk(app.handle()) {
+                let state = app.state::<CaptureState>();
+                *state.display.lock().unwrap() = loaded.display_options();
+                *state.keybindings.lock().unwrap() = Keybindings::from_map(&loaded.keybindings);
+                state.replay.lock().unwrap().configure(
+                    loaded.replay_secs,
+                    loaded.replay_memory_mb,
+                    loaded.fps,
+                );
+                if migrated {
+                    let _ = save_settings_to_disk(app.handle(), &loaded);
+                }
+                *state.settings.lock().unwrap() = loaded;
+            }
+            let slot = app.state::<CaptureState>().sessions.clone();
+            let display = app.state::<CaptureState>().display.clone();
+            let keys = app.state::<CaptureState>().keybindings.clone();
+            let render_status = app.state::<CaptureState>().render_status.clone();
+            let action_app = app.handle().clone();
+            let on_action = Box::new(move |action: PreviewAction| match action {
+                PreviewAction::CycleArrangement => {
+                    if let Err(e) = arrange::cycle(&action_app) {
+                        emit_capture_error(&action_app, e);
+                    }
+                }
+                PreviewAction::CycleColorBlindness => {
+                    let _ = update_settings(&action_app, |s| {
+                        s.color_blindness = s.color_blindness.next();
+                    });
+                }
+                PreviewAction::Rotate => {
+                    let _ = update_settings(&action_app, |s| {
+                        let mut adjustments = s.effective_adjustments();
+                        adjustments.rotation = (adjustments.rotation + 90) % 360;
+                        s.set_effective_adjustments(adjustments);
+                    });
+                }
+                PreviewAction::ResetAdjustments => {
+                    let _ = update_settings(&action_app, |s| {
+                        // Orientation is not a picture control; it stays.
+                        s.set_effective_adjustments(PictureAdjustments {
+                            rotation: s.effective_adjustments().rotation,
+                            ..Default::default()
+                        });
+                    });
+                }
+                PreviewAction::CycleScaleMode => {
+                    let _ = update_settings(&action_app, |s| {
+                        s.scale_mode = s.scale_mode.next();
+                    });
+                }
+                PreviewAction::ToggleFrameGraph => {
+                    let _ = update_settings(&action_app, |s| {
+                        s.show_frame_graph = !s.show_frame_graph;
+                    });
+                }
+                PreviewAction::ToggleInfoOverlay => {
+                    let _ = update_settings(&action_app, |s| {
+                        s.show_info_overlay = !s.show_info_overlay;
+                    });
+                }
+                PreviewAction::ToggleCrosshair => {
+                    let _ = update_settings(&action_app, |s| {
+                        s.show_crosshair = !s.show_crosshair;
+                    });
+                }
+                PreviewAction::CycleGrid => {
+                    let _ = update_settings(&action_app, |s| {
+                        s.grid_mode = s.grid_mode.next();
+                    });
+                }
+                PreviewAction::ToggleOverlay => {
+                    if let Err(e) = overlay_mode::toggle(&action_app) {
+                        emit_capture_error(&action_app, e);
+                    }
+                }
+                PreviewAction::TogglePip => {
+                    if let Err(e) = pip::toggle(&action_app) {
+                        emit_capture_error(&action_app, e);
+                    }
+                }
+                PreviewAction::Toggle(toggle) => {
+                    if let Err(e) = toggle_menu::flip(&action_app, toggle) {
+                        emit_capture_error(&action_app, e);
+                    }
+                }
+                PreviewAction::Resize(preset) => {
+                    if let Err(e) = arrange::apply_size(&action_app, preset) {
+                        emit_capture_error(&action_app, e);
+                    }
+                }
+                PreviewAction::TogglePause => {
+                    if let Err(e) = toggle_capture_paused(&action_app) {
+                        emit_capture_error(&action_app, e);
+                    }
+                }
+                PreviewAction::ToggleFreeze => {
+                    let frozen = action_app
+                        .state::<CaptureState>()
+                        .primary_session()
+                        .is_some_and(|p| p.is_frozen());
+                    if let Err(e) = set_frozen(&action_app, !frozen) {
+                        emit_capture_error(&action_app, e);
+                    }
+                }
+                PreviewAction::Screenshot => {
+                    let app = action_app.clone();
+                    tauri::async_runtime::spawn_blocking(move || save_screenshot_notified(&app));
+                }
+                PreviewAction::ToggleFullscreen => {
+                    let monitor = action_app
+                        .state::<CaptureState>()
+                        .primary_session()
+                        .and_then(|p| p.geometry())
+                        .and_then(|g| monitors::index_of(&action_app, g));
+                    let _ = update_settings(&action_app, |s| {
+                        s.fullscreen_monitor = match s.fullscreen_monitor {
+                            Some(_) => None,
+                            None => Some(monitor.unwrap_or(0)),
+                        };
+                    });
+                }
+                // Handled by the preview window.
+                PreviewAction::ResetZoom
+                | PreviewAction::ZoomIn
+                | PreviewAction::ZoomOut
+                | PreviewAction::ToggleZoomInset
+                | PreviewAction::ClosePreview
+                | PreviewAction::ShowShortcuts => {}
+                PreviewAction::CopyFrame => {
+                    // Converting a large frame takes a moment; keep the preview responsive.
+                    let app = action_app.clone();
+                    thread::spawn(move || {
+                        if let Err(e) = clipboard::copy_frame(&app) {
+                            emit_capture_error(&app, e);
+                        }
+                    });
+                }
+            });
+            thread::spawn(move || {
+                preview::run_preview_window(slot, display, keys, render_status, on_action)
+            });
+
+            let layout_name = layout::cli_layout_name();
+            let autostart = app.state::<CaptureState>().settings.lock().unwrap().autostart_capture;
+            recovery::check_previous_session(
+                app.handle(),
+                !headless && !autostart && layout_name.is_none(),
+            );
+            if let Some(name) = layout_name {
+                if let Err(e) = layout::apply_layout(app.handle(), &name) {
+                    let message = format!("Layout \"{name}\": {e}");
+                    eprintln!("{message}");
+                    error_log::record(app.handle(), Level::Error, message);
+                }
+            } else if headless {
+                restart_capture(app.handle());
+            } else if autostart {
+                autostart_capture(app.handle());
             }
-            let slot = app.state::<CaptureState>().preview_state.clone();
-            thread::spawn(move || preview::run_preview_window(slot));
 
             let start_capture_i = MenuItem::with_id(
                 app,
@@ -474,21 +2615,109 @@ pub fn run() {
                 true,
                 None::<&str>,
             )?;
+            let target_i = Submenu::with_items(app, "Capture target", false, &[])?;
+            let target_menu = TargetMenu::new(target_i.clone());
+            if has_permission() {
+                let state = app.state::<CaptureState>();
+                let targets = target_dtos(state.targets.current().into_vec());
+                target_menu.rebuild(app.handle(), &targets, &state.settings.lock().unwrap());
+            }
+            app.manage(target_menu);
             let stop_capture_i =
                 MenuItem::with_id(app, "stop_capture", "Stop capture", true, None::<&str>)?;
+            let pause_capture_i =
+                MenuItem::with_id(app, "pause_capture", "Pause/resume capture", true, None::<&str>)?;
+            let show_preview_i =
+                MenuItem::with_id(app, "show_preview", "Show preview", true, None::<&str>)?;
+            let screenshot_i =
+                MenuItem::with_id(app, "screenshot", "Save screenshot", true, None::<&str>)?;
+            let replay_i =
+                MenuItem::with_id(app, "save_replay", "Save replay", true, None::<&str>)?;
+            let arrange_grid_i =
+                MenuItem::with_id(app, "arrange_grid", "Grid", true, None::<&str>)?;
+            let arrange_horizontal_i =
+                MenuItem::with_id(app, "arrange_horizontal", "Horizontal strip", true, None::<&str>)?;
+            let arrange_vertical_i =
+                MenuItem::with_id(app, "arrange_vertical", "Vertical strip", true, None::<&str>)?;
+            let arrange_i = Submenu::with_items(
+                app,
+                "Arrange previews",
+                true,
+                &[&arrange_grid_i, &arrange_horizontal_i, &arrange_vertical_i],
+            )?;
+            let size_items = SizePreset::ALL
+                .into_iter()
+                .map(|p| {
+                    CheckMenuItem::with_id(app, p.id(), p.label(), false, false, None::<&str>)
+                        .map(|item| (p, item))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let size_refs: Vec<&dyn IsMenuItem<tauri::Wry>> = size_items
+                .iter()
+                .map(|(_, item)| item as &dyn IsMenuItem<tauri::Wry>)
+                .collect();
+            let size_i = Submenu::with_items(app, "Preview size", true, &size_refs)?;
+            app.manage(SizeMenu::new(size_items.clone()));
+            let toggle_items = {
+                let settings = app.state::<CaptureState>().settings.lock().unwrap().clone();
+                Toggle::ALL
+                    .into_iter()
+                    .map(|t| {
+                        let checked = t.get(&settings);
+                        CheckMenuItem::with_id(app, t.id(), t.label(), true, checked, None::<&str>)
+                            .map(|item| (t, item))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?
+            };
+            let toggle_refs: Vec<&dyn IsMenuItem<tauri::Wry>> = toggle_items
+                .iter()
+                .map(|(_, item)| item as &dyn IsMenuItem<tauri::Wry>)
+                .collect();
+            let toggles_i = Submenu::with_items(app, "Preview window", true, &toggle_refs)?;
+            app.manage(ToggleMenu::new(toggle_items.clone()));
+            arrange::spawn_size_menu_watcher(app.handle().clone());
+            anchor::spawn_anchor_follower(app.handle().clone());
+            target_watch::spawn_target_watcher(app.handle().clone());
+            global_hotkey::spawn_hotkey_listener(app.handle().clone());
+            let mirror_i =
+                MenuItem::with_id(app, "mirror_mode", "Mirror mode on/off", true, None::<&str>)?;
+            let overlay_i =
+                MenuItem::with_id(app, "overlay_mode", "Overlay mode on/off", true, None::<&str>)?;
+            let pip_i = MenuItem::with_id(
+                app,
+                "pip_mode",
+                "Picture-in-picture on/off",
+                true,
+                None::<&str>,
+            )?;
             let sep1 = PredefinedMenuItem::separator(app)?;
-            let show_i = MenuItem::with_id(app, "show", "Show window", true, None::<&str>)?;
-            let settings_i = MenuItem::with_id(app, "settings", "Settings", true, None::<&str>)?;
+            let show_i = MenuItem::with_id(app, "show", "Show window", !headless, None::<&str>)?;
+            let settings_i =
+                MenuItem::with_id(app, "settings", "Settings", !headless, None::<&str>)?;
+            let about_i =
+                MenuItem::with_id(app, "about", "About LiteView", true, None::<&str>)?;
             let sep2 = PredefinedMenuItem::separator(app)?;
             let quit_i = MenuItem::with_id(app, "quit", "Quit LiteView", true, None::<&str>)?;
             let menu = Menu::with_items(
                 app,
                 &[
                     &start_capture_i,
+                    &target_i,
                     &stop_capture_i,
+                    &pause_capture_i,
+                    &show_preview_i,
+                    &screenshot_i,
+                    &replay_i,
+                    &arrange_i,
+                    &size_i,
+                    &toggles_i,
+                    &mirror_i,
+                    &overlay_i,
+                    &pip_i,
                     &sep1,
                     &show_i,
                     &settings_i,
+                    &about_i,
                     &sep2,
                     &quit_i,
                 ],
@@ -502,26 +2731,346 @@ pub fn run() {
                 .menu(&menu)
                 .show_menu_on_left_click(true)
                 .tooltip("LiteView")
-                .on_menu_event(|app, event| match event.id.as_ref() {
-                    "start_capture" => {
-                        let _ = app.emit("capture-start", ());
+                .on_menu_event(move |app, event| {
+                    if let Some(preset) = SizePreset::from_id(event.id.as_ref()) {
+                        if let Err(e) = arrange::apply_size(app, preset) {
+                            emit_capture_error(app, e);
+                        }
+                        // The click toggled the checkmark; let the watcher set it right
+                        app.state::<SizeMenu>().invalidate();
+                        return;
                     }
-                    "stop_capture" => {
-                        let _ = app.emit("capture-stop", ());
+                    if let Some(id) = target_menu::target_from_id(event.id.as_ref()) {
+                        if let Err(e) = target_menu::select(app, id) {
+                            emit_capture_error(app, e);
+                        }
+                        return;
+                    }
+                    if let Some(toggle) = Toggle::from_id(event.id.as_ref()) {
+                        if let Err(e) = toggle_menu::flip(app, toggle) {
+                            emit_capture_error(app, e);
+                        }
+                        return;
                     }
-                    "show" | "settings" => {
-                        if let Some(window) = app.get_webview_window("main") {
-                            let _ = window.unminimize();
-                            let _ = window.show();
-                            let _ = window.set_focus();
+                    match event.id.as_ref() {
+                        "start_capture" if headless => restart_capture(app),
+                        "start_capture" => {
+                            let _ = app.emit("capture-start", ());
+                        }
+                        "stop_capture" if headless => {
+                            stop_capture_session(&app.state::<CaptureState>());
+                        }
+                        "stop_capture" => {
+                            let _ = app.emit("capture-stop", ());
+                        }
+                        "show_preview" => {
+                            if let Err(e) = set_preview_dismissed(app, None, false) {
+                                emit_capture_error(app, e);
+                            }
+                        }
+                        "pause_capture" => {
+                            if let Err(e) = toggle_capture_paused(app) {
+                                emit_capture_error(app, e);
+                            }
+                        }
+                        "screenshot" => {
+                            let app = app.clone();
+                            tauri::async_runtime::spawn_blocking(move || {
+                                save_screenshot_notified(&app);
+                            });
                         }
+                        "save_replay" => {
+                            if let Err(e) = replay::write_replay(app, None, true) {
+                                emit_capture_error(app, e);
+                            }
+                        }
+                        "show" | "settings" => {
+                            if let Some(window) = app.get_webview_window("main") {
+                                let _ = window.unminimize();
+                                let _ = window.show();
+                                let _ = window.set_focus();
+                            }
+                            // Off the taskbar, the tray is the way to find the previews.
+                            if event.id.as_ref() == "show" {
+                                for preview in app.state::<CaptureState>().preview_sessions() {
+                                    preview.request_raise();
+                                }
+                            }
+                        }
+                        "arrange_grid" | "arrange_horizontal" | "arrange_vertical" => {
+                            let mode = match event.id.as_ref() {
+                                "arrange_grid" => ArrangeMode::Grid,
+                                "arrange_horizontal" => ArrangeMode::Horizontal,
+                                _ => ArrangeMode::Vertical,
+                            };
+                            if let Err(e) = arrange::arrange(app, mode, None) {
+                                emit_capture_error(app, e);
+                            }
+                        }
+                        "mirror_mode" => {
+                            if let Err(e) = mirror::toggle(app) {
+                                emit_capture_error(app, e);
+                            }
+                        }
+                        "overlay_mode" => {
+                            if let Err(e) = overlay_mode::toggle(app) {
+                                emit_capture_error(app, e);
+                            }
+                        }
+                        "pip_mode" => {
+                            if let Err(e) = pip::toggle(app) {
+                                emit_capture_error(app, e);
+                            }
+                        }
+                        "about" => {
+                            if let Err(e) = about::open_about_window(app) {
+                                emit_capture_error(app, e.to_string());
+                            }
+                        }
+                        "quit" => app.exit(0),
+                        _ => {}
                     }
-                    "quit" => app.exit(0),
-                    _ => {}
                 })
                 .build(app)?;
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(context)
+        .expect("error while building tauri application")
+        .run(|app, event| match event {
+            // Closing the last webview (e.g. the About window in headless mode) must not quit
+            // the tray app; only app.exit passes an exit code.
+            tauri::RunEvent::ExitRequested {
+                code: None, api, ..
+            } => api.prevent_exit(),
+            tauri::RunEvent::Exit => {
+                recovery::clear_marker(&app.state::<CaptureState>());
+                if let Err(e) = app.state::<CaptureState>().settings_writer.flush() {
+                    eprintln!("Saving settings: {e}");
+                }
+            }
+            _ => {}
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 0x00RRGGBB test pixels of a 3 × 3 frame.
+    const PIXELS: [[u32; 3]; 3] = [
+        [0x102030, 0x405060, 0x708090],
+        [0xA0B0C0, 0xD0E0F0, 0x010203],
+        [0xFF0000, 0x00FF00, 0x0000FF],
+    ];
+    const EXPECTED: [u32; 9] = [
+        0x102030, 0x405060, 0x708090, 0xA0B0C0, 0xD0E0F0, 0x010203, 0xFF0000, 0x00FF00, 0x0000FF,
+    ];
+
+    /// `PIXELS` as `bpp`-byte pixels with red, green and blue at `channels` (other bytes 0xAA),
+    /// each row followed by `padding` bytes of 0xEE except the last when `pad_last` is false.
+    fn padded(bpp: usize, [r, g, b]: [usize; 3], padding: usize, pad_last: bool) -> Vec<u8> {
+        let mut data = Vec::new();
+        for (y, row) in PIXELS.iter().enumerate() {
+            for &px in row {
+                let mut bytes = vec![0xAA; bpp];
+                bytes[r] = (px >> 16) as u8;
+                bytes[g] = (px >> 8) as u8;
+                bytes[b] = px as u8;
+                data.extend(bytes);
+            }
+            if pad_last || y + 1 < PIXELS.len() {
+                data.extend(std::iter::repeat_n(0xEE, padding));
+            }
+        }
+        data
+    }
+
+    fn packed(data: &[u8], bpp: usize, channels: [usize; 3]) -> Option<Vec<u32>> {
+        let mut buffer = vec![0xDEAD_BEEF; 2];
+        pack_rows(data, (3, 3), bpp, channels, 1, &mut buffer)?;
+        Some(buffer)
+    }
+
+    #[test]
+    fn row_stride_infers_padding() {
+        // 4 rows of 12 bytes: tight, padded to 16, and padded but for the last row.
+        assert_eq!(row_stride(48, 12, 4), Some(12));
+        assert_eq!(row_stride(64, 12, 4), Some(16));
+        assert_eq!(row_stride(16 * 2 + 12, 12, 3), Some(16));
+        assert_eq!(row_stride(12, 12, 1), Some(12));
+        // Too short for the rows.
+        assert_eq!(row_stride(40, 12, 4), None);
+        assert_eq!(row_stride(8, 12, 1), None);
+    }
+
+    #[test]
+    fn pack_rows_skips_padding_of_bgra() {
+        for pad_last in [true, false] {
+            let data = padded(4, [2, 1, 0], 4, pad_last);
+            assert_eq!(row_stride(data.len(), 12, 3), Some(16));
+            assert_eq!(packed(&data, 4, [2, 1, 0]).unwrap(), EXPECTED);
+        }
+    }
+
+    #[test]
+    fn pack_rows_drops_the_fourth_byte_of_bgrx() {
+        // 0xAA in the x byte must not leak into the packed pixel's top byte.
+        let data = padded(4, [2, 1, 0], 20, true);
+        assert_eq!(row_stride(data.len(), 12, 3), Some(32));
+        assert_eq!(packed(&data, 4, [2, 1, 0]).unwrap(), EXPECTED);
+    }
+
+    #[test]
+    fn pack_rows_reorders_padded_rgb_and_xbgr() {
+        let data = padded(3, [0, 1, 2], 7, false);
+        assert_eq!(row_stride(data.len(), 9, 3), Some(16));
+        assert_eq!(packed(&data, 3, [0, 1, 2]).unwrap(), EXPECTED);
+        let data = padded(4, [3, 2, 1], 8, true);
+        assert_eq!(packed(&data, 4, [3, 2, 1]).unwrap(), EXPECTED);
+    }
+
+    #[test]
+    fn pack_rows_refuses_short_or_empty_frames() {
+        let data = padded(4, [2, 1, 0], 0, true);
+        assert_eq!(packed(&data[..30], 4, [2, 1, 0]), None);
+        let mut buffer = Vec::new();
+        assert_eq!(pack_rows(&data, (0, 3), 4, [2, 1, 0], 1, &mut buffer), None);
+    }
+
+    fn source_frame(frame: Frame) -> (Frame, Option<SourcePixels>) {
+        let mut frame = frame;
+        let source = take_source_pixels(&mut frame, &FrameLimits::default());
+        (frame, source)
+    }
+
+    #[test]
+    fn source_pixels_keep_the_bytes_and_their_layout() {
+        let data = padded(4, [2, 1, 0], 4, false);
+        let (frame, source) = source_frame(Frame::BGRA(scap::frame::BGRAFrame {
+            display_time: 0,
+            width: 3,
+            height: 3,
+            data: data.clone(),
+        }));
+        let source = source.unwrap();
+        assert_eq!((source.width, source.height), (3, 3));
+        assert_eq!((source.format, source.stride), (PixelFormat::Bgrx, 16));
+        assert_eq!(source.bytes, data);
+        assert!(matches!(frame, Frame::BGRA(f) if f.data.is_empty()));
+
+        let data = padded(4, [0, 1, 2], 8, true);
+        let frame = Frame::RGBx(scap::frame::RGBxFrame {
+            display_time: 0,
+            width: 3,
+            height: 3,
+            data,
+        });
+        let source = source_frame(frame).1.unwrap();
+        assert_eq!((source.format, source.stride), (PixelFormat::Rgbx, 20));
+    }
+
+    #[test]
+    fn source_pixels_leave_frames_that_need_converting() {
+        let data = padded(3, [0, 1, 2], 0, true);
+        let (frame, source) = source_frame(Frame::RGB(scap::frame::RGBFrame {
+            display_time: 0,
+            width: 3,
+            height: 3,
+            data: data.clone(),
+        }));
+        assert!(source.is_none());
+        assert!(matches!(frame, Frame::RGB(f) if f.data == data));
+        // Beyond the limits, frames are left for frame_to_buffer to refuse.
+        let limits = FrameLimits {
+            max_dimension: 2,
+            ..FrameLimits::default()
+        };
+        let mut frame = Frame::BGRA(scap::frame::BGRAFrame {
+            display_time: 0,
+            width: 3,
+            height: 3,
+            data: padded(4, [2, 1, 0], 0, true),
+        });
+        assert!(take_source_pixels(&mut frame, &limits).is_none());
+    }
+
+    #[test]
+    fn raw_frames_convert_to_packed_pixels_on_demand() {
+        let now = Instant::now();
+        let frame = |format, bytes: Vec<u8>, stride| FrameData {
+            width: 3,
+            height: 3,
+            display_size: (3, 3),
+            stride,
+            format,
+            bytes: FrameBytes::Raw(Arc::new(bytes)),
+            captured_at: now,
+            received_at: now,
+            corner_radius: 0.0,
+        };
+        let bgrx = frame(PixelFormat::Bgrx, padded(4, [2, 1, 0], 4, false), 16);
+        assert_eq!(*bgrx.packed().unwrap(), EXPECTED);
+        let rgbx = frame(PixelFormat::Rgbx, padded(4, [0, 1, 2], 0, true), 12);
+        assert_eq!(*rgbx.packed().unwrap(), EXPECTED);
+        let short = frame(PixelFormat::Bgrx, vec![0; 20], 12);
+        assert!(short.packed().is_none());
+    }
+
+    /// Widths whose rows are 4 bytes, just under 256 and just over 1024: a pixel, 63 and 257.
+    const WIDTHS: [usize; 3] = [1, 63, 257];
+    /// Eleven rows, so no padding in the tests is a multiple of the height, which would make
+    /// an unpadded last row look like tight rows.
+    const HEIGHT: usize = 11;
+
+    /// The 0x00RRGGBB pixel at `(x, y)` of a `WIDTHS` test frame.
+    fn pixel(x: usize, y: usize) -> u32 {
+        ((x as u32 & 0xFF) << 16) | (((x >> 8) as u32 ^ 0x40) << 8) | (y as u32 * 23)
+    }
+
+    /// A `width` × `HEIGHT` frame of BGRA pixels with rows `stride` bytes apart, padded with
+    /// 0xEE except after the last row when `pad_last` is false.
+    fn bgra_rows(width: usize, stride: usize, pad_last: bool) -> Vec<u8> {
+        let mut data = Vec::new();
+        for y in 0..HEIGHT {
+            for x in 0..width {
+                data.extend(&pixel(x, y).to_le_bytes()[..3]);
+                data.push(0xAA);
+            }
+            if pad_last || y + 1 < HEIGHT {
+                data.resize((y + 1) * stride, 0xEE);
+            }
+        }
+        data
+    }
+
+    #[test]
+    fn rows_at_odd_widths_keep_their_stride_and_pack_intact() {
+        for width in WIDTHS {
+            let expected: Vec<u32> = (0..HEIGHT)
+                .flat_map(|y| (0..width).map(move |x| pixel(x, y)))
+                .collect();
+            let row = width * 4;
+            // Tight, and padded to 64 bytes (Windows' capture) and to 256.
+            for stride in [row, row.div_ceil(64) * 64, row.div_ceil(256) * 256] {
+                for pad_last in [true, false] {
+                    let data = bgra_rows(width, stride, pad_last);
+                    assert_eq!(row_stride(data.len(), row, HEIGHT), Some(stride));
+                    for threads in [1, 4] {
+                        let mut buffer = Vec::new();
+                        let size = (width, HEIGHT);
+                        pack_rows(&data, size, 4, [2, 1, 0], threads, &mut buffer).unwrap();
+                        assert!(buffer == expected, "{width} at {stride}, {threads} threads");
+                    }
+                    let mut frame = Frame::BGRA(scap::frame::BGRAFrame {
+                        display_time: 0,
+                        width: width as i32,
+                        height: HEIGHT as i32,
+                        data,
+                    });
+                    let source = take_source_pixels(&mut frame, &FrameLimits::default()).unwrap();
+                    assert_eq!(source.stride as usize, stride);
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,87 @@
+use crate::display::ScaleMode;
+use crate::{update_settings, CaptureSettings, CaptureState};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+/// Settings changed by mirror mode, captured on entry so leaving restores them exactly.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MirrorSnapshot {
+    flip_horizontal: bool,
+    always_on_top: bool,
+    borderless: bool,
+    click_through: bool,
+    scale_mode: ScaleMode,
+    fullscreen_monitor: Option<usize>,
+}
+
+impl MirrorSnapshot {
+    fn take(s: &CaptureSettings) -> Self {
+        Self {
+            flip_horizontal: s.flip_horizontal,
+            always_on_top: s.always_on_top,
+            borderless: s.borderless,
+            click_through: s.click_through,
+            scale_mode: s.scale_mode,
+            fullscreen_monitor: s.fullscreen_monitor,
+        }
+    }
+
+    fn restore(self, s: &mut CaptureSettings) {
+        s.flip_horizontal = self.flip_horizontal;
+        s.always_on_top = self.always_on_top;
+        s.borderless = self.borderless;
+        s.click_through = self.click_through;
+        s.scale_mode = self.scale_mode;
+        s.fullscreen_monitor = self.fullscreen_monitor;
+    }
+}
+
+/// Applies the mirror preset. Entering again only re-applies it; the first snapshot is kept.
+pub fn enter(app: &AppHandle, fullscreen_monitor: Option<usize>) -> Result<(), String> {
+    update_settings(app, |s| {
+        if s.mirror_snapshot.is_none() {
+            s.mirror_snapshot = Some(MirrorSnapshot::take(s));
+        }
+        s.flip_horizontal = true;
+        s.always_on_top = true;
+        s.borderless = true;
+        s.click_through = false;
+        s.scale_mode = ScaleMode::Fit;
+        s.fullscreen_monitor = fullscreen_monitor;
+    })
+}
+
+/// Restores the settings from before mirror mode (no-op when it is not active).
+pub fn exit(app: &AppHandle) -> Result<(), String> {
+    update_settings(app, |s| {
+        if let Some(snapshot) = s.mirror_snapshot.take() {
+            snapshot.restore(s);
+        }
+    })
+}
+
+pub fn toggle(app: &AppHandle) -> Result<(), String> {
+    let active = app
+        .state::<CaptureState>()
+        .settings
+        .lock()
+        .unwrap()
+        .mirror_snapshot
+        .is_some();
+    if active {
+        exit(app)
+    } else {
+        enter(app, None)
+    }
+}
+
+/// Mirror mode: flipped, on top, borderless, fit scaling; fullscreen on `monitor` if given.
+#[tauri::command]
+pub fn enter_mirror_mode(app: AppHandle, monitor: Option<usize>) -> Result<(), String> {
+    enter(&app, monitor)
+}
+
+#[tauri::command]
+pub fn exit_mirror_mode(app: AppHandle) -> Result<(), String> {
+    exit(&app)
+}
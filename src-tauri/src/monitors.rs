@@ -0,0 +1,58 @@
+use crate::preview::WindowGeometry;
+use serde::Serialize;
+use tauri::AppHandle;
+
+/// A connected monitor, in the order the preview window's monitor settings count them.
+#[derive(Debug, Clone, Serialize)]
+pub struct MonitorInfo {
+    index: usize,
+    /// Identifies the monitor in `preview_monitor`; None when the platform reports no name.
+    name: Option<String>,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    primary: bool,
+}
+
+/// Whether a monitor called `name` is connected.
+pub fn is_connected(app: &AppHandle, name: &str) -> bool {
+    app.available_monitors()
+        .is_ok_and(|monitors| monitors.iter().any(|m| m.name().is_some_and(|n| n == name)))
+}
+
+/// Index of the monitor showing the center of a window at `geometry`, as counted by
+/// `fullscreen_monitor`.
+pub fn index_of(app: &AppHandle, geometry: WindowGeometry) -> Option<usize> {
+    let x = geometry.x + geometry.width as i32 / 2;
+    let y = geometry.y + geometry.height as i32 / 2;
+    app.available_monitors().ok()?.iter().position(|m| {
+        let (pos, size) = (m.position(), m.size());
+        (pos.x..pos.x + size.width as i32).contains(&x)
+            && (pos.y..pos.y + size.height as i32).contains(&y)
+    })
+}
+
+/// Connected monitors, for choosing where the preview opens.
+#[tauri::command]
+pub fn get_monitors(app: AppHandle) -> Result<Vec<MonitorInfo>, String> {
+    let primary = app
+        .primary_monitor()
+        .ok()
+        .flatten()
+        .and_then(|m| m.name().cloned());
+    let monitors = app.available_monitors().map_err(|e| e.to_string())?;
+    Ok(monitors
+        .iter()
+        .enumerate()
+        .map(|(index, m)| MonitorInfo {
+            index,
+            name: m.name().cloned(),
+            x: m.position().x,
+            y: m.position().y,
+            width: m.size().width,
+            height: m.size().height,
+            primary: primary.is_some() && m.name() == primary.as_ref(),
+        })
+        .collect())
+}
@@ -0,0 +1,595 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+use wgpu::{Device, Queue, TextureFormat};
+
+/// How much history the frame-time graph shows.
+const GRAPH_WINDOW: Duration = Duration::from_secs(3);
+/// Frame time at the top of the graph; longer intervals are clipped.
+const GRAPH_MAX_MS: f32 = 50.0;
+const GRAPH_WIDTH: f32 = 300.0;
+const GRAPH_HEIGHT: f32 = 80.0;
+const GRAPH_MARGIN: f32 = 8.0;
+
+const BACKGROUND_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 0.6];
+const CAPTURE_COLOR: [f32; 4] = [0.1, 0.6, 0.9, 0.9];
+const PRESENT_COLOR: [f32; 4] = [1.0, 0.6, 0.1, 0.9];
+const GUIDE_60_COLOR: [f32; 4] = [0.3, 0.9, 0.3, 0.8];
+const GUIDE_30_COLOR: [f32; 4] = [0.9, 0.3, 0.3, 0.8];
+/// Arm length of the small crosshair beyond its gap, in pixels.
+const CROSSHAIR_ARM: f32 = 12.0;
+const INFO_TEXT_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 0.9];
+/// Longest line of the info overlay, in characters; longer source names are cut.
+const INFO_MAX_CHARS: usize = 48;
+const INSET_BORDER_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 0.6];
+const INSET_VIEWPORT_COLOR: [f32; 4] = [1.0, 0.8, 0.1, 0.95];
+/// Size of one cell of the 3×5 digit font, in pixels.
+const DIGIT_CELL: f32 = 2.0;
+/// Rows of the 3×5 digits 0-9, most significant bit on the left.
+pub const DIGITS: [[u8; 5]; 10] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b001, 0b001, 0b001],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+];
+
+/// One solid quad; matches `Instance` in overlay.wgsl.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct OverlayQuad {
+    /// x0, y0, x1, y1 in clip space.
+    rect: [f32; 4],
+    color: [f32; 4],
+}
+
+impl OverlayQuad {
+    /// Quad from a pixel rectangle (origin top-left) on a `surface` of the given size.
+    fn from_pixels(x: f32, y: f32, w: f32, h: f32, surface: (u32, u32), color: [f32; 4]) -> Self {
+        let (sw, sh) = (surface.0.max(1) as f32, surface.1.max(1) as f32);
+        Self {
+            rect: [
+                x / sw * 2.0 - 1.0,
+                1.0 - (y + h) / sh * 2.0,
+                (x + w) / sw * 2.0 - 1.0,
+                1.0 - y / sh * 2.0,
+            ],
+            color,
+        }
+    }
+}
+
+/// Rows of a 3×5 glyph for `c`, in the style of `DIGITS`. Letters are drawn in upper case;
+/// None for characters the font lacks, which are left blank.
+fn glyph(c: char) -> Option<[u8; 5]> {
+    if let Some(d) = c.to_digit(10) {
+        return Some(DIGITS[d as usize]);
+    }
+    Some(match c.to_ascii_uppercase() {
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        '×' => [0b000, 0b101, 0b010, 0b101, 0b000],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        ',' => [0b000, 0b000, 0b000, 0b010, 0b100],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        '-' | '—' | '–' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        '_' => [0b000, 0b000, 0b000, 0b000, 0b111],
+        '+' => [0b000, 0b010, 0b111, 0b010, 0b000],
+        '/' => [0b001, 0b001, 0b010, 0b100, 0b100],
+        '%' => [0b101, 0b001, 0b010, 0b100, 0b101],
+        '(' | '[' => [0b001, 0b010, 0b010, 0b010, 0b001],
+        ')' | ']' => [0b100, 0b010, 0b010, 0b010, 0b100],
+        '!' => [0b010, 0b010, 0b010, 0b000, 0b010],
+        '?' => [0b111, 0b001, 0b010, 0b000, 0b010],
+        '\'' => [0b010, 0b010, 0b000, 0b000, 0b000],
+        _ => return None,
+    })
+}
+
+/// Quads drawing `value` in the digit font with its top-left corner at (x, y).
+fn number_quads(
+    value: u32,
+    x: f32,
+    y: f32,
+    surface: (u32, u32),
+    color: [f32; 4],
+) -> Vec<OverlayQuad> {
+    text_quads(&value.to_string(), x, y, surface, color)
+}
+
+/// Quads drawing `text` in the 3×5 font with its top-left corner at (x, y).
+fn text_quads(
+    text: &str,
+    x: f32,
+    y: f32,
+    surface: (u32, u32),
+    color: [f32; 4],
+) -> Vec<OverlayQuad> {
+    let mut quads = Vec::new();
+    for (i, ch) in text.chars().enumerate() {
+        let Some(glyph) = glyph(ch) else {
+            continue;
+        };
+        let left = x + i as f32 * 4.0 * DIGIT_CELL;
+        for (row, bits) in glyph.iter().enumerate() {
+            for col in 0..3 {
+                if bits & (0b100 >> col) != 0 {
+                    quads.push(OverlayQuad::from_pixels(
+                        left + col as f32 * DIGIT_CELL,
+                        y + row as f32 * DIGIT_CELL,
+                        DIGIT_CELL,
+                        DIGIT_CELL,
+                        surface,
+                        color,
+                    ));
+                }
+            }
+        }
+    }
+    quads
+}
+
+/// Alpha-blended instanced quads drawn on top of the frame.
+pub struct OverlayRenderer {
+    shader: wgpu::ShaderModule,
+    pipeline: wgpu::RenderPipeline,
+    instances: wgpu::Buffer,
+    /// Number of quads `instances` can hold.
+    capacity: usize,
+    count: u32,
+}
+
+fn create_overlay_pipeline(
+    device: &Device,
+    shader: &wgpu::ShaderModule,
+    format: TextureFormat,
+) -> wgpu::RenderPipeline {
+    let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Overlay Pipeline Layout"),
+        bind_group_layouts: &[],
+        push_constant_ranges: &[],
+    });
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Overlay Pipeline"),
+        layout: Some(&layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: Some("vs_main"),
+            buffers: &[wgpu::VertexBufferLayout {
+                array_stride: std::mem::size_of::<OverlayQuad>() as u64,
+                step_mode: wgpu::VertexStepMode::Instance,
+                attributes: &wgpu::vertex_attr_array![0 => Float32x4, 1 => Float32x4],
+            }],
+            compilation_options: Default::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: Some("fs_main"),
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: Default::default(),
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+        cache: None,
+    })
+}
+
+fn create_instance_buffer(device: &Device, capacity: usize) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Overlay Instances"),
+        size: (capacity * std::mem::size_of::<OverlayQuad>()) as u64,
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+impl OverlayRenderer {
+    pub fn new(device: &Device, format: TextureFormat) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Overlay Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("overlay.wgsl").into()),
+        });
+        let pipeline = create_overlay_pipeline(device, &shader, format);
+        let capacity = 256;
+        Self {
+            instances: create_instance_buffer(device, capacity),
+            shader,
+            pipeline,
+            capacity,
+            count: 0,
+        }
+    }
+
+    /// Rebuilds the pipeline after the surface format changed.
+    pub fn set_format(&mut self, device: &Device, format: TextureFormat) {
+        self.pipeline = create_overlay_pipeline(device, &self.shader, format);
+    }
+
+    /// Replaces the quads drawn by the next `draw` calls.
+    pub fn set_quads(&mut self, device: &Device, queue: &Queue, quads: &[OverlayQuad]) {
+        if quads.len() > self.capacity {
+            self.capacity = quads.len().next_power_of_two();
+            self.instances = create_instance_buffer(device, self.capacity);
+        }
+        if !quads.is_empty() {
+            queue.write_buffer(&self.instances, 0, bytemuck::cast_slice(quads));
+        }
+        self.count = quads.len() as u32;
+    }
+
+    pub fn draw(&self, pass: &mut wgpu::RenderPass<'_>) {
+        if self.count == 0 {
+            return;
+        }
+        pass.set_pipeline(&self.pipeline);
+        pass.set_vertex_buffer(0, self.instances.slice(..));
+        pass.draw(0..6, 0..self.count);
+    }
+}
+
+/// A clip-space rectangle (x0, y0, x1, y1) in pixels (left, top, right, bottom) of a surface
+/// of the given size.
+fn image_pixels(image: [f32; 4], surface: (u32, u32)) -> [f32; 4] {
+    let (sw, sh) = (surface.0 as f32, surface.1 as f32);
+    [
+        (image[0] + 1.0) / 2.0 * sw,
+        (1.0 - image[3]) / 2.0 * sh,
+        (image[2] + 1.0) / 2.0 * sw,
+        (1.0 - image[1]) / 2.0 * sh,
+    ]
+}
+
+/// Crosshair at the center of the displayed image.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Crosshair {
+    /// sRGB.
+    pub color: [u8; 3],
+    /// Line width in pixels.
+    pub thickness: u32,
+    /// Empty space between the center and each arm, in pixels.
+    pub gap: u32,
+    /// Arms across the whole image instead of a small cross.
+    pub full: bool,
+}
+
+impl Crosshair {
+    /// Quads of the crosshair for an image covering `image` (x0, y0, x1, y1 in clip space) on a
+    /// surface of the given size, cut off at the surface edges. `color` is already encoded for
+    /// the surface.
+    pub fn quads(&self, image: [f32; 4], surface: (u32, u32), color: [f32; 4]) -> Vec<OverlayQuad> {
+        let [x0, y0, x1, y1] = image_pixels(image, surface);
+        let (cx, cy) = ((x0 + x1) / 2.0, (y0 + y1) / 2.0);
+        let (left, right) = (x0.max(0.0), x1.min(surface.0 as f32));
+        let (top, bottom) = (y0.max(0.0), y1.min(surface.1 as f32));
+        let (thickness, gap) = (self.thickness as f32, self.gap as f32);
+        let reach = |from: f32, to: f32| {
+            if self.full {
+                to
+            } else {
+                from + (to - from).signum() * (gap + CROSSHAIR_ARM)
+            }
+        };
+
+        let mut quads = Vec::with_capacity(4);
+        let mut push = |x0: f32, y0: f32, x1: f32, y1: f32| {
+            let (x0, x1) = (x0.min(x1).max(left), x0.max(x1).min(right));
+            let (y0, y1) = (y0.min(y1).max(top), y0.max(y1).min(bottom));
+            if x1 > x0 && y1 > y0 {
+                quads.push(OverlayQuad::from_pixels(
+                    x0,
+                    y0,
+                    x1 - x0,
+                    y1 - y0,
+                    surface,
+                    color,
+                ));
+            }
+        };
+        let (y0, y1) = (cy - thickness / 2.0, cy + thickness / 2.0);
+        push(reach(cx, left), y0, cx - gap, y1);
+        push(cx + gap, y0, reach(cx, right), y1);
+        let (x0, x1) = (cx - thickness / 2.0, cx + thickness / 2.0);
+        push(x0, reach(cy, top), x1, cy - gap);
+        push(x0, cy + gap, x1, reach(cy, bottom));
+        quads
+    }
+}
+
+/// Composition grid over the preview image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GridMode {
+    #[default]
+    Off,
+    /// Rule of thirds.
+    Thirds,
+    /// `grid_columns` × `grid_rows` cells.
+    Custom,
+}
+
+impl GridMode {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Off => Self::Thirds,
+            Self::Thirds => Self::Custom,
+            Self::Custom => Self::Off,
+        }
+    }
+}
+
+/// Grid lines dividing the displayed image into equal cells.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Grid {
+    pub columns: u32,
+    pub rows: u32,
+    /// sRGB.
+    pub color: [u8; 3],
+    pub opacity: f32,
+}
+
+impl Grid {
+    /// One-pixel lines between the cells of an image covering `image` (clip space, as for
+    /// `Crosshair::quads`), cut off at the surface edges. `color` is already encoded for the
+    /// surface; its alpha is replaced by the opacity.
+    pub fn quads(&self, image: [f32; 4], surface: (u32, u32), color: [f32; 4]) -> Vec<OverlayQuad> {
+        let [x0, y0, x1, y1] = image_pixels(image, surface);
+        let (left, right) = (x0.max(0.0), x1.min(surface.0 as f32));
+        let (top, bottom) = (y0.max(0.0), y1.min(surface.1 as f32));
+        let color = [color[0], color[1], color[2], self.opacity];
+
+        // (x, y, width, height) of each line.
+        let mut lines = Vec::new();
+        for i in 1..self.columns {
+            let x = (x0 + (x1 - x0) * i as f32 / self.columns as f32).floor();
+            if x >= left && x < right && bottom > top {
+                lines.push((x, top, 1.0, bottom - top));
+            }
+        }
+        for i in 1..self.rows {
+            let y = (y0 + (y1 - y0) * i as f32 / self.rows as f32).floor();
+            if y >= top && y < bottom && right > left {
+                lines.push((left, y, right - left, 1.0));
+            }
+        }
+        lines
+            .into_iter()
+            .map(|(x, y, w, h)| OverlayQuad::from_pixels(x, y, w, h, surface, color))
+            .collect()
+    }
+}
+
+/// Lines of text in the top-left corner of a surface of the given size, on a dark box.
+pub fn info_quads(lines: &[String], surface: (u32, u32)) -> Vec<OverlayQuad> {
+    if lines.is_empty() {
+        return Vec::new();
+    }
+    let lines: Vec<String> = lines
+        .iter()
+        .map(|line| {
+            if line.chars().count() > INFO_MAX_CHARS {
+                line.chars()
+                    .take(INFO_MAX_CHARS - 3)
+                    .chain("...".chars())
+                    .collect()
+            } else {
+                line.clone()
+            }
+        })
+        .collect();
+    let padding = 3.0 * DIGIT_CELL;
+    let line_height = 7.0 * DIGIT_CELL;
+    let longest = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+    let width = longest as f32 * 4.0 * DIGIT_CELL - DIGIT_CELL + 2.0 * padding;
+    let height = lines.len() as f32 * line_height - 2.0 * DIGIT_CELL + 2.0 * padding;
+
+    let mut quads = vec![OverlayQuad::from_pixels(
+        GRAPH_MARGIN,
+        GRAPH_MARGIN,
+        width,
+        height,
+        surface,
+        BACKGROUND_COLOR,
+    )];
+    for (i, line) in lines.iter().enumerate() {
+        let x = GRAPH_MARGIN + padding;
+        let y = GRAPH_MARGIN + padding + i as f32 * line_height;
+        quads.extend(text_quads(line, x, y, surface, INFO_TEXT_COLOR));
+    }
+    quads
+}
+
+/// `text` on a dark box in the top-right corner of a surface of the given size.
+pub fn badge_quads(text: &str, surface: (u32, u32)) -> Vec<OverlayQuad> {
+    let padding = 3.0 * DIGIT_CELL;
+    let width = text.chars().count() as f32 * 4.0 * DIGIT_CELL - DIGIT_CELL + 2.0 * padding;
+    let height = 5.0 * DIGIT_CELL + 2.0 * padding;
+    let x = surface.0 as f32 - GRAPH_MARGIN - width;
+    let y = GRAPH_MARGIN;
+    let mut quads = vec![OverlayQuad::from_pixels(
+        x,
+        y,
+        width,
+        height,
+        surface,
+        BACKGROUND_COLOR,
+    )];
+    quads.extend(text_quads(
+        text,
+        x + padding,
+        y + padding,
+        surface,
+        INFO_TEXT_COLOR,
+    ));
+    quads
+}
+
+/// Four `thickness`-pixel lines along the inside edges of a pixel rectangle.
+fn outline_quads(
+    rect: [f32; 4],
+    thickness: f32,
+    surface: (u32, u32),
+    color: [f32; 4],
+) -> Vec<OverlayQuad> {
+    let [x, y, w, h] = rect;
+    let t = thickness.min(w / 2.0).min(h / 2.0);
+    [
+        (x, y, w, t),
+        (x, y + h - t, w, t),
+        (x, y + t, t, h - 2.0 * t),
+        (x + w - t, y + t, t, h - 2.0 * t),
+    ]
+    .into_iter()
+    .map(|(x, y, w, h)| OverlayQuad::from_pixels(x, y, w, h, surface, color))
+    .collect()
+}
+
+/// Border of the full-frame inset at `inset` (pixels: x, y, width, height) and the outline of
+/// the zoomed viewport inside it, `viewport` in image coordinates (x0, y0, x1, y1, 0..1).
+pub fn inset_quads(inset: [f32; 4], viewport: [f32; 4], surface: (u32, u32)) -> Vec<OverlayQuad> {
+    let [x, y, w, h] = inset;
+    let border = [x - 1.0, y - 1.0, w + 2.0, h + 2.0];
+    let mut quads = outline_quads(border, 1.0, surface, INSET_BORDER_COLOR);
+    let [u0, v0, u1, v1] = viewport;
+    let rect = [x + u0 * w, y + v0 * h, (u1 - u0) * w, (v1 - v0) * h];
+    quads.extend(outline_quads(rect, 2.0, surface, INSET_VIEWPORT_COLOR));
+    quads
+}
+
+/// Recent capture and present intervals for the frame-time graph.
+#[derive(Debug, Default)]
+pub struct FrameTimeGraph {
+    /// (capture timestamp, interval since the previous displayed frame's capture)
+    capture: VecDeque<(Instant, Duration)>,
+    /// (present time, interval since the previous present)
+    present: VecDeque<(Instant, Duration)>,
+    last_capture: Option<Instant>,
+    last_present: Option<Instant>,
+    /// Latest (capture fps, render fps), drawn in the graph's top-left corner.
+    rates: Option<(f32, f32)>,
+}
+
+fn push_sample(
+    samples: &mut VecDeque<(Instant, Duration)>,
+    last: &mut Option<Instant>,
+    at: Instant,
+) {
+    if let Some(prev) = last.replace(at) {
+        samples.push_back((at, at.saturating_duration_since(prev)));
+    }
+    while samples
+        .front()
+        .is_some_and(|(t, _)| at.saturating_duration_since(*t) > GRAPH_WINDOW)
+    {
+        samples.pop_front();
+    }
+}
+
+impl FrameTimeGraph {
+    pub fn record_capture(&mut self, captured_at: Instant) {
+        push_sample(&mut self.capture, &mut self.last_capture, captured_at);
+    }
+
+    pub fn record_present(&mut self, presented_at: Instant) {
+        push_sample(&mut self.present, &mut self.last_present, presented_at);
+    }
+
+    pub fn set_rates(&mut self, capture_fps: f32, render_fps: f32) {
+        self.rates = Some((capture_fps, render_fps));
+    }
+
+    /// Bars for the last `GRAPH_WINDOW`, newest on the right, in the bottom-left corner of a
+    /// surface of the given size. Capture intervals are wide bars, present intervals narrow ones;
+    /// the capture and render fps are printed top-left in the same colors.
+    pub fn quads(&self, surface: (u32, u32)) -> Vec<OverlayQuad> {
+        let now = Instant::now();
+        let x0 = GRAPH_MARGIN;
+        let y0 = surface.1 as f32 - GRAPH_MARGIN - GRAPH_HEIGHT;
+        let window = GRAPH_WINDOW.as_secs_f32();
+        let ms_to_px = |ms: f32| ms.min(GRAPH_MAX_MS) / GRAPH_MAX_MS * GRAPH_HEIGHT;
+
+        let mut quads = Vec::with_capacity(self.capture.len() + self.present.len() + 3);
+        quads.push(OverlayQuad::from_pixels(
+            x0,
+            y0,
+            GRAPH_WIDTH,
+            GRAPH_HEIGHT,
+            surface,
+            BACKGROUND_COLOR,
+        ));
+        let mut bars = |samples: &VecDeque<(Instant, Duration)>, shrink: f32, color: [f32; 4]| {
+            for (at, interval) in samples {
+                let age = now.saturating_duration_since(*at).as_secs_f32();
+                if age > window {
+                    continue;
+                }
+                let w = (interval.as_secs_f32() / window * GRAPH_WIDTH).clamp(1.0, GRAPH_WIDTH);
+                let right = x0 + (1.0 - age / window) * GRAPH_WIDTH;
+                let left = (right - w).max(x0);
+                let h = ms_to_px(interval.as_secs_f32() * 1000.0);
+                let inset = (right - left) * shrink / 2.0;
+                quads.push(OverlayQuad::from_pixels(
+                    left + inset,
+                    y0 + GRAPH_HEIGHT - h,
+                    (right - left - 2.0 * inset).max(1.0),
+                    h,
+                    surface,
+                    color,
+                ));
+            }
+        };
+        bars(&self.capture, 0.1, CAPTURE_COLOR);
+        bars(&self.present, 0.6, PRESENT_COLOR);
+        if let Some((capture_fps, render_fps)) = self.rates {
+            let (x, y) = (x0 + 4.0, y0 + 4.0);
+            quads.extend(number_quads(capture_fps.round() as u32, x, y, surface, CAPTURE_COLOR));
+            let x = x + 18.0 * DIGIT_CELL;
+            quads.extend(number_quads(render_fps.round() as u32, x, y, surface, PRESENT_COLOR));
+        }
+        for (ms, color) in [(1000.0 / 60.0, GUIDE_60_COLOR), (1000.0 / 30.0, GUIDE_30_COLOR)] {
+            quads.push(OverlayQuad::from_pixels(
+                x0,
+                y0 + GRAPH_HEIGHT - ms_to_px(ms),
+                GRAPH_WIDTH,
+                1.0,
+                surface,
+                color,
+            ));
+        }
+        quads
+    }
+}
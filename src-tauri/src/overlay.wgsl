@@ -0,0 +1,39 @@
+// Solid-color quads for debug overlays, one instance per quad
+struct Instance {
+    // x0, y0, x1, y1 in clip space
+    @location(0) rect: vec4<f32>,
+    @location(1) color: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) in_vertex_index: u32, inst: Instance) -> VertexOutput {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+    );
+    let c = corners[in_vertex_index];
+
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(
+        mix(inst.rect.x, inst.rect.z, c.x),
+        mix(inst.rect.y, inst.rect.w, c.y),
+        0.0,
+        1.0,
+    );
+    out.color = inst.color;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return in.color;
+}
@@ -0,0 +1,80 @@
+use crate::preview::WindowGeometry;
+use crate::{update_settings, CaptureState};
+use tauri::{AppHandle, Manager};
+
+/// Turns overlay mode on or off: the preview as a translucent, always-on-top window placed
+/// exactly over a screen rectangle. Without a saved rectangle it starts in calibration (drag
+/// with the left mouse button, resize with the right one, then lock).
+pub fn set_enabled(app: &AppHandle, enabled: bool) -> Result<(), String> {
+    update_settings(app, |s| {
+        s.overlay_mode = enabled;
+        s.overlay_locked = enabled && s.overlay_geometry.is_some();
+    })?;
+    let state = app.state::<CaptureState>();
+    let geometry = state.settings.lock().unwrap().overlay_geometry;
+    if let (true, Some(g)) = (enabled, geometry) {
+        for preview in state.preview_sessions() {
+            preview.request_geometry(g);
+        }
+    }
+    Ok(())
+}
+
+pub fn toggle(app: &AppHandle) -> Result<(), String> {
+    let enabled = app.state::<CaptureState>().settings.lock().unwrap().overlay_mode;
+    set_enabled(app, !enabled)
+}
+
+#[tauri::command]
+pub fn set_overlay_mode(app: AppHandle, enabled: bool) -> Result<(), String> {
+    set_enabled(&app, enabled)
+}
+
+/// Locking saves the preview's current rectangle and makes it ignore the mouse; unlocking
+/// returns to calibration.
+#[tauri::command]
+pub fn set_overlay_locked(app: AppHandle, locked: bool) -> Result<(), String> {
+    let geometry = if locked {
+        let geometry = app
+            .state::<CaptureState>()
+            .primary_session()
+            .and_then(|p| p.geometry())
+            .ok_or("Start capturing so the overlay can be placed first.")?;
+        Some(geometry)
+    } else {
+        None
+    };
+    update_settings(&app, |s| {
+        s.overlay_locked = locked;
+        if let Some(g) = geometry {
+            s.overlay_geometry = Some(g);
+        }
+    })
+}
+
+/// Moves and resizes the preview window (physical pixels: outer position, inner size).
+#[tauri::command]
+pub fn set_preview_bounds(
+    app: AppHandle,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+) -> Result<(), String> {
+    if width == 0 || height == 0 {
+        return Err("Width and height must be positive.".to_string());
+    }
+    let previews = app.state::<CaptureState>().preview_sessions();
+    if previews.is_empty() {
+        return Err("No preview window is open.".to_string());
+    }
+    for preview in previews {
+        preview.request_geometry(WindowGeometry {
+            x,
+            y,
+            width,
+            height,
+        });
+    }
+    Ok(())
+}
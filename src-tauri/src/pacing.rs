@@ -0,0 +1,272 @@
+use scap::frame::Frame;
+use std::time::{Duration, Instant};
+
+/// Units of scap's `display_time` per second: the capture API's 100 ns ticks on Windows,
+/// nanoseconds elsewhere.
+#[cfg(target_os = "windows")]
+const TICKS_PER_SEC: u64 = 10_000_000;
+#[cfg(not(target_os = "windows"))]
+const TICKS_PER_SEC: u64 = 1_000_000_000;
+/// Timestamps mapping further than this before their arrival are treated as bogus.
+const MAX_LATENCY: Duration = Duration::from_secs(1);
+/// Capture rate of dynamic FPS while the content is static.
+const STATIC_FPS: u32 = 5;
+/// How long the content must stay unchanged before dynamic FPS backs off.
+const STATIC_AFTER: Duration = Duration::from_secs(2);
+
+/// Capture timestamp the backend put on the frame; None if it supplied none.
+pub fn display_time(frame: &Frame) -> Option<u64> {
+    let ticks = match frame {
+        Frame::BGRA(f) => f.display_time,
+        Frame::BGR0(f) => f.display_time,
+        Frame::RGB(f) => f.display_time,
+        Frame::RGBx(f) => f.display_time,
+        Frame::XBGR(f) => f.display_time,
+        Frame::BGRx(f) => f.display_time,
+        Frame::YUVFrame(f) => f.display_time,
+    };
+    (ticks != 0).then_some(ticks)
+}
+
+/// Maps backend timestamps onto `Instant`s so they can be compared with the rest of the app.
+/// The clock is anchored at (timestamp, arrival) of the frame with the lowest observed latency:
+/// whenever a frame would map to after its own arrival, it becomes the new anchor.
+#[derive(Debug, Default)]
+pub struct FrameClock {
+    anchor: Option<(u64, Instant)>,
+}
+
+impl FrameClock {
+    /// When a frame stamped `ticks` that arrived at `arrival` was captured. Falls back to
+    /// `arrival` without a timestamp, and re-anchors on timestamps that jump around.
+    pub fn captured_at(&mut self, ticks: Option<u64>, arrival: Instant) -> Instant {
+        let Some(ticks) = ticks else {
+            return arrival;
+        };
+        let mapped = self.anchor.and_then(|(anchor_ticks, anchor_at)| {
+            let delta = ticks.checked_sub(anchor_ticks)?;
+            let nanos = delta as u128 * 1_000_000_000 / TICKS_PER_SEC as u128;
+            anchor_at.checked_add(Duration::from_nanos(u64::try_from(nanos).ok()?))
+        });
+        match mapped {
+            Some(at) if at <= arrival && arrival.duration_since(at) <= MAX_LATENCY => at,
+            _ => {
+                self.anchor = Some((ticks, arrival));
+                arrival
+            }
+        }
+    }
+}
+
+/// Picks the frames to show at the target rate from the source's, by their capture timestamps:
+/// output frames are due on an ideal timeline one interval apart, and each tick takes the first
+/// frame less than half a source frame before it. Throttling by the time since the last frame
+/// instead lets capture jitter decide, which beats against the source cadence (a 60 fps source
+/// at 30 shows 2-1-2-1 patterns).
+#[derive(Debug)]
+pub struct FrameThrottle {
+    interval: Duration,
+    /// When the next output frame is due.
+    next: Option<Instant>,
+    /// Timestamp of the last source frame, and the smoothed gap between source frames (seeded
+    /// by the first gap).
+    last_seen: Option<Instant>,
+    source_interval: Duration,
+}
+
+impl FrameThrottle {
+    pub fn new(fps: u32) -> Self {
+        Self {
+            interval: Self::interval(fps),
+            next: None,
+            last_seen: None,
+            source_interval: Duration::ZERO,
+        }
+    }
+
+    fn interval(fps: u32) -> Duration {
+        Duration::from_secs_f64(1.0 / fps.max(1) as f64)
+    }
+
+    pub fn set_fps(&mut self, fps: u32) {
+        self.interval = Self::interval(fps);
+    }
+
+    /// Whether the frame captured at `captured_at` should be shown.
+    pub fn admit(&mut self, captured_at: Instant) -> bool {
+        if let Some(gap) = self
+            .last_seen
+            .and_then(|last| captured_at.checked_duration_since(last))
+        {
+            // Each gap moves the estimate by at most an eighth, so a stall doesn't widen the
+            // tolerance and let the next frames through early.
+            self.source_interval = if self.source_interval.is_zero() {
+                gap
+            } else {
+                (self.source_interval * 7 + gap.min(self.source_interval * 2)) / 8
+            };
+        }
+        self.last_seen = Some(captured_at);
+        let due = match self.next {
+            Some(due) if captured_at + self.source_interval / 2 < due => return false,
+            Some(due) => due,
+            None => captured_at,
+        };
+        // After a stall (or with a source slower than the target rate) the timeline restarts
+        // at this frame rather than letting a backlog of ticks through.
+        let next = due + self.interval;
+        self.next = Some(if captured_at >= next {
+            captured_at + self.interval
+        } else {
+            next
+        });
+        true
+    }
+}
+
+/// Dynamic FPS: once frames have been identical for `STATIC_AFTER`, the capture rate drops to
+/// `STATIC_FPS`; the first changed frame restores the configured rate.
+#[derive(Debug)]
+pub struct DynamicRate {
+    fps: u32,
+    last_checksum: Option<u64>,
+    unchanged_since: Option<Instant>,
+}
+
+impl DynamicRate {
+    pub fn new(fps: u32) -> Self {
+        Self {
+            fps,
+            last_checksum: None,
+            unchanged_since: None,
+        }
+    }
+
+    /// Records the checksum of the frame captured at `at`; returns whether it changed.
+    pub fn update(&mut self, checksum: u64, at: Instant) -> bool {
+        let changed = self.last_checksum != Some(checksum);
+        self.last_checksum = Some(checksum);
+        if changed {
+            self.unchanged_since = None;
+        } else if self.unchanged_since.is_none() {
+            self.unchanged_since = Some(at);
+        }
+        changed
+    }
+
+    /// Whether the content has been static long enough to back off.
+    pub fn is_static(&self, now: Instant) -> bool {
+        self.unchanged_since
+            .is_some_and(|since| now.saturating_duration_since(since) >= STATIC_AFTER)
+    }
+
+    /// Capture rate to use at `now`.
+    pub fn fps(&self, now: Instant) -> u32 {
+        if self.is_static(now) {
+            STATIC_FPS.min(self.fps)
+        } else {
+            self.fps
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Capture timestamps of a `fps` source, one per frame, with up to ±`jitter_us` of
+    /// deterministic noise.
+    fn source(start: Instant, fps: u32, frames: usize, jitter_us: i64) -> Vec<Instant> {
+        let period = 1_000_000_000 / fps as u64;
+        let mut seed = 0x2545_F491_4F6C_DD1Du64;
+        (0..frames as u64)
+            .map(|i| {
+                seed = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+                let noise = if jitter_us == 0 {
+                    0
+                } else {
+                    (seed >> 33) as i64 % (2 * jitter_us + 1) - jitter_us
+                };
+                let at = start + Duration::from_nanos(i * period);
+                if noise >= 0 {
+                    at + Duration::from_micros(noise as u64)
+                } else {
+                    at - Duration::from_micros(noise.unsigned_abs())
+                }
+            })
+            .collect()
+    }
+
+    fn admitted(throttle: &mut FrameThrottle, frames: &[Instant]) -> Vec<bool> {
+        frames.iter().map(|&at| throttle.admit(at)).collect()
+    }
+
+    /// Indices of admitted frames.
+    fn shown(admitted: &[bool]) -> Vec<usize> {
+        admitted
+            .iter()
+            .enumerate()
+            .filter_map(|(i, &a)| a.then_some(i))
+            .collect()
+    }
+
+    #[test]
+    fn sixty_to_thirty_shows_every_other_frame() {
+        let frames = source(Instant::now(), 60, 600, 0);
+        let shown = shown(&admitted(&mut FrameThrottle::new(30), &frames));
+        assert_eq!(shown, (0..600).step_by(2).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn sixty_to_thirty_keeps_its_cadence_under_jitter() {
+        let frames = source(Instant::now(), 60, 600, 4_000);
+        let admitted = admitted(&mut FrameThrottle::new(30), &frames);
+        // No 2-1-2-1 beating: every gap is exactly two source frames.
+        let shown = shown(&admitted);
+        let gaps: Vec<usize> = shown.windows(2).map(|w| w[1] - w[0]).collect();
+        assert!(gaps.iter().all(|&gap| gap == 2), "{gaps:?}");
+    }
+
+    #[test]
+    fn sixty_to_twenty_shows_every_third_frame() {
+        let frames = source(Instant::now(), 60, 600, 2_000);
+        let admitted = admitted(&mut FrameThrottle::new(20), &frames);
+        let shown = shown(&admitted);
+        assert!(shown.windows(2).all(|w| w[1] - w[0] == 3), "{shown:?}");
+    }
+
+    #[test]
+    fn a_stall_restarts_the_timeline_without_a_burst() {
+        let start = Instant::now();
+        let mut frames = source(start, 60, 120, 0);
+        let resume = *frames.last().unwrap() + Duration::from_millis(500);
+        frames.extend(source(resume, 60, 120, 0));
+        let admitted = admitted(&mut FrameThrottle::new(30), &frames);
+        // The first frame after the stall is shown, and the cadence carries on from it.
+        assert!(admitted[120]);
+        assert_eq!(
+            shown(&admitted[120..]),
+            (0..120).step_by(2).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn a_source_slower_than_the_target_is_shown_in_full() {
+        for (source_fps, target_fps) in [(24, 30), (30, 60), (59, 60)] {
+            let frames = source(Instant::now(), source_fps, 300, 1_000);
+            let admitted = admitted(&mut FrameThrottle::new(target_fps), &frames);
+            assert!(admitted.iter().all(|&a| a), "{source_fps} at {target_fps}");
+        }
+    }
+
+    #[test]
+    fn an_uneven_ratio_keeps_the_target_rate() {
+        // 50 fps at 30: three frames in every five, over ten seconds.
+        let frames = source(Instant::now(), 50, 500, 1_000);
+        let count = admitted(&mut FrameThrottle::new(30), &frames)
+            .iter()
+            .filter(|&&a| a)
+            .count();
+        assert!((299..=301).contains(&count), "{count}");
+    }
+}
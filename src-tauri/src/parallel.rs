@@ -0,0 +1,126 @@
+use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use std::mem::MaybeUninit;
+use std::num::NonZeroUsize;
+use std::sync::OnceLock;
+use std::thread;
+
+/// Upper bound on conversion threads; memory bandwidth, not cores, limits beyond this.
+const MAX_THREADS: usize = 8;
+/// Frames with fewer pixels are converted on the capture thread alone, where handing bands to
+/// the workers would cost more than it saves.
+const MIN_PARALLEL_PIXELS: usize = 1 << 20;
+
+/// Threads to convert frames on: the available cores up to `MAX_THREADS` when `enabled`, else
+/// only the capture thread.
+pub fn conversion_threads(enabled: bool) -> usize {
+    if !enabled {
+        return 1;
+    }
+    thread::available_parallelism()
+        .map_or(1, NonZeroUsize::get)
+        .min(MAX_THREADS)
+}
+
+/// Workers the conversion bands run on, started with the first parallel frame and kept for
+/// the next ones rather than spawned per frame.
+fn pool() -> &'static ThreadPool {
+    static POOL: OnceLock<ThreadPool> = OnceLock::new();
+    POOL.get_or_init(|| {
+        ThreadPoolBuilder::new()
+            .num_threads(conversion_threads(true))
+            .thread_name(|i| format!("conversion-{i}"))
+            .build()
+            .expect("conversion thread pool")
+    })
+}
+
+/// Fills `buffer` with `height` rows of `width` pixels, row `y` taken from `row(y)`. With more
+/// than one thread the rows are split into `threads` bands converted side by side on the
+/// `pool`, written straight into `buffer`'s capacity so nothing is allocated per band. None
+/// when a row yields fewer than `width` pixels.
+pub fn fill_rows<F, I>(
+    buffer: &mut Vec<u32>,
+    width: usize,
+    height: usize,
+    threads: usize,
+    row: F,
+) -> Option<()>
+where
+    F: Fn(usize) -> I + Sync,
+    I: Iterator<Item = u32>,
+{
+    let len = width.checked_mul(height)?;
+    buffer.clear();
+    buffer.reserve(len);
+    if threads <= 1 || len < MIN_PARALLEL_PIXELS {
+        for y in 0..height {
+            buffer.extend(row(y).take(width));
+        }
+        return (buffer.len() == len).then_some(());
+    }
+
+    let band_rows = height.div_ceil(threads);
+    let out = &mut buffer.spare_capacity_mut()[..len];
+    let complete = pool().install(|| {
+        out.par_chunks_mut(band_rows * width)
+            .enumerate()
+            .all(|(band, out)| fill_band(out, width, band * band_rows, &row))
+    });
+    if !complete {
+        return None;
+    }
+    // SAFETY: every band wrote each of its pixels, so the first `len` elements are initialized.
+    unsafe { buffer.set_len(len) };
+    Some(())
+}
+
+/// Writes rows `first_row..` into `out`; whether every pixel of it was written.
+fn fill_band<F, I>(out: &mut [MaybeUninit<u32>], width: usize, first_row: usize, row: &F) -> bool
+where
+    F: Fn(usize) -> I,
+    I: Iterator<Item = u32>,
+{
+    out.chunks_mut(width).enumerate().all(|(i, out)| {
+        let mut written = 0;
+        for (slot, px) in out.iter_mut().zip(row(first_row + i)) {
+            slot.write(px);
+            written += 1;
+        }
+        written == width
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Just over `MIN_PARALLEL_PIXELS`, in a height no band count divides.
+    const SIZE: (usize, usize) = (1024, 1031);
+
+    fn pixel(x: usize, y: usize) -> u32 {
+        (y * SIZE.0 + x) as u32
+    }
+
+    #[test]
+    fn bands_fill_the_same_rows_as_the_capture_thread() {
+        let (width, height) = SIZE;
+        let row = |y| (0..width).map(move |x| pixel(x, y));
+        let expected: Vec<u32> = (0..height).flat_map(row).collect();
+        for threads in [1, 2, 3, 8] {
+            let mut buffer = vec![7; 3];
+            fill_rows(&mut buffer, width, height, threads, row).unwrap();
+            assert!(buffer == expected, "{threads} threads");
+        }
+    }
+
+    #[test]
+    fn a_short_row_in_any_band_fails_the_frame() {
+        let (width, height) = SIZE;
+        let row = |y| (0..width - usize::from(y == height - 1)).map(move |x| pixel(x, y));
+        for threads in [1, 4] {
+            let mut buffer = Vec::new();
+            assert_eq!(fill_rows(&mut buffer, width, height, threads, row), None);
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use crate::error_log::{self, Level};
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+
+/// How long a scheduled write waits for further changes before it hits the disk.
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Replaces `path` with `contents` so that a crash leaves either the old or the new file, never
+/// a truncated one: writes a temporary file next to it, syncs it and renames it over `path`.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    write_atomic_with(path, contents, |from, to| fs::rename(from, to))
+}
+
+/// `write_atomic` with the final rename supplied, so tests can make it fail.
+fn write_atomic_with(
+    path: &Path,
+    contents: &[u8],
+    rename: impl FnOnce(&Path, &Path) -> io::Result<()>,
+) -> io::Result<()> {
+    let parent = path.parent().unwrap_or(Path::new("."));
+    fs::create_dir_all(parent)?;
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(".tmp");
+    let tmp = parent.join(tmp_name);
+    let result = (|| {
+        let mut file = File::create(&tmp)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        rename(&tmp, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+        return result;
+    }
+    // Persist the rename itself; directories cannot be opened for syncing on Windows.
+    #[cfg(unix)]
+    if let Ok(dir) = File::open(parent) {
+        let _ = dir.sync_all();
+    }
+    Ok(())
+}
+
+struct PendingWrite {
+    /// Where a failed write is reported.
+    app: AppHandle,
+    path: PathBuf,
+    contents: String,
+    due: Instant,
+}
+
+/// Coalesces rapid writes of a file (e.g. while a slider is dragged): only the latest contents
+/// are written, once nothing changed for `DEBOUNCE`. Call `flush` before exiting.
+pub struct DebouncedWriter {
+    pending: Mutex<Option<PendingWrite>>,
+    wake: Condvar,
+    /// Held while writing, so `flush` waits for a write the background thread has started.
+    writing: Mutex<()>,
+}
+
+impl DebouncedWriter {
+    pub fn spawn() -> Arc<Self> {
+        let writer = Arc::new(Self {
+            pending: Mutex::new(None),
+            wake: Condvar::new(),
+            writing: Mutex::new(()),
+        });
+        let worker = writer.clone();
+        thread::spawn(move || loop {
+            let mut pending = worker.pending.lock().unwrap();
+            let now = Instant::now();
+            match pending.as_ref().map(|p| p.due) {
+                None => {
+                    drop(worker.wake.wait(pending).unwrap());
+                }
+                Some(due) if due > now => {
+                    drop(worker.wake.wait_timeout(pending, due - now).unwrap());
+                }
+                Some(_) => {
+                    let write = pending.take().unwrap();
+                    let _writing = worker.writing.lock().unwrap();
+                    drop(pending);
+                    if let Err(e) = write_atomic(&write.path, write.contents.as_bytes()) {
+                        let message = format!("Saving {}: {e}", write.path.display());
+                        error_log::record(&write.app, Level::Error, message);
+                    }
+                }
+            }
+        });
+        writer
+    }
+
+    /// Writes `contents` to `path` after the debounce delay, replacing any write still pending.
+    /// A failure is recorded in the error log.
+    pub fn schedule(&self, app: &AppHandle, path: PathBuf, contents: String) {
+        *self.pending.lock().unwrap() = Some(PendingWrite {
+            app: app.clone(),
+            path,
+            contents,
+            due: Instant::now() + DEBOUNCE,
+        });
+        self.wake.notify_one();
+    }
+
+    /// Writes a pending change now and waits for a write already in progress.
+    pub fn flush(&self) -> io::Result<()> {
+        let mut pending = self.pending.lock().unwrap();
+        let write = pending.take();
+        let _writing = self.writing.lock().unwrap();
+        drop(pending);
+        match write {
+            Some(w) => write_atomic(&w.path, w.contents.as_bytes()),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An empty directory of its own for each test.
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("liteview-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn replaces_the_file_and_leaves_no_temporary() {
+        let dir = scratch_dir("persist-replace");
+        let path = dir.join("settings.json");
+        fs::write(&path, "old").unwrap();
+        write_atomic(&path, b"new").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert!(!dir.join("settings.json.tmp").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn a_failed_rename_keeps_the_original() {
+        let dir = scratch_dir("persist-rename");
+        let path = dir.join("settings.json");
+        fs::write(&path, "old").unwrap();
+        let result = write_atomic_with(&path, b"new", |tmp, _| {
+            // The new contents are fully on disk when the rename fails.
+            assert_eq!(fs::read_to_string(tmp).unwrap(), "new");
+            Err(io::Error::other("rename failed"))
+        });
+        assert!(result.is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "old");
+        assert!(!dir.join("settings.json.tmp").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn a_read_only_directory_keeps_the_original() {
+        use std::os::unix::fs::{MetadataExt, PermissionsExt};
+        let dir = scratch_dir("persist-readonly");
+        let path = dir.join("settings.json");
+        fs::write(&path, "old").unwrap();
+        // New files belong to the effective user; root ignores directory permissions.
+        if fs::metadata(&path).unwrap().uid() == 0 {
+            eprintln!("running as root, directory permissions not enforced; skipping");
+            fs::remove_dir_all(&dir).unwrap();
+            return;
+        }
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o555)).unwrap();
+        let result = write_atomic(&path, b"new");
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o755)).unwrap();
+        assert!(result.is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "old");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,84 @@
+use crate::preview::WindowGeometry;
+use crate::{update_settings, CaptureState};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+/// Width of the picture-in-picture window in logical pixels; the height follows the frame.
+pub const DEFAULT_PIP_WIDTH: u32 = 320;
+pub const MIN_PIP_WIDTH: u32 = 120;
+pub const MAX_PIP_WIDTH: u32 = 1280;
+
+/// Corner of the monitor's work area the picture-in-picture window docks to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
+}
+
+impl Corner {
+    /// The corner of `area` nearest to the center of `window`, where a dragged window snaps.
+    pub fn nearest(area: WindowGeometry, window: WindowGeometry) -> Self {
+        // Doubled coordinates keep the centers whole.
+        let center_x = 2 * window.x as i64 + window.width as i64;
+        let center_y = 2 * window.y as i64 + window.height as i64;
+        let left = center_x < 2 * area.x as i64 + area.width as i64;
+        let top = center_y < 2 * area.y as i64 + area.height as i64;
+        match (left, top) {
+            (true, true) => Self::TopLeft,
+            (false, true) => Self::TopRight,
+            (true, false) => Self::BottomLeft,
+            (false, false) => Self::BottomRight,
+        }
+    }
+
+    /// Position of a `width` x `height` window in this corner of `area`, `margin` pixels in.
+    pub fn dock(self, area: WindowGeometry, width: u32, height: u32, margin: i32) -> (i32, i32) {
+        let left = area.x + margin;
+        let right = area.x + area.width as i32 - width as i32 - margin;
+        let top = area.y + margin;
+        let bottom = area.y + area.height as i32 - height as i32 - margin;
+        match self {
+            Self::TopLeft => (left, top),
+            Self::TopRight => (right, top),
+            Self::BottomLeft => (left, bottom),
+            Self::BottomRight => (right, bottom),
+        }
+    }
+}
+
+/// Picture-in-picture as the preview thread applies it: a small always-on-top window, off the
+/// taskbar, docked to a corner of the monitor it is on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pip {
+    /// Logical pixels.
+    pub width: u32,
+    /// Where the window docks when it enters picture-in-picture; dragging it to another corner
+    /// moves it there until the preview closes.
+    pub corner: Corner,
+}
+
+/// Turns picture-in-picture on or off. The preview thread remembers each window's normal
+/// placement on entry and restores it on exit; the window options come back with the settings,
+/// which picture-in-picture only overrides.
+pub fn set_enabled(app: &AppHandle, enabled: bool) -> Result<(), String> {
+    update_settings(app, |s| s.pip_mode = enabled)
+}
+
+pub fn toggle(app: &AppHandle) -> Result<(), String> {
+    let enabled = app
+        .state::<CaptureState>()
+        .settings
+        .lock()
+        .unwrap()
+        .pip_mode;
+    set_enabled(app, !enabled)
+}
+
+#[tauri::command]
+pub fn set_pip_mode(app: AppHandle, enabled: bool) -> Result<(), String> {
+    set_enabled(&app, enabled)
+}
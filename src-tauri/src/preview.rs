@@ -1,5 +1,22 @@
-use std::sync::atomic::Ordering;
-use std::sync::{Arc, Condvar, Mutex};
+use crate::arrange::{self, SizePreset};
+use crate::crop::AppliedCrop;
+use crate::display::{
+    self, DisplayOptions, DisplayOptionsSlot, HdrOutput, OutputEncoding, PresentPreference,
+    ScaleMode, Uniforms, WindowOptions,
+};
+use crate::handoff::{triple_buffer, Reader, Writer};
+use crate::keybindings::KeybindingsSlot;
+use crate::resize::{self, ResizeAlgorithm};
+use crate::stats::CaptureStats;
+use crate::toggle_menu::Toggle;
+use crate::zoom::{self, ZoomView};
+use crate::overlay::{self, FrameTimeGraph, OverlayRenderer};
+use crate::pip::{Corner, Pip};
+use crate::CaptureSettings;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 use wgpu::{
     Backends, Device, DeviceDescriptor, Features, Instance, InstanceDescriptor, Limits,
@@ -7,10 +24,14 @@ use wgpu::{
     TextureDimension, TextureFormat, TextureUsages, TextureView,
 };
 use winit::application::ApplicationHandler;
-use winit::dpi::LogicalSize;
-use winit::event::WindowEvent;
-use winit::event_loop::{ActiveEventLoop, ControlFlow};
-use winit::window::{Window, WindowAttributes, WindowId, WindowLevel};
+use winit::dpi::{LogicalSize, PhysicalPosition, PhysicalSize};
+use winit::event::{ElementState, MouseButton, MouseScrollDelta, WindowEvent};
+use winit::event_loop::{ActiveEventLoop, ControlFlow, EventLoopProxy};
+use winit::monitor::MonitorHandle;
+use winit::keyboard::ModifiersState;
+use winit::window::{
+    CursorIcon, Fullscreen, ResizeDirection, Window, WindowAttributes, WindowId, WindowLevel,
+};
 
 #[cfg(target_os = "linux")]
 use winit::platform::wayland::EventLoopBuilderExtWayland;
@@ -20,27 +41,619 @@ use winit::platform::x11::EventLoopBuilderExtX11;
 use winit::platform::windows::EventLoopBuilderExtWindows;
 
 const FPS_UPDATE_INTERVAL: Duration = Duration::from_secs(1);
+/// Poll interval while a window animates (frame smoothing, picture-in-picture snapping).
+const ANIMATION_INTERVAL: Duration = Duration::from_millis(16);
+/// Poll interval otherwise while sessions run, for state that changes without a `wake`.
+/// New frames wake the loop right away.
+const HOUSEKEEPING_INTERVAL: Duration = Duration::from_millis(100);
+/// Border of a borderless preview window that resizes it when dragged, in logical pixels.
+const RESIZE_MARGIN: f64 = 6.0;
+/// Gap between a picture-in-picture window and the edges of the work area, in logical pixels.
+const PIP_MARGIN: f64 = 16.0;
+
+/// Layout of a frame's pixels, four bytes each.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelFormat {
+    /// Blue, green, red, padding: BGRA/BGRx/BGR0 sources, and packed 0x00RRGGBB `u32`s in
+    /// (little-endian) memory.
+    Bgrx,
+    /// Red, green, blue, padding: RGBx sources.
+    Rgbx,
+}
+
+impl PixelFormat {
+    /// Byte offsets of red, green and blue.
+    fn channels(self) -> [usize; 3] {
+        match self {
+            Self::Bgrx => [2, 1, 0],
+            Self::Rgbx => [0, 1, 2],
+        }
+    }
+
+    fn texture_format(self) -> TextureFormat {
+        match self {
+            Self::Bgrx => TextureFormat::Bgra8UnormSrgb,
+            Self::Rgbx => TextureFormat::Rgba8UnormSrgb,
+        }
+    }
+}
+
+/// Pixel bytes of a frame, shared by everything holding it.
+#[derive(Clone)]
+pub enum FrameBytes {
+    /// Packed 0x00RRGGBB pixels the capture thread converted (and cropped or scaled).
+    Packed(Arc<Vec<u32>>),
+    /// The source's bytes, untouched; converted only where packed pixels are needed.
+    Raw(Arc<Vec<u8>>),
+}
+
+impl FrameBytes {
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            Self::Packed(buffer) => bytemuck::cast_slice(buffer),
+            Self::Raw(bytes) => bytes,
+        }
+    }
+}
 
+#[derive(Clone)]
 pub struct FrameData {
     pub width: u32,
     pub height: u32,
-    pub buffer: Vec<u32>,
+    /// Size the frame is shown at: the output resolution when the preview scales frames on the
+    /// GPU, else the buffer's size.
+    pub display_size: (u32, u32),
+    /// Bytes from one row to the next; rows of raw frames may be padded.
+    pub stride: u32,
+    pub format: PixelFormat,
+    pub bytes: FrameBytes,
+    /// When the source was captured: the backend's timestamp where it supplies one, else when
+    /// the frame reached the capture thread.
+    pub captured_at: Instant,
+    /// When the capture thread received the frame from the source, for the latency stats.
+    pub received_at: Instant,
+    /// Rounded window corners to mask, in frame pixels (0 = none).
+    pub corner_radius: f32,
+}
+
+impl FrameData {
+    /// The frame as packed 0x00RRGGBB pixels: its own buffer when the capture thread converted
+    /// it, else converted now. None when the bytes are too short for the size.
+    pub fn packed(&self) -> Option<Arc<Vec<u32>>> {
+        let bytes = match &self.bytes {
+            FrameBytes::Packed(buffer) => return Some(buffer.clone()),
+            FrameBytes::Raw(bytes) => bytes,
+        };
+        let mut buffer = Vec::new();
+        let size = (self.width as usize, self.height as usize);
+        crate::pack_rows(bytes, size, 4, self.format.channels(), 1, &mut buffer)?;
+        Some(Arc::new(buffer))
+    }
+}
+
+/// Writing end of a session's frames to its preview windows.
+struct FrameWriter {
+    frames: Writer<(u64, FrameData)>,
+    /// Frames published so far.
+    generation: u64,
+}
+
+/// Newest frame for the preview windows of a session, in a triple buffer: the capture side
+/// never waits for the windows, and they always find the newest whole frame. Every window
+/// shows it once; the number of frames published so far tells each window whether it has
+/// seen the current one. The locks only keep the writers apart (the capture and the delay
+/// thread) and the readers (windows on the preview thread); neither side contends with the
+/// other.
+struct FrameSlot {
+    writer: Mutex<FrameWriter>,
+    reader: Mutex<Reader<(u64, FrameData)>>,
+}
+
+impl Default for FrameSlot {
+    fn default() -> Self {
+        let (frames, reader) = triple_buffer();
+        let writer = FrameWriter {
+            frames,
+            generation: 0,
+        };
+        Self {
+            writer: Mutex::new(writer),
+            reader: Mutex::new(reader),
+        }
+    }
+}
+
+/// A preview window size chosen relative to the frame (size presets, set_preview_scale).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SizeScale {
+    pub percent: u32,
+    /// Work area of the monitor the window was on, which the size is kept within.
+    pub area: WindowGeometry,
+}
+
+/// Preview window placement in physical pixels (outer position, inner size).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WindowGeometry {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+fn window_geometry(window: &Window) -> Option<WindowGeometry> {
+    let pos = window.outer_position().ok()?;
+    let size = window.inner_size();
+    Some(WindowGeometry {
+        x: pos.x,
+        y: pos.y,
+        width: size.width,
+        height: size.height,
+    })
 }
 
 pub struct PreviewState {
-    pub frame: Mutex<Option<FrameData>>,
+    /// Session ID, unique for the lifetime of the process.
+    pub id: u32,
+    /// Target the session was started for; None for the default target and applications.
+    pub target: Option<u32>,
+    /// Title of `target` at start; key for its remembered crop.
+    pub target_title: Option<String>,
+    frame: FrameSlot,
+    /// `FrameWriter::generation` once the frame is in place: windows check it on every pass and
+    /// only go for the slot when there is a new frame to take.
+    published: AtomicU64,
+    /// IDs of the cloned windows requested for this session (open_preview_window); the
+    /// session's own window is not among them.
+    clones: Mutex<BTreeSet<u32>>,
     pub running: std::sync::atomic::AtomicBool,
-    pub frame_available: Condvar,
+    /// Keep the preview window hidden (while its anchor window is minimized).
+    hidden: std::sync::atomic::AtomicBool,
+    /// Hidden by the user (hide_preview) while capture keeps running; unlike `hidden` it is not
+    /// undone when the anchor window comes back.
+    dismissed: std::sync::atomic::AtomicBool,
+    /// None of the session's windows can be seen: all are minimized, hidden or occluded. The
+    /// capture thread then skips converting frames nobody else needs.
+    unseen: std::sync::atomic::AtomicBool,
+    /// Bring the session's window to the front on its next update (tray "Show window").
+    raise_requested: std::sync::atomic::AtomicBool,
+    /// Freeze the preview on its last frame; the capture thread keeps running but drops frames.
+    paused: std::sync::atomic::AtomicBool,
+    /// Keep the preview on its current frame while capture (and recording) carries on.
+    frozen: std::sync::atomic::AtomicBool,
+    /// `published_frame` when the preview was frozen.
+    frozen_frame: Mutex<Option<FrameData>>,
+    /// Last visible change in the source or user interaction with the preview window.
+    last_activity: Mutex<Instant>,
+    /// When the session's duration limit stops it.
+    ends_at: Mutex<Option<Instant>>,
+    /// Applied when the preview window is created (restored layouts, restarts).
+    initial_geometry: Option<WindowGeometry>,
+    /// Monitor (by name) to center the window on when there is no `initial_geometry`.
+    initial_monitor: Option<String>,
+    /// Current window geometry, updated as the window moves/resizes.
+    geometry: Mutex<Option<WindowGeometry>>,
+    /// Title bar and borders around the window's client area (outer minus inner size), as of
+    /// the last `geometry` update.
+    decoration_size: Mutex<(u32, u32)>,
+    /// Move/resize requested from the app side, applied on the preview thread.
+    pending_geometry: Mutex<Option<WindowGeometry>>,
+    /// Size of the last frame handed to the preview (after scaling).
+    frame_size: Mutex<Option<(u32, u32)>>,
+    /// Last frame handed to the preview; unlike `frame` it is not taken by the preview thread.
+    latest_frame: Mutex<Option<FrameData>>,
+    /// Last frame published to the windows; behind `latest_frame` while the preview is delayed.
+    published_frame: Mutex<Option<FrameData>>,
+    /// Scaler the capture thread last used; None while frames are passed through unscaled.
+    resize_algorithm: Mutex<Option<ResizeAlgorithm>>,
+    /// Capture rate the capture thread currently aims for (lower than the setting while
+    /// dynamic FPS backs off).
+    effective_fps: AtomicU32,
+    /// Frames the capture thread skipped for being identical to the previous one.
+    duplicate_frames: AtomicU64,
+    /// Seconds the preview runs behind the capture (see `DelayLine`).
+    delay_secs: AtomicU32,
+    /// Frame counters of the capture thread and the preview window (get_capture_stats).
+    pub stats: CaptureStats,
+    /// Size of the last captured frame before crop and scaling.
+    source_size: Mutex<Option<(u32, u32)>>,
+    /// Crop the capture thread applies to each frame.
+    crop: Mutex<Option<AppliedCrop>>,
+    /// Black-bar crop found by auto-crop, in source pixels.
+    detected_crop: Mutex<Option<(u32, u32, u32, u32)>>,
+    /// Name of what is being captured, shown in the window title.
+    source_title: Mutex<Option<String>>,
+    /// ID of the target the capturer records (the display for cropped window captures).
+    capture_target: Mutex<Option<u32>>,
+    /// Output resolution setting, read by the capture thread for every frame.
+    resolution: Mutex<String>,
+    /// When `resolution` or the orientation last changed; the window is fitted to the new
+    /// frame size once it has settled.
+    resolution_changed_at: Mutex<Option<Instant>>,
+    /// Size chosen relative to the frame; re-applied when the frame size changes.
+    size_scale: Mutex<Option<SizeScale>>,
+    /// Clockwise rotation of the preview in degrees.
+    rotation: AtomicU32,
+    /// Settings changed while the session runs, taken by the capture thread before its next
+    /// frame (see `CaptureSettings::update_session`).
+    settings_update: Mutex<Option<CaptureSettings>>,
 }
 
 impl Default for PreviewState {
     fn default() -> Self {
         Self {
-            frame: Mutex::new(None),
+            id: 0,
+            target: None,
+            target_title: None,
+            frame: FrameSlot::default(),
+            published: AtomicU64::new(0),
+            clones: Mutex::new(BTreeSet::new()),
             running: std::sync::atomic::AtomicBool::new(true),
-            frame_available: Condvar::new(),
+            hidden: std::sync::atomic::AtomicBool::new(false),
+            unseen: std::sync::atomic::AtomicBool::new(false),
+            dismissed: std::sync::atomic::AtomicBool::new(false),
+            raise_requested: std::sync::atomic::AtomicBool::new(false),
+            paused: std::sync::atomic::AtomicBool::new(false),
+            frozen: std::sync::atomic::AtomicBool::new(false),
+            frozen_frame: Mutex::new(None),
+            last_activity: Mutex::new(Instant::now()),
+            ends_at: Mutex::new(None),
+            initial_geometry: None,
+            initial_monitor: None,
+            geometry: Mutex::new(None),
+            pending_geometry: Mutex::new(None),
+            decoration_size: Mutex::new((0, 0)),
+            frame_size: Mutex::new(None),
+            latest_frame: Mutex::new(None),
+            published_frame: Mutex::new(None),
+            resize_algorithm: Mutex::new(None),
+            effective_fps: AtomicU32::new(0),
+            duplicate_frames: AtomicU64::new(0),
+            delay_secs: AtomicU32::new(0),
+            stats: CaptureStats::default(),
+            source_size: Mutex::new(None),
+            crop: Mutex::new(None),
+            detected_crop: Mutex::new(None),
+            source_title: Mutex::new(None),
+            capture_target: Mutex::new(None),
+            resolution: Mutex::new(String::new()),
+            resolution_changed_at: Mutex::new(None),
+            size_scale: Mutex::new(None),
+            rotation: AtomicU32::new(0),
+            settings_update: Mutex::new(None),
+        }
+    }
+}
+
+impl PreviewState {
+    pub fn new(
+        id: u32,
+        target: Option<u32>,
+        target_title: Option<String>,
+        geometry: Option<WindowGeometry>,
+        monitor: Option<String>,
+    ) -> Self {
+        Self {
+            id,
+            target,
+            target_title,
+            initial_geometry: geometry,
+            initial_monitor: monitor,
+            ..Default::default()
+        }
+    }
+
+    /// Geometry of the preview window, or None while it doesn't exist (or on Wayland, where
+    /// the position is not observable).
+    pub fn geometry(&self) -> Option<WindowGeometry> {
+        *self.geometry.lock().unwrap()
+    }
+
+    pub fn set_hidden(&self, hidden: bool) {
+        self.hidden.store(hidden, Ordering::Relaxed);
+        wake();
+    }
+
+    pub fn is_dismissed(&self) -> bool {
+        self.dismissed.load(Ordering::Relaxed)
+    }
+
+    pub fn set_dismissed(&self, dismissed: bool) {
+        self.dismissed.store(dismissed, Ordering::Relaxed);
+        wake();
+    }
+
+    pub fn is_unseen(&self) -> bool {
+        self.unseen.load(Ordering::Relaxed)
+    }
+
+    /// Asks for the preview window to be brought to the front, e.g. when it is not on the
+    /// taskbar to click.
+    pub fn request_raise(&self) {
+        self.raise_requested.store(true, Ordering::Relaxed);
+        wake();
+    }
+
+    /// Moves/resizes the preview window (or places it on creation if it doesn't exist yet).
+    pub fn request_geometry(&self, geometry: WindowGeometry) {
+        *self.pending_geometry.lock().unwrap() = Some(geometry);
+        wake();
+    }
+
+    pub fn frame_size(&self) -> Option<(u32, u32)> {
+        *self.frame_size.lock().unwrap()
+    }
+
+    pub fn set_frame_size(&self, width: u32, height: u32) {
+        let previous = self.frame_size.lock().unwrap().replace((width, height));
+        // A size chosen relative to the frame follows source size changes too.
+        if previous.is_some_and(|p| p != (width, height)) && self.size_scale().is_some() {
+            *self.resolution_changed_at.lock().unwrap() = Some(Instant::now());
+        }
+    }
+
+    pub fn size_scale(&self) -> Option<SizeScale> {
+        *self.size_scale.lock().unwrap()
+    }
+
+    pub fn set_size_scale(&self, scale: Option<SizeScale>) {
+        *self.size_scale.lock().unwrap() = scale;
+    }
+
+    /// `frame_size` as shown, with width and height swapped by a quarter turn.
+    pub fn display_size(&self) -> Option<(u32, u32)> {
+        let (width, height) = self.frame_size()?;
+        if self.rotation.load(Ordering::Relaxed) % 180 == 90 {
+            Some((height, width))
+        } else {
+            Some((width, height))
+        }
+    }
+
+    pub fn set_rotation(&self, degrees: u32) {
+        let previous = self.rotation.swap(degrees, Ordering::Relaxed);
+        // Turning between portrait and landscape refits an open window.
+        if previous % 180 != degrees % 180 && self.frame_size().is_some() {
+            *self.resolution_changed_at.lock().unwrap() = Some(Instant::now());
+        }
+    }
+
+    /// The frame the preview shows, as packed pixels; while frozen, the one it was frozen on.
+    pub fn latest_frame(&self) -> Option<(u32, u32, Arc<Vec<u32>>)> {
+        let mut frame = None;
+        if self.is_frozen() {
+            frame = self.frozen_frame.lock().unwrap().clone();
+        }
+        let frame = frame.or_else(|| self.latest_frame.lock().unwrap().clone())?;
+        Some((frame.width, frame.height, frame.packed()?))
+    }
+
+    pub fn set_latest_frame(&self, frame: FrameData) {
+        *self.latest_frame.lock().unwrap() = Some(frame);
+    }
+
+    pub fn resize_algorithm(&self) -> Option<ResizeAlgorithm> {
+        *self.resize_algorithm.lock().unwrap()
+    }
+
+    pub fn set_resize_algorithm(&self, algorithm: Option<ResizeAlgorithm>) {
+        *self.resize_algorithm.lock().unwrap() = algorithm;
+    }
+
+    pub fn effective_fps(&self) -> u32 {
+        self.effective_fps.load(Ordering::Relaxed)
+    }
+
+    pub fn set_effective_fps(&self, fps: u32) {
+        self.effective_fps.store(fps, Ordering::Relaxed);
+    }
+
+    pub fn duplicate_frames(&self) -> u64 {
+        self.duplicate_frames.load(Ordering::Relaxed)
+    }
+
+    pub fn count_duplicate_frame(&self) {
+        self.duplicate_frames.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn delay(&self) -> Duration {
+        Duration::from_secs(self.delay_secs.load(Ordering::Relaxed).into())
+    }
+
+    pub fn set_delay(&self, secs: u32) {
+        self.delay_secs.store(secs, Ordering::Relaxed);
+    }
+
+    /// Hands changed settings to the capture thread; a newer update replaces one not yet taken.
+    pub fn push_settings(&self, settings: CaptureSettings) {
+        *self.settings_update.lock().unwrap() = Some(settings);
+    }
+
+    pub fn take_settings_update(&self) -> Option<CaptureSettings> {
+        self.settings_update.lock().unwrap().take()
+    }
+
+    /// Hands `frame` to the preview windows, replacing one none of them has shown yet.
+    pub fn publish_frame(&self, frame: FrameData) {
+        *self.published_frame.lock().unwrap() = Some(frame.clone());
+        let mut writer = self.frame.writer.lock().unwrap();
+        writer.generation += 1;
+        let generation = writer.generation;
+        // Frames replaced while frozen were never meant to be shown.
+        if writer.frames.write((generation, frame)) && !self.is_frozen() {
+            self.stats.count_overwritten();
+        }
+        self.published.store(generation, Ordering::Release);
+        drop(writer);
+        wake();
+    }
+
+    /// The newest frame when it is newer than generation `seen`, which is updated.
+    fn newest_frame(&self, seen: &mut u64) -> Option<FrameData> {
+        if !self.has_frame_after(*seen) {
+            return None;
+        }
+        let mut reader = self.frame.reader.lock().unwrap();
+        reader.update();
+        let (generation, frame) = reader.get().filter(|(generation, _)| *generation > *seen)?;
+        *seen = *generation;
+        Some(frame.clone())
+    }
+
+    fn has_frame_after(&self, seen: u64) -> bool {
+        self.published.load(Ordering::Acquire) > seen
+    }
+
+    pub fn clones(&self) -> Vec<u32> {
+        self.clones.lock().unwrap().iter().copied().collect()
+    }
+
+    /// Asks the preview thread for another window showing this session.
+    pub fn add_clone(&self, id: u32) {
+        self.clones.lock().unwrap().insert(id);
+        wake();
+    }
+
+    /// Closes cloned window `id`; returns whether this session had it.
+    pub fn remove_clone(&self, id: u32) -> bool {
+        let removed = self.clones.lock().unwrap().remove(&id);
+        wake();
+        removed
+    }
+
+    pub fn source_size(&self) -> Option<(u32, u32)> {
+        *self.source_size.lock().unwrap()
+    }
+
+    pub fn set_source_size(&self, width: u32, height: u32) {
+        *self.source_size.lock().unwrap() = Some((width, height));
+    }
+
+    pub fn crop(&self) -> Option<AppliedCrop> {
+        *self.crop.lock().unwrap()
+    }
+
+    pub fn set_crop(&self, crop: Option<AppliedCrop>) {
+        *self.crop.lock().unwrap() = crop;
+    }
+
+    pub fn resolution(&self) -> String {
+        self.resolution.lock().unwrap().clone()
+    }
+
+    pub fn set_resolution(&self, resolution: &str) {
+        let mut current = self.resolution.lock().unwrap();
+        if *current != resolution {
+            if !current.is_empty() {
+                *self.resolution_changed_at.lock().unwrap() = Some(Instant::now());
+            }
+            *current = resolution.to_string();
+        }
+    }
+
+    pub fn source_title(&self) -> Option<String> {
+        self.source_title.lock().unwrap().clone()
+    }
+
+    pub fn set_source_title(&self, title: Option<String>) {
+        *self.source_title.lock().unwrap() = title;
+    }
+
+    pub fn capture_target(&self) -> Option<u32> {
+        *self.capture_target.lock().unwrap()
+    }
+
+    pub fn set_capture_target(&self, id: Option<u32>) {
+        *self.capture_target.lock().unwrap() = id;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::Relaxed);
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.frozen.load(Ordering::Relaxed)
+    }
+
+    /// Unlike pausing, freezing leaves the capture thread alone: it keeps replacing `frame`,
+    /// which the preview leaves untouched until it is unfrozen. The frozen frame is the one
+    /// last published, which a delayed preview shows well after the capture thread had it.
+    pub fn set_frozen(&self, frozen: bool) {
+        *self.frozen_frame.lock().unwrap() = if frozen {
+            self.published_frame.lock().unwrap().clone()
+        } else {
+            None
+        };
+        self.frozen.store(frozen, Ordering::Relaxed);
+    }
+
+    /// Preview window title: "LiteView — <source>", so meeting apps' window pickers can tell
+    /// previews apart.
+    fn window_title(&self) -> String {
+        let mut title = match self.source_title.lock().unwrap().as_deref() {
+            Some(title) if !title.is_empty() => format!("LiteView — {title}"),
+            _ => "LiteView Preview".to_string(),
+        };
+        let delay = self.delay();
+        if !delay.is_zero() {
+            title.push_str(&format!(" (delayed by {} s)", delay.as_secs()));
+        }
+        if self.is_paused() {
+            format!("{title} (paused)")
+        } else if self.is_frozen() {
+            format!("{title} (frozen)")
+        } else {
+            title
+        }
+    }
+
+    pub fn detected_crop(&self) -> Option<(u32, u32, u32, u32)> {
+        *self.detected_crop.lock().unwrap()
+    }
+
+    pub fn set_detected_crop(&self, rect: Option<(u32, u32, u32, u32)>) {
+        *self.detected_crop.lock().unwrap() = rect;
+    }
+
+    fn record_geometry(&self, window: &Window) {
+        if let Some(g) = window_geometry(window) {
+            *self.geometry.lock().unwrap() = Some(g);
+            let (outer, inner) = (window.outer_size(), window.inner_size());
+            *self.decoration_size.lock().unwrap() = (
+                outer.width.saturating_sub(inner.width),
+                outer.height.saturating_sub(inner.height),
+            );
         }
     }
+
+    /// Size of the window's title bar and borders, (0, 0) for a borderless window.
+    pub fn decoration_size(&self) -> (u32, u32) {
+        *self.decoration_size.lock().unwrap()
+    }
+
+    /// Resets the idle countdown.
+    pub fn touch(&self) {
+        *self.last_activity.lock().unwrap() = Instant::now();
+    }
+
+    pub fn idle_for(&self) -> Duration {
+        self.last_activity.lock().unwrap().elapsed()
+    }
+
+    pub fn set_ends_at(&self, at: Option<Instant>) {
+        *self.ends_at.lock().unwrap() = at;
+    }
+
+    /// Time left until the duration limit; None without one.
+    pub fn remaining(&self) -> Option<Duration> {
+        self.ends_at
+            .lock()
+            .unwrap()
+            .map(|at| at.saturating_duration_since(Instant::now()))
+    }
 }
 
 struct WgpuContext {
@@ -50,11 +663,239 @@ struct WgpuContext {
     config: SurfaceConfiguration,
     texture: Option<Texture>,
     texture_view: Option<TextureView>,
+    /// Copy of the frame before `texture`, for frame smoothing.
+    previous_texture: Option<Texture>,
     texture_size: (u32, u32),
+    /// `display_size` of the uploaded frame, which sizes the image quad.
+    frame_size: (u32, u32),
     render_pipeline: wgpu::RenderPipeline,
+    shader: wgpu::ShaderModule,
+    pipeline_layout: wgpu::PipelineLayout,
+    surface_caps: wgpu::SurfaceCapabilities,
     bind_group_layout: wgpu::BindGroupLayout,
     sampler: wgpu::Sampler,
     bind_group: Option<wgpu::BindGroup>,
+    uniform_buffer: wgpu::Buffer,
+    /// Options currently written to `uniform_buffer`.
+    display: DisplayOptions,
+    encoding: OutputEncoding,
+    /// Radius written to `uniform_buffer`, in frame pixels.
+    corner_radius: f32,
+    /// Texture pixels per frame buffer pixel; below 1 when frames exceed the texture size limit.
+    texture_scale: f32,
+    /// Scratch for frames downscaled to fit the texture, kept between frames.
+    scaled: Vec<u32>,
+    zoom: ZoomView,
+    /// Uniforms and bindings of the full-frame inset drawn while zoomed in.
+    inset_buffer: wgpu::Buffer,
+    inset_bind_group: Option<wgpu::BindGroup>,
+    /// Whether the inset is shown while zoomed in (default shortcut Z).
+    show_inset: bool,
+    /// When the last two frames arrived; the crossfade spans their interval.
+    frame_arrivals: (Option<Instant>, Option<Instant>),
+    overlay: OverlayRenderer,
+    /// Adapter name and graphics API, for diagnostics.
+    adapter_label: String,
+}
+
+/// Renderer state reported back to the app (get_capture_status).
+#[derive(Debug, Clone, Default)]
+pub struct RenderStatus {
+    /// Active output encoding; None while no preview window exists.
+    pub output: Option<OutputEncoding>,
+    /// Present mode of the surface, which may differ from `DisplayOptions::present_mode`.
+    pub present_mode: Option<PresentMode>,
+    /// GPU adapter of the most recent preview window, e.g. "Intel(R) UHD Graphics (Vulkan)".
+    pub adapter: Option<String>,
+    /// New frames shown per second, measured on their capture timestamps.
+    pub capture_fps: Option<f32>,
+    /// Frames presented per second, including redraws without a new frame.
+    pub render_fps: Option<f32>,
+    /// Average time spent in render + present, in milliseconds.
+    pub present_ms: Option<f32>,
+}
+
+pub type RenderStatusSlot = Arc<Mutex<RenderStatus>>;
+
+fn create_shader(device: &Device) -> wgpu::ShaderModule {
+    device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Texture Shader"),
+        source: wgpu::ShaderSource::Wgsl(include_str!("shader.wgsl").into()),
+    })
+}
+
+/// Bindings of shader.wgsl: frame texture, sampler, uniforms and the previous frame.
+fn create_bind_group_layout(device: &Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Texture Bind Group Layout"),
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 2,
+                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 3,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            },
+        ],
+    })
+}
+
+/// Bind group of `create_bind_group_layout` for the current and previous frame.
+fn create_bind_group(
+    device: &Device,
+    layout: &wgpu::BindGroupLayout,
+    frame: &TextureView,
+    previous: &TextureView,
+    sampler: &wgpu::Sampler,
+    uniforms: &wgpu::Buffer,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Texture Bind Group"),
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(frame),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: uniforms.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 3,
+                resource: wgpu::BindingResource::TextureView(previous),
+            },
+        ],
+    })
+}
+
+fn create_render_pipeline(
+    device: &Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    format: TextureFormat,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Render Pipeline"),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: Some("vs_main"),
+            buffers: &[],
+            compilation_options: Default::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: Some("fs_main"),
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState::REPLACE),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: Default::default(),
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: None,
+            polygon_mode: wgpu::PolygonMode::Fill,
+            unclipped_depth: false,
+            conservative: false,
+        },
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState {
+            count: 1,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+        cache: None,
+    })
+}
+
+/// Picks the present mode for `preference` among the surface's; Fifo is always supported.
+fn choose_present_mode(
+    caps: &wgpu::SurfaceCapabilities,
+    preference: PresentPreference,
+) -> PresentMode {
+    let wanted: &[PresentMode] = match preference {
+        PresentPreference::Vsync => &[],
+        PresentPreference::LowLatency => &[PresentMode::Mailbox, PresentMode::Immediate],
+    };
+    let supported = wanted.iter().find(|m| caps.present_modes.contains(m));
+    supported.copied().unwrap_or(PresentMode::Fifo)
+}
+
+pub fn present_mode_label(mode: PresentMode) -> &'static str {
+    match mode {
+        PresentMode::Mailbox => "mailbox",
+        PresentMode::Immediate => "immediate",
+        _ => "fifo",
+    }
+}
+
+/// Whether `len` bytes hold `height` rows of `width` four-byte pixels, `stride` bytes apart.
+/// write_texture takes such rows at any width, with or without padding after the last one;
+/// bytes too short for their size would fail its validation instead.
+fn rows_fit(width: u32, height: u32, stride: u32, len: usize) -> bool {
+    let row = width as usize * 4;
+    width > 0
+        && height > 0
+        && stride as usize >= row
+        && len >= stride as usize * (height as usize - 1) + row
+}
+
+/// Picks the surface format. Frames are uploaded to an sRGB (BGRA or RGBA) texture, so the
+/// shader works in linear RGB; an sRGB surface encodes on write. Without one, fall back to the
+/// first format and let the shader encode (otherwise the image comes out too dark). HDR uses Rgba16Float
+/// (scRGB) when requested and offered, else falls back to the SDR path.
+fn choose_surface_format(
+    caps: &wgpu::SurfaceCapabilities,
+    hdr: HdrOutput,
+) -> (TextureFormat, OutputEncoding) {
+    // Sources are always SDR for now, so Auto never asks for HDR.
+    let want_hdr = hdr == HdrOutput::On;
+    if want_hdr && caps.formats.contains(&TextureFormat::Rgba16Float) {
+        return (TextureFormat::Rgba16Float, OutputEncoding::ScRgb);
+    }
+    match caps.formats.iter().find(|f| f.is_srgb()) {
+        Some(f) => (*f, OutputEncoding::Srgb),
+        None => (caps.formats[0], OutputEncoding::SrgbInShader),
+    }
 }
 
 impl WgpuContext {
@@ -80,7 +921,9 @@ impl WgpuContext {
                 &DeviceDescriptor {
                     label: Some("Preview Device"),
                     required_features: Features::empty(),
-                    required_limits: Limits::default(),
+                    // Everything else stays at the defaults; large captures need the
+                    // adapter's full texture size.
+                    required_limits: Limits::default().using_resolution(adapter.limits()),
                     memory_hints: Default::default(),
                 },
                 None,
@@ -90,12 +933,8 @@ impl WgpuContext {
 
         let size = window.inner_size();
         let surface_caps = surface.get_capabilities(&adapter);
-        let surface_format = surface_caps
-            .formats
-            .iter()
-            .find(|f| f.is_srgb())
-            .copied()
-            .unwrap_or(surface_caps.formats[0]);
+        let display = DisplayOptions::default();
+        let (surface_format, encoding) = choose_surface_format(&surface_caps, display.hdr_output);
 
         let config = SurfaceConfiguration {
             usage: TextureUsages::RENDER_ATTACHMENT,
@@ -110,31 +949,35 @@ impl WgpuContext {
 
         surface.configure(&device, &config);
 
-        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some("Texture Shader"),
-            source: wgpu::ShaderSource::Wgsl(include_str!("shader.wgsl").into()),
+        let shader = create_shader(&device);
+
+        let bind_group_layout = create_bind_group_layout(&device);
+
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Display Uniforms"),
+            size: std::mem::size_of::<Uniforms>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
         });
+        queue.write_buffer(
+            &uniform_buffer,
+            0,
+            bytemuck::bytes_of(&Uniforms::new(
+                &display,
+                encoding,
+                (0, 0),
+                (config.width, config.height),
+                0.0,
+                1.0,
+                &ZoomView::default(),
+            )),
+        );
 
-        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: Some("Texture Bind Group Layout"),
-            entries: &[
-                wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Texture {
-                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                        view_dimension: wgpu::TextureViewDimension::D2,
-                        multisampled: false,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 1,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
-                    count: None,
-                },
-            ],
+        let inset_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Inset Uniforms"),
+            size: std::mem::size_of::<Uniforms>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
         });
 
         let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
@@ -154,43 +997,11 @@ impl WgpuContext {
             push_constant_ranges: &[],
         });
 
-        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Render Pipeline"),
-            layout: Some(&pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: Some("vs_main"),
-                buffers: &[],
-                compilation_options: Default::default(),
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader,
-                entry_point: Some("fs_main"),
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: config.format,
-                    blend: Some(wgpu::BlendState::REPLACE),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-                compilation_options: Default::default(),
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: None,
-                polygon_mode: wgpu::PolygonMode::Fill,
-                unclipped_depth: false,
-                conservative: false,
-            },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
-            },
-            multiview: None,
-            cache: None,
-        });
+        let render_pipeline =
+            create_render_pipeline(&device, &pipeline_layout, &shader, config.format);
+        let overlay = OverlayRenderer::new(&device, config.format);
+        let info = adapter.get_info();
+        let adapter_label = format!("{} ({:?})", info.name, info.backend);
 
         Self {
             surface,
@@ -199,11 +1010,93 @@ impl WgpuContext {
             config,
             texture: None,
             texture_view: None,
+            previous_texture: None,
             texture_size: (0, 0),
+            frame_size: (0, 0),
             render_pipeline,
+            shader,
+            pipeline_layout,
+            surface_caps,
             bind_group_layout,
             sampler,
             bind_group: None,
+            uniform_buffer,
+            display,
+            encoding,
+            corner_radius: 0.0,
+            texture_scale: 1.0,
+            scaled: Vec::new(),
+            frame_arrivals: (None, None),
+            zoom: ZoomView::default(),
+            inset_buffer,
+            inset_bind_group: None,
+            show_inset: true,
+            overlay,
+            adapter_label,
+        }
+    }
+
+    /// Uploads new display options if they changed since the last call; switching HDR on/off
+    /// reconfigures the surface and rebuilds the pipeline for the new target format.
+    fn set_display_options(&mut self, options: &DisplayOptions) {
+        if *options == self.display {
+            return;
+        }
+        if options.present_mode != self.display.present_mode {
+            let mode = choose_present_mode(&self.surface_caps, options.present_mode);
+            if mode != self.config.present_mode {
+                self.config.present_mode = mode;
+                self.surface.configure(&self.device, &self.config);
+            }
+        }
+        if options.hdr_output != self.display.hdr_output {
+            let (format, encoding) = choose_surface_format(&self.surface_caps, options.hdr_output);
+            if format != self.config.format {
+                self.config.format = format;
+                self.surface.configure(&self.device, &self.config);
+                self.render_pipeline = create_render_pipeline(
+                    &self.device,
+                    &self.pipeline_layout,
+                    &self.shader,
+                    format,
+                );
+                self.overlay.set_format(&self.device, format);
+            }
+            self.encoding = encoding;
+        }
+        self.display = options.clone();
+        self.write_uniforms();
+    }
+
+    /// Also re-applies the pan limits, which depend on the window, frame and scale mode.
+    fn write_uniforms(&mut self) {
+        let surface = (self.config.width, self.config.height);
+        self.zoom
+            .clamp(self.display.quad_scale(self.frame_size, surface));
+        let uniforms = Uniforms::new(
+            &self.display,
+            self.encoding,
+            self.frame_size,
+            (self.config.width, self.config.height),
+            self.corner_radius * self.texture_scale,
+            self.blend(),
+            &self.zoom,
+        );
+        self.queue
+            .write_buffer(&self.uniform_buffer, 0, bytemuck::bytes_of(&uniforms));
+        if let Some([x, y, w, h]) = self.inset_rect() {
+            let (sw, sh) = (surface.0 as f32, surface.1 as f32);
+            let rect = [
+                x / sw * 2.0 - 1.0,
+                1.0 - (y + h) / sh * 2.0,
+                (x + w) / sw * 2.0 - 1.0,
+                1.0 - y / sh * 2.0,
+            ];
+            self.queue.write_buffer(
+                &self.inset_buffer,
+                0,
+                bytemuck::bytes_of(&uniforms.inset(rect)),
+            );
         }
     }
 
@@ -212,71 +1105,218 @@ impl WgpuContext {
             self.config.width = new_size.0;
             self.config.height = new_size.1;
             self.surface.configure(&self.device, &self.config);
+            self.write_uniforms();
         }
     }
 
-    fn update_texture(&mut self, width: u32, height: u32, data: &[u32]) {
-        if self.texture.is_none() || self.texture_size != (width, height) {
-            self.texture = Some(self.device.create_texture(&TextureDescriptor {
-                label: Some("Frame Texture"),
-                size: wgpu::Extent3d {
-                    width,
-                    height,
-                    depth_or_array_layers: 1,
-                },
-                mip_level_count: 1,
-                sample_count: 1,
-                dimension: TextureDimension::D2,
-                format: TextureFormat::Rgba8UnormSrgb,
-                usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
-                view_formats: &[],
-            }));
-            let view = self
-                .texture
-                .as_ref()
-                .unwrap()
-                .create_view(&wgpu::TextureViewDescriptor::default());
+    /// Zooms by `notches` mouse wheel steps around `cursor` (clip space).
+    fn zoom_at(&mut self, cursor: [f32; 2], notches: f32) {
+        let surface = (self.config.width, self.config.height);
+        let quad = self.display.quad_scale(self.frame_size, surface);
+        self.zoom.zoom_at(cursor, notches, quad);
+        self.write_uniforms();
+    }
 
-            self.bind_group = Some(self.device.create_bind_group(&wgpu::BindGroupDescriptor {
-                label: Some("Texture Bind Group"),
-                layout: &self.bind_group_layout,
-                entries: &[
-                    wgpu::BindGroupEntry {
-                        binding: 0,
-                        resource: wgpu::BindingResource::TextureView(&view),
-                    },
-                    wgpu::BindGroupEntry {
-                        binding: 1,
-                        resource: wgpu::BindingResource::Sampler(&self.sampler),
-                    },
-                ],
-            }));
+    fn pan_by(&mut self, delta: [f32; 2]) {
+        let surface = (self.config.width, self.config.height);
+        let quad = self.display.quad_scale(self.frame_size, surface);
+        self.zoom.pan_by(delta, quad);
+        self.write_uniforms();
+    }
 
-            self.texture_view = Some(view);
-            self.texture_size = (width, height);
-        }
+    fn reset_zoom(&mut self) {
+        self.zoom = ZoomView::default();
+        self.write_uniforms();
+    }
 
-        let rgba_data: Vec<u8> = data
-            .iter()
-            .flat_map(|&pixel| {
-                let r = ((pixel >> 16) & 0xFF) as u8;
-                let g = ((pixel >> 8) & 0xFF) as u8;
-                let b = (pixel & 0xFF) as u8;
-                [r, g, b, 255]
-            })
-            .collect();
+    /// Pans the zoomed image so `point` (image coordinates, see `ZoomView::visible`) is centered.
+    fn center_on(&mut self, point: [f32; 2]) {
+        let surface = (self.config.width, self.config.height);
+        let quad = self.display.quad_scale(self.frame_size, surface);
+        self.zoom.center_on(point, quad);
+        self.write_uniforms();
+    }
 
-        self.queue.write_texture(
+    /// Pixel rectangle (x, y, width, height) of the full-frame inset; None while it is hidden,
+    /// which it is unless zoomed in.
+    fn inset_rect(&self) -> Option<[f32; 4]> {
+        if !self.show_inset || !self.zoom.is_zoomed() || self.frame_size.0 == 0 {
+            return None;
+        }
+        let surface = (self.config.width, self.config.height);
+        let quad = self.display.quad_scale(self.frame_size, surface);
+        let rect = zoom::inset_rect(quad, surface);
+        (rect[2] >= 1.0 && rect[3] >= 1.0).then_some(rect)
+    }
+
+    /// The image point (image coordinates) shown in the inset at window pixel `position`.
+    fn inset_point(&self, position: PhysicalPosition<f64>) -> Option<[f32; 2]> {
+        let [x, y, w, h] = self.inset_rect()?;
+        let u = (position.x as f32 - x) / w;
+        let v = (position.y as f32 - y) / h;
+        ((0.0..=1.0).contains(&u) && (0.0..=1.0).contains(&v)).then_some([u, v])
+    }
+
+    /// The part of the image the window shows, in image coordinates (`ZoomView::visible`).
+    fn visible(&self) -> [f32; 4] {
+        let surface = (self.config.width, self.config.height);
+        let quad = self.display.quad_scale(self.frame_size, surface);
+        self.zoom.visible(quad)
+    }
+
+    /// The displayed image in clip space (x0, y0, x1, y1), after scaling, zoom and pan.
+    fn image_rect(&self) -> [f32; 4] {
+        let surface = (self.config.width, self.config.height);
+        let quad = self.display.quad_scale(self.frame_size, surface);
+        let [w, h] = quad.map(|q| q * self.zoom.factor);
+        let [x, y] = self.zoom.pan;
+        [x - w, y - h, x + w, y + h]
+    }
+
+    /// Crossfade progress from the previous to the current frame (1 when not smoothing).
+    fn blend(&self) -> f32 {
+        // Long gaps (static content) would make the fade crawl.
+        const MAX_FADE: Duration = Duration::from_millis(250);
+        let (Some(previous), Some(last)) = self.frame_arrivals else {
+            return 1.0;
+        };
+        if !self.display.frame_smoothing {
+            return 1.0;
+        }
+        let interval = last.duration_since(previous).min(MAX_FADE);
+        if interval.is_zero() {
+            return 1.0;
+        }
+        (last.elapsed().as_secs_f32() / interval.as_secs_f32()).min(1.0)
+    }
+
+    /// Whether a crossfade is in progress and needs further redraws.
+    fn blending(&self) -> bool {
+        self.blend() < 1.0
+    }
+
+    fn set_corner_radius(&mut self, radius: f32) {
+        if self.corner_radius != radius {
+            self.corner_radius = radius;
+            self.write_uniforms();
+        }
+    }
+
+    /// Uploads a frame as is, into a texture of its pixel format (recreated when that changes).
+    /// Frames larger than the device's texture limit are converted and downscaled on the CPU
+    /// first (wgpu would reject the texture).
+    fn update_texture(&mut self, frame: &FrameData) {
+        let (width, height, stride) = (frame.width, frame.height, frame.stride);
+        if !rows_fit(width, height, stride, frame.bytes.as_bytes().len()) {
+            return;
+        }
+        self.frame_size = frame.display_size;
+        let max = self.device.limits().max_texture_dimension_2d;
+        let mut scaled = std::mem::take(&mut self.scaled);
+        let (width, height, stride, format, bytes) = if width > max || height > max {
+            let scale = max as f64 / width.max(height) as f64;
+            let tw = ((width as f64 * scale) as u32).clamp(1, max);
+            let th = ((height as f64 * scale) as u32).clamp(1, max);
+            let algorithm = ResizeAlgorithm::Auto.resolve(width, height, tw, th);
+            let Some(packed) = frame.packed() else {
+                self.scaled = scaled;
+                return;
+            };
+            resize::resize_into(algorithm, (width, height), &packed, (tw, th), &mut scaled);
+            self.texture_scale = tw as f32 / width as f32;
+            let bytes: &[u8] = bytemuck::cast_slice(&scaled);
+            (tw, th, tw * 4, PixelFormat::Bgrx, bytes)
+        } else {
+            self.texture_scale = 1.0;
+            (width, height, stride, frame.format, frame.bytes.as_bytes())
+        };
+        self.frame_arrivals = (self.frame_arrivals.1, Some(Instant::now()));
+        let format = format.texture_format();
+        let resized = self.texture_size != (width, height)
+            || self.texture.as_ref().is_none_or(|t| t.format() != format);
+        if resized {
+            let descriptor = TextureDescriptor {
+                label: Some("Frame Texture"),
+                size: wgpu::Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format,
+                usage: TextureUsages::TEXTURE_BINDING
+                    | TextureUsages::COPY_DST
+                    | TextureUsages::COPY_SRC,
+                view_formats: &[],
+            };
+            self.texture = Some(self.device.create_texture(&descriptor));
+            self.previous_texture = Some(self.device.create_texture(&descriptor));
+            let view = self
+                .texture
+                .as_ref()
+                .unwrap()
+                .create_view(&wgpu::TextureViewDescriptor::default());
+            let previous_view = self
+                .previous_texture
+                .as_ref()
+                .unwrap()
+                .create_view(&wgpu::TextureViewDescriptor::default());
+
+            self.bind_group = Some(create_bind_group(
+                &self.device,
+                &self.bind_group_layout,
+                &view,
+                &previous_view,
+                &self.sampler,
+                &self.uniform_buffer,
+            ));
+            self.inset_bind_group = Some(create_bind_group(
+                &self.device,
+                &self.bind_group_layout,
+                &view,
+                &previous_view,
+                &self.sampler,
+                &self.inset_buffer,
+            ));
+
+            self.texture_view = Some(view);
+            self.texture_size = (width, height);
+            // Nothing to fade from after a size change.
+            self.frame_arrivals.0 = None;
+            self.write_uniforms();
+        } else if self.display.frame_smoothing {
+            // Submitted on its own: queued texture writes run before the next submission's
+            // commands, so a copy recorded alongside would already see the new frame.
+            let mut encoder = self
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Previous Frame Copy"),
+                });
+            encoder.copy_texture_to_texture(
+                self.texture.as_ref().unwrap().as_image_copy(),
+                self.previous_texture.as_ref().unwrap().as_image_copy(),
+                wgpu::Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+            );
+            self.queue.submit(std::iter::once(encoder.finish()));
+        }
+
+        self.queue.write_texture(
             wgpu::ImageCopyTexture {
                 texture: self.texture.as_ref().unwrap(),
                 mip_level: 0,
                 origin: wgpu::Origin3d::ZERO,
                 aspect: wgpu::TextureAspect::All,
             },
-            &rgba_data,
+            bytes,
             wgpu::ImageDataLayout {
                 offset: 0,
-                bytes_per_row: Some(4 * width),
+                bytes_per_row: Some(stride),
                 rows_per_image: Some(height),
             },
             wgpu::Extent3d {
@@ -285,6 +1325,23 @@ impl WgpuContext {
                 depth_or_array_layers: 1,
             },
         );
+        if resized {
+            self.queue.write_texture(
+                self.previous_texture.as_ref().unwrap().as_image_copy(),
+                bytes,
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(stride),
+                    rows_per_image: Some(height),
+                },
+                wgpu::Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+        self.scaled = scaled;
     }
 
     fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
@@ -303,6 +1360,13 @@ impl WgpuContext {
                 label: Some("Render Encoder"),
             });
 
+        let [r, g, b, a] = display::background_color(self.display.background, self.encoding);
+        let clear_color = wgpu::Color {
+            r: r as f64,
+            g: g as f64,
+            b: b as f64,
+            a: a as f64,
+        };
         {
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Render Pass"),
@@ -310,7 +1374,7 @@ impl WgpuContext {
                     view: &view,
                     resolve_target: None,
                     ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        load: wgpu::LoadOp::Clear(clear_color),
                         store: wgpu::StoreOp::Store,
                     },
                 })],
@@ -322,6 +1386,16 @@ impl WgpuContext {
             render_pass.set_pipeline(&self.render_pipeline);
             render_pass.set_bind_group(0, self.bind_group.as_ref().unwrap(), &[]);
             render_pass.draw(0..6, 0..1);
+            let inset = self.inset_rect().zip(self.inset_bind_group.as_ref());
+            if let Some(([x, y, w, h], inset)) = inset {
+                // A second draw of the frame; the covering triangles reach past the inset, and
+                // the scissor keeps them (and their letterbox fill) inside it.
+                render_pass.set_scissor_rect(x as u32, y as u32, w as u32, h as u32);
+                render_pass.set_bind_group(0, inset, &[]);
+                render_pass.draw(0..6, 0..1);
+                render_pass.set_scissor_rect(0, 0, self.config.width, self.config.height);
+            }
+            self.overlay.draw(&mut render_pass);
         }
 
         self.queue.submit(std::iter::once(encoder.finish()));
@@ -331,15 +1405,869 @@ impl WgpuContext {
     }
 }
 
-/// Shared slot: current preview state (None when no capture). Preview thread reads this and never exits the event loop.
-pub type PreviewStateSlot = Arc<Mutex<Option<Arc<PreviewState>>>>;
+/// Shared slot: preview states of the running capture sessions by session ID. Preview thread reads this and never exits the event loop.
+pub type PreviewStateSlot = Arc<Mutex<BTreeMap<u32, Arc<PreviewState>>>>;
 
-struct PreviewApp {
-    slot: PreviewStateSlot,
-    window: Option<Arc<Window>>,
-    wgpu_context: Option<WgpuContext>,
+/// Actions triggered from inside the preview window that the app side carries out.
+#[derive(Debug, Clone, Copy)]
+pub enum PreviewAction {
+    /// Cycle the preview window arrangement (grid / horizontal / vertical).
+    CycleArrangement,
+    /// Cycle the color-blindness simulation (none / protanopia / deuteranopia / tritanopia).
+    CycleColorBlindness,
+    /// Show/hide the frame-time graph.
+    ToggleFrameGraph,
+    /// Show/hide the FPS, resolution and source name.
+    ToggleInfoOverlay,
+    /// Show/hide the crosshair.
+    ToggleCrosshair,
+    /// Cycle the composition grid (off / thirds / custom).
+    CycleGrid,
+    /// Turn overlay mode on/off.
+    ToggleOverlay,
+    /// Turn picture-in-picture on/off.
+    TogglePip,
+    /// Switch a preview window option such as always-on-top.
+    Toggle(Toggle),
+    /// Resize the preview window to a preset relative to the frame size.
+    Resize(SizePreset),
+    /// Turn the image a quarter clockwise.
+    Rotate,
+    /// Neutral brightness, contrast, gamma, hue and saturation for the selected target.
+    ResetAdjustments,
+    /// Cycle the scaling (stretch / fit / fill / integer).
+    CycleScaleMode,
+    /// Copy the current frame to the clipboard.
+    CopyFrame,
+    /// Show the whole image again after zooming in.
+    ResetZoom,
+    /// Zoom in/out one step around the window center.
+    ZoomIn,
+    ZoomOut,
+    /// Show/hide the full-frame inset marking the zoomed viewport.
+    ToggleZoomInset,
+    /// Pause/resume the capture, like the tray item.
+    TogglePause,
+    /// Freeze the preview on its current frame while capture carries on, or unfreeze it.
+    ToggleFreeze,
+    /// Save a screenshot, like the tray item.
+    Screenshot,
+    /// Switch fullscreen on the monitor showing the preview on/off.
+    ToggleFullscreen,
+    /// Close the preview window, like its close button.
+    ClosePreview,
+    /// Show/hide the list of shortcuts.
+    ShowShortcuts,
+}
+
+pub type PreviewActionHandler = Box<dyn Fn(PreviewAction) + Send>;
+
+/// The preview window of one capture session and its renderer.
+struct PreviewWindow {
+    state: Arc<PreviewState>,
+    /// None for the session's own window, else the ID from `PreviewState::add_clone`.
+    clone_id: Option<u32>,
+    window: Arc<Window>,
+    ctx: WgpuContext,
+    /// Generation of the last frame taken from the session (`PreviewState::newest_frame`).
+    seen: u64,
+    /// Scale mode and fullscreen monitor a clone uses in place of the settings.
+    scale_mode: Option<ScaleMode>,
+    fullscreen_monitor: Option<usize>,
     frame_count: u32,
+    /// Capture time of the first frame counted in `frame_count`.
     fps_last: Instant,
+    last_captured_at: Instant,
+    /// Latest capture rate, for the status and the overlays.
+    capture_fps: f32,
+    /// Presents since `render_last` and the time they took.
+    render_count: u32,
+    present_time: Duration,
+    render_last: Instant,
+    frame_graph: FrameTimeGraph,
+    /// Text of the info overlay, refreshed with the FPS.
+    info: Vec<String>,
+    /// Shortcut list shown in place of the info overlay (default shortcut ?).
+    help: Option<Vec<String>>,
+    /// Window options last applied to `window`.
+    window_options: Option<WindowOptions>,
+    /// Whether `window` is currently hidden by `PreviewState::set_hidden` or `set_dismissed`.
+    hidden: bool,
+    /// Whether the platform reported the window as fully covered (or minimized).
+    occluded: bool,
+    /// Whether the window title currently says the capture is paused or the preview frozen.
+    paused: bool,
+    frozen: bool,
+    /// Last cursor position inside the window.
+    cursor: Option<PhysicalPosition<f64>>,
+    /// Whether the left button is held to pan the zoomed image.
+    panning: bool,
+    /// Last left click, to detect double-clicks.
+    last_click: Option<Instant>,
+    /// Cursor shape last set on `window`.
+    cursor_icon: CursorIcon,
+    /// Set while the window is in picture-in-picture (`DisplayOptions::pip`).
+    pip: Option<PipState>,
+    /// Corner the window was last docked to; kept when picture-in-picture is left.
+    pip_corner: Option<Corner>,
+}
+
+/// A window in picture-in-picture. Its normal placement is kept here rather than recorded, so
+/// the session remembers the two independently.
+struct PipState {
+    /// Placement to go back to.
+    restore: Option<WindowGeometry>,
+    /// When the window was last moved; once it rests, it snaps to the nearest corner.
+    moved_at: Option<Instant>,
+}
+
+struct PreviewApp {
+    slot: PreviewStateSlot,
+    display: DisplayOptionsSlot,
+    keys: KeybindingsSlot,
+    modifiers: ModifiersState,
+    /// Written by the window of the lowest session ID (the one the app calls primary).
+    status: RenderStatusSlot,
+    on_action: PreviewActionHandler,
+    /// Open windows by session ID and clone ID. A session's own window (no clone ID, so it
+    /// comes first) opens with its first frame; its clones open once it is there.
+    windows: BTreeMap<(u32, Option<u32>), PreviewWindow>,
+}
+
+/// Makes the window translucent via a layered window (no transparent surface needed).
+#[cfg(target_os = "windows")]
+fn set_window_alpha(window: &Window, alpha: u8) {
+    use windows::Win32::Foundation::{COLORREF, HWND};
+    use windows::Win32::UI::WindowsAndMessaging::{
+        GetWindowLongPtrW, SetLayeredWindowAttributes, SetWindowLongPtrW, GWL_EXSTYLE, LWA_ALPHA,
+        WS_EX_LAYERED,
+    };
+    use winit::raw_window_handle::{HasWindowHandle, RawWindowHandle};
+
+    let Ok(handle) = window.window_handle() else {
+        return;
+    };
+    let RawWindowHandle::Win32(handle) = handle.as_raw() else {
+        return;
+    };
+    let hwnd = HWND(handle.hwnd.get() as _);
+    unsafe {
+        let style = GetWindowLongPtrW(hwnd, GWL_EXSTYLE);
+        let layered = style & WS_EX_LAYERED.0 as isize != 0;
+        if alpha == 255 && !layered {
+            return;
+        }
+        if !layered {
+            SetWindowLongPtrW(hwnd, GWL_EXSTYLE, style | WS_EX_LAYERED.0 as isize);
+        }
+        let _ = SetLayeredWindowAttributes(hwnd, COLORREF(0), alpha, LWA_ALPHA);
+    }
+}
+
+/// A window of `width` x `height` (shrunk to fit) centered on `monitor`.
+fn centered_on(monitor: &MonitorHandle, width: u32, height: u32) -> WindowGeometry {
+    let (pos, size) = (monitor.position(), monitor.size());
+    let width = width.min(size.width);
+    let height = height.min(size.height);
+    WindowGeometry {
+        x: pos.x + ((size.width - width) / 2) as i32,
+        y: pos.y + ((size.height - height) / 2) as i32,
+        width,
+        height,
+    }
+}
+
+/// `g`, or `g` centered on the primary monitor when no monitor shows enough of it (the
+/// monitor it was saved on is gone), shrunk to fit.
+fn visible_geometry(event_loop: &ActiveEventLoop, g: WindowGeometry) -> WindowGeometry {
+    const MIN_VISIBLE: i32 = 64;
+
+    let (width, height) = (g.width as i32, g.height as i32);
+    let shown = event_loop.available_monitors().any(|m| {
+        let (pos, size) = (m.position(), m.size());
+        let overlap_x = (g.x + width).min(pos.x + size.width as i32) - g.x.max(pos.x);
+        let overlap_y = (g.y + height).min(pos.y + size.height as i32) - g.y.max(pos.y);
+        overlap_x >= MIN_VISIBLE.min(width) && overlap_y >= MIN_VISIBLE.min(height)
+    });
+    if shown {
+        return g;
+    }
+    event_loop
+        .primary_monitor()
+        .or_else(|| event_loop.available_monitors().next())
+        .map_or(g, |monitor| centered_on(&monitor, g.width, g.height))
+}
+
+/// Work area (without the taskbar) of the monitor showing `window`, on Windows; elsewhere the
+/// whole monitor.
+fn work_area(window: &Window) -> Option<WindowGeometry> {
+    #[cfg(target_os = "windows")]
+    {
+        use windows::Win32::Foundation::HWND;
+        use windows::Win32::Graphics::Gdi::{
+            GetMonitorInfoW, MonitorFromWindow, MONITORINFO, MONITOR_DEFAULTTONEAREST,
+        };
+        use winit::raw_window_handle::{HasWindowHandle, RawWindowHandle};
+
+        if let Ok(RawWindowHandle::Win32(handle)) = window.window_handle().map(|h| h.as_raw()) {
+            let hwnd = HWND(handle.hwnd.get() as _);
+            let monitor = unsafe { MonitorFromWindow(hwnd, MONITOR_DEFAULTTONEAREST) };
+            let mut info = MONITORINFO {
+                cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+                ..Default::default()
+            };
+            if unsafe { GetMonitorInfoW(monitor, &mut info) }.as_bool() {
+                let r = info.rcWork;
+                return Some(WindowGeometry {
+                    x: r.left,
+                    y: r.top,
+                    width: (r.right - r.left) as u32,
+                    height: (r.bottom - r.top) as u32,
+                });
+            }
+        }
+    }
+    let monitor = window.current_monitor()?;
+    let (pos, size) = (monitor.position(), monitor.size());
+    Some(WindowGeometry {
+        x: pos.x,
+        y: pos.y,
+        width: size.width,
+        height: size.height,
+    })
+}
+
+fn window_level(options: &WindowOptions) -> WindowLevel {
+    if options.always_on_top {
+        WindowLevel::AlwaysOnTop
+    } else {
+        WindowLevel::Normal
+    }
+}
+
+/// Title of a preview window of `state`; clones are marked so they can be told apart.
+fn window_title(state: &PreviewState, clone_id: Option<u32>) -> String {
+    match clone_id {
+        Some(_) => format!("{} (copy)", state.window_title()),
+        None => state.window_title(),
+    }
+}
+
+impl PreviewWindow {
+    /// Opens the window of `state` once its first frame has arrived, or clone `clone_id` of
+    /// it next to the session's own window at `near`.
+    fn open(
+        event_loop: &ActiveEventLoop,
+        state: Arc<PreviewState>,
+        display: &DisplayOptions,
+        clone_id: Option<u32>,
+        near: Option<WindowGeometry>,
+    ) -> Option<Self> {
+        const CLONE_OFFSET: i32 = 32;
+
+        let mut seen = 0;
+        let frame_data = state.newest_frame(&mut seen)?;
+        let mut window_options = display.window;
+        if clone_id.is_some() {
+            // Fullscreen on the settings' monitor belongs to the session's own window.
+            window_options.fullscreen_monitor = None;
+        }
+        let (width, height) = state
+            .display_size()
+            .unwrap_or((frame_data.width, frame_data.height));
+        let mut attrs = WindowAttributes::default()
+            .with_title(window_title(&state, clone_id))
+            .with_inner_size(LogicalSize::new(width, height))
+            .with_resizable(true)
+            .with_decorations(!window_options.borderless)
+            .with_window_level(window_level(&window_options))
+            .with_visible(true);
+        #[cfg(target_os = "windows")]
+        {
+            use winit::platform::windows::WindowAttributesExtWindows;
+            attrs = attrs.with_skip_taskbar(window_options.skip_taskbar);
+        }
+        // X11 has no runtime switch; utility windows stay off taskbars on most window
+        // managers, so the option takes effect when the window opens.
+        #[cfg(target_os = "linux")]
+        if window_options.skip_taskbar {
+            use winit::platform::x11::{WindowAttributesExtX11, WindowType};
+            attrs = attrs.with_x11_window_type(vec![WindowType::Utility]);
+        }
+        let geometry = match clone_id {
+            Some(_) => near.map(|g| {
+                let offset = WindowGeometry {
+                    x: g.x + CLONE_OFFSET,
+                    y: g.y + CLONE_OFFSET,
+                    ..g
+                };
+                visible_geometry(event_loop, offset)
+            }),
+            None => state
+                .pending_geometry
+                .lock()
+                .unwrap()
+                .take()
+                .or(state.initial_geometry)
+                .map(|g| visible_geometry(event_loop, g))
+                .or_else(|| {
+                    let name = state.initial_monitor.as_ref()?;
+                    let monitor = event_loop
+                        .available_monitors()
+                        .find(|m| m.name().as_ref() == Some(name))?;
+                    Some(centered_on(&monitor, width, height))
+                }),
+        };
+        if let Some(g) = geometry {
+            attrs = attrs
+                .with_position(PhysicalPosition::new(g.x, g.y))
+                .with_inner_size(PhysicalSize::new(g.width, g.height));
+        }
+
+        let window = Arc::new(event_loop.create_window(attrs).ok()?);
+        #[cfg(target_os = "windows")]
+        window.set_visible(true);
+        let mut ctx = pollster::block_on(WgpuContext::new(window.clone()));
+        ctx.set_corner_radius(frame_data.corner_radius);
+        ctx.update_texture(&frame_data);
+        let mut frame_graph = FrameTimeGraph::default();
+        frame_graph.record_capture(frame_data.captured_at);
+        ctx.set_display_options(display);
+        // The session's statistics count what its own window shows.
+        let rendered = ctx.render().is_ok();
+        if clone_id.is_none() {
+            state.stats.count_displayed();
+            if rendered {
+                state.stats.count_rendered();
+            }
+            state.record_geometry(&window);
+        }
+        let mut preview = Self {
+            state,
+            clone_id,
+            window: window.clone(),
+            ctx,
+            seen,
+            scale_mode: None,
+            fullscreen_monitor: None,
+            frame_count: 1,
+            fps_last: frame_data.captured_at,
+            last_captured_at: frame_data.captured_at,
+            capture_fps: 0.0,
+            render_count: 0,
+            present_time: Duration::ZERO,
+            render_last: Instant::now(),
+            frame_graph,
+            info: Vec::new(),
+            help: None,
+            window_options: None,
+            hidden: false,
+            occluded: false,
+            paused: false,
+            frozen: false,
+            cursor: None,
+            panning: false,
+            last_click: None,
+            cursor_icon: CursorIcon::Default,
+            pip: None,
+            pip_corner: None,
+        };
+        preview.apply_window_options(event_loop, window_options);
+        window.request_redraw();
+        Some(preview)
+    }
+
+    /// Saves the session window's placement; clones and picture-in-picture are not
+    /// remembered.
+    fn record_geometry(&self) {
+        if self.clone_id.is_none() && self.pip.is_none() {
+            self.state.record_geometry(&self.window);
+        }
+    }
+
+    fn moved(&mut self) {
+        match &mut self.pip {
+            Some(pip) => pip.moved_at = Some(Instant::now()),
+            None => self.record_geometry(),
+        }
+    }
+
+    fn set_geometry(&self, g: WindowGeometry) {
+        let window = &self.window;
+        window.set_outer_position(PhysicalPosition::new(g.x, g.y));
+        let _ = window.request_inner_size(PhysicalSize::new(g.width, g.height));
+    }
+
+    /// Places the window in `corner` of the work area it is on, `pip.width` wide with the
+    /// frame's aspect ratio.
+    fn dock(&mut self, pip: Pip, corner: Corner) {
+        self.pip_corner = Some(corner);
+        let (Some((fw, fh)), Some(area)) = (self.state.display_size(), work_area(&self.window))
+        else {
+            return;
+        };
+        let scale = self.window.scale_factor();
+        let width = ((pip.width as f64 * scale).round() as u32).min(area.width);
+        let height = (width as u64 * fh as u64 / fw.max(1) as u64).clamp(1, area.height as u64);
+        let height = height as u32;
+        let (x, y) = corner.dock(area, width, height, (PIP_MARGIN * scale).round() as i32);
+        let g = WindowGeometry {
+            x,
+            y,
+            width,
+            height,
+        };
+        // Docking moves the window too; it settles once it is in place.
+        if window_geometry(&self.window) != Some(g) {
+            self.set_geometry(g);
+        }
+    }
+
+    /// Enters or leaves picture-in-picture with `DisplayOptions::pip`; in it, snaps a window
+    /// dragged elsewhere to the nearest corner once it has stopped moving.
+    fn update_pip(&mut self, pip: Option<Pip>) {
+        const SNAP_DELAY: Duration = Duration::from_millis(300);
+
+        let Some(pip) = pip else {
+            if let Some(g) = self.pip.take().and_then(|p| p.restore) {
+                self.set_geometry(g);
+            }
+            return;
+        };
+        let Some(state) = &mut self.pip else {
+            self.pip = Some(PipState {
+                restore: window_geometry(&self.window),
+                moved_at: None,
+            });
+            self.dock(pip, self.pip_corner.unwrap_or(pip.corner));
+            return;
+        };
+        if state.moved_at.is_none_or(|t| t.elapsed() < SNAP_DELAY) {
+            return;
+        }
+        state.moved_at = None;
+        let (Some(area), Some(current)) = (work_area(&self.window), window_geometry(&self.window))
+        else {
+            return;
+        };
+        self.dock(pip, Corner::nearest(area, current));
+    }
+
+    /// Whether the window needs updates between frames: a crossfade, or a picture-in-picture
+    /// window waiting to snap to a corner.
+    fn animating(&self) -> bool {
+        self.ctx.blending() || self.pip.as_ref().is_some_and(|p| p.moved_at.is_some())
+    }
+
+    fn is_visible(&self) -> bool {
+        !self.hidden && !self.occluded && self.window.is_minimized() != Some(true)
+    }
+
+    /// `display` with a clone's own scale mode and fullscreen monitor; clones stay out of
+    /// picture-in-picture.
+    fn own_display(&self, display: &DisplayOptions) -> DisplayOptions {
+        let mut display = display.clone();
+        if self.clone_id.is_some() {
+            display.scale_mode = self.scale_mode.unwrap_or(display.scale_mode);
+            display.window.fullscreen_monitor = self.fullscreen_monitor;
+            display.pip = None;
+        }
+        display
+    }
+
+    /// Switches a clone between a window and fullscreen on the monitor it is on.
+    fn toggle_clone_fullscreen(&mut self, event_loop: &ActiveEventLoop) {
+        self.fullscreen_monitor = match self.fullscreen_monitor {
+            Some(_) => None,
+            None => {
+                let current = self.window.current_monitor();
+                event_loop
+                    .available_monitors()
+                    .position(|m| Some(m) == current)
+            }
+        };
+    }
+
+    /// Resizes the window to the frame size once the output resolution has stopped changing
+    /// (so stepping through presets does not thrash it). Sized like a new window, but kept
+    /// within the current monitor; a size chosen relative to the frame is kept instead, and a
+    /// picture-in-picture window stays docked.
+    fn fit_to_new_resolution(&mut self, pip: Option<Pip>) {
+        const SETTLE: Duration = Duration::from_millis(500);
+        if self.clone_id.is_some() {
+            return;
+        }
+        let mut changed_at = self.state.resolution_changed_at.lock().unwrap();
+        if !changed_at.is_some_and(|t| t.elapsed() >= SETTLE) {
+            return;
+        }
+        *changed_at = None;
+        drop(changed_at);
+        if let (Some(pip), Some(_)) = (pip, &self.pip) {
+            self.dock(pip, self.pip_corner.unwrap_or(pip.corner));
+            return;
+        }
+        let Some((width, height)) = self.state.display_size() else {
+            return;
+        };
+        if self.window.fullscreen().is_some() {
+            return;
+        }
+        if let Some(scale) = self.state.size_scale() {
+            let Some(current) = window_geometry(&self.window) else {
+                return;
+            };
+            let g = arrange::scaled_geometry((width, height), scale.percent, current, scale.area);
+            self.set_geometry(g);
+            return;
+        }
+        let mut size = LogicalSize::new(width as f64, height as f64);
+        if let Some(monitor) = self.window.current_monitor() {
+            let bounds = monitor.size().to_logical::<f64>(monitor.scale_factor());
+            let fit = (bounds.width / size.width).min(bounds.height / size.height).min(1.0);
+            size = LogicalSize::new(size.width * fit, size.height * fit);
+        }
+        let _ = self.window.request_inner_size(size);
+    }
+
+    /// Zooms by `notches` mouse wheel steps around the cursor.
+    fn zoom(&mut self, notches: f32) {
+        let size = self.window.inner_size();
+        let cursor = self.cursor.map_or([0.0, 0.0], |p| zoom::to_clip(p, size));
+        self.ctx.zoom_at(cursor, notches);
+        self.window.request_redraw();
+    }
+
+    /// Zooms by `notches` steps around the window center.
+    fn zoom_centered(&mut self, notches: f32) {
+        self.ctx.zoom_at([0.0, 0.0], notches);
+        self.window.request_redraw();
+    }
+
+    fn reset_zoom(&mut self) {
+        self.ctx.reset_zoom();
+        self.window.request_redraw();
+    }
+
+    fn toggle_inset(&mut self) {
+        self.ctx.show_inset = !self.ctx.show_inset;
+        self.ctx.write_uniforms();
+        self.window.request_redraw();
+    }
+
+    /// Whether the window has no frame of its own to move and resize it with.
+    fn is_frameless(&self) -> bool {
+        self.window_options
+            .is_some_and(|o| o.borderless && o.fullscreen_monitor.is_none())
+            && !self.window.is_maximized()
+    }
+
+    /// The edge or corner of a frameless window under `position` that resizes it.
+    fn resize_direction(&self, position: PhysicalPosition<f64>) -> Option<ResizeDirection> {
+        if !self.is_frameless() {
+            return None;
+        }
+        let margin = RESIZE_MARGIN * self.window.scale_factor();
+        let size = self.window.inner_size();
+        let west = position.x < margin;
+        let east = position.x >= size.width as f64 - margin;
+        let north = position.y < margin;
+        let south = position.y >= size.height as f64 - margin;
+        Some(match (west, east, north, south) {
+            (true, _, true, _) => ResizeDirection::NorthWest,
+            (_, true, true, _) => ResizeDirection::NorthEast,
+            (true, _, _, true) => ResizeDirection::SouthWest,
+            (_, true, _, true) => ResizeDirection::SouthEast,
+            (true, ..) => ResizeDirection::West,
+            (_, true, ..) => ResizeDirection::East,
+            (_, _, true, _) => ResizeDirection::North,
+            (.., true) => ResizeDirection::South,
+            _ => return None,
+        })
+    }
+
+    /// Left button pressed outside drag-to-place. In a frameless window the edges resize it,
+    /// and dragging elsewhere moves it; while zoomed in, dragging pans the image instead unless
+    /// `alt` is held, and a click in the full-frame inset jumps there. A double-click resets the
+    /// zoom.
+    fn press_left(&mut self, alt: bool) {
+        const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+
+        if let Some(direction) = self.cursor.and_then(|p| self.resize_direction(p)) {
+            let _ = self.window.drag_resize_window(direction);
+            return;
+        }
+        if let Some(point) = self.cursor.and_then(|p| self.ctx.inset_point(p)) {
+            self.last_click = None;
+            self.ctx.center_on(point);
+            self.window.request_redraw();
+            return;
+        }
+        let now = Instant::now();
+        if self
+            .last_click
+            .is_some_and(|at| now.duration_since(at) < DOUBLE_CLICK)
+        {
+            self.last_click = None;
+            self.panning = false;
+            self.reset_zoom();
+            return;
+        }
+        self.last_click = Some(now);
+        self.panning = self.ctx.zoom.is_zoomed() && !alt;
+        if !self.panning && self.is_frameless() {
+            let _ = self.window.drag_window();
+        }
+    }
+
+    fn move_cursor(&mut self, position: PhysicalPosition<f64>) {
+        if let (true, Some(from)) = (self.panning, self.cursor) {
+            let size = self.window.inner_size();
+            let (from, to) = (zoom::to_clip(from, size), zoom::to_clip(position, size));
+            self.ctx.pan_by([to[0] - from[0], to[1] - from[1]]);
+            self.window.request_redraw();
+        }
+        self.cursor = Some(position);
+        let icon = match self.resize_direction(position) {
+            Some(ResizeDirection::West | ResizeDirection::East) => CursorIcon::EwResize,
+            Some(ResizeDirection::North | ResizeDirection::South) => CursorIcon::NsResize,
+            Some(ResizeDirection::NorthEast | ResizeDirection::SouthWest) => CursorIcon::NeswResize,
+            Some(ResizeDirection::NorthWest | ResizeDirection::SouthEast) => CursorIcon::NwseResize,
+            None => CursorIcon::Default,
+        };
+        if icon != self.cursor_icon {
+            self.window.set_cursor(icon);
+            self.cursor_icon = icon;
+        }
+    }
+
+    /// Applies window options that changed since the last call.
+    fn apply_window_options(&mut self, event_loop: &ActiveEventLoop, options: WindowOptions) {
+        if self.window_options == Some(options) {
+            return;
+        }
+        let window = &self.window;
+        window.set_window_level(window_level(&options));
+        window.set_decorations(!options.borderless);
+        let _ = window.set_cursor_hittest(!options.click_through);
+        let fullscreen = options
+            .fullscreen_monitor
+            .map(|i| Fullscreen::Borderless(event_loop.available_monitors().nth(i)));
+        window.set_fullscreen(fullscreen);
+        #[cfg(target_os = "windows")]
+        {
+            use winit::platform::windows::WindowExtWindows;
+            window.set_skip_taskbar(options.skip_taskbar);
+        }
+        // Last: winit rewrites the extended window style in the calls above.
+        #[cfg(target_os = "windows")]
+        set_window_alpha(window, options.alpha);
+        self.window_options = Some(options);
+    }
+
+    /// Uploads a new frame if there is one and presents. `status` is Some for the window that
+    /// reports the render status.
+    fn redraw(&mut self, display: &DisplayOptions, status: Option<&RenderStatusSlot>) {
+        let ctx = &mut self.ctx;
+        let mut new_frame_captured_at = None;
+        let mut new_frame_received_at = None;
+        let counts_stats = self.clone_id.is_none();
+        let new_frame = if self.state.is_frozen() {
+            None
+        } else {
+            self.state.newest_frame(&mut self.seen)
+        };
+        if let Some(frame_data) = new_frame {
+            ctx.set_corner_radius(frame_data.corner_radius);
+            ctx.update_texture(&frame_data);
+            self.frame_graph.record_capture(frame_data.captured_at);
+            if counts_stats {
+                self.state.stats.count_displayed();
+            }
+            new_frame_captured_at = Some(frame_data.captured_at);
+            new_frame_received_at = Some(frame_data.received_at);
+        }
+        ctx.set_display_options(display);
+        if display.frame_smoothing {
+            ctx.write_uniforms();
+        }
+        if let Some(status) = status {
+            let mut status = status.lock().unwrap();
+            status.output = Some(ctx.encoding);
+            status.present_mode = Some(ctx.config.present_mode);
+        }
+        let surface = (ctx.config.width, ctx.config.height);
+        let mut quads = Vec::new();
+        if display.show_frame_graph {
+            quads.extend(self.frame_graph.quads(surface));
+        }
+        if let Some(grid) = display.grid {
+            let color = display::background_color(grid.color, ctx.encoding);
+            quads.extend(grid.quads(ctx.image_rect(), surface, color));
+        }
+        if let Some(crosshair) = display.crosshair {
+            let color = display::background_color(crosshair.color, ctx.encoding);
+            quads.extend(crosshair.quads(ctx.image_rect(), surface, color));
+        }
+        if let Some(inset) = ctx.inset_rect() {
+            quads.extend(overlay::inset_quads(inset, ctx.visible(), surface));
+        }
+        let mut badges = Vec::new();
+        if self.state.is_frozen() {
+            badges.push("FROZEN".to_string());
+        }
+        // Shown on the picture itself so screenshots of the preview say what they simulate.
+        badges.extend(display.simulation_label());
+        if !badges.is_empty() {
+            quads.extend(overlay::badge_quads(&badges.join(" - "), surface));
+        }
+        if let Some(help) = &self.help {
+            quads.extend(overlay::info_quads(help, surface));
+        } else if display.show_info_overlay {
+            quads.extend(overlay::info_quads(&self.info, surface));
+        }
+        ctx.overlay.set_quads(&ctx.device, &ctx.queue, &quads);
+        let render_started = Instant::now();
+        if ctx.render().is_err() {
+            return;
+        }
+        let presented_at = Instant::now();
+        self.frame_graph.record_present(presented_at);
+        if counts_stats {
+            self.state.stats.count_rendered();
+            if let Some(received_at) = new_frame_received_at {
+                self.state.stats.record_latency(received_at, presented_at);
+            }
+        }
+        self.render_count += 1;
+        self.present_time += presented_at - render_started;
+        // Capture rate between the capture timestamps of the frames shown, so a backlog or a
+        // stall in rendering does not skew it.
+        if let Some(captured_at) = new_frame_captured_at {
+            self.frame_count += 1;
+            if self.frame_count == 1 {
+                self.fps_last = captured_at;
+            }
+            self.last_captured_at = captured_at;
+        }
+        let render_elapsed = self.render_last.elapsed();
+        if render_elapsed < FPS_UPDATE_INTERVAL {
+            return;
+        }
+        let span = self.last_captured_at.saturating_duration_since(self.fps_last);
+        if self.frame_count > 1 && !span.is_zero() {
+            self.capture_fps = (self.frame_count - 1) as f32 / span.as_secs_f32();
+            self.frame_count = 1;
+            self.fps_last = self.last_captured_at;
+        } else if self.fps_last.elapsed() >= FPS_UPDATE_INTERVAL {
+            self.capture_fps = 0.0;
+        }
+        let render_fps = self.render_count as f32 / render_elapsed.as_secs_f32();
+        let present_ms = self.present_time.as_secs_f32() * 1000.0 / self.render_count as f32;
+        if let Some(status) = status {
+            let mut status = status.lock().unwrap();
+            status.capture_fps = Some(self.capture_fps);
+            status.render_fps = Some(render_fps);
+            status.present_ms = Some(present_ms);
+        }
+        self.frame_graph.set_rates(self.capture_fps, render_fps);
+        self.info = vec![format!(
+            "{:.0} fps (render {:.0})",
+            self.capture_fps, render_fps
+        )];
+        let dropped = self.state.stats.counts().overwritten;
+        if dropped > 0 {
+            self.info.push(format!("dropped: {dropped}"));
+        }
+        if let Some(latency) = self.state.stats.latency(presented_at) {
+            let (min, avg, max) = (latency.min_ms, latency.avg_ms, latency.max_ms);
+            self.info
+                .push(format!("latency: {avg:.0} ms ({min:.0}-{max:.0})"));
+        }
+        if let Some((width, height)) = self.state.frame_size() {
+            self.info.push(format!("{width}×{height}"));
+        }
+        self.info.extend(self.state.source_title().filter(|t| !t.is_empty()));
+        self.info.extend(display.status_label());
+        // The window has no title bar; the title is for taskbars and window pickers.
+        let title = window_title(&self.state, self.clone_id);
+        self.window.set_title(&title);
+        self.render_count = 0;
+        self.present_time = Duration::ZERO;
+        self.render_last = presented_at;
+    }
+
+    /// Applies what the app side changed since the last call and requests a redraw when there
+    /// is something new to show.
+    fn update(&mut self, event_loop: &ActiveEventLoop, display: &DisplayOptions) {
+        let state = self.state.clone();
+        let pending_geometry = match self.clone_id {
+            Some(_) => None,
+            None => state.pending_geometry.lock().unwrap().take(),
+        };
+        if let Some(g) = pending_geometry {
+            self.window.set_outer_position(PhysicalPosition::new(g.x, g.y));
+            let _ = self.window.request_inner_size(PhysicalSize::new(g.width, g.height));
+        }
+        self.fit_to_new_resolution(display.pip);
+        let hidden = state.hidden.load(Ordering::Relaxed) || state.is_dismissed();
+        if hidden != self.hidden {
+            self.window.set_visible(!hidden);
+            self.hidden = hidden;
+            // Showing the window again resets its style (opacity)
+            self.window_options = None;
+        }
+        // No frames (and so no title updates) arrive while paused; mark it right away.
+        let (paused, frozen) = (state.is_paused(), state.is_frozen());
+        if (paused, frozen) != (self.paused, self.frozen) {
+            self.window.set_title(&window_title(&state, self.clone_id));
+            self.paused = paused;
+            self.frozen = frozen;
+            self.window.request_redraw();
+        }
+        self.apply_window_options(event_loop, display.window);
+        self.update_pip(display.pip);
+        let raise = self.clone_id.is_none() && state.raise_requested.swap(false, Ordering::Relaxed);
+        if raise && !self.hidden {
+            self.window.focus_window();
+        }
+        let display_changed = self.ctx.display != *display;
+        let has_frame = !frozen && state.has_frame_after(self.seen);
+        if display_changed || has_frame || self.ctx.blending() {
+            self.window.request_redraw();
+        }
+    }
+}
+
+impl PreviewApp {
+    /// The user closed window `key`. A clone just closes; the session's own window stops the
+    /// session with it, unless closing only hides the window (`close_hides_preview`).
+    fn close_requested(&mut self, key: (u32, Option<u32>)) {
+        let hide = self.display.lock().unwrap().close_hides_preview;
+        if let Some(preview) = self.windows.get(&key) {
+            if let Some(clone_id) = key.1 {
+                preview.state.remove_clone(clone_id);
+            } else if hide {
+                preview.state.set_dismissed(true);
+                return;
+            }
+            preview.state.running.store(false, Ordering::Relaxed);
+        }
+        self.close_window(key);
+    }
+
+    fn close_window(&mut self, key: (u32, Option<u32>)) {
+        let primary = self.windows.keys().next() == Some(&key);
+        if self.windows.remove(&key).is_some() && primary {
+            // The next window, if any, reports from its next update on.
+            let mut status = self.status.lock().unwrap();
+            status.output = None;
+            status.present_mode = None;
+            status.capture_fps = None;
+            status.render_fps = None;
+            status.present_ms = None;
+        }
+    }
 }
 
 impl ApplicationHandler for PreviewApp {
@@ -347,123 +2275,202 @@ impl ApplicationHandler for PreviewApp {
 
     fn window_event(
         &mut self,
-        _event_loop: &ActiveEventLoop,
-        _window_id: WindowId,
+        event_loop: &ActiveEventLoop,
+        window_id: WindowId,
         event: WindowEvent,
     ) {
+        if let WindowEvent::ModifiersChanged(modifiers) = &event {
+            self.modifiers = modifiers.state();
+            return;
+        }
+        let primary = self.windows.keys().next().copied();
+        let Some((&key, preview)) = self
+            .windows
+            .iter_mut()
+            .find(|(_, w)| w.window.id() == window_id)
+        else {
+            return;
+        };
         match event {
-            WindowEvent::CloseRequested => {
-                if let Some(state) = self.slot.lock().unwrap().as_ref() {
-                    state.running.store(false, Ordering::Relaxed);
-                    state.frame_available.notify_one();
-                }
-                self.window = None;
-                self.wgpu_context = None;
-            }
+            WindowEvent::CloseRequested => self.close_requested(key),
             WindowEvent::Resized(size) => {
-                if let Some(ref mut ctx) = self.wgpu_context {
-                    ctx.resize((size.width, size.height));
-                }
+                preview.ctx.resize((size.width, size.height));
+                preview.record_geometry();
             }
-            WindowEvent::RedrawRequested => {
-                let state = match self.slot.lock().unwrap().clone() {
-                    Some(s) => s,
-                    None => return,
-                };
-                if let (Some(ref window), Some(ref mut ctx)) =
-                    (&self.window, &mut self.wgpu_context)
-                {
-                    let mut has_new_frame = false;
-                    if let Ok(mut guard) = state.frame.try_lock() {
-                        if let Some(frame_data) = guard.take() {
-                            ctx.update_texture(
-                                frame_data.width,
-                                frame_data.height,
-                                &frame_data.buffer,
-                            );
-                            has_new_frame = true;
-                        }
+            WindowEvent::Moved(_) => preview.moved(),
+            WindowEvent::Occluded(occluded) => preview.occluded = occluded,
+            WindowEvent::KeyboardInput { event, .. } => {
+                preview.state.touch();
+                if event.state != ElementState::Pressed || event.repeat {
+                    return;
+                }
+                // Look up first: the handler may update settings, which replaces the bindings.
+                let action = self
+                    .keys
+                    .lock()
+                    .unwrap()
+                    .action_for(&event.logical_key, self.modifiers);
+                match action {
+                    // The zoom and the shortcut list are the window's own, not settings.
+                    Some(PreviewAction::ResetZoom) => preview.reset_zoom(),
+                    Some(PreviewAction::ZoomIn) => preview.zoom_centered(1.0),
+                    Some(PreviewAction::ZoomOut) => preview.zoom_centered(-1.0),
+                    Some(PreviewAction::ToggleZoomInset) => preview.toggle_inset(),
+                    Some(PreviewAction::ShowShortcuts) => {
+                        preview.help = match preview.help {
+                            Some(_) => None,
+                            None => Some(self.keys.lock().unwrap().describe()),
+                        };
+                        preview.window.request_redraw();
                     }
-                    if let Ok(()) = ctx.render() {
-                        if has_new_frame {
-                            self.frame_count += 1;
-                            if self.frame_count == 1 {
-                                self.fps_last = Instant::now();
-                            }
-                        }
-                        let elapsed = self.fps_last.elapsed();
-                        if elapsed >= FPS_UPDATE_INTERVAL && self.frame_count > 0 {
-                            let fps = self.frame_count as f64 / elapsed.as_secs_f64();
-                            window.set_title(&format!("LiteView Preview — {:.0} fps", fps));
-                            self.frame_count = 0;
-                            self.fps_last = Instant::now();
-                        }
+                    Some(PreviewAction::ClosePreview) => self.close_requested(key),
+                    // A clone keeps its own scale mode and fullscreen.
+                    Some(PreviewAction::CycleScaleMode) if preview.clone_id.is_some() => {
+                        let current = self.display.lock().unwrap().scale_mode;
+                        preview.scale_mode = Some(preview.scale_mode.unwrap_or(current).next());
+                        preview.window.request_redraw();
                     }
+                    Some(PreviewAction::ToggleFullscreen) if preview.clone_id.is_some() => {
+                        preview.toggle_clone_fullscreen(event_loop);
+                    }
+                    Some(action) => (self.on_action)(action),
+                    None => {}
                 }
             }
+            WindowEvent::MouseInput { state: pressed, button, .. } => {
+                preview.state.touch();
+                let placing = preview.window_options.is_some_and(|o| o.drag_to_place);
+                if let (true, ElementState::Pressed) = (placing, pressed) {
+                    let window = &preview.window;
+                    let _ = match button {
+                        MouseButton::Left => window.drag_window(),
+                        MouseButton::Right => window.drag_resize_window(ResizeDirection::SouthEast),
+                        _ => Ok(()),
+                    };
+                } else if button == MouseButton::Left {
+                    match pressed {
+                        ElementState::Pressed => preview.press_left(self.modifiers.alt_key()),
+                        ElementState::Released => preview.panning = false,
+                    }
+                }
+            }
+            WindowEvent::CursorMoved { position, .. } => preview.move_cursor(position),
+            WindowEvent::CursorLeft { .. } => {
+                preview.cursor = None;
+                preview.panning = false;
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                preview.state.touch();
+                let notches = match delta {
+                    MouseScrollDelta::LineDelta(_, y) => y,
+                    // One notch is 120 units on Windows.
+                    MouseScrollDelta::PixelDelta(p) => p.y as f32 / 120.0,
+                };
+                preview.zoom(notches);
+            }
+            WindowEvent::RedrawRequested => {
+                let display = preview.own_display(&self.display.lock().unwrap());
+                let status = (primary == Some(key)).then_some(&self.status);
+                preview.redraw(&display, status);
+            }
             _ => {}
         }
     }
 
     fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
-        let wait_duration = Duration::from_millis(16);
-        event_loop.set_control_flow(ControlFlow::WaitUntil(Instant::now() + wait_duration));
-
-        let state = match self.slot.lock().unwrap().clone() {
-            Some(s) => s,
-            None => {
-                self.window = None;
-                self.wgpu_context = None;
-                return;
-            }
-        };
-
-        if !state.running.load(Ordering::Relaxed) {
-            self.window = None;
-            self.wgpu_context = None;
-            return;
+        let sessions = self.slot.lock().unwrap().clone();
+        let idle = sessions.is_empty();
+        let ended: Vec<(u32, Option<u32>)> = self
+            .windows
+            .keys()
+            .filter(|(id, clone_id)| {
+                !sessions.get(id).is_some_and(|s| {
+                    s.running.load(Ordering::Relaxed)
+                        && clone_id.is_none_or(|c| s.clones().contains(&c))
+                })
+            })
+            .copied()
+            .collect();
+        for key in ended {
+            self.close_window(key);
         }
 
-        if self.window.is_none() {
-            if let Ok(mut guard) = state.frame.try_lock() {
-                if let Some(frame_data) = guard.take() {
-                    let attrs = WindowAttributes::default()
-                        .with_title("LiteView Preview")
-                        .with_inner_size(LogicalSize::new(frame_data.width, frame_data.height))
-                        .with_resizable(true)
-                        .with_decorations(false)
-                        .with_window_level(WindowLevel::AlwaysOnTop)
-                        .with_visible(true);
-
-                    if let Ok(window) = event_loop.create_window(attrs) {
-                        let window = Arc::new(window);
-                        #[cfg(target_os = "windows")]
-                        window.set_visible(true);
-                        let mut ctx = pollster::block_on(WgpuContext::new(window.clone()));
-                        ctx.update_texture(frame_data.width, frame_data.height, &frame_data.buffer);
-                        let _ = ctx.render();
-                        self.wgpu_context = Some(ctx);
-                        self.window = Some(window.clone());
-                        self.frame_count = 1;
-                        self.fps_last = Instant::now();
-                        window.request_redraw();
-                    }
-                }
+        let display = self.display.lock().unwrap().clone();
+        for (id, state) in sessions {
+            if !state.running.load(Ordering::Relaxed) {
+                continue;
             }
-        } else {
-            if let Ok(guard) = state.frame.try_lock() {
-                if guard.is_some() {
-                    if let Some(ref window) = self.window {
-                        window.request_redraw();
-                    }
+            let wanted = std::iter::once(None).chain(state.clones().into_iter().map(Some));
+            for clone_id in wanted {
+                let key = (id, clone_id);
+                if let Some(preview) = self.windows.get_mut(&key) {
+                    let display = preview.own_display(&display);
+                    preview.update(event_loop, &display);
+                    continue;
                 }
+                // Clones open next to the session's own window, so they wait for it.
+                let near = match clone_id {
+                    Some(_) => match self.windows.get(&(id, None)) {
+                        Some(own) => window_geometry(&own.window),
+                        None => continue,
+                    },
+                    None => None,
+                };
+                let Some(preview) =
+                    PreviewWindow::open(event_loop, state.clone(), &display, clone_id, near)
+                else {
+                    continue;
+                };
+                let mut status = self.status.lock().unwrap();
+                if self.windows.keys().next().is_none_or(|&first| key < first) {
+                    status.output = Some(preview.ctx.encoding);
+                    status.present_mode = Some(preview.ctx.config.present_mode);
+                }
+                status.adapter = Some(preview.ctx.adapter_label.clone());
+                drop(status);
+                self.windows.insert(key, preview);
             }
+            // Before its first window opens, the session's frames are needed to open it.
+            let range = (id, None)..=(id, Some(u32::MAX));
+            let mut windows = self.windows.range(range).map(|(_, w)| w).peekable();
+            let unseen = windows.peek().is_some() && windows.all(|w| !w.is_visible());
+            state.unseen.store(unseen, Ordering::Relaxed);
         }
+
+        // Without sessions, only a wake (a session starting) has anything to do.
+        let interval = if self.windows.values().any(PreviewWindow::animating) {
+            Some(ANIMATION_INTERVAL)
+        } else {
+            (!idle).then_some(HOUSEKEEPING_INTERVAL)
+        };
+        event_loop.set_control_flow(match interval {
+            Some(interval) => ControlFlow::WaitUntil(Instant::now() + interval),
+            None => ControlFlow::Wait,
+        });
     }
 }
 
-/// Runs the preview event loop forever. Reads current preview state from `slot`; never exits (so the event loop can be created once per process).
-pub fn run_preview_window(slot: PreviewStateSlot) {
+/// The running preview event loop, for `wake`.
+static WAKER: Mutex<Option<EventLoopProxy<()>>> = Mutex::new(None);
+
+/// Wakes the preview thread to act on a new frame or changed state now rather than on its next
+/// housekeeping pass.
+pub fn wake() {
+    if let Some(proxy) = WAKER.lock().unwrap().as_ref() {
+        let _ = proxy.send_event(());
+    }
+}
+
+/// Runs the preview event loop forever, with one window per session in `slot`; never exits (so the event loop can be created once per process).
+/// `display` and `keys` hold live display options and shortcuts, `status` receives renderer
+/// state; `on_action` is called on the preview thread for actions triggered inside the window.
+pub fn run_preview_window(
+    slot: PreviewStateSlot,
+    display: DisplayOptionsSlot,
+    keys: KeybindingsSlot,
+    status: RenderStatusSlot,
+    on_action: PreviewActionHandler,
+) {
     let mut event_loop_builder = winit::event_loop::EventLoop::builder();
 
     #[cfg(target_os = "linux")]
@@ -491,11 +2498,349 @@ pub fn run_preview_window(slot: PreviewStateSlot) {
 
     let mut app = PreviewApp {
         slot,
-        window: None,
-        wgpu_context: None,
-        frame_count: 0,
-        fps_last: Instant::now(),
+        display,
+        keys,
+        modifiers: ModifiersState::default(),
+        status,
+        on_action,
+        windows: BTreeMap::new(),
     };
 
+    *WAKER.lock().unwrap() = Some(event_loop.create_proxy());
     let _ = event_loop.run_app(&mut app);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIZE: u32 = 4;
+
+    /// The preview pipeline on the first adapter found, rendering into offscreen targets.
+    struct Offscreen {
+        device: Device,
+        queue: Queue,
+        bind_group_layout: wgpu::BindGroupLayout,
+        pipeline_layout: wgpu::PipelineLayout,
+        shader: wgpu::ShaderModule,
+    }
+
+    impl Offscreen {
+        /// None without a usable adapter, e.g. on a machine without any GPU driver.
+        fn new() -> Option<Self> {
+            let instance = Instance::new(InstanceDescriptor {
+                backends: Backends::all(),
+                ..Default::default()
+            });
+            let adapter = pollster::block_on(instance.request_adapter(&Default::default()))?;
+            let descriptor = DeviceDescriptor {
+                required_limits: adapter.limits(),
+                ..Default::default()
+            };
+            let (device, queue) =
+                pollster::block_on(adapter.request_device(&descriptor, None)).ok()?;
+            let bind_group_layout = create_bind_group_layout(&device);
+            let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: None,
+                bind_group_layouts: &[&bind_group_layout],
+                push_constant_ranges: &[],
+            });
+            let shader = create_shader(&device);
+            Some(Self {
+                device,
+                queue,
+                bind_group_layout,
+                pipeline_layout,
+                shader,
+            })
+        }
+
+        /// Renders a frame of gray `value` bytes into a `format` target and reads back a pixel
+        /// of it, as R, G, B, A bytes.
+        fn render_gray(
+            &self,
+            value: u8,
+            format: TextureFormat,
+            encoding: OutputEncoding,
+        ) -> [u8; 4] {
+            let (device, queue) = (&self.device, &self.queue);
+            let extent = wgpu::Extent3d {
+                width: SIZE,
+                height: SIZE,
+                depth_or_array_layers: 1,
+            };
+            let texture = |format, usage| {
+                device.create_texture(&TextureDescriptor {
+                    label: None,
+                    size: extent,
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: TextureDimension::D2,
+                    format,
+                    usage,
+                    view_formats: &[],
+                })
+            };
+            let frame = texture(
+                TextureFormat::Bgra8UnormSrgb,
+                TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+            );
+            let pixels = vec![u32::from_le_bytes([value, value, value, 0]); (SIZE * SIZE) as usize];
+            queue.write_texture(
+                frame.as_image_copy(),
+                bytemuck::cast_slice(&pixels),
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(4 * SIZE),
+                    rows_per_image: Some(SIZE),
+                },
+                extent,
+            );
+            let frame_view = frame.create_view(&Default::default());
+
+            let uniforms = Uniforms::new(
+                &DisplayOptions::default(),
+                encoding,
+                (SIZE, SIZE),
+                (SIZE, SIZE),
+                0.0,
+                1.0,
+                &ZoomView::default(),
+            );
+            let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                label: None,
+                size: std::mem::size_of::<Uniforms>() as u64,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+            queue.write_buffer(&uniform_buffer, 0, bytemuck::bytes_of(&uniforms));
+            let sampler = device.create_sampler(&Default::default());
+            let bind_group = create_bind_group(
+                device,
+                &self.bind_group_layout,
+                &frame_view,
+                &frame_view,
+                &sampler,
+                &uniform_buffer,
+            );
+            let pipeline =
+                create_render_pipeline(device, &self.pipeline_layout, &self.shader, format);
+
+            let target = texture(
+                format,
+                TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC,
+            );
+            let target_view = target.create_view(&Default::default());
+            let row_bytes = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+            let readback = device.create_buffer(&wgpu::BufferDescriptor {
+                label: None,
+                size: (row_bytes * SIZE) as u64,
+                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                mapped_at_creation: false,
+            });
+            let mut encoder = device.create_command_encoder(&Default::default());
+            {
+                let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: None,
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: &target_view,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                            store: wgpu::StoreOp::Store,
+                        },
+                    })],
+                    depth_stencil_attachment: None,
+                    timestamp_writes: None,
+                    occlusion_query_set: None,
+                });
+                pass.set_pipeline(&pipeline);
+                pass.set_bind_group(0, &bind_group, &[]);
+                pass.draw(0..6, 0..1);
+            }
+            encoder.copy_texture_to_buffer(
+                target.as_image_copy(),
+                wgpu::ImageCopyBuffer {
+                    buffer: &readback,
+                    layout: wgpu::ImageDataLayout {
+                        offset: 0,
+                        bytes_per_row: Some(row_bytes),
+                        rows_per_image: Some(SIZE),
+                    },
+                },
+                extent,
+            );
+            queue.submit(std::iter::once(encoder.finish()));
+
+            let slice = readback.slice(..);
+            slice.map_async(wgpu::MapMode::Read, |result| result.unwrap());
+            device.poll(wgpu::Maintain::Wait);
+            let data = slice.get_mapped_range();
+            let center = (SIZE / 2 * row_bytes + SIZE / 2 * 4) as usize;
+            data[center..center + 4].try_into().unwrap()
+        }
+    }
+
+    impl Offscreen {
+        /// Uploads `height` rows of `width` pixels, `stride` bytes apart, the way
+        /// `update_texture` does, and reads the texture back as tightly packed rows.
+        fn upload(&self, (width, height): (u32, u32), stride: u32, bytes: &[u8]) -> Vec<u8> {
+            let (device, queue) = (&self.device, &self.queue);
+            let extent = wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            };
+            let texture = device.create_texture(&TextureDescriptor {
+                label: None,
+                size: extent,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format: PixelFormat::Bgrx.texture_format(),
+                usage: TextureUsages::COPY_DST | TextureUsages::COPY_SRC,
+                view_formats: &[],
+            });
+            let layout = wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(stride),
+                rows_per_image: Some(height),
+            };
+            queue.write_texture(texture.as_image_copy(), bytes, layout, extent);
+            // Buffer copies, unlike write_texture, need 256-byte aligned rows.
+            let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+            let row_bytes = (width * 4).div_ceil(align) * align;
+            let readback = device.create_buffer(&wgpu::BufferDescriptor {
+                label: None,
+                size: (row_bytes * height) as u64,
+                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                mapped_at_creation: false,
+            });
+            let mut encoder = device.create_command_encoder(&Default::default());
+            encoder.copy_texture_to_buffer(
+                texture.as_image_copy(),
+                wgpu::ImageCopyBuffer {
+                    buffer: &readback,
+                    layout: wgpu::ImageDataLayout {
+                        offset: 0,
+                        bytes_per_row: Some(row_bytes),
+                        rows_per_image: Some(height),
+                    },
+                },
+                extent,
+            );
+            queue.submit(std::iter::once(encoder.finish()));
+            let slice = readback.slice(..);
+            slice.map_async(wgpu::MapMode::Read, |result| result.unwrap());
+            device.poll(wgpu::Maintain::Wait);
+            let data = slice.get_mapped_range();
+            data.chunks(row_bytes as usize)
+                .flat_map(|row| &row[..width as usize * 4])
+                .copied()
+                .collect()
+        }
+    }
+
+    /// Widths whose rows are 4 bytes, just under 256 and just over 1024: a pixel, 63 and 257.
+    const WIDTHS: [u32; 3] = [1, 63, 257];
+    const HEIGHT: u32 = 5;
+
+    /// `HEIGHT` rows of `width` distinct pixels, `stride` bytes apart, padded with 0xEE except
+    /// after the last row; and the same rows packed.
+    fn padded_rows(width: u32, stride: u32) -> (Vec<u8>, Vec<u8>) {
+        let pixel = |x: u32, y: u32| [x as u8, (x >> 8) as u8 ^ 0x40, y as u8, 0xFF];
+        let row = |y| (0..width).flat_map(move |x| pixel(x, y));
+        let packed: Vec<u8> = (0..HEIGHT).flat_map(row).collect();
+        let mut bytes = Vec::new();
+        for y in 0..HEIGHT {
+            bytes.extend(row(y));
+            if y + 1 < HEIGHT {
+                bytes.resize(bytes.len() + (stride - width * 4) as usize, 0xEE);
+            }
+        }
+        (bytes, packed)
+    }
+
+    /// Strides of `width`: tight, and padded to 64 bytes (Windows' capture) and to 256.
+    fn strides(width: u32) -> [u32; 3] {
+        let row = width * 4;
+        [row, row.div_ceil(64) * 64, row.div_ceil(256) * 256]
+    }
+
+    #[test]
+    fn rows_fit_at_odd_widths_with_any_padding() {
+        for width in WIDTHS {
+            for stride in strides(width) {
+                let (bytes, _) = padded_rows(width, stride);
+                let len = bytes.len();
+                assert!(rows_fit(width, HEIGHT, stride, len), "{width} at {stride}");
+                // Padding after the last row is welcome too.
+                assert!(rows_fit(width, HEIGHT, stride, len + 60));
+                assert!(!rows_fit(width, HEIGHT, stride, len - 1));
+            }
+            assert!(!rows_fit(width, HEIGHT, width * 4 - 4, 1 << 20));
+        }
+        assert!(!rows_fit(0, HEIGHT, 64, 1 << 20));
+        assert!(!rows_fit(63, 0, 256, 1 << 20));
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter; run with --ignored"]
+    fn padded_rows_upload_intact_at_odd_widths() {
+        let gpu = Offscreen::new().expect("no GPU adapter");
+        for width in WIDTHS {
+            for stride in strides(width) {
+                let (bytes, packed) = padded_rows(width, stride);
+                let out = gpu.upload((width, HEIGHT), stride, &bytes);
+                assert!(out == packed, "width {width}, stride {stride}");
+            }
+        }
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter; run with --ignored"]
+    fn gray_levels_round_trip_on_srgb_and_non_srgb_targets() {
+        let gpu = Offscreen::new().expect("no GPU adapter");
+        let targets = [
+            (TextureFormat::Rgba8UnormSrgb, OutputEncoding::Srgb),
+            (TextureFormat::Rgba8Unorm, OutputEncoding::SrgbInShader),
+        ];
+        for (format, encoding) in targets {
+            for value in [0, 1, 10, 50, 128, 188, 250, 255] {
+                let out = gpu.render_gray(value, format, encoding);
+                assert!(
+                    out[..3].iter().all(|c| c.abs_diff(value) <= 1),
+                    "{format:?}: {value} came out as {out:?}"
+                );
+            }
+        }
+    }
+
+    /// A one-pixel frame of `value`.
+    fn pixel_frame(value: u32) -> FrameData {
+        let now = Instant::now();
+        FrameData {
+            width: 1,
+            height: 1,
+            display_size: (1, 1),
+            stride: 4,
+            format: PixelFormat::Bgrx,
+            bytes: FrameBytes::Packed(Arc::new(vec![value])),
+            captured_at: now,
+            received_at: now,
+            corner_radius: 0.0,
+        }
+    }
+
+    #[test]
+    fn freezing_a_delayed_preview_keeps_the_frame_it_shows() {
+        let state = PreviewState::default();
+        state.set_latest_frame(pixel_frame(1));
+        state.publish_frame(pixel_frame(1));
+        // The capture thread is ahead of the window by the delay.
+        state.set_latest_frame(pixel_frame(2));
+        state.set_frozen(true);
+        state.publish_frame(pixel_frame(2));
+        assert_eq!(*state.latest_frame().unwrap().2, [1]);
+    }
+}
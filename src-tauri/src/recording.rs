@@ -0,0 +1,459 @@
+use crate::{crop, CaptureState};
+use bytes::Bytes;
+use openh264::encoder::{BitRate, Encoder, EncoderConfig, FrameRate};
+use openh264::formats::{RgbSliceU8, YUVBuffer};
+use openh264::OpenH264API;
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// Sample times in the MP4 are in 1/90000 s, the usual video timescale.
+const TIMESCALE: u32 = 90_000;
+/// Frames queued for the encoder; newer frames are dropped while it is behind, and the
+/// previous frame simply lasts longer in the file.
+const QUEUE_LEN: usize = 2;
+/// Target bitrate per pixel per frame: about 6 Mbit/s for 1080p at 30 fps.
+const BITS_PER_PIXEL: f64 = 0.1;
+
+pub struct RecordedFrame {
+    pub width: u32,
+    pub height: u32,
+    pub buffer: Arc<Vec<u32>>,
+    pub captured_at: Instant,
+}
+
+/// A running recording; dropping it finishes the file.
+pub struct Recording {
+    frames: SyncSender<RecordedFrame>,
+    /// Session whose frames are recorded.
+    session: u32,
+}
+
+impl Recording {
+    pub fn session(&self) -> u32 {
+        self.session
+    }
+}
+
+/// Payload of "recording-finished".
+#[derive(Debug, Clone, Serialize)]
+struct RecordingFinished {
+    path: String,
+    duration_secs: f64,
+}
+
+/// Payload of "recording-error".
+#[derive(Debug, Clone, Serialize)]
+struct RecordingError {
+    path: String,
+    message: String,
+}
+
+/// Whether session `session` is being recorded.
+pub fn is_recording(state: &CaptureState, session: u32) -> bool {
+    let recording = state.recording.lock().unwrap();
+    recording.as_ref().is_some_and(|r| r.session == session)
+}
+
+/// Hands a frame of session `session` to the recording, if it is the one being recorded.
+/// Called by the capture thread for every frame it passes to the preview.
+pub fn push_frame(
+    state: &CaptureState,
+    session: u32,
+    width: u32,
+    height: u32,
+    buffer: &Arc<Vec<u32>>,
+    captured_at: Instant,
+) {
+    let mut recording = state.recording.lock().unwrap();
+    let Some(r) = recording.as_ref().filter(|r| r.session == session) else {
+        return;
+    };
+    let frame = RecordedFrame {
+        width,
+        height,
+        buffer: buffer.clone(),
+        captured_at,
+    };
+    if let Err(TrySendError::Disconnected(_)) = r.frames.try_send(frame) {
+        // The encoder stopped on an error and has reported it.
+        recording.take();
+    }
+}
+
+/// Splits an Annex B byte stream into NAL units (without start codes).
+fn nal_units(stream: &[u8]) -> Vec<&[u8]> {
+    let mut starts = Vec::new();
+    let mut i = 0;
+    while i + 3 <= stream.len() {
+        if stream[i..i + 3] == [0, 0, 1] {
+            starts.push(i + 3);
+            i += 3;
+        } else {
+            i += 1;
+        }
+    }
+    starts
+        .iter()
+        .enumerate()
+        .map(|(n, &start)| {
+            let mut end = starts.get(n + 1).map_or(stream.len(), |&next| next - 3);
+            // A four-byte start code leaves a zero byte behind.
+            while end > start && stream[end - 1] == 0 {
+                end -= 1;
+            }
+            &stream[start..end]
+        })
+        .filter(|nal| !nal.is_empty())
+        .collect()
+}
+
+/// An encoded frame waiting for the next one, which decides how long it is shown.
+struct PendingSample {
+    bytes: Vec<u8>,
+    is_sync: bool,
+    captured_at: Instant,
+}
+
+struct Mp4Output {
+    writer: mp4::Mp4Writer<BufWriter<File>>,
+    track_added: bool,
+    width: u32,
+    height: u32,
+    encoder: Encoder,
+    pending: Option<PendingSample>,
+    first_at: Option<Instant>,
+    last_at: Option<Instant>,
+    frame_interval: Duration,
+}
+
+impl Mp4Output {
+    fn create(path: &Path, width: u32, height: u32, fps: u32) -> Result<Self, String> {
+        let file = File::create(path).map_err(|e| e.to_string())?;
+        let config = mp4::Mp4Config {
+            major_brand: "isom".parse().map_err(|e: mp4::Error| e.to_string())?,
+            minor_version: 512,
+            compatible_brands: ["isom", "iso2", "avc1", "mp41"]
+                .iter()
+                .map(|b| b.parse::<mp4::FourCC>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| e.to_string())?,
+            timescale: 1000,
+        };
+        let writer = mp4::Mp4Writer::write_start(BufWriter::new(file), &config)
+            .map_err(|e| e.to_string())?;
+        let bitrate = (width as f64 * height as f64 * fps as f64 * BITS_PER_PIXEL) as u32;
+        let encoder_config = EncoderConfig::new()
+            .bitrate(BitRate::from_bps(bitrate))
+            .max_frame_rate(FrameRate::from_hz(fps as f32));
+        let encoder = Encoder::with_api_config(OpenH264API::from_source(), encoder_config)
+            .map_err(|e| e.to_string())?;
+        Ok(Self {
+            writer,
+            track_added: false,
+            width,
+            height,
+            encoder,
+            pending: None,
+            first_at: None,
+            last_at: None,
+            frame_interval: Duration::from_secs(1) / fps.max(1),
+        })
+    }
+
+    /// Encodes a frame of exactly `self.width` × `self.height`.
+    fn encode(&mut self, buffer: &[u32], captured_at: Instant) -> Result<(), String> {
+        let rgb: Vec<u8> = buffer
+            .iter()
+            .flat_map(|&p| [(p >> 16) as u8, (p >> 8) as u8, p as u8])
+            .collect();
+        let size = (self.width as usize, self.height as usize);
+        let yuv = YUVBuffer::from_rgb_source(RgbSliceU8::new(&rgb, size));
+        let stream = self
+            .encoder
+            .encode(&yuv)
+            .map_err(|e| e.to_string())?
+            .to_vec();
+
+        let mut sps = None;
+        let mut pps = None;
+        let mut bytes = Vec::with_capacity(stream.len());
+        let mut is_sync = false;
+        for nal in nal_units(&stream) {
+            match nal[0] & 0x1F {
+                7 => sps = Some(nal.to_vec()),
+                8 => pps = Some(nal.to_vec()),
+                nal_type => {
+                    is_sync |= nal_type == 5;
+                    // MP4 stores NAL units with a length prefix instead of start codes.
+                    bytes.extend_from_slice(&(nal.len() as u32).to_be_bytes());
+                    bytes.extend_from_slice(nal);
+                }
+            }
+        }
+        if !self.track_added {
+            let (Some(seq_param_set), Some(pic_param_set)) = (sps, pps) else {
+                // Nothing is shown before the first key frame.
+                return Ok(());
+            };
+            self.writer
+                .add_track(&mp4::TrackConfig {
+                    track_type: mp4::TrackType::Video,
+                    timescale: TIMESCALE,
+                    language: "und".to_string(),
+                    media_conf: mp4::MediaConfig::AvcConfig(mp4::AvcConfig {
+                        width: self.width as u16,
+                        height: self.height as u16,
+                        seq_param_set,
+                        pic_param_set,
+                    }),
+                })
+                .map_err(|e| e.to_string())?;
+            self.track_added = true;
+        }
+        if bytes.is_empty() {
+            // The encoder skipped the frame (rate control).
+            return Ok(());
+        }
+        self.first_at.get_or_insert(captured_at);
+        if let Some(pending) = self.pending.take() {
+            self.write_sample(pending, captured_at)?;
+        }
+        self.pending = Some(PendingSample {
+            bytes,
+            is_sync,
+            captured_at,
+        });
+        Ok(())
+    }
+
+    /// Encodes `frame`, dropping the odd last row or column `even_size` leaves out.
+    fn encode_frame(&mut self, frame: &RecordedFrame) -> Result<(), String> {
+        if (self.width, self.height) == (frame.width, frame.height) {
+            self.encode(&frame.buffer, frame.captured_at)
+        } else {
+            let rect = (0, 0, self.width, self.height);
+            let even = crop::crop_buffer(&frame.buffer, frame.width, rect);
+            self.encode(&even, frame.captured_at)
+        }
+    }
+
+    /// Writes `sample`, shown until `until`.
+    fn write_sample(&mut self, sample: PendingSample, until: Instant) -> Result<(), String> {
+        let shown = until.saturating_duration_since(sample.captured_at);
+        let duration = ((shown.as_secs_f64() * TIMESCALE as f64).round() as u32).max(1);
+        let start_time = self.first_at.map_or(0.0, |first| {
+            sample.captured_at.duration_since(first).as_secs_f64()
+        }) * TIMESCALE as f64;
+        self.writer
+            .write_sample(
+                1,
+                &mp4::Mp4Sample {
+                    start_time: start_time as u64,
+                    duration,
+                    rendering_offset: 0,
+                    is_sync: sample.is_sync,
+                    bytes: Bytes::from(sample.bytes),
+                },
+            )
+            .map_err(|e| e.to_string())?;
+        self.last_at = Some(until);
+        Ok(())
+    }
+
+    /// Writes the last frame and the index; returns the recorded duration.
+    fn finish(mut self) -> Result<Duration, String> {
+        if let Some(pending) = self.pending.take() {
+            let until = pending.captured_at + self.frame_interval;
+            self.write_sample(pending, until)?;
+        }
+        self.writer.write_end().map_err(|e| e.to_string())?;
+        let mut file = self.writer.into_writer();
+        std::io::Write::flush(&mut file).map_err(|e| e.to_string())?;
+        let file = file.into_inner().map_err(|e| e.to_string())?;
+        file.sync_all().map_err(|e| e.to_string())?;
+        Ok(match (self.first_at, self.last_at) {
+            (Some(first), Some(last)) => last.saturating_duration_since(first),
+            _ => Duration::ZERO,
+        })
+    }
+}
+
+/// Frames are encoded at an even size (H.264 4:2:0 needs it), dropping an odd last row or
+/// column.
+fn even_size(width: u32, height: u32) -> (u32, u32) {
+    (width & !1, height & !1)
+}
+
+/// Encodes `frames`, which must all have the size of the first, into a new MP4 file at `path`
+/// and returns its duration. A failed file is removed again.
+pub fn write_mp4(path: &Path, fps: u32, frames: &[RecordedFrame]) -> Result<Duration, String> {
+    let first = frames.first().ok_or("There are no frames to write.")?;
+    let (width, height) = even_size(first.width, first.height);
+    if width == 0 || height == 0 {
+        return Err("The frames are too small to encode.".to_string());
+    }
+    let result = Mp4Output::create(path, width, height, fps).and_then(|mut out| {
+        for frame in frames {
+            out.encode_frame(frame)?;
+        }
+        out.finish()
+    });
+    if result.is_err() {
+        let _ = fs::remove_file(path);
+    }
+    result
+}
+
+/// Encoder thread: writes frames until the recording is dropped or an error stops it.
+fn run_encoder(app: AppHandle, path: PathBuf, fps: u32, frames: Receiver<RecordedFrame>) {
+    let path_str = path.to_string_lossy().into_owned();
+    let report_error = |message: String| {
+        let _ = app.emit(
+            "recording-error",
+            RecordingError {
+                path: path_str.clone(),
+                message,
+            },
+        );
+    };
+
+    let mut output: Option<Mp4Output> = None;
+    let mut size_changed = None;
+    for frame in frames.iter() {
+        let (width, height) = even_size(frame.width, frame.height);
+        if width == 0 || height == 0 {
+            continue;
+        }
+        if output
+            .as_ref()
+            .is_some_and(|out| (out.width, out.height) != (width, height))
+        {
+            size_changed = Some((width, height));
+            break;
+        }
+        if output.is_none() {
+            match Mp4Output::create(&path, width, height, fps) {
+                Ok(out) => output = Some(out),
+                Err(e) => {
+                    report_error(format!("Cannot start recording: {e}"));
+                    return;
+                }
+            }
+        }
+        let Some(out) = output.as_mut() else {
+            continue;
+        };
+        if let Err(e) = out.encode_frame(&frame) {
+            // Usually a full disk; a partial file without an index is unplayable.
+            drop(output);
+            let _ = fs::remove_file(&path);
+            report_error(format!("Recording failed and was discarded: {e}"));
+            return;
+        }
+    }
+    // Dropping the receiver makes push_frame clear the recording.
+    drop(frames);
+
+    let Some(out) = output else {
+        report_error("No frames were recorded.".to_string());
+        return;
+    };
+    match out.finish() {
+        Ok(duration) => {
+            if let Some((w, h)) = size_changed {
+                report_error(format!(
+                    "The frame size changed to {w}×{h}; the recording was stopped and saved."
+                ));
+            }
+            let _ = app.emit(
+                "recording-finished",
+                RecordingFinished {
+                    path: path_str.clone(),
+                    duration_secs: duration.as_secs_f64(),
+                },
+            );
+        }
+        Err(e) => {
+            let _ = fs::remove_file(&path);
+            report_error(format!("Recording failed and was discarded: {e}"));
+        }
+    }
+}
+
+/// Starts recording the primary session to an MP4 file (H.264) at `path`. Frames are the ones
+/// the preview gets, so recording works with the preview window closed or hidden.
+#[tauri::command]
+pub fn start_recording(app: AppHandle, path: String) -> Result<(), String> {
+    let state = app.state::<CaptureState>();
+    let Some(session) = state.primary_session().map(|p| p.id) else {
+        return Err("Start a capture first.".to_string());
+    };
+    let mut recording = state.recording.lock().unwrap();
+    if recording.is_some() {
+        return Err("Already recording.".to_string());
+    }
+    let path = PathBuf::from(path);
+    if path.file_name().is_none() {
+        return Err("Choose a file to record to.".to_string());
+    }
+    let fps = state.settings.lock().unwrap().fps;
+    let (frames, receiver) = mpsc::sync_channel(QUEUE_LEN);
+    let app_handle = app.clone();
+    thread::spawn(move || run_encoder(app_handle, path, fps, receiver));
+    *recording = Some(Recording { frames, session });
+    Ok(())
+}
+
+/// Stops the recording; "recording-finished" follows once the file is complete.
+#[tauri::command]
+pub fn stop_recording(state: State<CaptureState>) -> Result<(), String> {
+    match state.recording.lock().unwrap().take() {
+        Some(_) => Ok(()),
+        None => Err("Not recording.".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_at_three_and_four_byte_start_codes() {
+        let stream = [
+            0, 0, 1, 0x67, 0x42, 0, 0, 0, 1, 0x68, 0xCE, 0, 0, 1, 0x65, 0x88,
+        ];
+        let nals = nal_units(&stream);
+        assert_eq!(nals, [&[0x67, 0x42][..], &[0x68, 0xCE], &[0x65, 0x88]]);
+    }
+
+    #[test]
+    fn the_zero_before_a_four_byte_start_code_is_not_kept() {
+        let stream = [0, 0, 0, 1, 0x67, 0x42, 0, 0, 0, 1, 0x68];
+        assert_eq!(nal_units(&stream), [&[0x67, 0x42][..], &[0x68]]);
+    }
+
+    #[test]
+    fn empty_nal_units_are_dropped() {
+        // Back-to-back start codes, and one at the very end.
+        let stream = [0, 0, 1, 0, 0, 0, 1, 0x65, 0x01, 0, 0, 1];
+        assert_eq!(nal_units(&stream), [&[0x65, 0x01][..]]);
+        assert!(nal_units(&[0, 0, 1]).is_empty());
+        assert!(nal_units(&[0x65, 0x01, 0x02]).is_empty());
+        assert!(nal_units(&[]).is_empty());
+    }
+
+    #[test]
+    fn even_size_drops_an_odd_row_or_column() {
+        assert_eq!(even_size(1920, 1080), (1920, 1080));
+        assert_eq!(even_size(1921, 1081), (1920, 1080));
+        assert_eq!(even_size(2, 3), (2, 2));
+        assert_eq!(even_size(1, 1), (0, 0));
+    }
+}
@@ -0,0 +1,163 @@
+use crate::error_log::{self, Level};
+use crate::{
+    apps, persist, start_capture_session, store_settings, target_id, target_title, test_pattern,
+    validate_settings, CaptureSettings, CaptureState, SessionOptions,
+};
+use scap::get_all_targets;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_notification::NotificationExt;
+
+const MARKER_FILENAME: &str = "session.json";
+
+/// Written while a capture runs and removed when it stops cleanly, so a marker found at launch
+/// means LiteView died mid-capture. It carries its own copy of the settings because the
+/// settings file is written with a delay and may not have caught up.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionMarker {
+    settings: CaptureSettings,
+    /// Unix seconds.
+    started_at: u64,
+}
+
+/// What the frontend shows when offering to resume.
+#[derive(Debug, Clone, Serialize)]
+pub struct ResumableSession {
+    target_title: Option<String>,
+    started_at: u64,
+}
+
+fn marker_path(app: &AppHandle) -> Option<PathBuf> {
+    app.path()
+        .app_data_dir()
+        .ok()
+        .map(|p| p.join(MARKER_FILENAME))
+}
+
+/// Records that a capture with `settings` is running. Written synchronously and atomically:
+/// a kill at any point leaves either no marker or a complete one.
+pub fn write_marker(app: &AppHandle, settings: &CaptureSettings) {
+    let Some(path) = marker_path(app) else {
+        return;
+    };
+    let marker = SessionMarker {
+        settings: settings.clone(),
+        started_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+    };
+    let Ok(contents) = serde_json::to_string(&marker) else {
+        return;
+    };
+    match persist::write_atomic(&path, contents.as_bytes()) {
+        Ok(()) => *app.state::<CaptureState>().session_marker.lock().unwrap() = Some(path),
+        Err(e) => error_log::record(app, Level::Warning, format!("Session marker: {e}")),
+    }
+}
+
+/// Removes the marker after a clean stop or exit.
+pub fn clear_marker(state: &CaptureState) {
+    if let Some(path) = state.session_marker.lock().unwrap().take() {
+        let _ = fs::remove_file(path);
+    }
+}
+
+/// Reads and deletes the marker of a session that did not end cleanly.
+fn take_marker(app: &AppHandle) -> Option<SessionMarker> {
+    let path = marker_path(app)?;
+    let contents = fs::read_to_string(&path).ok()?;
+    let _ = fs::remove_file(&path);
+    serde_json::from_str(&contents).ok()
+}
+
+/// At launch: keeps a crashed session for the frontend to offer resuming it, and points the
+/// user there with a notification. `offer` is false when the launch starts capturing anyway
+/// (--headless, --layout, autostart); the marker is dropped then.
+pub fn check_previous_session(app: &AppHandle, offer: bool) {
+    let Some(marker) = take_marker(app) else {
+        return;
+    };
+    if !offer {
+        return;
+    }
+    let what = marker
+        .settings
+        .target_title
+        .as_deref()
+        .map(|t| format!(" \"{t}\""))
+        .unwrap_or_default();
+    let _ = app
+        .notification()
+        .builder()
+        .title("LiteView")
+        .body(format!(
+            "LiteView closed unexpectedly while capturing{what}. Open LiteView to resume."
+        ))
+        .show();
+    *app.state::<CaptureState>()
+        .resumable_session
+        .lock()
+        .unwrap() = Some(marker);
+}
+
+#[tauri::command]
+pub fn get_resumable_session(state: State<CaptureState>) -> Option<ResumableSession> {
+    state
+        .resumable_session
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|m| ResumableSession {
+            target_title: m.settings.target_title.clone(),
+            started_at: m.started_at,
+        })
+}
+
+#[tauri::command]
+pub fn dismiss_resumable_session(state: State<CaptureState>) {
+    state.resumable_session.lock().unwrap().take();
+}
+
+/// Restores the crashed session's settings and starts capturing its target again. Ok(false)
+/// means the target is gone (settings are restored, but nothing is started) so the frontend
+/// can let the user pick another one.
+#[tauri::command]
+pub fn resume_session(app: AppHandle) -> Result<bool, String> {
+    let marker = app
+        .state::<CaptureState>()
+        .resumable_session
+        .lock()
+        .unwrap()
+        .take()
+        .ok_or("There is no session to resume.")?;
+    let mut settings = marker.settings;
+    let targets = get_all_targets();
+    let found = settings
+        .target_id
+        .filter(|&id| id == test_pattern::TARGET_ID || targets.iter().any(|t| target_id(t) == id))
+        .or_else(|| {
+            let title = settings.target_title.as_deref()?;
+            targets
+                .iter()
+                .find(|t| target_title(t) == title)
+                .map(target_id)
+        });
+    let missing = match settings.target_pid {
+        Some(pid) => apps::window_ids(&targets, pid).is_empty(),
+        None => settings.target_id.is_some() && found.is_none(),
+    };
+    if missing {
+        settings.target_pid = None;
+    }
+    settings.target_id = found;
+    store_settings(&app, validate_settings(settings)?)?;
+    if missing {
+        return Ok(false);
+    }
+    start_capture_session(&app, SessionOptions::default())?;
+    Ok(true)
+}
@@ -0,0 +1,445 @@
+use crate::persist::write_atomic;
+use crate::recording::{self, RecordedFrame};
+use crate::resize::{self, ResizeAlgorithm};
+use crate::{current_frame, screenshot, CaptureState};
+use image::codecs::png::PngEncoder;
+use image::{ExtendedColorType, ImageEncoder};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+pub const MAX_REPLAY_SECS: u32 = 300;
+pub const MIN_REPLAY_MEMORY_MB: u32 = 16;
+pub const MAX_REPLAY_MEMORY_MB: u32 = 8192;
+const FOLDER_NAME: &str = "LiteView";
+
+/// How a replay is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReplayFormat {
+    /// One H.264 MP4 file, timed like the capture.
+    #[default]
+    Mp4,
+    /// A folder of numbered PNG files, one per frame, without timing.
+    Png,
+}
+
+/// The last seconds of the primary session's frames, for save_replay.
+///
+/// Holds at most `max_secs` of frames and `max_bytes` of pixels. Frames too large for the
+/// memory limit at the capture rate are downscaled before they are kept. Another session or
+/// frame size starts the buffer over, since a replay shows one source at one size.
+#[derive(Default)]
+pub struct ReplayBuffer {
+    frames: VecDeque<RecordedFrame>,
+    bytes: usize,
+    max_secs: u32,
+    max_bytes: usize,
+    fps: u32,
+    /// Session and source size (before downscaling) of the frames in the buffer.
+    source: Option<(u32, u32, u32)>,
+}
+
+impl ReplayBuffer {
+    /// Applies the settings; turning the buffer off (`secs` 0) frees it.
+    pub fn configure(&mut self, secs: u32, max_mb: u32, fps: u32) {
+        let max_bytes = max_mb as usize * 1024 * 1024;
+        if (secs, max_bytes, fps) == (self.max_secs, self.max_bytes, self.fps) {
+            return;
+        }
+        (self.max_secs, self.max_bytes, self.fps) = (secs, max_bytes, fps);
+        // The downscaled size depends on all three.
+        self.clear();
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+        self.bytes = 0;
+        self.source = None;
+    }
+
+    /// Size frames of `width` × `height` are kept at: scaled down so that `max_secs` of them at
+    /// `fps` fit into `max_bytes`.
+    fn stored_size(&self, width: u32, height: u32) -> (u32, u32) {
+        let frame_bytes = width as f64 * height as f64 * 4.0;
+        let budget = self.max_bytes as f64 / (self.max_secs as f64 * self.fps.max(1) as f64);
+        let scale = (budget / frame_bytes).sqrt();
+        if scale >= 1.0 {
+            return (width, height);
+        }
+        // Even sizes encode without dropping a row or column.
+        let scaled = |n: u32| (((n as f64 * scale) as u32) & !1).max(2);
+        (scaled(width), scaled(height))
+    }
+
+    fn push(&mut self, session: u32, width: u32, height: u32, buffer: &Arc<Vec<u32>>, at: Instant) {
+        if self.max_secs == 0 {
+            return;
+        }
+        if self.source != Some((session, width, height)) {
+            self.clear();
+            self.source = Some((session, width, height));
+        }
+        let (w, h) = self.stored_size(width, height);
+        let buffer = if (w, h) == (width, height) {
+            buffer.clone()
+        } else {
+            let algorithm = ResizeAlgorithm::Auto.resolve(width, height, w, h);
+            Arc::new(resize::resize_frame(algorithm, width, height, buffer, w, h))
+        };
+        self.bytes += buffer.len() * 4;
+        self.frames.push_back(RecordedFrame {
+            width: w,
+            height: h,
+            buffer,
+            captured_at: at,
+        });
+
+        let max_age = Duration::from_secs(self.max_secs.into());
+        while let Some(oldest) = self.frames.front() {
+            let too_old = at.saturating_duration_since(oldest.captured_at) > max_age;
+            if !too_old && self.bytes <= self.max_bytes {
+                break;
+            }
+            self.bytes -= oldest.buffer.len() * 4;
+            self.frames.pop_front();
+        }
+    }
+
+    /// Seconds between the oldest and the newest frame.
+    pub fn buffered_secs(&self) -> f32 {
+        match (self.frames.front(), self.frames.back()) {
+            (Some(first), Some(last)) => last
+                .captured_at
+                .duration_since(first.captured_at)
+                .as_secs_f32(),
+            _ => 0.0,
+        }
+    }
+
+    /// Memory held by the frames' pixels.
+    pub fn memory_bytes(&self) -> u64 {
+        self.bytes as u64
+    }
+
+    /// The buffered frames, sharing their pixels with the buffer.
+    fn snapshot(&self) -> Vec<RecordedFrame> {
+        self.frames
+            .iter()
+            .map(|f| RecordedFrame {
+                width: f.width,
+                height: f.height,
+                buffer: f.buffer.clone(),
+                captured_at: f.captured_at,
+            })
+            .collect()
+    }
+}
+
+/// Whether the replay buffer keeps frames of session `session`.
+pub fn is_buffering(state: &CaptureState, session: u32) -> bool {
+    state.primary_session().map(|p| p.id) == Some(session)
+        && state.replay.lock().unwrap().max_secs > 0
+}
+
+/// Keeps a frame of session `session` if it is the primary one. Called by the capture thread
+/// for every frame it passes to the preview.
+pub fn push_frame(
+    state: &CaptureState,
+    session: u32,
+    width: u32,
+    height: u32,
+    buffer: &Arc<Vec<u32>>,
+    captured_at: Instant,
+) {
+    if state.primary_session().map(|p| p.id) != Some(session) {
+        return;
+    }
+    state
+        .replay
+        .lock()
+        .unwrap()
+        .push(session, width, height, buffer, captured_at);
+}
+
+/// Payload of "replay-saved".
+#[derive(Debug, Clone, Serialize)]
+struct ReplaySaved {
+    path: String,
+    duration_secs: f64,
+    frames: usize,
+}
+
+/// Payload of "replay-error".
+#[derive(Debug, Clone, Serialize)]
+struct ReplayError {
+    path: String,
+    message: String,
+}
+
+/// A file (MP4) or folder (PNG) in Videos/LiteView named after the current time, numbered if
+/// that name is taken.
+fn default_path(app: &AppHandle, format: ReplayFormat) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .video_dir()
+        .map_err(|e| format!("No videos folder: {e}"))?
+        .join(FOLDER_NAME);
+    let extension = match format {
+        ReplayFormat::Mp4 => ".mp4",
+        ReplayFormat::Png => "",
+    };
+    let stem = format!("LiteView Replay {}", screenshot::timestamp());
+    let mut path = dir.join(format!("{stem}{extension}"));
+    let mut n = 2;
+    while path.exists() {
+        path = dir.join(format!("{stem} ({n}){extension}"));
+        n += 1;
+    }
+    Ok(path)
+}
+
+/// Writes `frames` as numbered PNG files into the folder `dir`.
+fn write_png_sequence(dir: &Path, frames: &[RecordedFrame]) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Creating {}: {e}", dir.display()))?;
+    let digits = frames.len().to_string().len().max(4);
+    for (n, frame) in frames.iter().enumerate() {
+        let mut png = Vec::new();
+        PngEncoder::new(&mut png)
+            .write_image(
+                &current_frame::to_rgb(&frame.buffer),
+                frame.width,
+                frame.height,
+                ExtendedColorType::Rgb8,
+            )
+            .map_err(|e| format!("Encoding frame {}: {e}", n + 1))?;
+        let path = dir.join(format!("frame {:0digits$}.png", n + 1));
+        write_atomic(&path, &png).map_err(|e| format!("Saving {}: {e}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// Starts writing the replay buffer to `path`, or to a timestamped file in Videos/LiteView,
+/// and returns the path. A writer thread does the encoding while capture goes on; it emits
+/// "replay-saved" or "replay-error", and with `notify` also shows a notification.
+pub fn write_replay(
+    app: &AppHandle,
+    path: Option<String>,
+    notify: bool,
+) -> Result<PathBuf, String> {
+    let state = app.state::<CaptureState>();
+    let (format, fps) = {
+        let settings = state.settings.lock().unwrap();
+        if settings.replay_secs == 0 {
+            return Err("The replay buffer is turned off.".to_string());
+        }
+        (settings.replay_format, settings.fps)
+    };
+    let frames = state.replay.lock().unwrap().snapshot();
+    if frames.is_empty() {
+        return Err("The replay buffer is empty.".to_string());
+    }
+    let path = match path {
+        Some(path) if !path.trim().is_empty() => PathBuf::from(path),
+        _ => default_path(app, format)?,
+    };
+
+    let app = app.clone();
+    let writer_path = path.clone();
+    thread::spawn(move || {
+        let path_str = writer_path.display().to_string();
+        let result = match format {
+            ReplayFormat::Mp4 => writer_path
+                .parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .map_err(|e| e.to_string())
+                .and_then(|()| recording::write_mp4(&writer_path, fps, &frames)),
+            ReplayFormat::Png => write_png_sequence(&writer_path, &frames).map(|()| {
+                frames[frames.len() - 1]
+                    .captured_at
+                    .duration_since(frames[0].captured_at)
+            }),
+        };
+        let body = match result {
+            Ok(duration) => {
+                let _ = app.emit(
+                    "replay-saved",
+                    ReplaySaved {
+                        path: path_str.clone(),
+                        duration_secs: duration.as_secs_f64(),
+                        frames: frames.len(),
+                    },
+                );
+                format!("Replay saved to {path_str}")
+            }
+            Err(e) => {
+                let message = format!("Saving the replay failed: {e}");
+                let _ = app.emit(
+                    "replay-error",
+                    ReplayError {
+                        path: path_str,
+                        message: message.clone(),
+                    },
+                );
+                message
+            }
+        };
+        if notify {
+            let _ = app
+                .notification()
+                .builder()
+                .title("LiteView")
+                .body(body)
+                .show();
+        }
+    });
+    Ok(path)
+}
+
+/// Writes the last `replay_secs` seconds of the primary session to `path` (an MP4 file, or a
+/// folder for PNG sequences) or to Videos/LiteView and returns the path. "replay-saved"
+/// follows once the replay is complete.
+#[tauri::command]
+pub fn save_replay(app: AppHandle, path: Option<String>) -> Result<String, String> {
+    write_replay(&app, path, false).map(|path| path.display().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn buffer(secs: u32, max_mb: u32, fps: u32) -> ReplayBuffer {
+        let mut replay = ReplayBuffer::default();
+        replay.configure(secs, max_mb, fps);
+        replay
+    }
+
+    fn pixels(width: u32, height: u32) -> Arc<Vec<u32>> {
+        Arc::new(vec![0x00FF_8040; (width * height) as usize])
+    }
+
+    /// Pushes a `width` × `height` frame of `session` every `interval`, `count` times.
+    fn push_frames(
+        replay: &mut ReplayBuffer,
+        session: u32,
+        (width, height): (u32, u32),
+        start: Instant,
+        interval: Duration,
+        count: u32,
+    ) {
+        let frame = pixels(width, height);
+        for i in 0..count {
+            replay.push(session, width, height, &frame, start + interval * i);
+        }
+    }
+
+    #[test]
+    fn frames_within_the_budget_keep_their_size() {
+        let replay = buffer(10, 512, 60);
+        assert_eq!(replay.stored_size(320, 240), (320, 240));
+        assert_eq!(replay.stored_size(1, 1), (1, 1));
+    }
+
+    #[test]
+    fn large_frames_are_scaled_to_even_sizes_that_fit() {
+        let (secs, max_mb, fps) = (10, 512, 60);
+        let replay = buffer(secs, max_mb, fps);
+        for (width, height) in [(1920, 1080), (3841, 2161), (7680, 4320)] {
+            let (w, h) = replay.stored_size(width, height);
+            assert!(w < width && h < height, "{width}×{height} kept as {w}×{h}");
+            assert_eq!((w % 2, h % 2), (0, 0), "{w}×{h}");
+            let bytes = w as u64 * h as u64 * 4 * u64::from(secs * fps);
+            assert!(bytes <= u64::from(max_mb) << 20, "{w}×{h}");
+        }
+        // However thin the frame gets, it keeps a size it can be encoded at.
+        let (w, h) = buffer(300, 16, 1000).stored_size(10_000, 3);
+        assert_eq!((w % 2, h), (0, 2));
+    }
+
+    #[test]
+    fn a_frame_that_fits_is_kept_as_is() {
+        let mut replay = buffer(10, 512, 60);
+        let frame = pixels(320, 240);
+        replay.push(1, 320, 240, &frame, Instant::now());
+        assert!(Arc::ptr_eq(&replay.frames[0].buffer, &frame));
+        assert_eq!(replay.memory_bytes(), 320 * 240 * 4);
+    }
+
+    #[test]
+    fn a_downscaled_frame_is_stored_at_an_even_size() {
+        let mut replay = buffer(10, 512, 60);
+        replay.push(1, 1921, 1081, &pixels(1921, 1081), Instant::now());
+        let frame = &replay.frames[0];
+        assert_eq!((frame.width, frame.height), replay.stored_size(1921, 1081));
+        assert_eq!((frame.width % 2, frame.height % 2), (0, 0));
+        assert_eq!(frame.buffer.len(), (frame.width * frame.height) as usize);
+        assert_eq!(replay.memory_bytes(), frame.buffer.len() as u64 * 4);
+    }
+
+    #[test]
+    fn frames_older_than_max_secs_are_evicted() {
+        let mut replay = buffer(2, 512, 10);
+        let start = Instant::now();
+        push_frames(
+            &mut replay,
+            1,
+            (64, 48),
+            start,
+            Duration::from_millis(100),
+            31,
+        );
+        // 3 s of frames; the last 2 s are kept, both ends included.
+        assert_eq!(replay.frames.len(), 21);
+        assert_eq!(replay.buffered_secs(), 2.0);
+        assert_eq!(replay.memory_bytes(), 21 * 64 * 48 * 4);
+    }
+
+    #[test]
+    fn frames_beyond_max_bytes_are_evicted() {
+        // Frames arrive faster than `fps`, so the memory limit is reached before `max_secs`.
+        let mut replay = buffer(300, 1, 1);
+        let start = Instant::now();
+        push_frames(
+            &mut replay,
+            1,
+            (10, 10),
+            start,
+            Duration::from_millis(1),
+            3000,
+        );
+        assert_eq!(replay.frames.len(), (1 << 20) / 400);
+        assert!(replay.memory_bytes() <= 1 << 20);
+        let newest = replay.frames.back().unwrap().captured_at;
+        assert_eq!(newest, start + Duration::from_millis(2999));
+    }
+
+    #[test]
+    fn another_session_or_frame_size_starts_over() {
+        let mut replay = buffer(10, 512, 60);
+        let start = Instant::now();
+        let interval = Duration::from_millis(16);
+        push_frames(&mut replay, 1, (64, 48), start, interval, 5);
+        assert_eq!(replay.frames.len(), 5);
+        push_frames(&mut replay, 2, (64, 48), start + interval * 5, interval, 1);
+        assert_eq!(replay.frames.len(), 1);
+        push_frames(&mut replay, 2, (64, 64), start + interval * 6, interval, 2);
+        assert_eq!(replay.frames.len(), 2);
+        assert_eq!(replay.memory_bytes(), 2 * 64 * 64 * 4);
+    }
+
+    #[test]
+    fn a_buffer_turned_off_keeps_nothing() {
+        let mut replay = buffer(10, 512, 60);
+        push_frames(&mut replay, 1, (64, 48), Instant::now(), Duration::ZERO, 3);
+        replay.configure(0, 512, 60);
+        assert_eq!(replay.memory_bytes(), 0);
+        push_frames(&mut replay, 1, (64, 48), Instant::now(), Duration::ZERO, 3);
+        assert!(replay.frames.is_empty());
+    }
+}
@@ -0,0 +1,475 @@
+use serde::{Deserialize, Serialize};
+
+/// Where frames are scaled to the output resolution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResizeOn {
+    /// The preview gets frames at their captured size and its sampler scales them; the
+    /// resolution only sets the size they are shown at. Recordings are still scaled on the
+    /// CPU, since the encoder needs frames of that size.
+    #[default]
+    Gpu,
+    /// The capture thread scales every frame with the `ResizeAlgorithm`.
+    Cpu,
+}
+
+/// CPU scaler used when the output resolution differs from the captured size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResizeAlgorithm {
+    /// Picked per frame size: area for strong downscales, nearest for integer upscales,
+    /// bilinear otherwise.
+    #[default]
+    Auto,
+    /// Fastest; keeps hard pixel edges (pixel art).
+    Nearest,
+    /// Smooth; good for video and moderate scale factors.
+    Bilinear,
+    /// Box filter averaging every covered source pixel; keeps text legible when shrinking.
+    Area,
+    /// Three-lobe windowed sinc; sharpest for text and detail, and the slowest.
+    Lanczos,
+}
+
+impl ResizeAlgorithm {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Auto => "auto",
+            Self::Nearest => "nearest",
+            Self::Bilinear => "bilinear",
+            Self::Area => "area",
+            Self::Lanczos => "lanczos",
+        }
+    }
+
+    /// The concrete algorithm for this scale (never Auto).
+    pub fn resolve(self, src_w: u32, src_h: u32, dst_w: u32, dst_h: u32) -> Self {
+        if self != Self::Auto {
+            return self;
+        }
+        let ratio = (src_w as f32 / dst_w.max(1) as f32).max(src_h as f32 / dst_h.max(1) as f32);
+        let integer_upscale = src_w > 0
+            && src_h > 0
+            && dst_w.is_multiple_of(src_w)
+            && dst_h.is_multiple_of(src_h)
+            && dst_w / src_w == dst_h / src_h;
+        if ratio >= 2.0 {
+            Self::Area
+        } else if ratio < 1.0 && integer_upscale {
+            Self::Nearest
+        } else {
+            Self::Bilinear
+        }
+    }
+}
+
+/// Resizes a packed 0x00RRGGBB frame. `algorithm` must already be resolved (Auto is treated as
+/// nearest).
+pub fn resize_frame(
+    algorithm: ResizeAlgorithm,
+    src_w: u32,
+    src_h: u32,
+    src: &[u32],
+    dst_w: u32,
+    dst_h: u32,
+) -> Vec<u32> {
+    let mut dst = Vec::new();
+    resize_into(algorithm, (src_w, src_h), src, (dst_w, dst_h), &mut dst);
+    dst
+}
+
+/// `resize_frame` into `dst`, replacing its contents and reusing its allocation.
+pub fn resize_into(
+    algorithm: ResizeAlgorithm,
+    (src_w, src_h): (u32, u32),
+    src: &[u32],
+    (dst_w, dst_h): (u32, u32),
+    dst: &mut Vec<u32>,
+) {
+    dst.clear();
+    dst.resize((dst_w as usize).saturating_mul(dst_h as usize), 0);
+    if src_w == 0 || src_h == 0 || dst_w == 0 || dst_h == 0 {
+        return;
+    }
+    if src.len() < src_w as usize * src_h as usize {
+        return;
+    }
+    let (src_w, src_h) = (src_w as usize, src_h as usize);
+    let (dst_w, dst_h) = (dst_w as usize, dst_h as usize);
+    match algorithm {
+        ResizeAlgorithm::Auto | ResizeAlgorithm::Nearest => {
+            nearest(src_w, src_h, src, dst_w, dst_h, dst)
+        }
+        ResizeAlgorithm::Bilinear => bilinear(src_w, src_h, src, dst_w, dst_h, dst),
+        ResizeAlgorithm::Area => area(src_w, src_h, src, dst_w, dst_h, dst),
+        ResizeAlgorithm::Lanczos => lanczos(src_w, src_h, src, dst_w, dst_h, dst),
+    }
+}
+
+fn channels(p: u32) -> [u32; 3] {
+    [(p >> 16) & 0xFF, (p >> 8) & 0xFF, p & 0xFF]
+}
+
+fn pack(c: [u32; 3]) -> u32 {
+    (c[0].min(255) << 16) | (c[1].min(255) << 8) | c[2].min(255)
+}
+
+fn nearest(src_w: usize, src_h: usize, src: &[u32], dst_w: usize, dst_h: usize, dst: &mut [u32]) {
+    for y in 0..dst_h {
+        let sy = (y as u64 * src_h as u64 / dst_h as u64) as usize;
+        let src_row = sy * src_w;
+        for x in 0..dst_w {
+            let sx = (x as u64 * src_w as u64 / dst_w as u64) as usize;
+            dst[y * dst_w + x] = src[src_row + sx];
+        }
+    }
+}
+
+/// Source sample positions for bilinear filtering: (lower index, upper index, weight of upper
+/// in 1/256).
+fn bilinear_taps(src_len: usize, dst_len: usize) -> Vec<(usize, usize, u32)> {
+    let scale = src_len as f32 / dst_len as f32;
+    (0..dst_len)
+        .map(|i| {
+            let f = ((i as f32 + 0.5) * scale - 0.5).clamp(0.0, (src_len - 1) as f32);
+            let lo = f as usize;
+            let hi = (lo + 1).min(src_len - 1);
+            (lo, hi, ((f - lo as f32) * 256.0) as u32)
+        })
+        .collect()
+}
+
+fn bilinear(src_w: usize, src_h: usize, src: &[u32], dst_w: usize, dst_h: usize, dst: &mut [u32]) {
+    let cols = bilinear_taps(src_w, dst_w);
+    let rows = bilinear_taps(src_h, dst_h);
+    for (y, &(y0, y1, wy)) in rows.iter().enumerate() {
+        let (top, bottom) = (&src[y0 * src_w..], &src[y1 * src_w..]);
+        for (x, &(x0, x1, wx)) in cols.iter().enumerate() {
+            let (a, b) = (channels(top[x0]), channels(top[x1]));
+            let (c, d) = (channels(bottom[x0]), channels(bottom[x1]));
+            let out = std::array::from_fn(|i| {
+                let top = a[i] * (256 - wx) + b[i] * wx;
+                let btm = c[i] * (256 - wx) + d[i] * wx;
+                (top * (256 - wy) + btm * wy + (1 << 15)) >> 16
+            });
+            dst[y * dst_w + x] = pack(out);
+        }
+    }
+}
+
+/// Half-open source ranges covered by each destination pixel (at least one pixel wide).
+fn area_spans(src_len: usize, dst_len: usize) -> Vec<(usize, usize)> {
+    (0..dst_len)
+        .map(|i| {
+            let start = i * src_len / dst_len;
+            let end = ((i + 1) * src_len / dst_len).clamp(start + 1, src_len);
+            (start.min(src_len - 1), end)
+        })
+        .collect()
+}
+
+fn area(src_w: usize, src_h: usize, src: &[u32], dst_w: usize, dst_h: usize, dst: &mut [u32]) {
+    let cols = area_spans(src_w, dst_w);
+    let rows = area_spans(src_h, dst_h);
+    for (y, &(y0, y1)) in rows.iter().enumerate() {
+        for (x, &(x0, x1)) in cols.iter().enumerate() {
+            let mut sum = [0u64; 3];
+            for row in src[y0 * src_w..y1 * src_w].chunks_exact(src_w) {
+                for &p in &row[x0..x1] {
+                    let c = channels(p);
+                    sum[0] += c[0] as u64;
+                    sum[1] += c[1] as u64;
+                    sum[2] += c[2] as u64;
+                }
+            }
+            let n = ((y1 - y0) * (x1 - x0)) as u64;
+            dst[y * dst_w + x] = pack(sum.map(|s| ((s + n / 2) / n) as u32));
+        }
+    }
+}
+
+/// Lobes of the Lanczos kernel on each side.
+const LANCZOS_LOBES: f32 = 3.0;
+
+fn lanczos_kernel(x: f32) -> f32 {
+    if x.abs() < f32::EPSILON {
+        return 1.0;
+    }
+    if x.abs() >= LANCZOS_LOBES {
+        return 0.0;
+    }
+    let px = std::f32::consts::PI * x;
+    LANCZOS_LOBES * px.sin() * (px / LANCZOS_LOBES).sin() / (px * px)
+}
+
+/// Lanczos filter taps of each destination pixel: first source index and weights in 1/16384,
+/// summing to 16384. Shrinking widens the kernel so every source pixel contributes.
+fn lanczos_taps(src_len: usize, dst_len: usize) -> Vec<(usize, Vec<i32>)> {
+    let scale = src_len as f32 / dst_len as f32;
+    let stretch = scale.max(1.0);
+    let radius = LANCZOS_LOBES * stretch;
+    (0..dst_len)
+        .map(|i| {
+            let center = (i as f32 + 0.5) * scale;
+            let start = (center - radius).floor().max(0.0) as usize;
+            let end = ((center + radius).ceil() as usize).clamp(start + 1, src_len);
+            let weights: Vec<f32> = (start..end)
+                .map(|j| lanczos_kernel((j as f32 + 0.5 - center) / stretch))
+                .collect();
+            let sum: f32 = weights.iter().sum();
+            let fixed = weights.iter().map(|w| (w / sum * 16384.0).round() as i32);
+            (start, fixed.collect())
+        })
+        .collect()
+}
+
+/// Separable Lanczos: rows into an intermediate of `dst_w` × `src_h` (channels in 1/256),
+/// then columns. The negative lobes can over- and undershoot, so results are clamped.
+fn lanczos(src_w: usize, src_h: usize, src: &[u32], dst_w: usize, dst_h: usize, dst: &mut [u32]) {
+    let cols = lanczos_taps(src_w, dst_w);
+    let rows = lanczos_taps(src_h, dst_h);
+    let mut wide = vec![[0i32; 3]; dst_w * src_h];
+    for (row, out) in src.chunks_exact(src_w).zip(wide.chunks_exact_mut(dst_w)) {
+        for ((start, weights), out) in cols.iter().zip(out) {
+            let mut sum = [0i32; 3];
+            for (&p, &w) in row[*start..].iter().zip(weights) {
+                for (s, c) in sum.iter_mut().zip(channels(p)) {
+                    *s += c as i32 * w;
+                }
+            }
+            *out = sum.map(|s| (s + (1 << 5)) >> 6);
+        }
+    }
+    let mut sums = vec![[0i64; 3]; dst_w];
+    for ((start, weights), out) in rows.iter().zip(dst.chunks_exact_mut(dst_w)) {
+        sums.fill([0; 3]);
+        for (row, &w) in wide[start * dst_w..].chunks_exact(dst_w).zip(weights) {
+            for (sum, c) in sums.iter_mut().zip(row) {
+                for (s, &c) in sum.iter_mut().zip(c) {
+                    *s += c as i64 * w as i64;
+                }
+            }
+        }
+        for (out, sum) in out.iter_mut().zip(&sums) {
+            *out = pack(sum.map(|s| ((s + (1 << 21)) >> 22).clamp(0, 255) as u32));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLACK: u32 = 0x000000;
+    const WHITE: u32 = 0xFFFFFF;
+
+    fn gray(v: u32) -> u32 {
+        pack([v, v, v])
+    }
+
+    /// A `w` × `h` checkerboard of black and white pixels.
+    fn checker(w: u32, h: u32) -> Vec<u32> {
+        (0..h)
+            .flat_map(|y| (0..w).map(move |x| if (x + y) % 2 == 0 { BLACK } else { WHITE }))
+            .collect()
+    }
+
+    const ALL: [ResizeAlgorithm; 4] = [
+        ResizeAlgorithm::Nearest,
+        ResizeAlgorithm::Bilinear,
+        ResizeAlgorithm::Area,
+        ResizeAlgorithm::Lanczos,
+    ];
+
+    #[test]
+    fn auto_resolves_by_scale() {
+        let auto = ResizeAlgorithm::Auto;
+        assert_eq!(auto.resolve(3840, 2160, 1920, 1080), ResizeAlgorithm::Area);
+        assert_eq!(auto.resolve(640, 360, 1920, 1080), ResizeAlgorithm::Nearest);
+        assert_eq!(
+            auto.resolve(1920, 1080, 1280, 720),
+            ResizeAlgorithm::Bilinear
+        );
+        assert_eq!(
+            auto.resolve(1920, 1080, 2560, 1440),
+            ResizeAlgorithm::Bilinear
+        );
+        assert_eq!(
+            ResizeAlgorithm::Lanczos.resolve(3840, 2160, 1920, 1080),
+            ResizeAlgorithm::Lanczos
+        );
+    }
+
+    #[test]
+    fn every_algorithm_keeps_a_flat_color() {
+        let src = vec![0x336699; 16 * 9];
+        for algorithm in ALL {
+            for (w, h) in [(8, 4), (16, 9), (37, 21)] {
+                let out = resize_frame(algorithm, 16, 9, &src, w, h);
+                assert_eq!(out.len(), (w * h) as usize, "{algorithm:?}");
+                assert!(out.iter().all(|&p| p == 0x336699), "{algorithm:?} {w}x{h}");
+            }
+        }
+    }
+
+    #[test]
+    fn every_algorithm_is_identity_at_the_same_size() {
+        let src = checker(7, 5);
+        for algorithm in ALL {
+            assert_eq!(
+                resize_frame(algorithm, 7, 5, &src, 7, 5),
+                src,
+                "{algorithm:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn every_algorithm_keeps_rows_apart_at_odd_widths() {
+        // Rows of one color each, so resizing across only must give back the same rows.
+        const ROWS: [u32; 3] = [0x336699, 0xC08040, 0x10E0A0];
+        let rows = |w: u32| -> Vec<u32> {
+            ROWS.iter()
+                .flat_map(|&c| std::iter::repeat_n(c, w as usize))
+                .collect()
+        };
+        for algorithm in ALL {
+            for (src_w, dst_w) in [(1, 63), (63, 1), (63, 257), (257, 63), (257, 1), (1, 257)] {
+                let out = resize_frame(algorithm, src_w, 3, &rows(src_w), dst_w, 3);
+                assert!(out == rows(dst_w), "{algorithm:?} {src_w} to {dst_w}");
+            }
+        }
+    }
+
+    #[test]
+    fn nearest_duplicates_pixels_on_integer_upscale() {
+        let src = [0x110000, 0x002200, 0x000033, 0x444444];
+        let out = resize_frame(ResizeAlgorithm::Nearest, 2, 2, &src, 4, 4);
+        #[rustfmt::skip]
+        let expected = [
+            0x110000, 0x110000, 0x002200, 0x002200,
+            0x110000, 0x110000, 0x002200, 0x002200,
+            0x000033, 0x000033, 0x444444, 0x444444,
+            0x000033, 0x000033, 0x444444, 0x444444,
+        ];
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    fn nearest_keeps_hard_edges_when_shrinking() {
+        let out = resize_frame(ResizeAlgorithm::Nearest, 8, 8, &checker(8, 8), 4, 4);
+        assert!(out.iter().all(|&p| p == BLACK || p == WHITE));
+    }
+
+    #[test]
+    fn bilinear_interpolates_between_neighbours() {
+        let out = resize_frame(ResizeAlgorithm::Bilinear, 2, 1, &[BLACK, WHITE], 4, 1);
+        assert_eq!(out, [BLACK, gray(0x40), gray(0xBF), WHITE]);
+    }
+
+    #[test]
+    fn area_averages_a_checkerboard_to_gray() {
+        let out = resize_frame(ResizeAlgorithm::Area, 8, 8, &checker(8, 8), 4, 4);
+        assert!(out.iter().all(|&p| p == gray(0x80)));
+    }
+
+    #[test]
+    fn lanczos_is_sharper_than_bilinear_on_an_edge() {
+        let src: Vec<u32> = (0..8).map(|x| if x < 4 { BLACK } else { WHITE }).collect();
+        let lanczos = resize_frame(ResizeAlgorithm::Lanczos, 8, 1, &src, 16, 1);
+        let bilinear = resize_frame(ResizeAlgorithm::Bilinear, 8, 1, &src, 16, 1);
+        // The edge falls between pixels 7 and 8.
+        assert!(lanczos[7] & 0xFF < bilinear[7] & 0xFF);
+        assert!(lanczos[8] & 0xFF > bilinear[8] & 0xFF);
+        // The negative lobes ring on both sides, where bilinear stays flat.
+        assert!(lanczos[4] & 0xFF > 0 && bilinear[4] == BLACK);
+        assert!(lanczos[11] & 0xFF < 0xFF && bilinear[11] == WHITE);
+    }
+
+    fn grays(values: &[u32]) -> Vec<u32> {
+        values.iter().map(|&v| gray(v)).collect()
+    }
+
+    #[test]
+    fn bilinear_halves_between_pixel_pairs() {
+        let src = grays(&[0x00, 0x40, 0x80, 0xC0]);
+        let out = resize_frame(ResizeAlgorithm::Bilinear, 4, 1, &src, 2, 1);
+        assert_eq!(out, grays(&[0x20, 0xA0]));
+        let out = resize_frame(ResizeAlgorithm::Bilinear, 1, 4, &src, 1, 2);
+        assert_eq!(out, grays(&[0x20, 0xA0]));
+    }
+
+    #[test]
+    fn area_averages_whole_and_uneven_spans() {
+        let src = grays(&[0, 30, 60, 90, 120, 150]);
+        let out = resize_frame(ResizeAlgorithm::Area, 6, 1, &src, 2, 1);
+        assert_eq!(out, grays(&[30, 120]));
+        // 5 → 2: pixels 0..2 and 2..5.
+        let out = resize_frame(ResizeAlgorithm::Area, 5, 1, &src[..5], 2, 1);
+        assert_eq!(out, grays(&[15, 90]));
+    }
+
+    // Expected Lanczos values are a floating-point Lanczos-3 of the same input, clamped and
+    // rounded; the fixed-point filter matches them to within 1.
+
+    #[test]
+    fn lanczos_halves_a_step_with_ringing() {
+        let src = grays(&[0, 0, 0, 0, 0, 0, 255, 255, 255, 255, 255, 255]);
+        let out = resize_frame(ResizeAlgorithm::Lanczos, 12, 1, &src, 6, 1);
+        assert_eq!(out, grays(&[1, 0, 14, 241, 255, 254]));
+    }
+
+    #[test]
+    fn lanczos_halves_a_bar() {
+        let src = grays(&[0, 0, 0, 0, 255, 255, 255, 255, 0, 0, 0, 0]);
+        let out = resize_frame(ResizeAlgorithm::Lanczos, 12, 1, &src, 6, 1);
+        assert_eq!(out, grays(&[0, 13, 246, 246, 13, 0]));
+    }
+
+    #[test]
+    fn lanczos_halves_a_ramp() {
+        let src: Vec<u32> = (0..16).map(|x| gray(x * 17)).collect();
+        let out = resize_frame(ResizeAlgorithm::Lanczos, 16, 1, &src, 8, 1);
+        assert_eq!(out, grays(&[9, 42, 77, 111, 145, 178, 213, 246]));
+    }
+
+    #[test]
+    fn lanczos_filters_rows_and_columns_alike() {
+        let step = [0, 0, 0, 0, 0, 0, 255, 255, 255, 255, 255, 255];
+        let expected = grays(&[1, 0, 14, 241, 255, 254]);
+        // The step across 12 × 12 pixels, then down them.
+        let across: Vec<u32> = (0..12).flat_map(|_| grays(&step)).collect();
+        let out = resize_frame(ResizeAlgorithm::Lanczos, 12, 12, &across, 6, 6);
+        assert!(out.chunks_exact(6).all(|row| row == expected));
+        let down: Vec<u32> = step.iter().flat_map(|&v| [gray(v); 12]).collect();
+        let out = resize_frame(ResizeAlgorithm::Lanczos, 12, 12, &down, 6, 6);
+        for (row, &v) in out.chunks_exact(6).zip(&expected) {
+            assert!(row.iter().all(|&p| p == v));
+        }
+    }
+
+    #[test]
+    fn lanczos_keeps_channels_apart() {
+        let src: Vec<u32> = (0..12)
+            .map(|x| if x < 6 { 0xFF0000 } else { 0x0000FF })
+            .collect();
+        let out = resize_frame(ResizeAlgorithm::Lanczos, 12, 1, &src, 6, 1);
+        let expected = [1, 0, 14, 241, 255, 254].map(|b| ((255 - b) << 16) | b);
+        // Red falls where blue rises; the two may round one apart.
+        for (p, e) in out.iter().zip(expected) {
+            let [r, g, b] = channels(*p);
+            let [er, _, eb] = channels(e);
+            assert_eq!(g, 0);
+            assert!(r.abs_diff(er) <= 1 && b == eb, "{p:06X}");
+        }
+    }
+
+    #[test]
+    fn empty_or_short_input_gives_a_black_frame() {
+        for algorithm in ALL {
+            assert_eq!(resize_frame(algorithm, 0, 4, &[], 2, 2), [BLACK; 4]);
+            assert_eq!(resize_frame(algorithm, 4, 4, &[WHITE; 3], 2, 2), [BLACK; 4]);
+            assert!(resize_frame(algorithm, 4, 4, &[WHITE; 16], 0, 2).is_empty());
+        }
+    }
+}
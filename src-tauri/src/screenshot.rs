@@ -0,0 +1,110 @@
+use crate::current_frame;
+use crate::persist::write_atomic;
+use crate::preview::PreviewState;
+use crate::CaptureState;
+use image::codecs::png::PngEncoder;
+use image::{ExtendedColorType, ImageEncoder};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// How long to wait for the first frame of a capture that has not delivered one yet.
+const FIRST_FRAME_TIMEOUT: Duration = Duration::from_secs(3);
+const FOLDER_NAME: &str = "LiteView";
+
+/// The latest frame of the session, waiting up to `FIRST_FRAME_TIMEOUT` for one if the
+/// capture has only just started.
+fn wait_for_frame(preview_state: &PreviewState) -> Result<(u32, u32, Arc<Vec<u32>>), String> {
+    let deadline = Instant::now() + FIRST_FRAME_TIMEOUT;
+    loop {
+        if let Some(frame) = preview_state.latest_frame() {
+            return Ok(frame);
+        }
+        if Instant::now() >= deadline {
+            return Err("The capture has not delivered a frame yet.".to_string());
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+}
+
+/// "YYYY-MM-DD HH-MM-SS" in UTC, safe for file names on every platform.
+pub fn timestamp() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let (days, time) = (secs / 86_400, secs % 86_400);
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm).
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}-{:02}-{:02}",
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    )
+}
+
+/// A file in Pictures/LiteView named after the current time, numbered if that name is taken.
+fn default_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .picture_dir()
+        .map_err(|e| format!("No pictures folder: {e}"))?
+        .join(FOLDER_NAME);
+    let stem = format!("LiteView {}", timestamp());
+    let mut path = dir.join(format!("{stem}.png"));
+    let mut n = 2;
+    while path.exists() {
+        path = dir.join(format!("{stem} ({n}).png"));
+        n += 1;
+    }
+    Ok(path)
+}
+
+/// Saves the latest frame of the primary session as PNG to `path`, or to a timestamped file
+/// in Pictures/LiteView, and emits "screenshot-saved" with the path.
+pub fn save_screenshot(app: &AppHandle, path: Option<String>) -> Result<PathBuf, String> {
+    let preview_state = app
+        .state::<CaptureState>()
+        .primary_session()
+        .ok_or("No capture is running.")?;
+    let (width, height, buffer) = wait_for_frame(&preview_state)?;
+    let path = match path {
+        Some(path) if !path.trim().is_empty() => PathBuf::from(path),
+        _ => default_path(app)?,
+    };
+
+    let mut png = Vec::new();
+    PngEncoder::new(&mut png)
+        .write_image(
+            &current_frame::to_rgb(&buffer),
+            width,
+            height,
+            ExtendedColorType::Rgb8,
+        )
+        .map_err(|e| format!("Encoding the screenshot failed: {e}"))?;
+    write_atomic(&path, &png).map_err(|e| format!("Saving {}: {e}", path.display()))?;
+
+    let _ = app.emit("screenshot-saved", path.display().to_string());
+    Ok(path)
+}
+
+/// Saves the current frame as PNG and returns the file's path. Without `path` it goes to
+/// Pictures/LiteView. Fails when no capture is running.
+#[tauri::command]
+pub async fn capture_screenshot(app: AppHandle, path: Option<String>) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || save_screenshot(&app, path))
+        .await
+        .map_err(|e| e.to_string())?
+        .map(|path| path.display().to_string())
+}
@@ -0,0 +1,363 @@
+use crate::frame_limits::{self, FrameLimits};
+use crate::{frame_to_buffer, CaptureState};
+use scap::capturer::{Capturer, Options};
+use scap::frame::{Frame, FrameType};
+use scap::{get_all_targets, has_permission, is_supported, Target};
+use serde::Serialize;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+/// How long the one-frame capture may take before the step fails.
+const CAPTURE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Side of the offscreen render target; 64 RGBA pixels fill the 256-byte row alignment.
+const RENDER_SIZE: u32 = 64;
+/// Color the offscreen render draws, as RGBA8.
+const RENDER_COLOR: [u8; 4] = [255, 0, 255, 255];
+
+const RENDER_SHADER: &str = "
+@vertex
+fn vs_main(@builtin(vertex_index) i: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2<f32>(f32((i << 1u) & 2u), f32(i & 2u));
+    return vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+}
+
+@fragment
+fn fs_main() -> @location(0) vec4<f32> {
+    return vec4<f32>(1.0, 0.0, 1.0, 1.0);
+}
+";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StepStatus {
+    Pass,
+    Fail,
+    /// Not run because an earlier step it depends on failed.
+    Skip,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SelfTestStep {
+    name: &'static str,
+    status: StepStatus,
+    details: String,
+    duration_ms: f64,
+}
+
+/// Result of `run_self_test`; `passed` is true when every step passed.
+#[derive(Debug, Clone, Serialize)]
+pub struct SelfTestReport {
+    passed: bool,
+    steps: Vec<SelfTestStep>,
+}
+
+#[derive(Default)]
+struct Steps(Vec<SelfTestStep>);
+
+impl Steps {
+    /// Runs `step` and records its outcome; returns its value on success.
+    fn run<T>(
+        &mut self,
+        name: &'static str,
+        step: impl FnOnce() -> Result<(T, String), String>,
+    ) -> Option<T> {
+        let started = Instant::now();
+        let result = step();
+        let duration_ms = started.elapsed().as_secs_f64() * 1000.0;
+        let (status, details, value) = match result {
+            Ok((value, details)) => (StepStatus::Pass, details, Some(value)),
+            Err(details) => (StepStatus::Fail, details, None),
+        };
+        self.0.push(SelfTestStep {
+            name,
+            status,
+            details,
+            duration_ms,
+        });
+        value
+    }
+
+    fn skip(&mut self, name: &'static str, reason: &str) {
+        self.0.push(SelfTestStep {
+            name,
+            status: StepStatus::Skip,
+            details: reason.to_string(),
+            duration_ms: 0.0,
+        });
+    }
+}
+
+fn frame_format(frame: &Frame) -> &'static str {
+    match frame {
+        Frame::BGRA(_) => "BGRA",
+        Frame::BGR0(_) => "BGR0",
+        Frame::RGB(_) => "RGB",
+        Frame::RGBx(_) => "RGBx",
+        Frame::XBGR(_) => "XBGR",
+        Frame::BGRx(_) => "BGRx",
+        Frame::YUVFrame(_) => "NV12",
+    }
+}
+
+/// Captures one frame of the primary display (scap's default target) on a helper thread.
+/// `get_next_frame` blocks, so on timeout the helper is left to finish on its own.
+fn capture_one_frame() -> Result<Frame, String> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let options = Options {
+            fps: 30,
+            show_cursor: false,
+            show_highlight: false,
+            target: None,
+            output_type: FrameType::BGRAFrame,
+            ..Default::default()
+        };
+        let result = Capturer::build(options)
+            .map_err(|e| e.to_string())
+            .and_then(|mut c| {
+                c.start_capture();
+                let frame = c.get_next_frame().map_err(|e| e.to_string());
+                c.stop_capture();
+                frame
+            });
+        let _ = tx.send(result);
+    });
+    rx.recv_timeout(CAPTURE_TIMEOUT)
+        .unwrap_or_else(|_| Err(format!("No frame within {} s.", CAPTURE_TIMEOUT.as_secs())))
+}
+
+/// Draws a full-target triangle into an offscreen texture and reads one pixel back.
+fn render_offscreen(device: &wgpu::Device, queue: &wgpu::Queue) -> Result<String, String> {
+    let format = wgpu::TextureFormat::Rgba8Unorm;
+    let extent = wgpu::Extent3d {
+        width: RENDER_SIZE,
+        height: RENDER_SIZE,
+        depth_or_array_layers: 1,
+    };
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Self-test Target"),
+        size: extent,
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        view_formats: &[],
+    });
+    let view = texture.create_view(&Default::default());
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Self-test Shader"),
+        source: wgpu::ShaderSource::Wgsl(RENDER_SHADER.into()),
+    });
+    let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Self-test Pipeline"),
+        layout: None,
+        vertex: wgpu::VertexState {
+            module: &shader,
+            entry_point: Some("vs_main"),
+            buffers: &[],
+            compilation_options: Default::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader,
+            entry_point: Some("fs_main"),
+            targets: &[Some(format.into())],
+            compilation_options: Default::default(),
+        }),
+        primitive: Default::default(),
+        depth_stencil: None,
+        multisample: Default::default(),
+        multiview: None,
+        cache: None,
+    });
+    let bytes_per_row = 4 * RENDER_SIZE;
+    let readback = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Self-test Readback"),
+        size: (bytes_per_row * RENDER_SIZE) as u64,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = device.create_command_encoder(&Default::default());
+    {
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Self-test Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        pass.set_pipeline(&pipeline);
+        pass.draw(0..3, 0..1);
+    }
+    encoder.copy_texture_to_buffer(
+        texture.as_image_copy(),
+        wgpu::ImageCopyBuffer {
+            buffer: &readback,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(bytes_per_row),
+                rows_per_image: Some(RENDER_SIZE),
+            },
+        },
+        extent,
+    );
+    queue.submit([encoder.finish()]);
+
+    let slice = readback.slice(..);
+    let (tx, rx) = mpsc::channel();
+    slice.map_async(wgpu::MapMode::Read, move |r| {
+        let _ = tx.send(r);
+    });
+    let _ = device.poll(wgpu::Maintain::Wait);
+    rx.recv()
+        .map_err(|e| e.to_string())?
+        .map_err(|e| format!("Reading back the render failed: {e}"))?;
+    let pixel: [u8; 4] = slice.get_mapped_range()[..4].try_into().unwrap();
+    readback.unmap();
+    if pixel == RENDER_COLOR {
+        Ok(format!(
+            "Rendered {RENDER_SIZE}×{RENDER_SIZE} offscreen and read it back."
+        ))
+    } else {
+        Err(format!(
+            "Rendered pixel is {pixel:?}, expected {RENDER_COLOR:?}."
+        ))
+    }
+}
+
+fn run_steps(limits: FrameLimits) -> SelfTestReport {
+    let mut steps = Steps::default();
+
+    let permitted = steps.run("permission", || {
+        if !is_supported() {
+            return Err("Screen capture is not supported on this system.".to_string());
+        }
+        if !has_permission() {
+            return Err("Screen capture permission has not been granted.".to_string());
+        }
+        Ok(((), "Screen capture is supported and allowed.".to_string()))
+    });
+
+    steps.run("enumeration", || {
+        let targets = get_all_targets();
+        let displays = targets
+            .iter()
+            .filter(|t| matches!(t, Target::Display(_)))
+            .count();
+        let details = format!("{displays} displays, {} windows.", targets.len() - displays);
+        if displays == 0 {
+            Err(format!("No displays found; {details}"))
+        } else {
+            Ok(((), details))
+        }
+    });
+
+    let frame = match permitted {
+        Some(()) => steps.run("capture", || {
+            let frame = capture_one_frame()?;
+            Ok((
+                frame,
+                "Received a frame of the primary display.".to_string(),
+            ))
+        }),
+        None => {
+            steps.skip("capture", "Needs screen capture permission.");
+            None
+        }
+    };
+
+    let frame = match frame {
+        Some(frame) => steps.run("format", || {
+            let format = frame_format(&frame);
+            match frame_limits::frame_size(&frame) {
+                Some((w, h)) => Ok((frame, format!("{format}, {w}×{h}."))),
+                None => Err(format!("The {format} frame reports an invalid size.")),
+            }
+        }),
+        None => {
+            steps.skip("format", "Needs a captured frame.");
+            None
+        }
+    };
+
+    match frame {
+        Some(frame) => {
+            steps.run("conversion", || {
+                let (w, h) = frame_to_buffer(&frame, &limits, 1, &mut Vec::new())
+                    .ok_or("The frame is malformed or beyond the frame limits.")?;
+                Ok(((), format!("Converted {w}×{h} pixels.")))
+            });
+        }
+        None => steps.skip("conversion", "Needs a captured frame."),
+    }
+
+    let gpu = steps.run("gpu", || {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+            backends: wgpu::Backends::all(),
+            ..Default::default()
+        });
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            force_fallback_adapter: false,
+            compatible_surface: None,
+        }))
+        .ok_or("No GPU adapter found.")?;
+        let info = adapter.get_info();
+        let (device, queue) = pollster::block_on(adapter.request_device(
+            &wgpu::DeviceDescriptor {
+                label: Some("Self-test Device"),
+                required_features: wgpu::Features::empty(),
+                required_limits: wgpu::Limits::default().using_resolution(adapter.limits()),
+                memory_hints: Default::default(),
+            },
+            None,
+        ))
+        .map_err(|e| format!("Creating a device on {} failed: {e}", info.name))?;
+        Ok((
+            (device, queue),
+            format!(
+                "{} ({:?}), max texture size {}.",
+                info.name,
+                info.backend,
+                adapter.limits().max_texture_dimension_2d
+            ),
+        ))
+    });
+
+    match gpu {
+        Some((device, queue)) => {
+            steps.run("render", || Ok(((), render_offscreen(&device, &queue)?)));
+        }
+        None => steps.skip("render", "Needs a GPU device."),
+    }
+
+    SelfTestReport {
+        passed: steps.0.iter().all(|s| s.status == StepStatus::Pass),
+        steps: steps.0,
+    }
+}
+
+/// Runs the capture and render pipeline once, step by step, for troubleshooting. Takes a few
+/// seconds; on Wayland the capture step shows the portal's source picker.
+#[tauri::command]
+pub async fn run_self_test(app: AppHandle) -> Result<SelfTestReport, String> {
+    let limits = app
+        .state::<CaptureState>()
+        .settings
+        .lock()
+        .unwrap()
+        .frame_limits;
+    tauri::async_runtime::spawn_blocking(move || run_steps(limits))
+        .await
+        .map_err(|e| e.to_string())
+}
@@ -4,6 +4,44 @@ struct VertexOutput {
     @location(0) tex_coords: vec2<f32>,
 }
 
+// Keep in sync with display::Uniforms
+struct Uniforms {
+    // Rows of a 3x3 color matrix applied in linear RGB (color-blindness simulation)
+    color_row0: vec4<f32>,
+    color_row1: vec4<f32>,
+    color_row2: vec4<f32>,
+    // Picture adjustments (contrast/hue/saturation) as affine rows; brightness offset in .w
+    adjust_row0: vec4<f32>,
+    adjust_row1: vec4<f32>,
+    adjust_row2: vec4<f32>,
+    // 1 when the surface format is not sRGB: encode linear output here instead of in hardware
+    encode_srgb: u32,
+    // Scale on the final linear color: SDR white level for scRGB (HDR) surfaces, else 1.0
+    output_scale: f32,
+    gamma: f32,
+    // Clockwise quarter turns of the image
+    rotation: u32,
+    // Bit 0 mirrors the image left-to-right, bit 1 top-to-bottom
+    flip: u32,
+    // Rounded-corner mask radius in texture pixels (0 = off)
+    corner_radius: f32,
+    // Size of the image quad relative to the window (letterboxing)
+    scale: vec2<f32>,
+    // Weight of the current frame against the previous one (frame smoothing)
+    blend: f32,
+    // Magnification about the window center and offset of the zoomed quad (clip space)
+    zoom: f32,
+    pan: vec2<f32>,
+    // Letterbox color, already encoded for the surface
+    background: vec4<f32>,
+    // 1 to sample the nearest texel (nearest filtering, integer scaling)
+    nearest: u32,
+    // 0 = none, 1 = grayscale, 2 = invert (display::ColorFilter)
+    color_filter: u32,
+}
+@group(0) @binding(2)
+var<uniform> u: Uniforms;
+
 @vertex
 fn vs_main(@builtin(vertex_index) in_vertex_index: u32) -> VertexOutput {
     var out: VertexOutput;
@@ -12,8 +50,28 @@ fn vs_main(@builtin(vertex_index) in_vertex_index: u32) -> VertexOutput {
     let x = f32((in_vertex_index & 1u) << 1u);
     let y = f32((in_vertex_index & 2u));
     
-    out.clip_position = vec4<f32>(x * 2.0 - 1.0, 1.0 - y * 2.0, 0.0, 1.0);
-    out.tex_coords = vec2<f32>(x, y);
+    out.clip_position = vec4<f32>(
+        (x * 2.0 - 1.0) * u.scale.x * u.zoom + u.pan.x,
+        (1.0 - y * 2.0) * u.scale.y * u.zoom + u.pan.y,
+        0.0,
+        1.0,
+    );
+    // Rotate texture lookups about the center so the image turns clockwise on screen
+    var d = vec2<f32>(x, y) - vec2<f32>(0.5);
+    if ((u.flip & 1u) != 0u) {
+        d.x = -d.x;
+    }
+    if ((u.flip & 2u) != 0u) {
+        d.y = -d.y;
+    }
+    var r = d;
+    switch (u.rotation) {
+        case 1u: { r = vec2<f32>(d.y, -d.x); }
+        case 2u: { r = -d; }
+        case 3u: { r = vec2<f32>(-d.y, d.x); }
+        default: {}
+    }
+    out.tex_coords = r + vec2<f32>(0.5);
     
     return out;
 }
@@ -23,8 +81,85 @@ fn vs_main(@builtin(vertex_index) in_vertex_index: u32) -> VertexOutput {
 var t_diffuse: texture_2d<f32>;
 @group(0) @binding(1)
 var s_diffuse: sampler;
+@group(0) @binding(3)
+var t_previous: texture_2d<f32>;
+
+fn linear_to_srgb(c: vec3<f32>) -> vec3<f32> {
+    let lo = c * 12.92;
+    let hi = 1.055 * pow(c, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(hi, lo, c <= vec3<f32>(0.0031308));
+}
+
+fn srgb_to_linear(c: vec3<f32>) -> vec3<f32> {
+    let lo = c / 12.92;
+    let hi = pow((c + 0.055) / 1.055, vec3<f32>(2.4));
+    return select(hi, lo, c <= vec3<f32>(0.04045));
+}
+
+// Averages the area of `texels` (frame texels per screen pixel) around tc with a grid of
+// bilinear taps, so frames drawn far below their size (GPU scaling) don't shimmer. Up to two
+// texels per pixel this is the single bilinear tap of plain sampling.
+fn sample_frame(t: texture_2d<f32>, tc: vec2<f32>, texels: vec2<f32>) -> vec4<f32> {
+    let taps = vec2<i32>(clamp(ceil(texels * 0.5), vec2<f32>(1.0), vec2<f32>(4.0)));
+    let step = texels / vec2<f32>(taps) / vec2<f32>(textureDimensions(t));
+    let start = tc - step * (vec2<f32>(taps) - vec2<f32>(1.0)) * 0.5;
+    var sum = vec4<f32>(0.0);
+    for (var y = 0; y < taps.y; y++) {
+        for (var x = 0; x < taps.x; x++) {
+            sum += textureSampleLevel(t, s_diffuse, start + step * vec2<f32>(f32(x), f32(y)), 0.0);
+        }
+    }
+    return sum / f32(taps.x * taps.y);
+}
 
+// The frame texture is *Srgb, so samples arrive in linear RGB and all adjustments run there.
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    return textureSample(t_diffuse, s_diffuse, in.tex_coords);
+    // The covering triangles reach past the image; fill that part with the letterbox color
+    let outside = any(in.tex_coords < vec2<f32>(0.0)) || any(in.tex_coords > vec2<f32>(1.0));
+    var tc = in.tex_coords;
+    var texels = fwidth(in.tex_coords) * vec2<f32>(textureDimensions(t_diffuse));
+    if (u.nearest != 0u) {
+        // Sample texel centers: the linear sampler then returns single texels
+        let size = vec2<f32>(textureDimensions(t_diffuse));
+        tc = (floor(tc * size) + vec2<f32>(0.5)) / size;
+        texels = vec2<f32>(1.0);
+    }
+    let current = sample_frame(t_diffuse, tc, texels);
+    let previous = sample_frame(t_previous, tc, texels);
+    let c = mix(previous, current, u.blend);
+    if (outside) {
+        return u.background;
+    }
+    let g = pow(c.rgb, vec3<f32>(1.0 / u.gamma));
+    let adj = vec3<f32>(
+        dot(u.adjust_row0.xyz, g) + u.adjust_row0.w,
+        dot(u.adjust_row1.xyz, g) + u.adjust_row1.w,
+        dot(u.adjust_row2.xyz, g) + u.adjust_row2.w,
+    );
+    let rgb = vec3<f32>(
+        dot(u.color_row0.xyz, adj),
+        dot(u.color_row1.xyz, adj),
+        dot(u.color_row2.xyz, adj),
+    );
+    var out = clamp(rgb, vec3<f32>(0.0), vec3<f32>(1.0));
+    switch (u.color_filter) {
+        // Rec. 709 luminance
+        case 1u: { out = vec3<f32>(dot(out, vec3<f32>(0.2126, 0.7152, 0.0722))); }
+        case 2u: { out = srgb_to_linear(vec3<f32>(1.0) - linear_to_srgb(out)); }
+        default: {}
+    }
+    if (u.encode_srgb != 0u) {
+        out = linear_to_srgb(out);
+    }
+    // Fade the window's rounded corners (black in Windows 11 captures) into the background
+    if (u.corner_radius > 0.0) {
+        let size = vec2<f32>(textureDimensions(t_diffuse));
+        let q = abs(in.tex_coords * size - size * 0.5) - (size * 0.5 - vec2<f32>(u.corner_radius));
+        let d = length(max(q, vec2<f32>(0.0)));
+        out = out * clamp(u.corner_radius - d + 0.5, 0.0, 1.0);
+    }
+    out = out * u.output_scale;
+    // The frame's fourth byte is padding, not alpha
+    return vec4<f32>(out, 1.0);
 }
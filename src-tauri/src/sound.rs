@@ -0,0 +1,103 @@
+use rodio::source::{SineWave, Source, Zero};
+use rodio::{OutputStream, Sink};
+use serde::{Deserialize, Serialize};
+use std::thread;
+use std::time::Duration;
+
+const SAMPLE_RATE: u32 = 44_100;
+
+/// Events that can play a sound cue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cue {
+    Started,
+    Stopped,
+    Error,
+}
+
+/// Sound cue settings: a master switch, one switch per event and the volume.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SoundCues {
+    pub enabled: bool,
+    pub on_start: bool,
+    pub on_stop: bool,
+    pub on_error: bool,
+    /// 0.0..=1.0.
+    pub volume: f32,
+}
+
+impl Default for SoundCues {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            on_start: true,
+            on_stop: true,
+            on_error: true,
+            volume: 0.5,
+        }
+    }
+}
+
+impl SoundCues {
+    pub fn clamped(mut self) -> Self {
+        self.volume = if self.volume.is_finite() {
+            self.volume.clamp(0.0, 1.0)
+        } else {
+            Self::default().volume
+        };
+        self
+    }
+
+    fn wants(&self, cue: Cue) -> bool {
+        self.enabled
+            && self.volume > 0.0
+            && match cue {
+                Cue::Started => self.on_start,
+                Cue::Stopped => self.on_stop,
+                Cue::Error => self.on_error,
+            }
+    }
+}
+
+/// Notes of each cue as (frequency in Hz, milliseconds); frequency 0 is a pause. Rising for
+/// start, falling for stop, a low double beep for errors.
+fn notes(cue: Cue) -> &'static [(f32, u64)] {
+    match cue {
+        Cue::Started => &[(660.0, 70), (880.0, 110)],
+        Cue::Stopped => &[(880.0, 70), (660.0, 110)],
+        Cue::Error => &[(330.0, 90), (0.0, 60), (330.0, 90)],
+    }
+}
+
+/// Plays `cue` if enabled. Fire-and-forget on its own thread; a missing audio device or any
+/// other playback error is ignored.
+pub fn play(settings: SoundCues, cue: Cue) {
+    if !settings.wants(cue) {
+        return;
+    }
+    let _ = thread::Builder::new()
+        .name("sound-cue".into())
+        .spawn(move || {
+            let Ok((_stream, handle)) = OutputStream::try_default() else {
+                return;
+            };
+            let Ok(sink) = Sink::try_new(&handle) else {
+                return;
+            };
+            sink.set_volume(settings.volume);
+            for &(frequency, ms) in notes(cue) {
+                let duration = Duration::from_millis(ms);
+                if frequency > 0.0 {
+                    sink.append(
+                        SineWave::new(frequency)
+                            .take_duration(duration)
+                            .fade_in(Duration::from_millis(5))
+                            .amplify(0.4),
+                    );
+                } else {
+                    sink.append(Zero::<f32>::new(1, SAMPLE_RATE).take_duration(duration));
+                }
+            }
+            sink.sleep_until_end();
+        });
+}
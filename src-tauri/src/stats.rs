@@ -0,0 +1,305 @@
+use crate::CaptureState;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::State;
+
+/// Span the average rates are taken over.
+const RATE_WINDOW: Duration = Duration::from_secs(5);
+/// Minimum time between two samples of the counters.
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+/// The preview counts as behind when it loses at least this share of the frames the capture
+/// thread hands it, and at least `BEHIND_MIN_FPS` of them.
+const BEHIND_SHARE: f32 = 0.2;
+const BEHIND_MIN_FPS: f32 = 5.0;
+/// Minimum time between two "preview-behind" events of a session.
+const BEHIND_REPEAT: Duration = Duration::from_secs(60);
+/// Span the latency figures are taken over.
+const LATENCY_WINDOW: Duration = Duration::from_secs(1);
+
+/// Frames counted at each stage between the capture backend and the screen.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct StageCounts {
+    /// Delivered by the capture backend.
+    pub captured: u64,
+    /// Dropped by the capture thread for arriving sooner than the frame interval allows.
+    pub throttled: u64,
+    /// Dropped unconverted while none of the session's windows could be seen and nothing else
+    /// (recording, replay buffer) takes frames.
+    pub unseen: u64,
+    /// Handed to the converter thread but replaced by a newer frame before it took it.
+    pub discarded: u64,
+    /// Handed to the preview but replaced by a newer frame before the preview took it.
+    pub overwritten: u64,
+    /// Uploaded by the preview window.
+    pub displayed: u64,
+    /// Presented by the preview window, including redraws without a new frame.
+    pub rendered: u64,
+}
+
+/// Per-second averages of `StageCounts` over the last few seconds.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct StageRates {
+    pub captured: f32,
+    pub throttled: f32,
+    pub unseen: f32,
+    pub discarded: f32,
+    pub overwritten: f32,
+    pub displayed: f32,
+    pub rendered: f32,
+}
+
+/// Frame counters of one session, written by its capture thread and preview window.
+#[derive(Debug, Default)]
+pub struct CaptureStats {
+    captured: AtomicU64,
+    throttled: AtomicU64,
+    unseen: AtomicU64,
+    discarded: AtomicU64,
+    overwritten: AtomicU64,
+    displayed: AtomicU64,
+    rendered: AtomicU64,
+    /// Counter samples about `SAMPLE_INTERVAL` apart covering `RATE_WINDOW`, oldest first.
+    history: Mutex<VecDeque<(Instant, StageCounts)>>,
+    /// When `behind` last looked at the rates and last reported the preview behind.
+    behind_checks: Mutex<(Option<Instant>, Option<Instant>)>,
+    /// (presented at, latency) of the frames presented within `LATENCY_WINDOW`, oldest first.
+    latencies: Mutex<VecDeque<(Instant, Duration)>>,
+    /// Capture timestamps of the frames the throttle let through within `RATE_WINDOW`.
+    admitted: Mutex<VecDeque<Instant>>,
+}
+
+/// Time from the capture thread receiving a frame from the source to the preview presenting
+/// it, over the last `LATENCY_WINDOW`.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Latency {
+    pub min_ms: f32,
+    pub avg_ms: f32,
+    pub max_ms: f32,
+}
+
+/// Payload of "preview-behind": the preview loses a good share of its frames, so a lower
+/// frame rate or resolution may serve better.
+#[derive(Debug, Clone, Serialize)]
+pub struct PreviewBehind {
+    session_id: u32,
+    /// Frames per second handed to the preview, and those overwritten before it took them.
+    offered_fps: f32,
+    overwritten_fps: f32,
+}
+
+impl CaptureStats {
+    pub fn count_captured(&self) {
+        self.captured.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn count_throttled(&self) {
+        self.throttled.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn count_unseen(&self) {
+        self.unseen.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn count_discarded(&self) {
+        self.discarded.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn count_overwritten(&self) {
+        self.overwritten.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn count_displayed(&self) {
+        self.displayed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn count_rendered(&self) {
+        self.rendered.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn counts(&self) -> StageCounts {
+        StageCounts {
+            captured: self.captured.load(Ordering::Relaxed),
+            throttled: self.throttled.load(Ordering::Relaxed),
+            unseen: self.unseen.load(Ordering::Relaxed),
+            discarded: self.discarded.load(Ordering::Relaxed),
+            overwritten: self.overwritten.load(Ordering::Relaxed),
+            displayed: self.displayed.load(Ordering::Relaxed),
+            rendered: self.rendered.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Records the counters for the rate average if the last sample is old enough. Called by
+    /// the capture thread for every frame and by get_capture_stats, so the rates drop to zero
+    /// while the source is stalled.
+    pub fn sample(&self, now: Instant) {
+        let mut history = self.history.lock().unwrap();
+        if history
+            .back()
+            .is_some_and(|(at, _)| now.saturating_duration_since(*at) < SAMPLE_INTERVAL)
+        {
+            return;
+        }
+        history.push_back((now, self.counts()));
+        // Keep one sample at least RATE_WINDOW old as the base of the average.
+        while history.len() > 1 && now.saturating_duration_since(history[1].0) >= RATE_WINDOW {
+            history.pop_front();
+        }
+    }
+
+    /// Records that a frame the capture thread received at `received_at` was presented at
+    /// `presented_at`.
+    pub fn record_latency(&self, received_at: Instant, presented_at: Instant) {
+        let latency = presented_at.saturating_duration_since(received_at);
+        self.latencies
+            .lock()
+            .unwrap()
+            .push_back((presented_at, latency));
+    }
+
+    /// Records that the throttle let through the frame captured at `captured_at`.
+    pub fn record_admitted(&self, captured_at: Instant) {
+        let mut admitted = self.admitted.lock().unwrap();
+        while admitted
+            .front()
+            .is_some_and(|&at| captured_at.saturating_duration_since(at) > RATE_WINDOW)
+        {
+            admitted.pop_front();
+        }
+        admitted.push_back(captured_at);
+    }
+
+    /// Standard deviation in milliseconds of the gaps between the frames the throttle let
+    /// through within `RATE_WINDOW` before `now`, i.e. how unevenly the output is paced. None
+    /// with fewer than two gaps.
+    pub fn cadence_jitter(&self, now: Instant) -> Option<f32> {
+        let admitted = self.admitted.lock().unwrap();
+        let recent = admitted
+            .iter()
+            .skip_while(|&&at| now.saturating_duration_since(at) > RATE_WINDOW);
+        let gaps: Vec<f32> = recent
+            .clone()
+            .zip(recent.skip(1))
+            .map(|(a, b)| b.saturating_duration_since(*a).as_secs_f32() * 1000.0)
+            .collect();
+        if gaps.len() < 2 {
+            return None;
+        }
+        let mean = gaps.iter().sum::<f32>() / gaps.len() as f32;
+        let variance = gaps.iter().map(|g| (g - mean).powi(2)).sum::<f32>() / gaps.len() as f32;
+        Some(variance.sqrt())
+    }
+
+    /// Latency of the frames presented within `LATENCY_WINDOW` before `now`; None when there
+    /// were none.
+    pub fn latency(&self, now: Instant) -> Option<Latency> {
+        let mut latencies = self.latencies.lock().unwrap();
+        while latencies
+            .front()
+            .is_some_and(|&(at, _)| now.saturating_duration_since(at) > LATENCY_WINDOW)
+        {
+            latencies.pop_front();
+        }
+        let ms = |d: &Duration| d.as_secs_f32() * 1000.0;
+        let values = || latencies.iter().map(|(_, latency)| latency);
+        Some(Latency {
+            min_ms: ms(values().min()?),
+            avg_ms: values().map(ms).sum::<f32>() / latencies.len() as f32,
+            max_ms: ms(values().max()?),
+        })
+    }
+
+    /// Whether the preview of session `session_id` has fallen behind, at most once per
+    /// `BEHIND_REPEAT`. Called by the capture thread for every frame; looks at the rates once
+    /// per `SAMPLE_INTERVAL`.
+    pub fn behind(&self, session_id: u32, now: Instant) -> Option<PreviewBehind> {
+        let mut checks = self.behind_checks.lock().unwrap();
+        let (checked, reported) = &mut *checks;
+        let since = |at: &Option<Instant>| at.map(|at| now.saturating_duration_since(at));
+        if since(checked).is_some_and(|d| d < SAMPLE_INTERVAL)
+            || since(reported).is_some_and(|d| d < BEHIND_REPEAT)
+        {
+            return None;
+        }
+        *checked = Some(now);
+        let (_, rates) = self.snapshot(now);
+        let offered = rates.captured - rates.throttled - rates.unseen - rates.discarded;
+        if rates.overwritten < BEHIND_MIN_FPS || rates.overwritten < offered * BEHIND_SHARE {
+            return None;
+        }
+        *reported = Some(now);
+        Some(PreviewBehind {
+            session_id,
+            offered_fps: offered,
+            overwritten_fps: rates.overwritten,
+        })
+    }
+
+    /// Counts and their averages since the oldest sample.
+    pub fn snapshot(&self, now: Instant) -> (StageCounts, StageRates) {
+        self.sample(now);
+        let counts = self.counts();
+        let history = self.history.lock().unwrap();
+        let Some((since, base)) = history.front().copied() else {
+            return (counts, StageRates::default());
+        };
+        let secs = now.saturating_duration_since(since).as_secs_f32();
+        if secs <= 0.0 {
+            return (counts, StageRates::default());
+        }
+        let rate = |current: u64, then: u64| current.saturating_sub(then) as f32 / secs;
+        let rates = StageRates {
+            captured: rate(counts.captured, base.captured),
+            throttled: rate(counts.throttled, base.throttled),
+            unseen: rate(counts.unseen, base.unseen),
+            discarded: rate(counts.discarded, base.discarded),
+            overwritten: rate(counts.overwritten, base.overwritten),
+            displayed: rate(counts.displayed, base.displayed),
+            rendered: rate(counts.rendered, base.rendered),
+        };
+        (counts, rates)
+    }
+}
+
+/// Statistics of one running session.
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionStats {
+    session_id: u32,
+    /// Counted since the session started.
+    counts: StageCounts,
+    /// Frames skipped for being identical to the previous one (`skip_duplicate_frames`).
+    duplicates: u64,
+    /// Whether one of the session's windows can be seen (not minimized, hidden or occluded).
+    visible: bool,
+    /// Average frames per second of each stage over the last few seconds.
+    fps: StageRates,
+    /// Over the last second; None while no frame was presented in it.
+    latency: Option<Latency>,
+    /// Spread of the intervals between the frames the throttle let through, in milliseconds.
+    cadence_jitter_ms: Option<f32>,
+}
+
+/// Frame counters of every running session, oldest first, to tell whether the source, the
+/// capture thread or the preview drops frames.
+#[tauri::command]
+pub fn get_capture_stats(state: State<CaptureState>) -> Vec<SessionStats> {
+    let now = Instant::now();
+    state
+        .preview_sessions()
+        .iter()
+        .map(|p| {
+            let (counts, fps) = p.stats.snapshot(now);
+            SessionStats {
+                session_id: p.id,
+                counts,
+                duplicates: p.duplicate_frames(),
+                visible: !p.is_unseen(),
+                fps,
+                latency: p.stats.latency(now),
+                cadence_jitter_ms: p.stats.cadence_jitter(now),
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,63 @@
+use scap::{get_all_targets, Target};
+use std::ops::Deref;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// A cached list older than this is taken again before a capture starts from it.
+const MAX_AGE: Duration = Duration::from_secs(5);
+
+/// scap targets that can move to another thread. On Windows they hold their window or
+/// monitor handle, which makes them !Send only for being a raw pointer.
+#[derive(Default)]
+pub struct Targets(Vec<Target>);
+
+// SAFETY: HWND and HMONITOR values are process-wide identifiers without thread affinity; the
+// capture thread only passes them back to scap and the window functions that take them.
+unsafe impl Send for Targets {}
+
+impl Targets {
+    pub fn into_vec(self) -> Vec<Target> {
+        self.0
+    }
+}
+
+impl Deref for Targets {
+    type Target = [Target];
+
+    fn deref(&self) -> &[Target] {
+        &self.0
+    }
+}
+
+/// The target list last taken from scap, and when. get_capture_targets and the target watcher
+/// refresh it, so starting a capture resolves its target from the list it was picked from
+/// instead of listing all targets again.
+#[derive(Default)]
+pub struct TargetCache {
+    list: Mutex<Option<(Instant, Targets)>>,
+}
+
+impl TargetCache {
+    /// Lists the targets now and keeps the list.
+    pub fn refresh(&self) -> Targets {
+        let targets = get_all_targets();
+        self.store(targets.clone());
+        Targets(targets)
+    }
+
+    pub fn store(&self, targets: Vec<Target>) {
+        *self.list.lock().unwrap() = Some((Instant::now(), Targets(targets)));
+    }
+
+    /// The cached list, or a fresh one when there is none younger than `MAX_AGE`.
+    pub fn current(&self) -> Targets {
+        let list = self.list.lock().unwrap();
+        match &*list {
+            Some((at, targets)) if at.elapsed() < MAX_AGE => Targets(targets.to_vec()),
+            _ => {
+                drop(list);
+                self.refresh()
+            }
+        }
+    }
+}
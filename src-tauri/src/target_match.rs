@@ -0,0 +1,194 @@
+use crate::{
+    apps, start_capture_session, target_id, target_title, update_settings, SessionOptions,
+};
+use scap::{get_all_targets, has_permission, Target};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+/// Candidates listed when nothing matches.
+const MAX_CANDIDATES: usize = 5;
+
+/// What a pattern is matched against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MatchKind {
+    Window,
+    Display,
+    /// Executable name of a window's process (Windows only).
+    Process,
+}
+
+impl MatchKind {
+    fn parse(s: &str) -> Result<Self, String> {
+        match s.to_lowercase().as_str() {
+            "window" => Ok(Self::Window),
+            "display" => Ok(Self::Display),
+            "process" => Ok(Self::Process),
+            _ => Err(format!(
+                "Unknown target kind \"{s}\"; use window, display or process."
+            )),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Window => "window",
+            Self::Display => "display",
+            Self::Process => "process",
+        }
+    }
+
+    /// The text of `target` this kind matches against, if it applies to the target at all.
+    fn text(self, target: &Target) -> Option<String> {
+        match (self, target) {
+            (Self::Window, Target::Window(w)) => Some(w.title.clone()),
+            (Self::Display, Target::Display(d)) => Some(d.title.clone()),
+            (Self::Process, Target::Window(w)) => apps::process_name(apps::window_pid(w.id)?),
+            _ => None,
+        }
+    }
+}
+
+/// Payload of "capture-started".
+#[derive(Debug, Clone, Serialize)]
+struct CaptureStarted {
+    id: u32,
+    title: String,
+    /// What the pattern matched: "window", "display" or "process".
+    matched: &'static str,
+}
+
+/// Edit distance between `pattern` and the closest substring of `text` (Sellers' algorithm),
+/// so "firefx" is close to "Mozilla Firefox" however long the title is.
+fn substring_distance(pattern: &[char], text: &[char]) -> usize {
+    let mut row: Vec<usize> = (0..=pattern.len()).collect();
+    let mut best = row[pattern.len()];
+    for &t in text {
+        let mut diagonal = row[0];
+        row[0] = 0;
+        for (i, &p) in pattern.iter().enumerate() {
+            let substituted = diagonal + usize::from(p != t);
+            diagonal = row[i + 1];
+            row[i + 1] = substituted.min(row[i] + 1).min(row[i + 1] + 1);
+        }
+        best = best.min(row[pattern.len()]);
+    }
+    best
+}
+
+/// Starts capturing the first target whose title (or, for kind "process", executable name)
+/// contains `pattern`, ignoring case. Without `kind`, windows are searched first, then displays,
+/// then process names. Emits "capture-started" with the chosen target and returns its title;
+/// fails with the closest candidates when nothing matches.
+#[tauri::command]
+pub fn start_capture_by_title(
+    app: AppHandle,
+    pattern: String,
+    kind: Option<String>,
+) -> Result<String, String> {
+    let pattern = pattern.trim().to_lowercase();
+    if pattern.is_empty() {
+        return Err("The pattern is empty.".to_string());
+    }
+    let kinds = match kind.as_deref() {
+        Some(kind) => vec![MatchKind::parse(kind)?],
+        None => vec![MatchKind::Window, MatchKind::Display, MatchKind::Process],
+    };
+    if !has_permission() {
+        return Err("Screen capture permission has not been granted.".to_string());
+    }
+
+    let targets = get_all_targets();
+    let candidates: Vec<(MatchKind, &Target, String)> = kinds
+        .iter()
+        .flat_map(|&kind| {
+            targets
+                .iter()
+                .filter_map(move |t| Some((kind, t, kind.text(t)?)))
+        })
+        .collect();
+    let Some((kind, target, _)) = candidates
+        .iter()
+        .find(|(_, _, text)| text.to_lowercase().contains(&pattern))
+    else {
+        let pattern: Vec<char> = pattern.chars().collect();
+        let mut close: Vec<(usize, String)> = candidates
+            .iter()
+            .map(|(_, _, text)| {
+                let lower: Vec<char> = text.to_lowercase().chars().collect();
+                (substring_distance(&pattern, &lower), text.clone())
+            })
+            .collect();
+        close.sort();
+        close.dedup_by(|a, b| a.1 == b.1);
+        let close: Vec<String> = close
+            .into_iter()
+            .take(MAX_CANDIDATES)
+            .map(|(_, text)| format!("\"{text}\""))
+            .collect();
+        return Err(if close.is_empty() {
+            "No capture targets found.".to_string()
+        } else {
+            format!("No target matches. Closest: {}.", close.join(", "))
+        });
+    };
+
+    let (id, title) = (target_id(target), target_title(target).to_string());
+    update_settings(&app, |s| {
+        s.target_id = Some(id);
+        s.target_pid = None;
+    })?;
+    start_capture_session(&app, SessionOptions::default())?;
+    let _ = app.emit(
+        "capture-started",
+        CaptureStarted {
+            id,
+            title: title.clone(),
+            matched: kind.name(),
+        },
+    );
+    Ok(title)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn distance(pattern: &str, text: &str) -> usize {
+        let chars = |s: &str| s.chars().collect::<Vec<char>>();
+        substring_distance(&chars(pattern), &chars(text))
+    }
+
+    #[test]
+    fn a_substring_is_an_exact_match() {
+        assert_eq!(distance("firefox", "mozilla firefox"), 0);
+        assert_eq!(distance("moz", "mozilla firefox"), 0);
+        assert_eq!(distance("", "mozilla firefox"), 0);
+    }
+
+    #[test]
+    fn counts_the_edits_to_the_closest_substring() {
+        assert_eq!(distance("firefx", "mozilla firefox"), 1);
+        assert_eq!(distance("fierfox", "mozilla firefox"), 2);
+        assert_eq!(distance("firefox", "mozilla"), 6);
+        // The length of the text does not add to the distance.
+        let long = format!("{}firefox{}", "x".repeat(200), "y".repeat(200));
+        assert_eq!(distance("firefx", &long), 1);
+    }
+
+    #[test]
+    fn an_empty_text_costs_the_whole_pattern() {
+        assert_eq!(distance("firefox", ""), 7);
+        assert_eq!(distance("", ""), 0);
+    }
+
+    #[test]
+    fn parses_kinds_in_any_case() {
+        assert_eq!(MatchKind::parse("Window"), Ok(MatchKind::Window));
+        assert_eq!(MatchKind::parse("DISPLAY"), Ok(MatchKind::Display));
+        assert_eq!(
+            MatchKind::parse("process").map(MatchKind::name),
+            Ok("process")
+        );
+        assert!(MatchKind::parse("tab").is_err());
+    }
+}
@@ -0,0 +1,113 @@
+use crate::error_log::{self, Level};
+use crate::{
+    start_capture_session, stop_capture_session, update_settings, CaptureSettings, CaptureState,
+    SessionOptions, TargetDto,
+};
+use std::sync::Mutex;
+use tauri::menu::{CheckMenuItem, PredefinedMenuItem, Submenu};
+use tauri::{AppHandle, Manager, Wry};
+
+/// Tray menu item IDs of targets are this prefix followed by the target ID.
+const ID_PREFIX: &str = "target:";
+/// Longest item label, in characters; longer window titles are cut.
+const MAX_LABEL_CHARS: usize = 48;
+/// Groups of the submenu, in order, by `TargetDto::kind`.
+const KINDS: [&str; 3] = ["display", "window", "virtual"];
+
+/// The tray's "Capture target" submenu. It is built from the cached target list
+/// (`TargetCache`) and rebuilt when the target watcher sees targets come or go.
+pub struct TargetMenu {
+    submenu: Submenu<Wry>,
+    items: Mutex<Vec<(u32, CheckMenuItem<Wry>)>>,
+}
+
+impl TargetMenu {
+    pub fn new(submenu: Submenu<Wry>) -> Self {
+        Self {
+            submenu,
+            items: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Replaces the items with `targets`, displays first, and checks the selected one.
+    pub fn rebuild(&self, app: &AppHandle, targets: &[TargetDto], settings: &CaptureSettings) {
+        let mut items = self.items.lock().unwrap();
+        while let Ok(Some(_)) = self.submenu.remove_at(0) {}
+        items.clear();
+        for kind in KINDS {
+            let group: Vec<&TargetDto> = targets.iter().filter(|t| t.kind == kind).collect();
+            if group.is_empty() {
+                continue;
+            }
+            if !items.is_empty() {
+                if let Ok(separator) = PredefinedMenuItem::separator(app) {
+                    let _ = self.submenu.append(&separator);
+                }
+            }
+            for target in group {
+                let id = format!("{ID_PREFIX}{}", target.id);
+                let (label, checked) = (label(&target.title), is_selected(settings, target.id));
+                match CheckMenuItem::with_id(app, id, label, true, checked, None::<&str>) {
+                    Ok(item) => {
+                        let _ = self.submenu.append(&item);
+                        items.push((target.id, item));
+                    }
+                    Err(e) => {
+                        let message = format!("Tray target {}: {e}", target.id);
+                        error_log::record(app, Level::Warning, message);
+                    }
+                }
+            }
+        }
+        let _ = self.submenu.set_enabled(!items.is_empty());
+    }
+
+    /// Moves the checkmark to the selected target.
+    pub fn sync(&self, settings: &CaptureSettings) {
+        for (id, item) in self.items.lock().unwrap().iter() {
+            let _ = item.set_checked(is_selected(settings, *id));
+        }
+    }
+}
+
+/// Whether `id` is the target captured next; an application target selects none of them.
+fn is_selected(settings: &CaptureSettings, id: u32) -> bool {
+    settings.target_pid.is_none() && settings.target_id == Some(id)
+}
+
+fn label(title: &str) -> String {
+    if title.chars().count() > MAX_LABEL_CHARS {
+        title
+            .chars()
+            .take(MAX_LABEL_CHARS - 3)
+            .chain("...".chars())
+            .collect()
+    } else {
+        title.to_string()
+    }
+}
+
+/// The target ID of a tray menu item ID from `TargetMenu`.
+pub fn target_from_id(id: &str) -> Option<u32> {
+    id.strip_prefix(ID_PREFIX)?.parse().ok()
+}
+
+/// Makes target `id` the selected target; a running capture moves to it.
+pub fn select(app: &AppHandle, id: u32) -> Result<(), String> {
+    update_settings(app, |s| {
+        s.target_id = Some(id);
+        s.target_pid = None;
+    })?;
+    let state = app.state::<CaptureState>();
+    if state.is_capturing() {
+        stop_capture_session(&state);
+        start_capture_session(
+            app,
+            SessionOptions {
+                target_id: Some(id),
+                ..Default::default()
+            },
+        )?;
+    }
+    Ok(())
+}
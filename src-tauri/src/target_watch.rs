@@ -0,0 +1,59 @@
+use crate::target_menu::TargetMenu;
+use crate::{emit_capture_error, stop_session, target_dtos, target_id, target_title, CaptureState};
+use scap::{get_all_targets, has_permission};
+use std::collections::BTreeMap;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Polls the target list and emits "targets-changed" with fresh DTOs (and rebuilds the tray's
+/// target submenu) when a display or window appears or disappears (title changes alone don't
+/// count; browsers retitle constantly). Ends each session whose target is among the missing
+/// with a "target lost" error, unless `auto_reconnect` will wait for it.
+pub fn spawn_target_watcher(app: AppHandle) {
+    thread::spawn(move || {
+        let mut known: Option<BTreeMap<u32, String>> = None;
+        loop {
+            thread::sleep(POLL_INTERVAL);
+            // Listing targets must not trigger the permission prompt.
+            if !has_permission() {
+                continue;
+            }
+            let targets = get_all_targets();
+            app.state::<CaptureState>().targets.store(targets.clone());
+            let current: BTreeMap<u32, String> = targets
+                .iter()
+                .map(|t| (target_id(t), target_title(t).to_string()))
+                .collect();
+            let Some(previous) = known.replace(current.clone()) else {
+                continue;
+            };
+            if previous.keys().eq(current.keys()) {
+                continue;
+            }
+            let dtos = target_dtos(targets);
+            let state = app.state::<CaptureState>();
+            if let Some(menu) = app.try_state::<TargetMenu>() {
+                menu.rebuild(&app, &dtos, &state.settings.lock().unwrap());
+            }
+            let _ = app.emit("targets-changed", dtos);
+
+            if state.settings.lock().unwrap().auto_reconnect {
+                continue;
+            }
+            for preview in state.preview_sessions() {
+                let Some(id) = preview.capture_target().filter(|id| !current.contains_key(id))
+                else {
+                    continue;
+                };
+                // Unless it was stopped in the meantime.
+                if stop_session(&state, preview.id) {
+                    let title = previous.get(&id).map(String::as_str).unwrap_or("unknown");
+                    emit_capture_error(&app, format!("Capture target lost: \"{title}\" is gone."));
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,114 @@
+use crate::overlay::DIGITS;
+use scap::frame::{BGRAFrame, Frame};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Target id of the test pattern; scap never hands out this id.
+pub const TARGET_ID: u32 = u32::MAX;
+pub const TITLE: &str = "Test pattern";
+/// Frame size when the output resolution is "captured".
+pub const DEFAULT_SIZE: (u32, u32) = (1920, 1080);
+
+/// 75% color bars, left to right, as 0xRRGGBB.
+const BARS: [u32; 7] = [
+    0xBFBFBF, 0xBFBF00, 0x00BFBF, 0x00BF00, 0xBF00BF, 0xBF0000, 0x0000BF,
+];
+/// Pixels the gradient moves per frame, relative to a 1920-pixel-wide frame.
+const GRADIENT_SPEED: u64 = 8;
+
+/// Synthetic source: color bars, a scrolling gradient and a counter row with the frame number
+/// and its nominal timestamp in milliseconds. Frame n is always the same image, so it can
+/// drive the pipeline deterministically without capture permission.
+pub struct TestPattern {
+    width: u32,
+    height: u32,
+    fps: u32,
+    interval: Duration,
+    next_at: Instant,
+    frame: u64,
+}
+
+impl TestPattern {
+    pub fn new(fps: u32, (width, height): (u32, u32)) -> Self {
+        let fps = fps.max(1);
+        Self {
+            width: width.max(64),
+            height: height.max(64),
+            fps,
+            interval: Duration::from_secs(1) / fps,
+            next_at: Instant::now(),
+            frame: 0,
+        }
+    }
+
+    /// Waits until the next frame is due, then renders it.
+    pub fn next_frame(&mut self) -> Frame {
+        let now = Instant::now();
+        if self.next_at > now {
+            thread::sleep(self.next_at - now);
+        }
+        // Don't try to catch up after a stall.
+        self.next_at = (self.next_at + self.interval).max(now);
+        let data = self.render();
+        self.frame += 1;
+        Frame::BGRA(BGRAFrame {
+            display_time: 0,
+            width: self.width as i32,
+            height: self.height as i32,
+            data,
+        })
+    }
+
+    /// Renders frame `self.frame` as BGRA bytes.
+    fn render(&self) -> Vec<u8> {
+        let (w, h) = (self.width as usize, self.height as usize);
+        let bars_end = h * 3 / 5;
+        let gradient_end = h * 4 / 5;
+        let shift = (self.frame * GRADIENT_SPEED * w as u64 / 1920) as usize;
+        let mut data = vec![0u8; w * h * 4];
+        for (y, row) in data.chunks_exact_mut(w * 4).enumerate() {
+            for (x, px) in row.chunks_exact_mut(4).enumerate() {
+                let rgb = if y < bars_end {
+                    BARS[x * BARS.len() / w]
+                } else if y < gradient_end {
+                    let v = ((x + shift) % w * 255 / w) as u32;
+                    (v << 16) | (v << 8) | v
+                } else {
+                    0
+                };
+                px.copy_from_slice(&[rgb as u8, (rgb >> 8) as u8, (rgb >> 16) as u8, 255]);
+            }
+        }
+
+        // Counter row: frame number on the left, nominal milliseconds on the right.
+        let cell = ((h - gradient_end) / 9).max(1);
+        let top = gradient_end + 2 * cell;
+        let millis = self.frame * 1000 / self.fps as u64;
+        draw_number(&mut data, w, self.frame, 2 * cell, top, cell);
+        draw_number(&mut data, w, millis, w / 2, top, cell);
+        data
+    }
+}
+
+/// Draws `value` in white with `cell`-pixel font cells, clipped to the frame.
+fn draw_number(data: &mut [u8], width: usize, value: u64, x: usize, y: usize, cell: usize) {
+    let height = data.len() / (width * 4);
+    for (i, ch) in value.to_string().bytes().enumerate() {
+        let glyph = DIGITS[(ch - b'0') as usize];
+        let left = x + i * 4 * cell;
+        for (row, bits) in glyph.iter().enumerate() {
+            for col in 0..3 {
+                if bits & (0b100 >> col) == 0 {
+                    continue;
+                }
+                let (x0, y0) = (left + col * cell, y + row * cell);
+                for py in y0..(y0 + cell).min(height) {
+                    for px in x0..(x0 + cell).min(width) {
+                        let at = (py * width + px) * 4;
+                        data[at..at + 4].copy_from_slice(&[255, 255, 255, 255]);
+                    }
+                }
+            }
+        }
+    }
+}
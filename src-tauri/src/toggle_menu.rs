@@ -0,0 +1,116 @@
+use crate::display::ColorFilter;
+use crate::{update_settings, CaptureSettings};
+use tauri::menu::CheckMenuItem;
+use tauri::{AppHandle, Wry};
+
+/// Preview window options switched from the tray (and from shortcuts in the preview window or
+/// the global hotkey).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Toggle {
+    AlwaysOnTop,
+    ClickThrough,
+    SkipTaskbar,
+    FlipHorizontal,
+    FlipVertical,
+    /// The two color filters exclude each other; turning one on turns the other off.
+    Grayscale,
+    Invert,
+}
+
+impl Toggle {
+    pub const ALL: [Self; 7] = [
+        Self::AlwaysOnTop,
+        Self::ClickThrough,
+        Self::SkipTaskbar,
+        Self::FlipHorizontal,
+        Self::FlipVertical,
+        Self::Grayscale,
+        Self::Invert,
+    ];
+
+    /// Tray menu item ID.
+    pub fn id(self) -> &'static str {
+        match self {
+            Self::AlwaysOnTop => "always_on_top",
+            Self::ClickThrough => "click_through",
+            Self::SkipTaskbar => "skip_taskbar",
+            Self::FlipHorizontal => "flip_horizontal",
+            Self::FlipVertical => "flip_vertical",
+            Self::Grayscale => "filter_grayscale",
+            Self::Invert => "filter_invert",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::AlwaysOnTop => "Always on top",
+            Self::ClickThrough => "Click-through",
+            Self::SkipTaskbar => "Hide from taskbar",
+            Self::FlipHorizontal => "Mirror left-to-right",
+            Self::FlipVertical => "Mirror top-to-bottom",
+            Self::Grayscale => "Grayscale",
+            Self::Invert => "Invert colors",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|t| t.id() == id)
+    }
+
+    pub fn get(self, settings: &CaptureSettings) -> bool {
+        match self {
+            Self::AlwaysOnTop => settings.always_on_top,
+            Self::ClickThrough => settings.click_through,
+            Self::SkipTaskbar => settings.skip_taskbar,
+            Self::FlipHorizontal => settings.flip_horizontal,
+            Self::FlipVertical => settings.flip_vertical,
+            Self::Grayscale => settings.color_filter == ColorFilter::Grayscale,
+            Self::Invert => settings.color_filter == ColorFilter::Invert,
+        }
+    }
+
+    fn set(self, settings: &mut CaptureSettings, on: bool) {
+        match self {
+            Self::AlwaysOnTop => settings.always_on_top = on,
+            Self::ClickThrough => settings.click_through = on,
+            Self::SkipTaskbar => settings.skip_taskbar = on,
+            Self::FlipHorizontal => settings.flip_horizontal = on,
+            Self::FlipVertical => settings.flip_vertical = on,
+            Self::Grayscale | Self::Invert => {
+                let filter = if self == Self::Grayscale {
+                    ColorFilter::Grayscale
+                } else {
+                    ColorFilter::Invert
+                };
+                if on {
+                    settings.color_filter = filter;
+                } else if settings.color_filter == filter {
+                    settings.color_filter = ColorFilter::None;
+                }
+            }
+        }
+    }
+}
+
+/// Flips `toggle` in the settings; the preview windows pick it up with the display options,
+/// without restarting capture.
+pub fn flip(app: &AppHandle, toggle: Toggle) -> Result<(), String> {
+    update_settings(app, |s| toggle.set(s, !toggle.get(s)))
+}
+
+/// The tray's checkboxes for the `Toggle`s, kept in line with the settings by store_settings.
+pub struct ToggleMenu {
+    items: Vec<(Toggle, CheckMenuItem<Wry>)>,
+}
+
+impl ToggleMenu {
+    pub fn new(items: Vec<(Toggle, CheckMenuItem<Wry>)>) -> Self {
+        Self { items }
+    }
+
+    pub fn sync(&self, settings: &CaptureSettings) {
+        for (toggle, item) in &self.items {
+            let _ = item.set_checked(toggle.get(settings));
+        }
+    }
+}
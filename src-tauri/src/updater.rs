@@ -0,0 +1,150 @@
+use crate::CaptureState;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, Url};
+use tauri_plugin_updater::UpdaterExt;
+
+/// Update manifest (latest.json, written by tauri-action) of the newest stable release.
+const STABLE_ENDPOINT: &str =
+    "https://github.com/Katze719/LiteView/releases/latest/download/latest.json";
+/// Manifest of the rolling "beta" prerelease, republished with every beta build.
+const BETA_ENDPOINT: &str =
+    "https://github.com/Katze719/LiteView/releases/download/beta/latest.json";
+
+/// Which releases count as updates.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateChannel {
+    #[default]
+    Stable,
+    /// The latest beta build.
+    Beta,
+}
+
+impl UpdateChannel {
+    fn endpoint(self) -> &'static str {
+        match self {
+            Self::Stable => STABLE_ENDPOINT,
+            Self::Beta => BETA_ENDPOINT,
+        }
+    }
+}
+
+/// Why checking or updating failed; serialized as `{ "kind": ..., "message": ... }`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", content = "message", rename_all = "snake_case")]
+pub enum UpdateError {
+    /// This build has no updater public key (see `has_pubkey`).
+    NotConfigured,
+    /// A capture is running; installing restarts the app.
+    CaptureActive,
+    /// The release server could not be reached.
+    Offline(String),
+    /// The download does not match the release signature.
+    Signature(String),
+    Other(String),
+}
+
+impl From<tauri_plugin_updater::Error> for UpdateError {
+    fn from(e: tauri_plugin_updater::Error) -> Self {
+        use tauri_plugin_updater::Error;
+        match e {
+            Error::Reqwest(_) | Error::Network(_) => Self::Offline(e.to_string()),
+            Error::Minisign(_) | Error::Base64(_) | Error::SignatureUtf8(_) => {
+                Self::Signature(e.to_string())
+            }
+            _ => Self::Other(e.to_string()),
+        }
+    }
+}
+
+/// A release newer than the running version.
+#[derive(Debug, Clone, Serialize)]
+pub struct UpdateInfo {
+    version: String,
+    notes: Option<String>,
+}
+
+/// Download progress, emitted as "update-progress".
+#[derive(Debug, Clone, Serialize)]
+struct UpdateProgress {
+    downloaded: u64,
+    /// None when the server does not send a length.
+    total: Option<u64>,
+}
+
+/// Whether the build has an updater public key. Release builds add it by merging
+/// `{"plugins":{"updater":{"pubkey":...}}}` in through the `TAURI_CONFIG` environment variable;
+/// without one the plugin is not registered at all, since it refuses to start unconfigured.
+pub fn has_pubkey(config: &tauri::Config) -> bool {
+    config
+        .plugins
+        .0
+        .get("updater")
+        .and_then(|c| c.get("pubkey"))
+        .and_then(|k| k.as_str())
+        .is_some_and(|k| !k.trim().is_empty())
+}
+
+async fn find_update(app: &AppHandle) -> Result<Option<tauri_plugin_updater::Update>, UpdateError> {
+    if !has_pubkey(app.config()) {
+        return Err(UpdateError::NotConfigured);
+    }
+    let channel = app
+        .state::<CaptureState>()
+        .settings
+        .lock()
+        .unwrap()
+        .update_channel;
+    let endpoint = Url::parse(channel.endpoint()).map_err(|e| UpdateError::Other(e.to_string()))?;
+    let updater = app.updater_builder().endpoints(vec![endpoint])?.build()?;
+    Ok(updater.check().await?)
+}
+
+#[tauri::command]
+pub async fn check_for_update(app: AppHandle) -> Result<Option<UpdateInfo>, UpdateError> {
+    Ok(find_update(&app).await?.map(|u| UpdateInfo {
+        version: u.version,
+        notes: u.body,
+    }))
+}
+
+/// Downloads and installs the newest release of the selected channel, emitting
+/// "update-progress" while downloading and "update-ready" once a restart will apply it.
+/// Ok(false) means there was nothing to install.
+#[tauri::command]
+pub async fn install_update(app: AppHandle) -> Result<bool, UpdateError> {
+    let capturing = || app.state::<CaptureState>().is_capturing();
+    if capturing() {
+        return Err(UpdateError::CaptureActive);
+    }
+    let Some(update) = find_update(&app).await? else {
+        return Ok(false);
+    };
+    // A capture may have been started while checking.
+    if capturing() {
+        return Err(UpdateError::CaptureActive);
+    }
+    let mut downloaded = 0u64;
+    update
+        .download_and_install(
+            |chunk, total| {
+                downloaded += chunk as u64;
+                let _ = app.emit("update-progress", UpdateProgress { downloaded, total });
+            },
+            || {},
+        )
+        .await?;
+    let _ = app.emit("update-ready", update.version.clone());
+    Ok(true)
+}
+
+/// Restarts into the installed update. Pending settings are written first; restarting skips
+/// the exit handler that normally flushes them, so a failed write keeps LiteView running.
+#[tauri::command]
+pub fn restart_app(app: AppHandle) -> Result<(), String> {
+    app.state::<CaptureState>()
+        .settings_writer
+        .flush()
+        .map_err(|e| format!("Saving settings before the restart: {e}"))?;
+    app.restart();
+}
@@ -0,0 +1,255 @@
+use scap::Target;
+
+/// Tracks the on-screen rectangle of one or more windows so a capture of their monitor can be
+/// cropped to it. Unlike window capture this also shows popups, menus and tooltips drawn over
+/// the windows, which are separate top-level windows on Windows.
+///
+/// Limits: only Windows is supported (elsewhere `for_windows` returns None and the window is
+/// captured on its own); anything else overlapping the windows is shown too; only the monitor
+/// of the first window is captured, and frames are skipped while none of the windows is
+/// visible on it.
+#[derive(Clone)]
+pub struct WindowRegion {
+    #[cfg(target_os = "windows")]
+    hwnds: Vec<isize>,
+    #[cfg(target_os = "windows")]
+    monitor: isize,
+}
+
+#[cfg(target_os = "windows")]
+impl WindowRegion {
+    /// The display target showing the first of windows `ids`, plus a tracker for the bounding
+    /// box of all of them.
+    pub fn for_windows(ids: &[u32], targets: &[Target]) -> Option<(Target, Self)> {
+        use windows::Win32::Foundation::HWND;
+        use windows::Win32::Graphics::Gdi::{MonitorFromWindow, MONITOR_DEFAULTTONULL};
+
+        // scap uses the raw HWND / HMONITOR values as target IDs.
+        let hwnds: Vec<isize> = ids.iter().map(|&id| id as isize).collect();
+        let first = *hwnds.first()?;
+        let monitor = unsafe { MonitorFromWindow(HWND(first as _), MONITOR_DEFAULTTONULL) };
+        if monitor.is_invalid() {
+            return None;
+        }
+        let monitor = monitor.0 as isize;
+        let display = targets
+            .iter()
+            .find(|t| matches!(t, Target::Display(d) if d.id == monitor as u32))?
+            .clone();
+        Some((display, Self { hwnds, monitor }))
+    }
+
+    /// The windows' bounding box (x, y, width, height) within a `frame_w` x `frame_h` capture
+    /// of the monitor. None when none of them is open, visible and on that monitor.
+    pub fn rect(&self, frame_w: u32, frame_h: u32) -> Option<(u32, u32, u32, u32)> {
+        use windows::Win32::Foundation::RECT;
+        use windows::Win32::Graphics::Gdi::{GetMonitorInfoW, HMONITOR, MONITORINFO};
+
+        let mut info = MONITORINFO {
+            cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+            ..Default::default()
+        };
+        if !unsafe { GetMonitorInfoW(HMONITOR(self.monitor as _), &mut info) }.as_bool() {
+            return None;
+        }
+        let window = self
+            .hwnds
+            .iter()
+            .filter_map(|&hwnd| window_bounds(hwnd))
+            .reduce(|a, b| RECT {
+                left: a.left.min(b.left),
+                top: a.top.min(b.top),
+                right: a.right.max(b.right),
+                bottom: a.bottom.max(b.bottom),
+            })?;
+        map_to_frame(&window, &info.rcMonitor, frame_w, frame_h)
+    }
+}
+
+/// Screen rectangle (x, y, width, height) of window `id` in physical pixels; None while it is
+/// minimized, hidden or closed.
+#[cfg(target_os = "windows")]
+pub fn window_rect(id: u32) -> Option<(i32, i32, u32, u32)> {
+    let r = window_bounds(id as isize)?;
+    Some((r.left, r.top, (r.right - r.left).max(0) as u32, (r.bottom - r.top).max(0) as u32))
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn window_rect(_id: u32) -> Option<(i32, i32, u32, u32)> {
+    None
+}
+
+/// Whether window `id` is still open (minimized or not).
+#[cfg(target_os = "windows")]
+pub fn window_exists(id: u32) -> bool {
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::UI::WindowsAndMessaging::IsWindow;
+
+    unsafe { IsWindow(HWND(id as isize as _)) }.as_bool()
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn window_exists(_id: u32) -> bool {
+    false
+}
+
+/// Client area (x, y, width, height) of window `id` within a `frame_w` x `frame_h` capture of
+/// that window: the crop removing its title bar and borders. Measured on every call, since the
+/// chrome changes between maximized and restored. None when there is nothing to trim or it
+/// cannot be measured.
+#[cfg(target_os = "windows")]
+pub fn client_area(id: u32, frame_w: u32, frame_h: u32) -> Option<(u32, u32, u32, u32)> {
+    use windows::Win32::Foundation::{HWND, POINT, RECT};
+    use windows::Win32::Graphics::Gdi::ClientToScreen;
+    use windows::Win32::UI::WindowsAndMessaging::GetClientRect;
+
+    // The window capture shows the extended frame bounds.
+    let bounds = window_bounds(id as isize)?;
+    let hwnd = HWND(id as isize as _);
+    let mut client = RECT::default();
+    let mut origin = POINT::default();
+    unsafe {
+        GetClientRect(hwnd, &mut client).ok()?;
+        if !ClientToScreen(hwnd, &mut origin).as_bool() {
+            return None;
+        }
+    }
+    let client = RECT {
+        left: origin.x,
+        top: origin.y,
+        right: origin.x + client.right,
+        bottom: origin.y + client.bottom,
+    };
+    map_to_frame(&client, &bounds, frame_w, frame_h).filter(|r| (r.2, r.3) != (frame_w, frame_h))
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn client_area(_id: u32, _frame_w: u32, _frame_h: u32) -> Option<(u32, u32, u32, u32)> {
+    None
+}
+
+/// Whether the OS draws rounded window corners (Windows 11, build 22000 and later).
+#[cfg(target_os = "windows")]
+pub fn has_rounded_corners() -> bool {
+    use std::sync::OnceLock;
+    use windows::core::w;
+    use windows::Win32::Foundation::ERROR_SUCCESS;
+    use windows::Win32::System::Registry::{RegGetValueW, HKEY_LOCAL_MACHINE, RRF_RT_REG_SZ};
+
+    static ROUNDED: OnceLock<bool> = OnceLock::new();
+    *ROUNDED.get_or_init(|| {
+        let mut buf = [0u16; 16];
+        let mut size = std::mem::size_of_val(&buf) as u32;
+        let status = unsafe {
+            RegGetValueW(
+                HKEY_LOCAL_MACHINE,
+                w!("SOFTWARE\\Microsoft\\Windows NT\\CurrentVersion"),
+                w!("CurrentBuildNumber"),
+                RRF_RT_REG_SZ,
+                None,
+                Some(buf.as_mut_ptr() as *mut _),
+                Some(&mut size),
+            )
+        };
+        if status != ERROR_SUCCESS {
+            return false;
+        }
+        let len = buf.iter().position(|&c| c == 0).unwrap_or(buf.len());
+        String::from_utf16_lossy(&buf[..len])
+            .parse::<u32>()
+            .is_ok_and(|build| build >= 22000)
+    })
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn has_rounded_corners() -> bool {
+    false
+}
+
+/// Corner radius of window `id` in its physical pixels: 8 px scaled by the window's DPI.
+/// None where corners are square, including maximized windows.
+#[cfg(target_os = "windows")]
+pub fn corner_radius(id: u32) -> Option<f32> {
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::UI::HiDpi::GetDpiForWindow;
+    use windows::Win32::UI::WindowsAndMessaging::IsZoomed;
+
+    if !has_rounded_corners() {
+        return None;
+    }
+    let hwnd = HWND(id as isize as _);
+    unsafe {
+        if IsZoomed(hwnd).as_bool() {
+            return None;
+        }
+        Some(8.0 * GetDpiForWindow(hwnd).max(96) as f32 / 96.0)
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn corner_radius(_id: u32) -> Option<f32> {
+    None
+}
+
+/// Maps screen rectangle `rect` into a `frame_w` x `frame_h` capture of screen area `frame`
+/// (which may be scaled), clipped to the frame. None when nothing of it is inside.
+#[cfg(target_os = "windows")]
+fn map_to_frame(
+    rect: &windows::Win32::Foundation::RECT,
+    frame: &windows::Win32::Foundation::RECT,
+    frame_w: u32,
+    frame_h: u32,
+) -> Option<(u32, u32, u32, u32)> {
+    let (area_w, area_h) = (frame.right - frame.left, frame.bottom - frame.top);
+    if area_w <= 0 || area_h <= 0 {
+        return None;
+    }
+    let sx = frame_w as f64 / area_w as f64;
+    let sy = frame_h as f64 / area_h as f64;
+    let left = ((rect.left.max(frame.left) - frame.left) as f64 * sx).round() as u32;
+    let top = ((rect.top.max(frame.top) - frame.top) as f64 * sy).round() as u32;
+    let right = ((rect.right.min(frame.right) - frame.left) as f64 * sx).round() as u32;
+    let bottom = ((rect.bottom.min(frame.bottom) - frame.top) as f64 * sy).round() as u32;
+    let (right, bottom) = (right.min(frame_w), bottom.min(frame_h));
+    if right <= left || bottom <= top {
+        return None;
+    }
+    Some((left, top, right - left, bottom - top))
+}
+
+/// Screen rectangle of a visible, non-minimized window. Extended frame bounds exclude the
+/// invisible resize borders GetWindowRect includes.
+#[cfg(target_os = "windows")]
+fn window_bounds(hwnd: isize) -> Option<windows::Win32::Foundation::RECT> {
+    use windows::Win32::Foundation::{HWND, RECT};
+    use windows::Win32::Graphics::Dwm::{DwmGetWindowAttribute, DWMWA_EXTENDED_FRAME_BOUNDS};
+    use windows::Win32::UI::WindowsAndMessaging::{IsIconic, IsWindow, IsWindowVisible};
+
+    let hwnd = HWND(hwnd as _);
+    let mut rect = RECT::default();
+    unsafe {
+        if !IsWindow(hwnd).as_bool() || !IsWindowVisible(hwnd).as_bool() || IsIconic(hwnd).as_bool()
+        {
+            return None;
+        }
+        DwmGetWindowAttribute(
+            hwnd,
+            DWMWA_EXTENDED_FRAME_BOUNDS,
+            &mut rect as *mut RECT as *mut _,
+            std::mem::size_of::<RECT>() as u32,
+        )
+        .ok()?;
+    }
+    Some(rect)
+}
+
+#[cfg(not(target_os = "windows"))]
+impl WindowRegion {
+    pub fn for_windows(_ids: &[u32], _targets: &[Target]) -> Option<(Target, Self)> {
+        None
+    }
+
+    pub fn rect(&self, _frame_w: u32, _frame_h: u32) -> Option<(u32, u32, u32, u32)> {
+        None
+    }
+}
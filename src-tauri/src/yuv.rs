@@ -0,0 +1,348 @@
+use crate::parallel;
+use scap::frame::YUVFrame;
+
+/// Color matrix of YUV frames.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum YuvMatrix {
+    /// SD video.
+    Bt601,
+    /// HD video and screen capture.
+    Bt709,
+}
+
+/// Value range of YUV frames.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum YuvRange {
+    /// 16..=235 luma and 16..=240 chroma ("video range").
+    Limited,
+    /// 0..=255 luma and chroma.
+    Full,
+}
+
+/// How the samples of a YUV frame map to RGB.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct YuvFormat {
+    pub matrix: YuvMatrix,
+    pub range: YuvRange,
+}
+
+impl YuvFormat {
+    /// scap's YUV frames: ScreenCaptureKit's video-range 420v with its default BT.709 matrix.
+    pub const SCAP: Self = Self {
+        matrix: YuvMatrix::Bt709,
+        range: YuvRange::Limited,
+    };
+
+    fn coefficients(self) -> Coefficients {
+        // Luma weights of red and blue.
+        let (kr, kb) = match self.matrix {
+            YuvMatrix::Bt601 => (0.299, 0.114),
+            YuvMatrix::Bt709 => (0.2126, 0.0722),
+        };
+        let kg = 1.0 - kr - kb;
+        let (black, luma, chroma) = match self.range {
+            YuvRange::Limited => (16, 255.0 / 219.0, 255.0 / 224.0),
+            YuvRange::Full => (0, 1.0, 1.0),
+        };
+        let fixed = |value: f64| (value * 256.0).round() as i32;
+        Coefficients {
+            black,
+            luma: fixed(luma),
+            v_r: fixed(2.0 * (1.0 - kr) * chroma),
+            u_g: fixed(2.0 * kb * (1.0 - kb) / kg * chroma),
+            v_g: fixed(2.0 * kr * (1.0 - kr) / kg * chroma),
+            u_b: fixed(2.0 * (1.0 - kb) * chroma),
+        }
+    }
+}
+
+/// A `YuvFormat` as `yuv_to_rgb` uses it: the luma black level, then the luma scale and the
+/// chroma contributions to each channel in 8.8 fixed point.
+#[derive(Debug, Clone, Copy)]
+struct Coefficients {
+    black: i32,
+    luma: i32,
+    v_r: i32,
+    u_g: i32,
+    v_g: i32,
+    u_b: i32,
+}
+
+/// Converts an NV12 frame (full-resolution luma plane, then interleaved U/V at half resolution
+/// in both directions) to packed 0x00RRGGBB pixels. Odd widths and heights round the chroma
+/// planes up. None when a plane is too short. Converts on up to `threads` threads.
+pub fn nv12_to_buffer(
+    frame: &YUVFrame,
+    format: YuvFormat,
+    threads: usize,
+    buffer: &mut Vec<u32>,
+) -> Option<()> {
+    let width = usize::try_from(frame.width).ok()?;
+    let height = usize::try_from(frame.height).ok()?;
+    let y_stride = usize::try_from(frame.luminance_stride).ok()?;
+    let uv_stride = usize::try_from(frame.chrominance_stride).ok()?;
+    if width == 0 || height == 0 || y_stride < width || uv_stride < width.div_ceil(2) * 2 {
+        return None;
+    }
+    let (luma, chroma) = (&frame.luminance_bytes, &frame.chrominance_bytes);
+    if luma.len() < y_stride * (height - 1) + width
+        || chroma.len() < uv_stride * (height.div_ceil(2) - 1) + width.div_ceil(2) * 2
+    {
+        return None;
+    }
+
+    let coefficients = format.coefficients();
+    parallel::fill_rows(buffer, width, height, threads, |y| {
+        let luma_row = &luma[y * y_stride..][..width];
+        let chroma_row = &chroma[y / 2 * uv_stride..];
+        luma_row.iter().enumerate().map(move |(x, &l)| {
+            let uv = &chroma_row[x / 2 * 2..];
+            yuv_to_rgb(&coefficients, l, uv[0], uv[1])
+        })
+    })
+}
+
+/// One YUV pixel to 0x00RRGGBB.
+fn yuv_to_rgb(k: &Coefficients, y: u8, u: u8, v: u8) -> u32 {
+    let c = k.luma * (y as i32 - k.black);
+    let d = u as i32 - 128;
+    let e = v as i32 - 128;
+    let channel = |value: i32| ((value + 128) >> 8).clamp(0, 255) as u32;
+    let r = channel(c + k.v_r * e);
+    let g = channel(c - k.u_g * d - k.v_g * e);
+    let b = channel(c + k.u_b * d);
+    (r << 16) | (g << 8) | b
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BT601_LIMITED: YuvFormat = YuvFormat {
+        matrix: YuvMatrix::Bt601,
+        range: YuvRange::Limited,
+    };
+    const BT601_FULL: YuvFormat = YuvFormat {
+        matrix: YuvMatrix::Bt601,
+        range: YuvRange::Full,
+    };
+    const BT709_LIMITED: YuvFormat = YuvFormat::SCAP;
+    const BT709_FULL: YuvFormat = YuvFormat {
+        matrix: YuvMatrix::Bt709,
+        range: YuvRange::Full,
+    };
+
+    fn rgb(pixel: u32) -> [i32; 3] {
+        [
+            (pixel >> 16) as i32 & 0xFF,
+            (pixel >> 8) as i32 & 0xFF,
+            pixel as i32 & 0xFF,
+        ]
+    }
+
+    /// Converts reference `(Y, Cb, Cr)` samples and compares each channel with tolerance.
+    fn assert_decodes(format: YuvFormat, cases: &[([u8; 3], [i32; 3])], tolerance: i32) {
+        let k = format.coefficients();
+        for &([y, u, v], expected) in cases {
+            let actual = rgb(yuv_to_rgb(&k, y, u, v));
+            let off = actual
+                .iter()
+                .zip(expected)
+                .map(|(a, e)| (a - e).abs())
+                .max();
+            assert!(
+                off <= Some(tolerance),
+                "{format:?} ({y}, {u}, {v}): {actual:?}, expected {expected:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn coefficients_match_the_published_fixed_point_values() {
+        let table = |f: YuvFormat| {
+            let k = f.coefficients();
+            [k.black, k.luma, k.v_r, k.u_g, k.v_g, k.u_b]
+        };
+        assert_eq!(table(BT709_LIMITED), [16, 298, 459, 55, 136, 541]);
+        assert_eq!(table(BT709_FULL), [0, 256, 403, 48, 120, 475]);
+        assert_eq!(table(BT601_LIMITED), [16, 298, 409, 100, 208, 516]);
+        assert_eq!(table(BT601_FULL), [0, 256, 359, 88, 183, 454]);
+    }
+
+    #[test]
+    fn grays_are_exact() {
+        for format in [BT601_LIMITED, BT709_LIMITED] {
+            let grays = [
+                ([16, 128, 128], [0, 0, 0]),
+                ([126, 128, 128], [128, 128, 128]),
+                ([235, 128, 128], [255, 255, 255]),
+            ];
+            assert_decodes(format, &grays, 0);
+        }
+        for format in [BT601_FULL, BT709_FULL] {
+            let grays = [
+                ([0, 128, 128], [0, 0, 0]),
+                ([128, 128, 128], [128, 128, 128]),
+                ([255, 128, 128], [255, 255, 255]),
+            ];
+            assert_decodes(format, &grays, 0);
+        }
+    }
+
+    #[test]
+    fn limited_range_clamps_below_black_and_above_white() {
+        assert_decodes(BT709_LIMITED, &[([0, 128, 128], [0, 0, 0])], 0);
+        assert_decodes(BT709_LIMITED, &[([255, 128, 128], [255, 255, 255])], 0);
+    }
+
+    #[test]
+    fn bt709_limited_primaries() {
+        let cases = [
+            ([63, 102, 240], [255, 0, 0]),
+            ([173, 42, 26], [0, 255, 0]),
+            ([32, 240, 118], [0, 0, 255]),
+            ([219, 16, 138], [255, 255, 0]),
+        ];
+        assert_decodes(BT709_LIMITED, &cases, 1);
+    }
+
+    #[test]
+    fn bt709_full_primaries() {
+        let cases = [
+            ([54, 99, 255], [255, 0, 0]),
+            ([182, 30, 12], [0, 255, 0]),
+            ([18, 255, 116], [0, 0, 255]),
+        ];
+        assert_decodes(BT709_FULL, &cases, 1);
+    }
+
+    #[test]
+    fn bt601_limited_primaries() {
+        let cases = [
+            ([81, 90, 240], [255, 0, 0]),
+            ([145, 54, 34], [0, 255, 0]),
+            ([41, 240, 110], [0, 0, 255]),
+            ([210, 16, 146], [255, 255, 0]),
+        ];
+        assert_decodes(BT601_LIMITED, &cases, 1);
+    }
+
+    #[test]
+    fn bt601_full_primaries() {
+        let cases = [
+            ([76, 85, 255], [255, 0, 0]),
+            ([150, 44, 21], [0, 255, 0]),
+            ([29, 255, 107], [0, 0, 255]),
+        ];
+        assert_decodes(BT601_FULL, &cases, 1);
+    }
+
+    #[test]
+    fn the_matrix_matters() {
+        // BT.601 green through the BT.709 matrix loses 15% of its green.
+        let k = BT709_LIMITED.coefficients();
+        assert_eq!(rgb(yuv_to_rgb(&k, 145, 54, 34)), [0, 216, 0]);
+    }
+
+    fn frame(width: i32, height: i32, luma: Vec<u8>, chroma: Vec<u8>) -> YUVFrame {
+        YUVFrame {
+            display_time: 0,
+            width,
+            height,
+            luminance_stride: width,
+            chrominance_stride: (width + 1) / 2 * 2,
+            luminance_bytes: luma,
+            chrominance_bytes: chroma,
+        }
+    }
+
+    #[test]
+    fn nv12_shares_chroma_over_two_by_two_blocks() {
+        // 3×3 luma of limited-range white; chroma rounds up to 2×2: red, blue / gray, gray.
+        let red = [102, 240];
+        let blue = [240, 118];
+        let gray = [128, 128];
+        let chroma = [red, blue, gray, gray].concat();
+        let f = frame(3, 3, vec![235; 9], chroma);
+        let mut buffer = Vec::new();
+        nv12_to_buffer(&f, BT709_LIMITED, 1, &mut buffer).unwrap();
+        assert_eq!(buffer.len(), 9);
+        let k = BT709_LIMITED.coefficients();
+        let at = |uv: [u8; 2]| yuv_to_rgb(&k, 235, uv[0], uv[1]);
+        let expected = [
+            at(red),
+            at(red),
+            at(blue),
+            at(red),
+            at(red),
+            at(blue),
+            at(gray),
+            at(gray),
+            at(gray),
+        ];
+        assert_eq!(buffer, expected);
+        assert_eq!(buffer[8], 0xFFFFFF);
+    }
+
+    #[test]
+    fn nv12_keeps_padded_rows_apart_at_odd_widths() {
+        let height = 5;
+        let luma = |x: usize, y: usize| (16 + (x * 7 + y * 13) % 220) as u8;
+        let chroma = |x: usize, y: usize| [(x * 11 + y * 5) as u8, (x * 3 + y * 17 + 40) as u8];
+        /// `rows` `stride` bytes apart, padded with 0xEE except after the last row.
+        fn plane(rows: impl Iterator<Item = Vec<u8>>, stride: usize) -> Vec<u8> {
+            let mut bytes = Vec::new();
+            for row in rows {
+                bytes.resize(bytes.len().div_ceil(stride) * stride, 0xEE);
+                bytes.extend(row);
+            }
+            bytes
+        }
+        let k = BT709_LIMITED.coefficients();
+        for width in [1_usize, 63, 257] {
+            let chroma_width = width.div_ceil(2);
+            let (y_stride, uv_stride) = (
+                width.div_ceil(64) * 64,
+                (chroma_width * 2).div_ceil(64) * 64,
+            );
+            let luma_rows = (0..height).map(|y| (0..width).map(|x| luma(x, y)).collect());
+            let chroma_rows = (0..height.div_ceil(2))
+                .map(|y| (0..chroma_width).flat_map(|x| chroma(x, y)).collect());
+            let f = YUVFrame {
+                display_time: 0,
+                width: width as i32,
+                height: height as i32,
+                luminance_stride: y_stride as i32,
+                chrominance_stride: uv_stride as i32,
+                luminance_bytes: plane(luma_rows, y_stride),
+                chrominance_bytes: plane(chroma_rows, uv_stride),
+            };
+            let expected: Vec<u32> = (0..height)
+                .flat_map(|y| {
+                    (0..width).map(move |x| {
+                        let [u, v] = chroma(x / 2, y / 2);
+                        yuv_to_rgb(&k, luma(x, y), u, v)
+                    })
+                })
+                .collect();
+            for threads in [1, 3] {
+                let mut buffer = Vec::new();
+                nv12_to_buffer(&f, BT709_LIMITED, threads, &mut buffer).unwrap();
+                assert!(buffer == expected, "width {width}, {threads} threads");
+            }
+        }
+    }
+
+    #[test]
+    fn nv12_rejects_short_planes() {
+        let mut buffer = Vec::new();
+        let f = frame(4, 2, vec![16; 7], vec![128; 4]);
+        assert_eq!(nv12_to_buffer(&f, BT709_LIMITED, 1, &mut buffer), None);
+        let f = frame(4, 2, vec![16; 8], vec![128; 3]);
+        assert_eq!(nv12_to_buffer(&f, BT709_LIMITED, 1, &mut buffer), None);
+        let f = frame(0, 2, Vec::new(), Vec::new());
+        assert_eq!(nv12_to_buffer(&f, BT709_LIMITED, 1, &mut buffer), None);
+        let f = frame(4, 2, vec![16; 8], vec![128; 4]);
+        assert_eq!(nv12_to_buffer(&f, BT709_LIMITED, 1, &mut buffer), Some(()));
+    }
+}
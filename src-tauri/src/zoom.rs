@@ -0,0 +1,161 @@
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+
+/// Magnification of one mouse wheel notch.
+const STEP: f32 = 1.25;
+const MAX_ZOOM: f32 = 16.0;
+/// Largest share of the window either side of the full-frame inset may take.
+const INSET_SHARE: f32 = 0.25;
+/// Widest the inset gets, in pixels.
+const INSET_MAX_WIDTH: f32 = 240.0;
+/// Gap between the inset and the window edges, in pixels.
+const INSET_MARGIN: f32 = 8.0;
+
+/// Zoom and pan of a preview window in clip space: the image quad is scaled by `factor` about
+/// the window center, then moved by `pan`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ZoomView {
+    pub factor: f32,
+    pub pan: [f32; 2],
+}
+
+impl Default for ZoomView {
+    fn default() -> Self {
+        Self {
+            factor: 1.0,
+            pan: [0.0, 0.0],
+        }
+    }
+}
+
+impl ZoomView {
+    pub fn is_zoomed(&self) -> bool {
+        self.factor > 1.0
+    }
+
+    /// Zooms by `notches` wheel steps, keeping the image point under `cursor` (clip space) in
+    /// place. `quad` is the unzoomed image size relative to the window.
+    pub fn zoom_at(&mut self, cursor: [f32; 2], notches: f32, quad: [f32; 2]) {
+        let factor = (self.factor * STEP.powf(notches)).clamp(1.0, MAX_ZOOM);
+        for (pan, cursor) in self.pan.iter_mut().zip(cursor) {
+            let point = (cursor - *pan) / self.factor;
+            *pan = cursor - point * factor;
+        }
+        self.factor = factor;
+        self.clamp(quad);
+    }
+
+    pub fn pan_by(&mut self, delta: [f32; 2], quad: [f32; 2]) {
+        self.pan[0] += delta[0];
+        self.pan[1] += delta[1];
+        self.clamp(quad);
+    }
+
+    /// The part of the image inside the window as (x0, y0, x1, y1) in image coordinates: 0..1
+    /// across the displayed image, origin top-left.
+    pub fn visible(&self, quad: [f32; 2]) -> [f32; 4] {
+        let to_image = |clip: f32, axis: usize| {
+            let size = quad[axis] * self.factor;
+            ((clip - self.pan[axis]) / size.max(f32::EPSILON) + 1.0) / 2.0
+        };
+        [
+            to_image(-1.0, 0),
+            1.0 - to_image(1.0, 1),
+            to_image(1.0, 0),
+            1.0 - to_image(-1.0, 1),
+        ]
+        .map(|v| v.clamp(0.0, 1.0))
+    }
+
+    /// Pans so that `point` (image coordinates, as for `visible`) is at the window center, as
+    /// far as the pan limits allow.
+    pub fn center_on(&mut self, point: [f32; 2], quad: [f32; 2]) {
+        self.pan = [
+            (1.0 - point[0] * 2.0) * quad[0] * self.factor,
+            (point[1] * 2.0 - 1.0) * quad[1] * self.factor,
+        ];
+        self.clamp(quad);
+    }
+
+    /// Keeps the window covered on axes where the zoomed image is larger than the window and
+    /// centers it on the others, so the image cannot be scrolled off screen.
+    pub fn clamp(&mut self, quad: [f32; 2]) {
+        for (pan, quad) in self.pan.iter_mut().zip(quad) {
+            let slack = (quad * self.factor - 1.0).max(0.0);
+            *pan = pan.clamp(-slack, slack);
+        }
+    }
+}
+
+/// A window position in physical pixels in clip space (y up).
+pub fn to_clip(position: PhysicalPosition<f64>, size: PhysicalSize<u32>) -> [f32; 2] {
+    let width = size.width.max(1) as f64;
+    let height = size.height.max(1) as f64;
+    [
+        (position.x / width * 2.0 - 1.0) as f32,
+        (1.0 - position.y / height * 2.0) as f32,
+    ]
+}
+
+/// Pixel rectangle (x, y, width, height) of the full-frame inset in the bottom-right corner of
+/// a `surface` of the given size, shaped like the unzoomed image `quad`. Whole pixels, so the
+/// inset can be used as a scissor rectangle.
+pub fn inset_rect(quad: [f32; 2], surface: (u32, u32)) -> [f32; 4] {
+    let (sw, sh) = (surface.0 as f32, surface.1 as f32);
+    let aspect = (quad[0] * sw) / (quad[1] * sh).max(1.0);
+    let mut width = (sw * INSET_SHARE).min(INSET_MAX_WIDTH);
+    let mut height = width / aspect.max(f32::EPSILON);
+    if height > sh * INSET_SHARE {
+        height = sh * INSET_SHARE;
+        width = height * aspect;
+    }
+    let (width, height) = (width.floor().max(0.0), height.floor().max(0.0));
+    [
+        (sw - INSET_MARGIN - width).max(0.0).floor(),
+        (sh - INSET_MARGIN - height).max(0.0).floor(),
+        width,
+        height,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FULL: [f32; 2] = [1.0, 1.0];
+
+    fn zoomed(factor: f32) -> ZoomView {
+        ZoomView {
+            factor,
+            pan: [0.0, 0.0],
+        }
+    }
+
+    #[test]
+    fn visible_part_shrinks_about_the_center() {
+        assert_eq!(ZoomView::default().visible(FULL), [0.0, 0.0, 1.0, 1.0]);
+        assert_eq!(zoomed(2.0).visible(FULL), [0.25, 0.25, 0.75, 0.75]);
+        // A letterboxed image is fully visible along the letterboxed axis.
+        assert_eq!(zoomed(2.0).visible([1.0, 0.5]), [0.25, 0.0, 0.75, 1.0]);
+    }
+
+    #[test]
+    fn center_on_moves_the_viewport_within_the_limits() {
+        let mut view = zoomed(4.0);
+        view.center_on([0.25, 0.75], FULL);
+        assert_eq!(view.visible(FULL), [0.125, 0.625, 0.375, 0.875]);
+        // The corner itself cannot be centered; the viewport stops at the image edges.
+        view.center_on([0.0, 0.0], FULL);
+        assert_eq!(view.visible(FULL), [0.0, 0.0, 0.25, 0.25]);
+    }
+
+    #[test]
+    fn inset_sits_in_the_bottom_right_corner_shaped_like_the_image() {
+        // 16:9 image filling a 16:9 window.
+        assert_eq!(inset_rect(FULL, (1280, 720)), [1032.0, 577.0, 240.0, 135.0]);
+        // A tall image is limited by the window height instead.
+        assert_eq!(
+            inset_rect([0.25, 1.0], (800, 800)),
+            [742.0, 592.0, 50.0, 200.0]
+        );
+    }
+}